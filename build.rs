@@ -0,0 +1,72 @@
+//! Computes the hash of the detector ruleset that the entries of the scan cache are keyed on (see
+//! `src/trawl_source/scan_cache.rs`)
+
+use std::{env, fs, iter::Peekable, str::FromStr};
+
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+use sha2::{Digest, Sha256};
+
+/// The files that decide what is found in a source file and how it is classified. A file missing
+/// from here could change the findings without starting a new cache.
+const DETECTORS: &[&str] = &[
+    "src/finding.rs",
+    "src/trawl_source/ast_walker.rs",
+    "src/trawl_source/embedded.rs",
+    "src/trawl_source/file.rs",
+    "src/trawl_source/generated.rs",
+];
+
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let mut hasher = Sha256::new();
+    for detector in DETECTORS {
+        println!("cargo:rerun-if-changed={detector}");
+        let source = fs::read_to_string(format!("{manifest_dir}/{detector}")).unwrap();
+        let tokens = TokenStream::from_str(&source).unwrap();
+        // Comments and formatting do not change the tokens, and doc comments are left out, so
+        // that only changes to the code invalidate the cache.
+        hasher.update(without_docs(tokens).to_string());
+    }
+    let ruleset = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    println!("cargo:rustc-env=SIDEROPHILE_RULESET={ruleset}");
+}
+
+fn without_docs(tokens: TokenStream) -> TokenStream {
+    let mut tokens = tokens.into_iter().peekable();
+    let mut kept = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '#' && is_doc(&mut tokens) => {}
+            TokenTree::Group(group) => {
+                let mut stripped = Group::new(group.delimiter(), without_docs(group.stream()));
+                stripped.set_span(group.span());
+                kept.push(TokenTree::Group(stripped));
+            }
+            token => kept.push(token),
+        }
+    }
+    kept.into_iter().collect()
+}
+
+/// Whether the tokens after a `#` are the rest of a doc attribute, which are then consumed
+fn is_doc(tokens: &mut Peekable<impl Iterator<Item = TokenTree> + Clone>) -> bool {
+    let mut lookahead = tokens.clone();
+    if matches!(lookahead.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == '!') {
+        lookahead.next();
+    }
+    let is_doc = match lookahead.next() {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => matches!(
+            group.stream().into_iter().next(),
+            Some(TokenTree::Ident(ident)) if ident == "doc"
+        ),
+        _ => false,
+    };
+    if is_doc {
+        *tokens = lookahead;
+    }
+    is_doc
+}
@@ -0,0 +1,131 @@
+//! Suggests safer alternatives to the dependencies with findings, from the curated mapping in
+//! `data/alternatives.toml` and the files given with `--alternatives`
+
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use anyhow::Context;
+use serde::Deserialize;
+use structopt::StructOpt;
+
+use crate::report::{CrateSummary, Suggestion};
+
+const BUILTIN: &str = include_str!("../data/alternatives.toml");
+
+#[derive(StructOpt, Debug)]
+pub struct AlternativeOpts {
+    #[structopt(long = "alternatives", value_name = "PATH", number_of_values = 1)]
+    /// Suggest the alternatives in the TOML file at PATH too, in the form of
+    /// `data/alternatives.toml`, replacing the built-in ones for the same crates (can be given more
+    /// than once)
+    alternatives: Vec<PathBuf>,
+}
+
+/// What to suggest instead of a crate
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Entry {
+    /// Crate names, or `std`
+    alternatives: Vec<String>,
+
+    /// Why, or how to switch
+    note: Option<String>,
+}
+
+/// The alternatives to each crate, by crate name
+#[derive(Debug)]
+pub struct Alternatives(BTreeMap<String, Entry>);
+
+/// The built-in mapping, with the files of `opts` applied on top in order
+pub fn load(opts: &AlternativeOpts) -> anyhow::Result<Alternatives> {
+    let mut entries = parse(BUILTIN).context("Could not parse the built-in alternatives")?;
+    for path in &opts.alternatives {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Could not read `{}`", path.display()))?;
+        entries.extend(
+            parse(&contents)
+                .with_context(|| format!("Could not parse alternatives `{}`", path.display()))?,
+        );
+    }
+    Ok(Alternatives(entries))
+}
+
+fn parse(contents: &str) -> anyhow::Result<BTreeMap<String, Entry>> {
+    Ok(toml::from_str(contents)?)
+}
+
+impl Alternatives {
+    /// A suggestion for each package with findings that has alternatives, outside of the
+    /// workspace
+    pub fn suggest(&self, crates: &[CrateSummary]) -> Vec<Suggestion> {
+        crates
+            .iter()
+            .filter(|summary| !summary.workspace_member && summary.findings > 0)
+            .filter_map(|summary| {
+                let entry = self
+                    .0
+                    .get(&summary.name)
+                    .filter(|entry| !entry.alternatives.is_empty())?;
+                Some(Suggestion {
+                    krate: summary.name.clone(),
+                    version: summary.version.clone(),
+                    findings: summary.findings,
+                    alternatives: entry.alternatives.clone(),
+                    note: entry.note.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    fn summary(name: &str, findings: usize) -> CrateSummary {
+        CrateSummary {
+            findings,
+            ..fixture::summary(name)
+        }
+    }
+
+    #[test]
+    fn overrides_replace_builtin_entries() {
+        let mut entries = parse(BUILTIN).unwrap();
+        entries.extend(
+            parse(
+                r#"
+                [openssl]
+                alternatives = []
+
+                [internal-ffi]
+                alternatives = ["internal-safe"]
+                note = "Use the safe wrapper"
+                "#,
+            )
+            .unwrap(),
+        );
+        let alternatives = Alternatives(entries);
+        let crates = [
+            summary("openssl", 10),
+            summary("internal-ffi", 3),
+            summary("byteorder", 0),
+            summary("native-tls", 2),
+        ];
+        let suggestions = alternatives
+            .suggest(&crates)
+            .into_iter()
+            .map(|suggestion| (suggestion.krate, suggestion.alternatives))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            suggestions,
+            vec![
+                (
+                    "internal-ffi".to_string(),
+                    vec!["internal-safe".to_string()]
+                ),
+                ("native-tls".to_string(), vec!["rustls".to_string()]),
+            ]
+        );
+    }
+}
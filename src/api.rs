@@ -0,0 +1,639 @@
+//! The scanning entry points of the library, for embedders that want the findings without running
+//! the binary. What is found in each file is handed to a `ReportSink` as soon as the file has been
+//! scanned, so that embedders can store findings as they come rather than buffer whole reports.
+
+use std::{
+    error::Error,
+    fmt,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        mpsc::{self, Receiver, Sender, SyncSender, TryRecvError},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use futures_core::Stream;
+use serde_json::json;
+
+use crate::{
+    cancel::{CancellationToken, Cancelled},
+    finding::{Finding, Provenance},
+    pool::Pool,
+    trawl_source::{
+        ast_walker::{find_unsafe_in_file, ScanFileError},
+        file::{rs_files, to_findings},
+        generated,
+    },
+};
+
+/// Receives the findings of a scan one at a time, as soon as the file each is in has been
+/// scanned, and then the number of findings of the package
+pub trait ReportSink {
+    /// # Errors
+    ///
+    /// If the finding could not be stored, which stops the scan
+    fn push(&mut self, finding: Finding) -> io::Result<()>;
+
+    /// Called once the package `krate` has been scanned, with the number of findings pushed for it
+    ///
+    /// # Errors
+    ///
+    /// If the summary could not be stored
+    fn finish(&mut self, krate: &str, findings: usize) -> io::Result<()>;
+}
+
+/// Keeps the findings in memory
+#[derive(Clone, Debug, Default)]
+pub struct VecSink {
+    pub findings: Vec<Finding>,
+}
+
+impl ReportSink for VecSink {
+    fn push(&mut self, finding: Finding) -> io::Result<()> {
+        self.findings.push(finding);
+        Ok(())
+    }
+
+    fn finish(&mut self, _krate: &str, _findings: usize) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes each finding to a file as a line of JSON, and the summary of each package as a line of
+/// its own under a `summary` key
+#[derive(Debug)]
+pub struct FileSink {
+    out: BufWriter<File>,
+}
+
+impl FileSink {
+    /// Writes to the file at `path`, replacing it if it exists
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be created
+    pub fn create(path: &Path) -> io::Result<Self> {
+        File::create(path).map(|file| Self {
+            out: BufWriter::new(file),
+        })
+    }
+}
+
+impl ReportSink for FileSink {
+    fn push(&mut self, finding: Finding) -> io::Result<()> {
+        write_finding(&mut self.out, &finding)
+    }
+
+    fn finish(&mut self, krate: &str, findings: usize) -> io::Result<()> {
+        write_summary(&mut self.out, krate, findings)?;
+        self.out.flush()
+    }
+}
+
+/// Writes to the standard output like `FileSink` does to a file, flushing after each line so that
+/// readers see it right away
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdoutSink;
+
+impl ReportSink for StdoutSink {
+    fn push(&mut self, finding: Finding) -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        write_finding(&mut out, &finding)?;
+        out.flush()
+    }
+
+    fn finish(&mut self, krate: &str, findings: usize) -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        write_summary(&mut out, krate, findings)?;
+        out.flush()
+    }
+}
+
+fn write_finding(out: &mut impl Write, finding: &Finding) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, finding)?;
+    writeln!(out)
+}
+
+fn write_summary(out: &mut impl Write, krate: &str, findings: usize) -> io::Result<()> {
+    let summary = json!({ "summary": { "crate": krate, "findings": findings } });
+    serde_json::to_writer(&mut *out, &summary)?;
+    writeln!(out)
+}
+
+/// How `scan_dir` scans
+#[derive(Clone, Debug, Default)]
+pub struct ScanOptions {
+    /// Count unsafe usage inside tests
+    pub include_tests: bool,
+
+    /// Checked between files
+    pub cancel: CancellationToken,
+
+    /// Where to send how far the scan has got, if anywhere
+    pub progress: Option<Sender<Progress>>,
+}
+
+impl ScanOptions {
+    fn report(&self, progress: Progress) {
+        if let Some(sender) = &self.progress {
+            // Whoever was listening may have stopped, which need not stop the scan
+            let _ = sender.send(progress);
+        }
+    }
+}
+
+/// How far a scan has got, sent on `ScanOptions::progress`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Progress {
+    PackageStarted {
+        krate: String,
+    },
+
+    /// A file has been scanned. `files` and `findings` count those of the package so far.
+    FileScanned {
+        krate: String,
+        file: PathBuf,
+        files: usize,
+        findings: usize,
+    },
+
+    /// The package has been scanned and its findings handed to the sink. Not sent if the scan
+    /// failed.
+    PackageFinished {
+        krate: String,
+        files: usize,
+        findings: usize,
+    },
+}
+
+#[derive(Debug)]
+pub enum ScanDirError {
+    /// The directory could not be walked
+    Walk(walkdir::Error),
+
+    /// A file could not be read
+    Io(io::Error, PathBuf),
+
+    /// A file is not valid UTF-8 or Rust
+    Scan(ScanFileError),
+
+    /// The sink could not store a finding or the summary
+    Sink(io::Error),
+
+    Cancelled(Cancelled),
+}
+
+impl Error for ScanDirError {}
+
+/// Forward Display to Debug, like `ScanFileError`
+impl fmt::Display for ScanDirError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl From<Cancelled> for ScanDirError {
+    fn from(cancelled: Cancelled) -> Self {
+        Self::Cancelled(cancelled)
+    }
+}
+
+/// Scans the Rust files under `root`, the root of the package `krate`, like the trawl scans those
+/// of a package it could not build, and hands the findings of each to `sink` as soon as it has
+/// been scanned. The findings' files are relative to `root`, and their provenance is left empty,
+/// as the directory alone does not tell which build of the package it is. Returns the number of
+/// findings.
+///
+/// # Errors
+///
+/// If `root` could not be walked, one of its files could not be read or parsed, `sink` fails, or
+/// `opts.cancel` has been cancelled
+pub fn scan_dir(
+    krate: &str,
+    root: &Path,
+    opts: &ScanOptions,
+    sink: &mut dyn ReportSink,
+) -> Result<usize, ScanDirError> {
+    let krate = Arc::<str>::from(krate);
+    let provenance = Arc::new(Provenance::default());
+    let mut files = 0;
+    let mut findings = 0;
+    opts.report(Progress::PackageStarted {
+        krate: krate.to_string(),
+    });
+    for entry in rs_files(root) {
+        opts.cancel.check()?;
+        let entry = entry.map_err(ScanDirError::Walk)?;
+        let path = entry.path();
+        let contents = fs::read(path).map_err(|e| ScanDirError::Io(e, path.to_path_buf()))?;
+        // Relative, so that the module path of the items starts at the package's `src`
+        let file = Arc::<Path>::from(path.strip_prefix(root).unwrap_or(path));
+        let items = find_unsafe_in_file(&krate, &file, &contents, opts.include_tests)
+            .map_err(ScanDirError::Scan)?;
+        let generated = generated::is_generated(&krate, path, &contents);
+        for finding in to_findings(&krate, &provenance, &file, generated, items.items) {
+            sink.push(finding).map_err(ScanDirError::Sink)?;
+            findings += 1;
+        }
+        files += 1;
+        opts.report(Progress::FileScanned {
+            krate: krate.to_string(),
+            file: file.to_path_buf(),
+            files,
+            findings,
+        });
+    }
+    sink.finish(&krate, findings).map_err(ScanDirError::Sink)?;
+    opts.report(Progress::PackageFinished {
+        krate: krate.to_string(),
+        files,
+        findings,
+    });
+    Ok(findings)
+}
+
+/// What `scan_dirs_stream` yields: what a `ReportSink` would be handed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Scanned {
+    Finding(Finding),
+
+    /// A package has been scanned, with this many findings
+    Finished {
+        krate: String,
+        findings: usize,
+    },
+}
+
+/// How many of the findings of a stream can be waiting to be yielded before its scans wait for
+/// them to be
+const STREAM_CAPACITY: usize = 1024;
+
+/// Scans `packages`, pairs of package names and roots, like `scan_dir` does, on the threads of a
+/// pool shared by all streams, and yields what is found as it is found. Once `STREAM_CAPACITY`
+/// findings have not been yielded yet, the scans wait for the stream to be polled. A package whose
+/// scan fails yields its error, after whatever it yielded before, and the other packages are
+/// scanned all the same. Dropping the stream cancels the scans, but not `opts.cancel`, which
+/// cancels them too.
+#[must_use]
+pub fn scan_dirs_stream(packages: Vec<(String, PathBuf)>, opts: &ScanOptions) -> FindingStream {
+    let (sender, receiver) = mpsc::sync_channel(STREAM_CAPACITY);
+    let waker = Arc::new(Mutex::new(None));
+    let opts = ScanOptions {
+        cancel: opts.cancel.child_token(),
+        ..opts.clone()
+    };
+    for (krate, root) in packages {
+        let mut sink = StreamSink {
+            sender: Some(sender.clone()),
+            waker: Arc::clone(&waker),
+        };
+        let opts = opts.clone();
+        Pool::global().spawn(move || {
+            if let Err(e) = scan_dir(&krate, &root, &opts, &mut sink) {
+                sink.send(Err(e));
+            }
+        });
+    }
+    FindingStream {
+        receiver,
+        waker,
+        cancel: opts.cancel,
+    }
+}
+
+/// What `scan_dirs_stream` finds, yielded as it is found
+pub struct FindingStream {
+    receiver: Receiver<Result<Scanned, ScanDirError>>,
+
+    /// Of the task polling the stream, if it is waiting for more
+    waker: Arc<Mutex<Option<Waker>>>,
+
+    /// A child of the token of the options the stream was started with
+    cancel: CancellationToken,
+}
+
+impl Drop for FindingStream {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+impl Stream for FindingStream {
+    type Item = Result<Scanned, ScanDirError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.receiver.try_recv() {
+            Ok(item) => return Poll::Ready(Some(item)),
+            Err(TryRecvError::Disconnected) => return Poll::Ready(None),
+            Err(TryRecvError::Empty) => {}
+        }
+        *lock(&self.waker) = Some(cx.waker().clone());
+        // Whatever was sent before the waker was in place would not wake the task
+        match self.receiver.try_recv() {
+            Ok(item) => Poll::Ready(Some(item)),
+            Err(TryRecvError::Disconnected) => Poll::Ready(None),
+            Err(TryRecvError::Empty) => Poll::Pending,
+        }
+    }
+}
+
+/// Hands what `scan_dir` finds in one package to a `FindingStream`
+struct StreamSink {
+    /// Dropped once the package has been scanned, so that the stream ends with the last package
+    sender: Option<SyncSender<Result<Scanned, ScanDirError>>>,
+
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+impl StreamSink {
+    /// Sends `item`, waiting while the stream is full. Returns whether the stream is still there.
+    fn send(&self, item: Result<Scanned, ScanDirError>) -> bool {
+        let sent = self
+            .sender
+            .as_ref()
+            .is_some_and(|sender| sender.send(item).is_ok());
+        self.wake();
+        sent
+    }
+
+    fn wake(&self) {
+        let waker = lock(&self.waker).take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl ReportSink for StreamSink {
+    fn push(&mut self, finding: Finding) -> io::Result<()> {
+        if self.send(Ok(Scanned::Finding(finding))) {
+            Ok(())
+        } else {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    fn finish(&mut self, krate: &str, findings: usize) -> io::Result<()> {
+        self.send(Ok(Scanned::Finished {
+            krate: krate.to_string(),
+            findings,
+        }));
+        Ok(())
+    }
+}
+
+impl Drop for StreamSink {
+    fn drop(&mut self) {
+        // The stream may be waiting for the end of the last package
+        self.sender = None;
+        self.wake();
+    }
+}
+
+/// A thread that panicked while holding the lock left nothing half-done
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, task::Wake, thread};
+
+    use tempfile::{tempdir, TempDir};
+
+    use super::*;
+    use crate::finding::FindingKind;
+
+    /// A package `krate` with an unsafe function and an unsafe block in a submodule
+    fn package() -> Result<TempDir, Box<dyn Error>> {
+        let root = tempdir()?;
+        fs::create_dir_all(root.path().join("src/inner"))?;
+        fs::write(
+            root.path().join("src/lib.rs"),
+            "mod inner;\npub unsafe fn f() {}\n",
+        )?;
+        fs::write(
+            root.path().join("src/inner/mod.rs"),
+            "fn g(v: &[u8]) {\n    unsafe { v.get_unchecked(0); }\n}\n",
+        )?;
+        Ok(root)
+    }
+
+    #[test]
+    fn findings_reach_the_sink() -> Result<(), Box<dyn Error>> {
+        let root = package()?;
+        let mut sink = VecSink::default();
+        let findings = scan_dir("krate", root.path(), &ScanOptions::default(), &mut sink)?;
+        assert_eq!(findings, 2);
+
+        sink.findings
+            .sort_by(|a, b| a.location_key().cmp(&b.location_key()));
+        let found = sink
+            .findings
+            .iter()
+            .map(|finding| (&*finding.file, finding.item.as_str(), finding.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (
+                    Path::new("src/inner/mod.rs"),
+                    "krate::inner::g",
+                    FindingKind::Block
+                ),
+                (Path::new("src/lib.rs"), "krate::f", FindingKind::Function),
+            ]
+        );
+        assert_eq!(sink.findings[0].unchecked, ["get_unchecked"]);
+        Ok(())
+    }
+
+    #[test]
+    fn file_sink_ends_with_the_summary() -> Result<(), Box<dyn Error>> {
+        let root = package()?;
+        let out = tempdir()?;
+        let path = out.path().join("findings.jsonl");
+        let mut sink = FileSink::create(&path)?;
+        scan_dir("krate", root.path(), &ScanOptions::default(), &mut sink)?;
+
+        let lines = fs::read_to_string(&path)?;
+        let lines = lines.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        let finding: Finding = serde_json::from_str(lines[0])?;
+        assert_eq!(&*finding.krate, "krate");
+        let summary: serde_json::Value = serde_json::from_str(lines[2])?;
+        assert_eq!(summary["summary"]["findings"], 2);
+        Ok(())
+    }
+
+    #[test]
+    fn files_that_do_not_parse_fail_the_scan() -> Result<(), Box<dyn Error>> {
+        let root = package()?;
+        fs::write(root.path().join("src/broken.rs"), "fn (")?;
+        let result = scan_dir(
+            "krate",
+            root.path(),
+            &ScanOptions::default(),
+            &mut VecSink::default(),
+        );
+        assert!(matches!(
+            result,
+            Err(ScanDirError::Scan(ScanFileError::Syn(..)))
+        ));
+        Ok(())
+    }
+
+    /// Polls `stream` to its end on this thread, which is parked until the stream wakes it
+    fn poll_to_end(mut stream: FindingStream) -> Vec<Result<Scanned, ScanDirError>> {
+        struct Unpark(thread::Thread);
+
+        impl Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut items = Vec::new();
+        loop {
+            match Pin::new(&mut stream).poll_next(&mut cx) {
+                Poll::Ready(Some(item)) => items.push(item),
+                Poll::Ready(None) => return items,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn packages_are_streamed() -> Result<(), Box<dyn Error>> {
+        let (a, b) = (package()?, package()?);
+        let packages = vec![
+            ("a".to_string(), a.path().to_path_buf()),
+            ("b".to_string(), b.path().to_path_buf()),
+        ];
+        let stream = scan_dirs_stream(packages, &ScanOptions::default());
+        let scanned = poll_to_end(stream)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut findings = Vec::new();
+        let mut finished = Vec::new();
+        for item in scanned {
+            match item {
+                Scanned::Finding(finding) => {
+                    // A package's summary comes after all of its findings
+                    assert!(!finished.iter().any(|(krate, _)| **krate == *finding.krate));
+                    findings.push(finding.item);
+                }
+                Scanned::Finished {
+                    krate,
+                    findings: count,
+                } => finished.push((krate, count)),
+            }
+        }
+        findings.sort();
+        finished.sort();
+        assert_eq!(findings, ["a::f", "a::inner::g", "b::f", "b::inner::g"]);
+        assert_eq!(finished, [("a".to_string(), 2), ("b".to_string(), 2)]);
+        Ok(())
+    }
+
+    #[test]
+    fn a_failed_package_does_not_stop_the_others() -> Result<(), Box<dyn Error>> {
+        let a = package()?;
+        fs::write(a.path().join("src/broken.rs"), "fn (")?;
+        let b = package()?;
+        let packages = vec![
+            ("a".to_string(), a.path().to_path_buf()),
+            ("b".to_string(), b.path().to_path_buf()),
+        ];
+        let items = poll_to_end(scan_dirs_stream(packages, &ScanOptions::default()));
+
+        assert_eq!(
+            items
+                .iter()
+                .filter(|item| matches!(item, Err(ScanDirError::Scan(_))))
+                .count(),
+            1
+        );
+        assert!(items.iter().any(|item| matches!(
+            item,
+            Ok(Scanned::Finished { krate, .. }) if krate == "b"
+        )));
+        Ok(())
+    }
+
+    #[test]
+    fn progress_is_sent_after_each_file() -> Result<(), Box<dyn Error>> {
+        let root = package()?;
+        let (sender, receiver) = mpsc::channel();
+        let opts = ScanOptions {
+            progress: Some(sender),
+            ..ScanOptions::default()
+        };
+        scan_dir("krate", root.path(), &opts, &mut VecSink::default())?;
+
+        let progress = receiver.try_iter().collect::<Vec<_>>();
+        assert_eq!(
+            progress.first(),
+            Some(&Progress::PackageStarted {
+                krate: "krate".to_string()
+            })
+        );
+        assert_eq!(
+            progress.last(),
+            Some(&Progress::PackageFinished {
+                krate: "krate".to_string(),
+                files: 2,
+                findings: 2,
+            })
+        );
+        // The files are walked in no particular order, but counted in the order they are scanned
+        let mut files = Vec::new();
+        let mut counts = Vec::new();
+        for event in &progress[1..progress.len() - 1] {
+            let Progress::FileScanned {
+                file, files: count, ..
+            } = event
+            else {
+                return Err(format!("Not a file: {event:?}").into());
+            };
+            files.push(file.as_path());
+            counts.push(*count);
+        }
+        files.sort();
+        assert_eq!(
+            files,
+            [Path::new("src/inner/mod.rs"), Path::new("src/lib.rs")]
+        );
+        assert_eq!(counts, [1, 2]);
+        Ok(())
+    }
+
+    #[test]
+    fn a_cancelled_scan_stops() -> Result<(), Box<dyn Error>> {
+        let root = package()?;
+        let opts = ScanOptions::default();
+        opts.cancel.cancel();
+        let result = scan_dir("krate", root.path(), &opts, &mut VecSink::default());
+        assert!(matches!(result, Err(ScanDirError::Cancelled(Cancelled))));
+        Ok(())
+    }
+
+    #[test]
+    fn dropping_the_stream_cancels_its_scans_only() -> Result<(), Box<dyn Error>> {
+        let root = package()?;
+        let opts = ScanOptions::default();
+        let stream = scan_dirs_stream(vec![("krate".to_string(), root.path().into())], &opts);
+        let scans = stream.cancel.clone();
+        drop(stream);
+        assert!(scans.is_cancelled());
+        assert!(!opts.cancel.is_cancelled());
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use crate::report::Finding;
+
+#[derive(StructOpt, Debug)]
+pub struct BaselineOpts {
+    #[structopt(long = "baseline", value_name = "PATH")]
+    /// Only report and count findings that are not in the baseline at PATH
+    baseline: Option<PathBuf>,
+
+    #[structopt(long = "update-baseline", requires = "baseline")]
+    /// Write the current findings to the baseline
+    update_baseline: bool,
+}
+
+impl BaselineOpts {
+    pub const fn is_enabled(&self) -> bool {
+        self.baseline.is_some()
+    }
+}
+
+/// Findings that have already been accepted. A JSON report can also be used as a baseline.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Baseline {
+    findings: Vec<Finding>,
+}
+
+impl Baseline {
+    fn read(path: &Path) -> anyhow::Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Could not open `{}`", path.display()))?;
+        serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Could not parse baseline `{}`", path.display()))
+    }
+
+    fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let file =
+            File::create(path).with_context(|| format!("Could not create `{}`", path.display()))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)?;
+        Ok(())
+    }
+}
+
+/// Removes the findings that are in the baseline, matching them by fingerprint. With
+/// `--update-baseline`, the baseline is then replaced by all of `findings`, so the findings that
+/// are reported are those that are new since the previous baseline.
+pub fn apply(opts: &BaselineOpts, findings: Vec<Finding>) -> anyhow::Result<Vec<Finding>> {
+    let path = if let Some(path) = &opts.baseline {
+        path
+    } else {
+        return Ok(findings);
+    };
+
+    let baseline = if path.exists() {
+        Baseline::read(path)?
+    } else if opts.update_baseline {
+        Baseline::default()
+    } else {
+        bail!("Baseline `{}` does not exist", path.display());
+    };
+
+    if opts.update_baseline {
+        Baseline {
+            findings: findings.clone(),
+        }
+        .write(path)?;
+    }
+
+    let known = baseline
+        .findings
+        .iter()
+        .map(|finding| finding.fingerprint.as_str())
+        .collect::<HashSet<_>>();
+    Ok(findings
+        .into_iter()
+        .filter(|finding| !known.contains(finding.fingerprint.as_str()))
+        .collect())
+}
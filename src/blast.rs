@@ -0,0 +1,106 @@
+//! The "blast radius" of each package: how many functions of the crate only its unsafety reaches,
+//! i.e., how many would be left with no badness if the package were removed or replaced
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    callgraph_gen,
+    cancel::{CancellationToken, Cancelled},
+    report::Finding,
+    utils,
+};
+
+/// For each package with findings, as `(name, version)`, the number of functions of `badness`
+/// that no finding outside of it reaches. `crate_names` are those `badness` was traced for.
+pub fn blast_radius(
+    callgraph: &utils::CallGraph,
+    crate_names: &[&str],
+    findings: &[Finding],
+    badness: &HashMap<String, (u32, utils::LabelInfo)>,
+    cancel: &CancellationToken,
+) -> Result<HashMap<(String, String), usize>, Cancelled> {
+    let packages = findings
+        .iter()
+        .map(|finding| (&*finding.krate, finding.provenance.version.as_str()))
+        .collect::<BTreeSet<_>>();
+    let mut radii = HashMap::new();
+    for (krate, version) in packages {
+        let tainted = findings
+            .iter()
+            .filter(|finding| &*finding.krate != krate || finding.provenance.version != version)
+            .map(|finding| finding.item.clone())
+            .collect::<Vec<_>>();
+        let mut without = HashMap::new();
+        for crate_name in crate_names {
+            without.extend(callgraph_gen::trace_unsafety(
+                callgraph, crate_name, &tainted, cancel,
+            )?);
+        }
+        let radius = badness
+            .keys()
+            .filter(|function| !without.contains_key(*function))
+            .count();
+        radii.insert((krate.to_string(), version.to_string()), radius);
+    }
+    Ok(radii)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+    use std::collections::HashSet;
+
+    fn finding(krate: &str, item: &str) -> Finding {
+        Finding {
+            item: item.to_string(),
+            fingerprint: item.to_string(),
+            ..fixture::finding(krate, 1)
+        }
+    }
+
+    /// A callgraph in which each function is labeled with its own name
+    fn callgraph(calls: &[(&str, &str)]) -> utils::CallGraph {
+        let mut label_to_label_info = HashMap::<String, utils::LabelInfo>::new();
+        for (caller, callee) in calls {
+            for function in [caller, callee] {
+                label_to_label_info
+                    .entry(function.to_string())
+                    .or_default()
+                    .short_label = Some(function.to_string());
+            }
+            label_to_label_info
+                .get_mut(*callee)
+                .unwrap()
+                .caller_labels
+                .insert(caller.to_string());
+        }
+        let short_label_to_labels = label_to_label_info
+            .keys()
+            .map(|label| (label.clone(), HashSet::from([label.clone()])))
+            .collect();
+        utils::CallGraph {
+            label_to_label_info,
+            short_label_to_labels,
+        }
+    }
+
+    #[test]
+    fn only_functions_reached_by_the_package_alone_count() {
+        let callgraph = callgraph(&[
+            ("app::parse", "nom::take"),
+            ("app::run", "nom::take"),
+            ("app::run", "libc::read"),
+        ]);
+        let findings = [finding("nom", "nom::take"), finding("libc", "libc::read")];
+        let tainted = findings
+            .iter()
+            .map(|finding| finding.item.clone())
+            .collect::<Vec<_>>();
+        let cancel = CancellationToken::default();
+        let badness = callgraph_gen::trace_unsafety(&callgraph, "app", &tainted, &cancel).unwrap();
+        let radii = blast_radius(&callgraph, &["app"], &findings, &badness, &cancel).unwrap();
+        assert_eq!(radii[&("nom".to_string(), "1.0.0".to_string())], 1);
+        assert_eq!(radii[&("libc".to_string(), "1.0.0".to_string())], 0);
+    }
+}
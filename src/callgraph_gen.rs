@@ -1,3 +1,4 @@
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::path::Path;
@@ -14,6 +15,7 @@ use regex::Regex;
 use rustc_demangle::demangle;
 use utils::LabelInfo;
 
+use crate::cancel::{CancellationToken, Cancelled};
 use crate::utils;
 
 // emit llvm IR. disable optimizations. just want debug info and call graph...
@@ -121,13 +123,16 @@ pub fn gen_callgraph(ws: &Workspace, crate_name: &str) -> anyhow::Result<utils::
     parse_ir_file(&path)
 }
 
+/// # Errors
+///
+/// `Cancelled` if `cancel` is cancelled before every tainted function has been traced
 #[allow(clippy::missing_panics_doc, clippy::unwrap_used)]
-#[must_use]
 pub fn trace_unsafety(
     callgraph: &utils::CallGraph,
     crate_name: &str,
     tainted_function_names: &[String],
-) -> HashMap<String, (u32, LabelInfo)> {
+    cancel: &CancellationToken,
+) -> Result<HashMap<String, (u32, LabelInfo)>, Cancelled> {
     let mut tainted_function_labels = HashSet::new();
     for t in tainted_function_names {
         let short_label = utils::simplify_trait_paths(t);
@@ -138,6 +143,7 @@ pub fn trace_unsafety(
 
     let mut label_to_badness: HashMap<String, (u32, LabelInfo)> = HashMap::new();
     for tainted_function in tainted_function_labels {
+        cancel.check()?;
         // traversal of the call graph from tainted node
         let mut queued_to_traverse: Vec<String> = vec![tainted_function.to_string()];
         let mut tainted_by: HashSet<String> = HashSet::new();
@@ -176,5 +182,40 @@ pub fn trace_unsafety(
     // filter out any badness results that are not in the crate
     let re = Regex::new(&format!(r"^<*{}::", str::replace(crate_name, "-", "_"))).unwrap();
     ret_badness.retain(|k, _| re.is_match(k));
-    ret_badness
+    Ok(ret_badness)
+}
+
+/// The calls between `functions`, which are named like the functions of `trace_unsafety`'s result,
+/// as sorted `(caller, callee)` pairs
+#[must_use]
+pub fn calls_between(
+    callgraph: &utils::CallGraph,
+    functions: &BTreeSet<&str>,
+) -> Vec<(String, String)> {
+    let name = |label_info: &LabelInfo| {
+        label_info
+            .short_label
+            .as_deref()
+            .map(utils::simplify_trait_paths)
+            .filter(|name| functions.contains(name.as_str()))
+    };
+    let mut calls = BTreeSet::new();
+    for label_info in callgraph.label_to_label_info.values() {
+        let callee = match name(label_info) {
+            Some(callee) => callee,
+            None => continue,
+        };
+        for caller_label in &label_info.caller_labels {
+            if let Some(caller) = callgraph
+                .label_to_label_info
+                .get(caller_label)
+                .and_then(name)
+            {
+                if caller != callee {
+                    calls.insert((caller, callee.clone()));
+                }
+            }
+        }
+    }
+    calls.into_iter().collect()
 }
@@ -0,0 +1,99 @@
+//! Cooperative cancellation of scans. The scanning and tracing loops check a token between
+//! packages, files and tainted functions, and stop with `Cancelled` once it has been cancelled, so
+//! that a scan that is no longer wanted does not run to completion.
+
+use std::{
+    error::Error,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Shared between a scan and whoever may cancel it. Clones share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+
+    /// See `child_token`
+    parent: Option<Arc<CancellationToken>>,
+}
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.is_cancelled())
+    }
+
+    /// A token that is cancelled along with this one, but can also be cancelled on its own
+    /// without cancelling this one
+    #[must_use]
+    pub fn child_token(&self) -> Self {
+        Self {
+            cancelled: Arc::default(),
+            parent: Some(Arc::new(self.clone())),
+        }
+    }
+
+    /// # Errors
+    ///
+    /// `Cancelled` if the token has been cancelled
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Cancels the token once `timeout` has passed
+    pub fn cancel_after(&self, timeout: Duration) {
+        let token = self.clone();
+        thread::spawn(move || {
+            thread::sleep(timeout);
+            token.cancel();
+        });
+    }
+}
+
+/// The error a cancelled scan stops with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Error for Cancelled {}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("The scan was cancelled")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn children_are_cancelled_with_their_parent_but_not_the_other_way_around() {
+        let parent = CancellationToken::default();
+        let child = parent.child_token();
+        child.cancel();
+        assert!(!parent.is_cancelled());
+
+        let child = parent.child_token();
+        let grandchild = child.child_token();
+        parent.cancel();
+        assert!(child.is_cancelled());
+        assert!(grandchild.is_cancelled());
+    }
+}
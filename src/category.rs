@@ -0,0 +1,129 @@
+//! Classifies crates so that their unsafety can be aggregated by kind of crate
+
+use std::collections::BTreeMap;
+
+use crate::report::{CategorySummary, CrateSummary};
+
+/// The category of crates that could not be classified
+pub const UNCATEGORIZED: &str = "uncategorized";
+
+/// The category of the crates that stand in for parts of the standard library, like those built
+/// with `-Z build-std` or the `core` shims of embedded trees
+pub const STDLIB: &str = "stdlib";
+
+/// Crates that are, or stand in for, parts of the standard library
+const STDLIB_CRATES: &[&str] = &[
+    "alloc",
+    "compiler_builtins",
+    "core",
+    "core2",
+    "no-std-compat",
+    "panic_abort",
+    "panic_unwind",
+    "std",
+    "unwind",
+];
+
+/// For crates that declare no category, keywords that suggest one (a crates.io category slug)
+const KEYWORD_CATEGORIES: &[(&str, &str)] = &[
+    ("async", "asynchronous"),
+    ("bindings", "external-ffi-bindings"),
+    ("compression", "compression"),
+    ("crypto", "cryptography"),
+    ("cryptography", "cryptography"),
+    ("ffi", "external-ffi-bindings"),
+    ("futures", "asynchronous"),
+    ("gzip", "compression"),
+    ("parser", "parser-implementations"),
+    ("parsing", "parsing"),
+    ("serde", "encoding"),
+    ("serialization", "encoding"),
+    ("ssl", "cryptography"),
+    ("tls", "cryptography"),
+    ("zip", "compression"),
+];
+
+/// `STDLIB` for standard library crates and their replacements, else the top-level crates.io
+/// category of the first category a crate declares, else `-sys` crates are FFI bindings, else a
+/// category suggested by the crate's keywords
+pub fn categorize(name: &str, categories: &[String], keywords: &[String]) -> String {
+    if is_stdlib(name) {
+        return STDLIB.to_string();
+    }
+    if let Some(category) = categories.first() {
+        return category.split("::").next().unwrap_or(category).to_string();
+    }
+    if name.ends_with("-sys") {
+        return "external-ffi-bindings".to_string();
+    }
+    keywords
+        .iter()
+        .find_map(|keyword| {
+            KEYWORD_CATEGORIES
+                .iter()
+                .find(|(candidate, _)| keyword.eq_ignore_ascii_case(candidate))
+                .map(|(_, category)| (*category).to_string())
+        })
+        .unwrap_or_else(|| UNCATEGORIZED.to_string())
+}
+
+/// Whether `name` is a crate of the standard library, e.g. `compiler_builtins`, or a replacement for
+/// one, e.g. `rustc-std-workspace-core`
+fn is_stdlib(name: &str) -> bool {
+    STDLIB_CRATES.contains(&name) || name.starts_with("rustc-std-workspace-")
+}
+
+/// Totals per category, sorted by descending number of findings
+pub fn summarize(crates: &[CrateSummary]) -> Vec<CategorySummary> {
+    let mut categories = BTreeMap::<&str, CategorySummary>::new();
+    for summary in crates {
+        let category = categories
+            .entry(&summary.category)
+            .or_insert_with(|| CategorySummary {
+                category: summary.category.clone(),
+                crates: 0,
+                findings: 0,
+            });
+        category.crates += 1;
+        category.findings += summary.findings;
+    }
+    let mut categories = categories.into_values().collect::<Vec<_>>();
+    categories.sort_by(|a, b| {
+        b.findings
+            .cmp(&a.findings)
+            .then_with(|| a.category.cmp(&b.category))
+    });
+    categories
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categorize_prefers_declared_categories() {
+        let strings = |s: &[&str]| s.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            categorize("flate2", &strings(&["compression", "api-bindings"]), &[]),
+            "compression"
+        );
+        assert_eq!(
+            categorize("x", &strings(&["parser-implementations::json"]), &[]),
+            "parser-implementations"
+        );
+        assert_eq!(
+            categorize("openssl-sys", &[], &strings(&["tls"])),
+            "external-ffi-bindings"
+        );
+        assert_eq!(categorize("x", &[], &strings(&["TLS"])), "cryptography");
+        assert_eq!(categorize("x", &[], &[]), UNCATEGORIZED);
+    }
+
+    #[test]
+    fn categorize_recognizes_stdlib() {
+        let no_std = vec!["no-std".to_string()];
+        assert_eq!(categorize("compiler_builtins", &no_std, &[]), STDLIB);
+        assert_eq!(categorize("rustc-std-workspace-core", &[], &[]), STDLIB);
+        assert_eq!(categorize("core-foundation", &[], &[]), UNCATEGORIZED);
+    }
+}
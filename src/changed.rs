@@ -0,0 +1,258 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    fs,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use anyhow::bail;
+use structopt::StructOpt;
+
+use crate::{
+    git::git,
+    ignore,
+    report::Report,
+    utils::{self, LabelInfo},
+};
+
+#[derive(StructOpt, Debug)]
+pub struct ChangedOpts {
+    #[structopt(long = "changed-since", value_name = "REF")]
+    /// Only report and mark unsafety in lines changed since the merge base with REF, leaving out
+    /// the files ignored by `.siderophileignore`
+    changed_since: Option<String>,
+}
+
+impl ChangedOpts {
+    pub const fn is_enabled(&self) -> bool {
+        self.changed_since.is_some()
+    }
+}
+
+/// The lines added or modified in each file, keyed by canonical path
+pub struct ChangedLines {
+    files: HashMap<PathBuf, Vec<RangeInclusive<usize>>>,
+}
+
+impl ChangedLines {
+    pub fn load(opts: &ChangedOpts) -> anyhow::Result<Option<Self>> {
+        let reference = if let Some(reference) = &opts.changed_since {
+            reference
+        } else {
+            return Ok(None);
+        };
+
+        let root = git(&[OsStr::new("rev-parse"), OsStr::new("--show-toplevel")])?;
+        let root = fs::canonicalize(root.trim_end())?;
+
+        let diff = git(&[
+            OsStr::new("diff"),
+            OsStr::new("--unified=0"),
+            OsStr::new("--no-color"),
+            OsStr::new("--no-ext-diff"),
+            // Whatever `diff.noprefix` and `diff.mnemonicPrefix` say
+            OsStr::new("--src-prefix=a/"),
+            OsStr::new("--dst-prefix=b/"),
+            OsStr::new(&format!("{reference}...HEAD")),
+        ])?;
+
+        let ignore = ignore::load(&cargo::Config::default()?)?;
+        let mut files = parse_diff(&root, &diff)?;
+        files.retain(|file, _| !ignore.is_ignored(file));
+        Ok(Some(Self { files }))
+    }
+
+    pub fn contains_file(&self, file: &Path) -> bool {
+        self.files.contains_key(file)
+    }
+
+    pub fn contains(&self, file: &Path, line: usize) -> bool {
+        self.files
+            .get(file)
+            .is_some_and(|ranges| ranges.iter().any(|range| range.contains(&line)))
+    }
+
+    /// The findings in changed lines, and the functions in changed files
+    pub fn restrict(&self, report: &Report) -> Report {
+        let mut report = report.clone();
+        report
+            .findings
+            .retain(|finding| self.contains(&finding.file, finding.line));
+        report.badness.retain(|entry| {
+            entry
+                .file
+                .as_ref()
+                .is_some_and(|file| self.contains_file(file))
+        });
+        let functions = report
+            .badness
+            .iter()
+            .map(|entry| entry.function.clone())
+            .collect::<HashSet<_>>();
+        report
+            .calls
+            .retain(|call| functions.contains(&call.caller) && functions.contains(&call.callee));
+        report
+    }
+
+    /// The functions in changed files
+    pub fn restrict_badness(
+        &self,
+        badness: &HashMap<String, (u32, LabelInfo)>,
+    ) -> HashMap<String, (u32, LabelInfo)> {
+        badness
+            .iter()
+            .filter(|(_, (_, label_info))| {
+                label_info
+                    .debugloc
+                    .as_ref()
+                    .is_some_and(|debugloc| self.contains_file(&utils::debugloc_path(debugloc)))
+            })
+            .map(|(label, entry)| (label.clone(), entry.clone()))
+            .collect()
+    }
+}
+
+/// Parses the output of `git diff --unified=0`. Fails on a file name that is not in the `b/`
+/// prefix, rather than marking no lines as changed.
+fn parse_diff(
+    root: &Path,
+    diff: &str,
+) -> anyhow::Result<HashMap<PathBuf, Vec<RangeInclusive<usize>>>> {
+    let mut files = HashMap::<_, Vec<_>>::new();
+    let mut current = None;
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            if path == "/dev/null" {
+                current = None;
+                continue;
+            }
+            let Some(path) = unquote(path) else {
+                bail!("Could not parse `{}` in the output of `git diff`", line);
+            };
+            let Some(path) = path.strip_prefix("b/") else {
+                bail!("`{}` in the output of `git diff` is not in `b/`", line);
+            };
+            current = Some(root.join(path));
+        } else if let Some(hunk) = line.strip_prefix("@@ ") {
+            let (Some(file), Some(range)) = (&current, parse_hunk(hunk)) else {
+                continue;
+            };
+            files.entry(file.clone()).or_default().push(range);
+        }
+    }
+    Ok(files)
+}
+
+/// A file name from a diff header. Git puts the names with special characters, e.g., spaces with
+/// `core.quotePath`, in double quotes, with C escapes and the other bytes in octal.
+fn unquote(path: &str) -> Option<String> {
+    let Some(quoted) = path.strip_prefix('"') else {
+        return Some(path.to_string());
+    };
+    let quoted = quoted.strip_suffix('"')?;
+    let mut bytes = Vec::new();
+    let mut input = quoted.bytes();
+    while let Some(byte) = input.next() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        let byte = match input.next()? {
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b't' => b'\t',
+            b'n' => b'\n',
+            b'v' => 0x0b,
+            b'f' => 0x0c,
+            b'r' => b'\r',
+            digit @ b'0'..=b'7' => {
+                let mut value = u32::from(digit - b'0');
+                for _ in 0..2 {
+                    match input.next()? {
+                        digit @ b'0'..=b'7' => value = value * 8 + u32::from(digit - b'0'),
+                        _ => return None,
+                    }
+                }
+                u8::try_from(value).ok()?
+            }
+            other => other,
+        };
+        bytes.push(byte);
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Parses the new side of a hunk header like `-12,3 +14,5 @@`. Returns `None` for hunks that only
+/// remove lines.
+fn parse_hunk(hunk: &str) -> Option<RangeInclusive<usize>> {
+    let new = hunk.split_whitespace().find_map(|s| s.strip_prefix('+'))?;
+    let (start, len) = new.split_once(',').map_or(Some((new, 1)), |(start, len)| {
+        len.parse::<usize>().ok().map(|len| (start, len))
+    })?;
+    let start = start.parse::<usize>().ok()?;
+    if len == 0 {
+        None
+    } else {
+        Some(start..=start + len - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hunks() {
+        assert_eq!(parse_hunk("-12,3 +14,5 @@ fn f() {"), Some(14..=18));
+        assert_eq!(parse_hunk("-12 +14 @@"), Some(14..=14));
+        assert_eq!(parse_hunk("-12,3 +11,0 @@"), None);
+    }
+
+    #[test]
+    fn diff() {
+        let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,0 +2,2 @@
++unsafe {
++}
+diff --git a/src/old.rs b/src/old.rs
+--- a/src/old.rs
++++ /dev/null
+@@ -1,3 +0,0 @@
+";
+        let files = parse_diff(Path::new("/repo"), diff).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[Path::new("/repo/src/lib.rs")], vec![2..=3]);
+    }
+
+    #[test]
+    fn quoted_paths() {
+        let diff = "\
+diff --git \"a/src/with space.rs\" \"b/src/with space.rs\"
+--- \"a/src/with space.rs\"
++++ \"b/src/with space.rs\"
+@@ -1,0 +2 @@
++unsafe {}
+diff --git \"a/src/caf\\303\\251.rs\" \"b/src/caf\\303\\251.rs\"
++++ \"b/src/caf\\303\\251.rs\"
+@@ -4 +4 @@
+";
+        let files = parse_diff(Path::new("/repo"), diff).unwrap();
+        assert_eq!(files[Path::new("/repo/src/with space.rs")], vec![2..=2]);
+        assert_eq!(files[Path::new("/repo/src/caf\u{e9}.rs")], vec![4..=4]);
+        assert_eq!(unquote(r#""a\"b\\c\td""#).as_deref(), Some("a\"b\\c\td"));
+    }
+
+    #[test]
+    fn unprefixed_paths_are_errors() {
+        let diff = "\
++++ src/lib.rs
+@@ -1,0 +2,2 @@
+";
+        assert!(parse_diff(Path::new("/repo"), diff).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::bail;
+use serde::Serialize;
+use structopt::StructOpt;
+
+use crate::{output::Format, report::Report};
+
+#[derive(StructOpt, Debug)]
+pub struct AggregateOpts {
+    #[structopt(long = "format", value_name = "FORMAT", default_value = "text")]
+    /// Output format: text, json or yaml
+    format: Format,
+
+    #[structopt(value_name = "REPORT")]
+    /// JSON reports of the projects, one per project
+    reports: Vec<PathBuf>,
+}
+
+/// One package version across the projects
+#[derive(Debug, Serialize)]
+struct SharedCrate {
+    name: String,
+
+    version: String,
+
+    /// The projects whose trees contain the package, sorted
+    projects: Vec<String>,
+
+    /// The findings in the package, counted once however many projects depend on it
+    findings: usize,
+
+    /// The findings in the package, counted once per project that depends on it
+    total_findings: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct Aggregate {
+    /// Sorted
+    projects: Vec<String>,
+
+    /// The findings of every package in any project, each package counted once
+    findings: usize,
+
+    /// Packages outside of the projects themselves, by descending total findings, then by name
+    /// and version
+    crates: Vec<SharedCrate>,
+}
+
+pub fn run(opts: &AggregateOpts) -> anyhow::Result<()> {
+    if opts.reports.is_empty() {
+        bail!("No reports to aggregate");
+    }
+    let mut projects = Vec::new();
+    let mut crates = BTreeMap::<(String, String), SharedCrate>::new();
+    for path in &opts.reports {
+        let report = Report::read(path)?;
+        let project = project_name(&report, path);
+        for summary in report
+            .crates
+            .iter()
+            .filter(|summary| !summary.workspace_member)
+        {
+            let shared = crates
+                .entry((summary.name.clone(), summary.version.clone()))
+                .or_insert_with(|| SharedCrate {
+                    name: summary.name.clone(),
+                    version: summary.version.clone(),
+                    projects: Vec::new(),
+                    findings: 0,
+                    total_findings: 0,
+                });
+            // Projects may scan a package under different features or targets.
+            shared.findings = shared.findings.max(summary.findings);
+            shared.total_findings += summary.findings;
+            shared.projects.push(project.clone());
+        }
+        projects.push(project);
+    }
+    projects.sort();
+    let mut crates = crates.into_values().collect::<Vec<_>>();
+    for shared in &mut crates {
+        shared.projects.sort();
+        shared.projects.dedup();
+    }
+    crates.sort_by(|a, b| b.total_findings.cmp(&a.total_findings));
+    let aggregate = Aggregate {
+        projects,
+        findings: crates.iter().map(|shared| shared.findings).sum(),
+        crates,
+    };
+
+    let mut out = io::stdout().lock();
+    match opts.format {
+        Format::Text => write_text(&aggregate, &mut out)?,
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, &aggregate)?;
+            writeln!(out)?;
+        }
+        Format::Yaml => serde_yaml::to_writer(out, &aggregate)?,
+        format => bail!("The {format} format is only for reports"),
+    }
+    Ok(())
+}
+
+/// The workspace members of the report, or the file name of the report if it has none
+fn project_name(report: &Report, path: &Path) -> String {
+    let members = report
+        .crates
+        .iter()
+        .filter(|summary| summary.workspace_member)
+        .map(|summary| summary.name.as_str())
+        .collect::<Vec<_>>();
+    if members.is_empty() {
+        path.file_stem()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .into_owned()
+    } else {
+        members.join(",")
+    }
+}
+
+fn write_text(aggregate: &Aggregate, out: &mut dyn Write) -> anyhow::Result<()> {
+    writeln!(
+        out,
+        "{} projects, {} findings in their dependencies",
+        aggregate.projects.len(),
+        aggregate.findings
+    )?;
+    writeln!(out)?;
+    writeln!(out, "   Total  Findings  Projects  Crate")?;
+    for shared in &aggregate.crates {
+        writeln!(
+            out,
+            "{:>8}  {:>8}  {:>8}  {} {}",
+            shared.total_findings,
+            shared.findings,
+            shared.projects.len(),
+            shared.name,
+            shared.version
+        )?;
+    }
+    Ok(())
+}
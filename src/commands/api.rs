@@ -0,0 +1,91 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use anyhow::Context;
+use structopt::StructOpt;
+
+use crate::report::{ApiFunction, Report};
+
+#[derive(StructOpt, Debug)]
+pub struct ApiOpts {
+    #[structopt(long = "report", value_name = "PATH")]
+    /// JSON report to describe the API of (by default, the current crate is analyzed)
+    report: Option<PathBuf>,
+
+    #[structopt(long = "output", short = "o", value_name = "PATH")]
+    /// Where to write the document (by default, stdout)
+    output: Option<PathBuf>,
+}
+
+pub fn run(opts: &ApiOpts, analyze: &dyn Fn() -> anyhow::Result<Report>) -> anyhow::Result<()> {
+    let report = if let Some(path) = &opts.report {
+        Report::read(path)?
+    } else {
+        analyze()?
+    };
+
+    let mut document = Vec::new();
+    write_contract(&mut document, &report.api)?;
+    match &opts.output {
+        Some(path) => fs::write(path, document)
+            .with_context(|| format!("Could not write `{}`", path.display())),
+        None => Ok(io::stdout().lock().write_all(&document)?),
+    }
+}
+
+/// A Markdown "API safety contract": for each crate, its public functions, whether they are
+/// `unsafe fn`s, and the badness of the unsafety they reach
+fn write_contract(out: &mut dyn Write, api: &[ApiFunction]) -> anyhow::Result<()> {
+    let mut by_crate = BTreeMap::<_, Vec<_>>::new();
+    for function in api {
+        by_crate.entry(&function.krate).or_default().push(function);
+    }
+
+    writeln!(out, "# API safety contract")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "The public functions and the unsafe code they reach. Badness approximates how many \
+         unsafe expressions are evaluated when a function is called; a function with no badness \
+         reaches none. An `unsafe fn` leaves upholding its safety conditions to its callers."
+    )?;
+    for (krate, functions) in by_crate {
+        let unsafe_fns = functions
+            .iter()
+            .filter(|function| function.unsafe_fn)
+            .count();
+        let reaching = functions
+            .iter()
+            .filter(|function| function.badness > 0)
+            .count();
+        writeln!(out)?;
+        writeln!(out, "## `{}`", krate)?;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{} public functions, of which {} are `unsafe fn`s and {} reach unsafe code.",
+            functions.len(),
+            unsafe_fns,
+            reaching
+        )?;
+        writeln!(out)?;
+        writeln!(out, "| Function | `unsafe fn` | Badness | Location |")?;
+        writeln!(out, "|---|---|---:|---|")?;
+        for function in functions {
+            writeln!(
+                out,
+                "| `{}` | {} | {} | `{}:{}` |",
+                function.function,
+                if function.unsafe_fn { "yes" } else { "no" },
+                function.badness,
+                function.file.display(),
+                function.line
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Context};
+use cargo::{
+    core::{Package, Workspace},
+    util::important_paths::find_root_manifest_for_wd,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use structopt::StructOpt;
+
+use crate::{
+    exit::{Code, Failure},
+    git, trawl_source,
+};
+
+#[derive(StructOpt, Debug)]
+pub struct AttestOpts {
+    #[structopt(long = "package", short = "p", value_name = "NAME")]
+    /// Workspace member to attest (by default, the current package)
+    package: Option<String>,
+
+    #[structopt(long = "include-tests")]
+    /// Also require tests to be free of unsafety
+    include_tests: bool,
+
+    #[structopt(long = "output", short = "o", value_name = "PATH")]
+    /// Where to write the attestation (by default, stdout)
+    output: Option<PathBuf>,
+}
+
+/// What the attestation claims, and about which sources
+#[derive(Debug, Serialize)]
+struct Statement {
+    tool: &'static str,
+
+    tool_version: &'static str,
+
+    package: String,
+
+    version: String,
+
+    /// The commit checked out when the package was scanned, if it is in a git repository
+    commit: Option<String>,
+
+    /// Whether the package had uncommitted changes
+    dirty: bool,
+
+    /// `sha256:` digest of the paths and contents of the scanned files
+    checksum: String,
+
+    files: usize,
+
+    unsafe_items: usize,
+
+    /// Whether every crate root of the package has `#![forbid(unsafe_code)]`
+    forbid_unsafe_code: bool,
+
+    created: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Attestation {
+    #[serde(flatten)]
+    statement: Statement,
+
+    /// `sha256:` digest of the JSON serialization of the statement, so that edits to a published
+    /// attestation can be detected
+    digest: String,
+}
+
+pub fn run(opts: &AttestOpts) -> anyhow::Result<()> {
+    let config = cargo::Config::default()?;
+    let manifest = find_root_manifest_for_wd(config.cwd())?;
+    let ws = Workspace::new(&manifest, &config)?;
+    let pack = find_package(&ws, opts.package.as_deref())?;
+
+    let scan = trawl_source::scan_package(pack, opts.include_tests)?;
+    if scan.unsafe_items > 0 || !scan.roots_without_forbid.is_empty() {
+        let mut reasons = Vec::new();
+        if scan.unsafe_items > 0 {
+            reasons.push(format!("it contains {} unsafe items", scan.unsafe_items));
+        }
+        for root in &scan.roots_without_forbid {
+            reasons.push(format!(
+                "`{}` does not `#![forbid(unsafe_code)]`",
+                root.display()
+            ));
+        }
+        return Err(Failure::new(
+            Code::OverThreshold,
+            format!(
+                "`{}` cannot be attested unsafe-free: {}",
+                pack.name(),
+                reasons.join(", ")
+            ),
+        ));
+    }
+
+    let (commit, dirty) = git::commit(pack.root());
+    if dirty {
+        eprintln!(
+            "Warning: `{}` has uncommitted changes, so the attestation does not match its commit",
+            pack.name()
+        );
+    }
+
+    let mut hasher = Sha256::new();
+    for (path, digest) in &scan.files {
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update([0u8]);
+        hasher.update(digest);
+    }
+    let statement = Statement {
+        tool: env!("CARGO_PKG_NAME"),
+        tool_version: env!("CARGO_PKG_VERSION"),
+        package: pack.name().to_string(),
+        version: pack.version().to_string(),
+        commit,
+        dirty,
+        checksum: sha256(&hasher.finalize()),
+        files: scan.files.len(),
+        unsafe_items: scan.unsafe_items,
+        forbid_unsafe_code: true,
+        created: chrono::Utc::now().to_rfc3339(),
+    };
+    let digest = sha256(&Sha256::digest(serde_json::to_vec(&statement)?));
+    let attestation = Attestation { statement, digest };
+
+    let mut json = serde_json::to_vec_pretty(&attestation)?;
+    json.push(b'\n');
+    match &opts.output {
+        Some(path) => {
+            fs::write(path, json).with_context(|| format!("Could not write `{}`", path.display()))
+        }
+        None => Ok(io::stdout().lock().write_all(&json)?),
+    }
+}
+
+/// The workspace member `name`, or the current package
+pub(super) fn find_package<'ws>(
+    ws: &'ws Workspace,
+    name: Option<&str>,
+) -> anyhow::Result<&'ws Package> {
+    match name {
+        Some(name) => ws
+            .members()
+            .find(|package| package.name() == name)
+            .ok_or_else(|| anyhow!("Could not find package `{}`", name)),
+        None => Ok(ws.current()?),
+    }
+}
+
+fn sha256(digest: &[u8]) -> String {
+    let hex = digest
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    format!("sha256:{hex}")
+}
@@ -0,0 +1,284 @@
+use std::{
+    collections::HashSet,
+    ffi::OsStr,
+    fs,
+    path::{Component, Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, bail, Context};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use crate::{git::git, report::Report};
+
+#[derive(StructOpt, Debug)]
+pub struct BatchOpts {
+    #[structopt(long = "repos", value_name = "PATH")]
+    /// TOML file listing the repositories to analyze, as `[[repo]]` tables with a `url` and
+    /// optionally a `name`, `ref`, `path`, `package` and `args`
+    repos: PathBuf,
+
+    #[structopt(
+        long = "output",
+        short = "o",
+        value_name = "DIR",
+        default_value = "siderophile-batch"
+    )]
+    /// Where to keep the clones, and write the report of each repository and `index.json`
+    output: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct Repos {
+    #[serde(default)]
+    repo: Vec<Repo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Repo {
+    url: String,
+
+    /// Names the directories of the repository (by default, the last component of the URL)
+    name: Option<String>,
+
+    /// Branch, tag or commit to analyze (by default, the remote's `HEAD`)
+    #[serde(rename = "ref")]
+    reference: Option<String>,
+
+    /// Directory to analyze, relative to the root of the repository
+    path: Option<PathBuf>,
+
+    package: Option<String>,
+
+    /// Additional arguments to siderophile, e.g., `["--include-tests"]`
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+impl Repo {
+    fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            let url = self.url.trim_end_matches('/');
+            let last = url.rsplit(['/', ':']).next().unwrap_or(url);
+            last.trim_end_matches(".git").to_string()
+        })
+    }
+}
+
+/// One line of `index.json`
+#[derive(Debug, Serialize)]
+struct IndexEntry {
+    name: String,
+
+    url: String,
+
+    /// The commit analyzed, if the repository could be fetched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
+
+    /// The JSON report, relative to the output directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report: Option<PathBuf>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    crates: Option<usize>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    findings: Option<usize>,
+
+    /// The highest badness of a function of the analyzed crate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_badness: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub fn run(opts: &BatchOpts) -> anyhow::Result<()> {
+    let contents = fs::read_to_string(&opts.repos)
+        .with_context(|| format!("Could not read `{}`", opts.repos.display()))?;
+    let repos: Repos = toml::from_str(&contents)
+        .with_context(|| format!("Could not parse `{}`", opts.repos.display()))?;
+    let mut names = HashSet::new();
+    for repo in &repos.repo {
+        // The name is joined to the output directory.
+        let name = repo.name();
+        if name.is_empty() || name == "." || name == ".." || name.contains(std::path::is_separator)
+        {
+            bail!(
+                "`{}` cannot name the directories of `{}`; give it another `name`",
+                name,
+                repo.url
+            );
+        }
+        // The path is joined to the checkout, and must stay inside of it.
+        if let Some(path) = &repo.path {
+            if !path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+            {
+                bail!(
+                    "The `path` of `{}` must be relative to its repository, without `..`: `{}`",
+                    repo.url,
+                    path.display()
+                );
+            }
+        }
+        if !names.insert(name.clone()) {
+            bail!(
+                "Several repositories are named `{}`; give them distinct `name`s",
+                name
+            );
+        }
+    }
+
+    let checkouts = opts.output.join("checkouts");
+    fs::create_dir_all(&checkouts)
+        .with_context(|| format!("Could not create `{}`", checkouts.display()))?;
+    let mut index = Vec::new();
+    // One repository failing does not keep the others from being analyzed.
+    for repo in &repos.repo {
+        let name = repo.name();
+        eprintln!("Analyzing `{name}`");
+        let mut entry = IndexEntry {
+            name: name.clone(),
+            url: repo.url.clone(),
+            commit: None,
+            report: None,
+            crates: None,
+            findings: None,
+            max_badness: None,
+            error: None,
+        };
+        let checkout = checkouts.join(&name);
+        let result = update(repo, &checkout).and_then(|commit| {
+            entry.commit = Some(commit);
+            analyze(repo, &checkout, &opts.output.join(&name))
+        });
+        match result {
+            Ok(report) => {
+                entry.report = Some(Path::new(&name).join("report.json"));
+                entry.crates = Some(report.crates.len());
+                entry.findings = Some(report.findings.len());
+                entry.max_badness = Some(
+                    report
+                        .badness
+                        .first()
+                        .map(|entry| entry.badness)
+                        .unwrap_or_default(),
+                );
+            }
+            Err(e) => {
+                eprintln!("Warning: Could not analyze `{name}`: {e:?}");
+                entry.error = Some(format!("{e:?}"));
+            }
+        }
+        index.push(entry);
+    }
+
+    let path = opts.output.join("index.json");
+    let mut json = serde_json::to_vec_pretty(&index)?;
+    json.push(b'\n');
+    fs::write(&path, json).with_context(|| format!("Could not write `{}`", path.display()))?;
+
+    for entry in &index {
+        match entry.findings {
+            Some(findings) => println!(
+                "{}: {} findings in {} crates, max badness {}",
+                entry.name,
+                findings,
+                entry.crates.unwrap_or_default(),
+                entry.max_badness.unwrap_or_default()
+            ),
+            None => println!("{}: failed", entry.name),
+        }
+    }
+    let failed = index.iter().filter(|entry| entry.error.is_some()).count();
+    if failed > 0 {
+        bail!(
+            "{} of {} repositories could not be analyzed (see `{}`)",
+            failed,
+            index.len(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Clones `repo` into `checkout`, or fetches it if it was cloned by an earlier run, checks out the
+/// ref to analyze, and returns its commit
+fn update(repo: &Repo, checkout: &Path) -> anyhow::Result<String> {
+    if !checkout.join(".git").exists() {
+        git(&[
+            OsStr::new("clone"),
+            OsStr::new("--quiet"),
+            // So that a URL starting with `-` is not taken for an option
+            OsStr::new("--"),
+            OsStr::new(&repo.url),
+            checkout.as_os_str(),
+        ])?;
+    }
+    let dir = checkout.as_os_str();
+    git(&[
+        OsStr::new("-C"),
+        dir,
+        OsStr::new("fetch"),
+        OsStr::new("--quiet"),
+        // Nor a ref
+        OsStr::new("--"),
+        OsStr::new("origin"),
+        OsStr::new(repo.reference.as_deref().unwrap_or("HEAD")),
+    ])?;
+    git(&[
+        OsStr::new("-C"),
+        dir,
+        OsStr::new("checkout"),
+        OsStr::new("--quiet"),
+        OsStr::new("--force"),
+        OsStr::new("--detach"),
+        OsStr::new("FETCH_HEAD"),
+    ])?;
+    let commit = git(&[
+        OsStr::new("-C"),
+        dir,
+        OsStr::new("rev-parse"),
+        OsStr::new("HEAD"),
+    ])?;
+    Ok(commit.trim().to_string())
+}
+
+/// Runs siderophile in the checkout of `repo`, and writes its JSON report and log to `artifacts`
+fn analyze(repo: &Repo, checkout: &Path, artifacts: &Path) -> anyhow::Result<Report> {
+    fs::create_dir_all(artifacts)
+        .with_context(|| format!("Could not create `{}`", artifacts.display()))?;
+    let dir = match &repo.path {
+        Some(path) => checkout.join(path),
+        None => checkout.to_path_buf(),
+    };
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .current_dir(&dir)
+        .args(["--format", "json"])
+        .args(&repo.args);
+    if let Some(package) = &repo.package {
+        command.arg("--package").arg(package);
+    }
+    let output = command.output()?;
+    let log = artifacts.join("log.txt");
+    fs::write(&log, &output.stderr)
+        .with_context(|| format!("Could not write `{}`", log.display()))?;
+    let report: Report = serde_json::from_slice(&output.stdout).map_err(|_| {
+        anyhow!(
+            "siderophile failed in `{}` (see `{}`)",
+            dir.display(),
+            log.display()
+        )
+    })?;
+    // A report is written before a ratchet, limit or policy fails the run, and is kept.
+    let path = artifacts.join("report.json");
+    fs::write(&path, &output.stdout)
+        .with_context(|| format!("Could not write `{}`", path.display()))?;
+    Ok(report)
+}
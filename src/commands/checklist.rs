@@ -0,0 +1,184 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use regex::Regex;
+use structopt::StructOpt;
+
+use crate::report::{CrateSummary, Finding, Report};
+
+#[derive(StructOpt, Debug)]
+pub struct ChecklistOpts {
+    #[structopt(long = "report", value_name = "PATH")]
+    /// JSON report to make the checklist from (by default, the current crate is analyzed)
+    report: Option<PathBuf>,
+
+    #[structopt(long = "output", short = "o", value_name = "PATH")]
+    /// Checklist to write. Items already checked in an existing checklist stay checked.
+    output: PathBuf,
+}
+
+pub fn run(
+    opts: &ChecklistOpts,
+    analyze: &dyn Fn() -> anyhow::Result<Report>,
+) -> anyhow::Result<()> {
+    let report = if let Some(path) = &opts.report {
+        Report::read(path)?
+    } else {
+        analyze()?
+    };
+
+    let previous = if opts.output.exists() {
+        fs::read_to_string(&opts.output)
+            .with_context(|| format!("Could not read `{}`", opts.output.display()))?
+    } else {
+        String::new()
+    };
+    let previous = parse_checklist(&previous)?;
+
+    let mut checklist = Vec::new();
+    write_checklist(&mut checklist, &report, &previous)?;
+    fs::write(&opts.output, checklist)
+        .with_context(|| format!("Could not write `{}`", opts.output.display()))
+}
+
+/// Writes a checklist of the findings of `report` with nothing checked yet
+pub(super) fn write_new_checklist(out: &mut dyn Write, report: &Report) -> anyhow::Result<()> {
+    write_checklist(out, report, &BTreeMap::new())
+}
+
+/// An item of a checklist written by a previous run
+struct PreviousItem {
+    checked: bool,
+
+    /// The rest of the line after the checkbox
+    line: String,
+}
+
+fn parse_checklist(checklist: &str) -> anyhow::Result<BTreeMap<String, PreviousItem>> {
+    let re = Regex::new(r"^\s*- \[([ xX])\] (`([0-9a-f]+)`.*)$")?;
+    Ok(checklist
+        .lines()
+        .filter_map(|line| re.captures(line))
+        .map(|caps| {
+            (
+                caps[3].to_string(),
+                PreviousItem {
+                    checked: &caps[1] != " ",
+                    line: caps[2].to_string(),
+                },
+            )
+        })
+        .collect())
+}
+
+fn write_checklist(
+    out: &mut dyn Write,
+    report: &Report,
+    previous: &BTreeMap<String, PreviousItem>,
+) -> anyhow::Result<()> {
+    let mut by_crate = BTreeMap::<_, Vec<_>>::new();
+    for finding in &report.findings {
+        by_crate.entry(&finding.krate).or_default().push(finding);
+    }
+
+    writeln!(out, "# Unsafety audit checklist")?;
+    for (krate, findings) in by_crate {
+        writeln!(out)?;
+        writeln!(out, "## `{}` ({})", krate, findings.len())?;
+        writeln!(out)?;
+        // One line per version, joined by hard line breaks
+        let versions = report
+            .crates
+            .iter()
+            .filter(|summary| *summary.name == **krate)
+            .map(describe_crate)
+            .collect::<Vec<_>>();
+        if !versions.is_empty() {
+            writeln!(out, "{}", versions.join("  \n"))?;
+            writeln!(out)?;
+        }
+        for finding in findings {
+            let checked = previous
+                .get(&finding.fingerprint)
+                .is_some_and(|item| item.checked);
+            writeln!(
+                out,
+                "- [{}] {}",
+                if checked { 'x' } else { ' ' },
+                describe(finding)
+            )?;
+        }
+    }
+
+    let current = report
+        .findings
+        .iter()
+        .map(|finding| finding.fingerprint.as_str())
+        .collect::<HashSet<_>>();
+    let gone = previous
+        .iter()
+        .filter(|(fingerprint, _)| !current.contains(fingerprint.as_str()))
+        .collect::<Vec<_>>();
+    if !gone.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "## No longer present ({})", gone.len())?;
+        writeln!(out)?;
+        for (_, item) in gone {
+            writeln!(
+                out,
+                "- [{}] {}",
+                if item.checked { 'x' } else { ' ' },
+                item.line
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// E.g., ``Version `0.2.1` (latest `0.3.0`), license `MIT`, [repository](https://github.com/o/r)``
+fn describe_crate(summary: &CrateSummary) -> String {
+    let mut description = format!("Version `{}`", summary.version);
+    if let Some(latest) = summary
+        .latest_version
+        .as_ref()
+        .filter(|latest| **latest != summary.version)
+    {
+        description += &format!(" (latest `{}`)", latest);
+    }
+    if let Some(license) = &summary.license {
+        description += &format!(", license `{}`", license);
+    }
+    if let Some(repository) = &summary.repository {
+        description += &format!(", [repository]({})", repository);
+    }
+    description
+}
+
+fn describe(finding: &Finding) -> String {
+    let file = relative(&finding.file);
+    format!(
+        "`{}` `{}` ({}, {}) at [{}:{}]({}#L{})",
+        finding.fingerprint,
+        finding.item,
+        finding.describe_kind(),
+        finding.provenance.version,
+        file.display(),
+        finding.line,
+        file.display(),
+        finding.line
+    )
+}
+
+/// `path` relative to the current directory, if it is below it
+fn relative(path: &Path) -> PathBuf {
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf())
+}
@@ -0,0 +1,146 @@
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use anyhow::anyhow;
+use structopt::StructOpt;
+
+use crate::{
+    diff,
+    report::{Finding, Report},
+};
+
+#[derive(StructOpt, Debug)]
+pub struct CommentOpts {
+    #[structopt(long = "base", value_name = "PATH")]
+    /// JSON report of the base of the pull request
+    base: PathBuf,
+
+    #[structopt(long = "head", value_name = "PATH")]
+    /// JSON report of the head of the pull request
+    head: PathBuf,
+
+    #[structopt(long = "format", value_name = "FORMAT", default_value = "github-pr")]
+    /// Comment format (currently only `github-pr`)
+    format: CommentFormat,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum CommentFormat {
+    /// GitHub-flavored Markdown with collapsed details
+    GithubPr,
+}
+
+impl FromStr for CommentFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "github-pr" => Ok(Self::GithubPr),
+            _ => Err(anyhow!("Unknown comment format `{}`", s)),
+        }
+    }
+}
+
+pub fn run(opts: &CommentOpts) -> anyhow::Result<()> {
+    let base = Report::read(&opts.base)?;
+    let head = Report::read(&opts.head)?;
+    diff::warn_if_incomparable(&base, &head);
+    let diff = diff::diff(&base, &head);
+
+    write_comment(opts.format, &mut io::stdout().lock(), &diff)
+}
+
+pub fn write_comment(
+    format: CommentFormat,
+    out: &mut dyn Write,
+    diff: &diff::ReportDiff,
+) -> anyhow::Result<()> {
+    match format {
+        CommentFormat::GithubPr => write_github_pr(out, diff),
+    }
+}
+
+fn write_github_pr(out: &mut dyn Write, diff: &diff::ReportDiff) -> anyhow::Result<()> {
+    writeln!(out, "### Siderophile unsafety report")?;
+    writeln!(out)?;
+    if diff.added.is_empty() && diff.removed.is_empty() {
+        writeln!(out, "No unsafety was introduced or removed.")?;
+        return write_moved(out, &diff.moved);
+    }
+    writeln!(out, "| | Findings |")?;
+    writeln!(out, "|---|---:|")?;
+    writeln!(out, "| Introduced | {} |", diff.added.len())?;
+    writeln!(out, "| Removed | {} |", diff.removed.len())?;
+    writeln!(out, "| Moved | {} |", diff.moved.len())?;
+    write_details(out, "Introduced unsafety", &diff.added)?;
+    write_details(out, "Removed unsafety", &diff.removed)?;
+    write_moved(out, &diff.moved)
+}
+
+/// Findings that only moved are listed apart, so that a change that reshuffles code does not look
+/// like a regression
+fn write_moved(out: &mut dyn Write, moved: &[(&Finding, &Finding)]) -> anyhow::Result<()> {
+    if moved.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(
+        out,
+        "<details><summary>Moved unsafety ({})</summary>",
+        moved.len()
+    )?;
+    writeln!(out)?;
+    writeln!(out, "| Crate | Item | Kind | From | To |")?;
+    writeln!(out, "|---|---|---|---|---|")?;
+    for (from, to) in moved {
+        writeln!(
+            out,
+            "| `{} {}` | `{}` | {} | `{}:{}` | `{}:{}` |",
+            to.krate,
+            to.provenance.version,
+            to.item,
+            to.describe_kind(),
+            from.file.display(),
+            from.line,
+            to.file.display(),
+            to.line
+        )?;
+    }
+    writeln!(out)?;
+    writeln!(out, "</details>")?;
+    Ok(())
+}
+
+fn write_details(out: &mut dyn Write, summary: &str, findings: &[&Finding]) -> anyhow::Result<()> {
+    if findings.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(
+        out,
+        "<details><summary>{} ({})</summary>",
+        summary,
+        findings.len()
+    )?;
+    writeln!(out)?;
+    writeln!(out, "| Crate | Item | Kind | Location |")?;
+    writeln!(out, "|---|---|---|---|")?;
+    for finding in findings {
+        writeln!(
+            out,
+            "| `{} {}` | `{}` | {} | `{}:{}` |",
+            finding.krate,
+            finding.provenance.version,
+            finding.item,
+            finding.describe_kind(),
+            finding.file.display(),
+            finding.line
+        )?;
+    }
+    writeln!(out)?;
+    writeln!(out, "</details>")?;
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use std::{
+    ffi::OsStr,
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{bail, Context};
+use structopt::StructOpt;
+use tempfile::tempdir;
+
+use super::comment::{write_comment, CommentFormat};
+use crate::{diff, git::git, report::Report};
+
+#[derive(StructOpt, Debug)]
+pub struct CompareOpts {
+    #[structopt(long = "base", value_name = "REF")]
+    /// Git ref of the base
+    base: String,
+
+    #[structopt(long = "head", value_name = "REF", default_value = "HEAD")]
+    /// Git ref of the head
+    head: String,
+
+    #[structopt(long = "package", short = "p", value_name = "SPEC")]
+    /// Package to be used as the root of the tree
+    package: Option<String>,
+
+    #[structopt(long = "format", value_name = "FORMAT", default_value = "github-pr")]
+    /// Comment format (currently only `github-pr`)
+    format: CommentFormat,
+
+    #[structopt(last = true, value_name = "ARGS")]
+    /// Options for the analysis of each ref, after `--`, e.g., `-- --include-tests`
+    args: Vec<String>,
+}
+
+pub fn run(opts: &CompareOpts) -> anyhow::Result<()> {
+    // The directory we are in, relative to the root of the repository, so that the same directory
+    // can be analyzed in each worktree.
+    let prefix = git(&[OsStr::new("rev-parse"), OsStr::new("--show-prefix")])?;
+    let prefix = PathBuf::from(prefix.trim_end());
+
+    let base = scan_ref(&opts.base, &prefix, opts)?;
+    let head = scan_ref(&opts.head, &prefix, opts)?;
+    diff::warn_if_incomparable(&base, &head);
+    let diff = diff::diff(&base, &head);
+
+    write_comment(opts.format, &mut io::stdout().lock(), &diff)
+}
+
+/// Checks out `reference` in a temporary worktree and analyzes it
+fn scan_ref(reference: &str, prefix: &Path, opts: &CompareOpts) -> anyhow::Result<Report> {
+    let tempdir = tempdir()?;
+    let worktree = tempdir.path().join("worktree");
+    git(&[
+        OsStr::new("worktree"),
+        OsStr::new("add"),
+        OsStr::new("--detach"),
+        OsStr::new("--"),
+        worktree.as_os_str(),
+        OsStr::new(reference),
+    ])?;
+    let result = scan(&worktree.join(prefix), opts)
+        .with_context(|| format!("Failed to analyze `{reference}`"));
+    let removed = git(&[
+        OsStr::new("worktree"),
+        OsStr::new("remove"),
+        OsStr::new("--force"),
+        worktree.as_os_str(),
+    ]);
+    match (result, removed) {
+        // The failed scan is what is worth reporting.
+        (Err(e), Err(cleanup)) => {
+            eprintln!(
+                "Warning: Could not remove the worktree of `{reference}` at `{}`: {cleanup:?}",
+                worktree.display()
+            );
+            Err(e)
+        }
+        (result, removed) => removed.and(result),
+    }
+}
+
+/// Runs siderophile in `dir` with the options of `opts` and reads back its JSON report
+fn scan(dir: &Path, opts: &CompareOpts) -> anyhow::Result<Report> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .current_dir(dir)
+        .args(["--format", "json"])
+        .args(&opts.args);
+    if let Some(package) = &opts.package {
+        command.arg("--package").arg(package);
+    }
+    let output = command.output()?;
+    if !output.status.success() {
+        bail!(
+            "siderophile failed in `{}`:\n{}",
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    serde_json::from_slice(&output.stdout).map_err(Into::into)
+}
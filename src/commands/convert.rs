@@ -0,0 +1,58 @@
+use std::{fs, io, path::PathBuf};
+
+use anyhow::Context;
+use structopt::StructOpt;
+
+use crate::{
+    filter::FilterOpts,
+    output::{self, protobuf, Format},
+    report::Report,
+};
+
+#[derive(StructOpt, Debug)]
+pub struct ConvertOpts {
+    #[structopt(long = "format", value_name = "FORMAT", default_value = "json")]
+    /// Output format
+    format: Format,
+
+    #[structopt(flatten)]
+    html_opts: output::html::HtmlOpts,
+
+    #[structopt(flatten)]
+    tree_opts: output::tree::TreeOpts,
+
+    #[structopt(flatten)]
+    text_opts: output::TextOpts,
+
+    #[structopt(flatten)]
+    sqlite_opts: output::sqlite::SqliteOpts,
+
+    #[structopt(flatten)]
+    filter_opts: FilterOpts,
+
+    #[structopt(value_name = "REPORT")]
+    /// Report written with `--format json` or `--format protobuf`
+    report: PathBuf,
+}
+
+pub fn run(opts: &ConvertOpts) -> anyhow::Result<()> {
+    let bytes = fs::read(&opts.report)
+        .with_context(|| format!("Could not read `{}`", opts.report.display()))?;
+    // A JSON report is an object. A protobuf report never starts with `{`, which would be the
+    // key of a field 15.
+    let report = if bytes.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{') {
+        Report::read(&opts.report)?
+    } else {
+        protobuf::decode(&bytes)
+            .with_context(|| format!("Could not decode `{}`", opts.report.display()))?
+    };
+    output::write_report(
+        opts.format,
+        &opts.html_opts,
+        &opts.tree_opts,
+        &opts.text_opts,
+        &opts.sqlite_opts,
+        &opts.filter_opts.apply(&report, None),
+        &mut io::stdout().lock(),
+    )
+}
@@ -0,0 +1,224 @@
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail, Context};
+use cargo::core::{PackageId, SourceId};
+use serde::Serialize;
+use structopt::StructOpt;
+
+use crate::{
+    diff,
+    error::OnError,
+    output::Format,
+    report::{Finding, Report},
+    trawl_source,
+};
+
+#[derive(StructOpt, Debug)]
+pub struct CrateDiffOpts {
+    #[structopt(long = "include-tests")]
+    /// Count unsafe usage in tests
+    include_tests: bool,
+
+    #[structopt(long = "format", value_name = "FORMAT", default_value = "text")]
+    /// Output format: text, json or yaml
+    format: Format,
+
+    #[structopt(value_name = "BASE")]
+    /// Version of a crate on crates.io to compare from, as `NAME@VERSION`
+    base: CrateVersion,
+
+    #[structopt(value_name = "HEAD")]
+    /// Version to compare to, as `NAME@VERSION`
+    head: CrateVersion,
+}
+
+#[derive(Clone, Debug)]
+struct CrateVersion {
+    name: String,
+    version: String,
+}
+
+impl FromStr for CrateVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.split_once('@') {
+            Some((name, version)) if !name.is_empty() && !version.is_empty() => Ok(Self {
+                name: name.to_string(),
+                version: version.to_string(),
+            }),
+            _ => Err(anyhow!("Expected `NAME@VERSION`, got `{}`", s)),
+        }
+    }
+}
+
+/// A finding in both versions that moved or changed
+#[derive(Debug, Serialize)]
+struct ChangedFinding<'a> {
+    base: &'a Finding,
+
+    head: &'a Finding,
+
+    /// Whether it is at another file or line
+    moved: bool,
+
+    /// Whether its `cfg` conditions, `_unchecked` calls, tags, kind or hook differ
+    changed: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct CrateDiff<'a> {
+    base: String,
+
+    head: String,
+
+    base_unsafe_items: usize,
+
+    head_unsafe_items: usize,
+
+    added: Vec<&'a Finding>,
+
+    removed: Vec<&'a Finding>,
+
+    changed: Vec<ChangedFinding<'a>>,
+}
+
+pub fn run(opts: &CrateDiffOpts) -> anyhow::Result<()> {
+    let config = cargo::Config::default()?;
+    let source_id = SourceId::crates_io(&config)?;
+    // The index only needs to be brought up to date once.
+    let base = scan(&config, source_id, &opts.base, true, opts.include_tests)?;
+    let head = scan(&config, source_id, &opts.head, false, opts.include_tests)?;
+    let diff = diff::diff(&base, &head);
+
+    let mut changed = diff
+        .moved
+        .iter()
+        .map(|&(base, head)| ChangedFinding {
+            base,
+            head,
+            moved: true,
+            changed: false,
+        })
+        .collect::<Vec<_>>();
+    for &(base, head) in &diff.changed {
+        match changed
+            .iter_mut()
+            .find(|existing| std::ptr::eq(existing.head, head))
+        {
+            Some(existing) => existing.changed = true,
+            None => changed.push(ChangedFinding {
+                base,
+                head,
+                moved: false,
+                changed: true,
+            }),
+        }
+    }
+    changed.sort_by(|a, b| a.head.location_key().cmp(&b.head.location_key()));
+    let crate_diff = CrateDiff {
+        base: format!("{}@{}", opts.base.name, opts.base.version),
+        head: format!("{}@{}", opts.head.name, opts.head.version),
+        base_unsafe_items: base.findings.len(),
+        head_unsafe_items: head.findings.len(),
+        added: diff.added,
+        removed: diff.removed,
+        changed,
+    };
+
+    let mut out = io::stdout().lock();
+    match opts.format {
+        Format::Text => write_text(&crate_diff, &mut out)?,
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, &crate_diff)?;
+            writeln!(out)?;
+        }
+        Format::Yaml => serde_yaml::to_writer(out, &crate_diff)?,
+        format => bail!("The {format} format is only for reports"),
+    }
+    Ok(())
+}
+
+/// Downloads `version` from crates.io into the cargo cache and scans its `.crate` file
+fn scan(
+    config: &cargo::Config,
+    source_id: SourceId,
+    version: &CrateVersion,
+    update_index: bool,
+    include_tests: bool,
+) -> anyhow::Result<Report> {
+    eprintln!("Scanning `{}` {}", version.name, version.version);
+    let id = PackageId::new(version.name.as_str(), version.version.as_str(), source_id)?;
+    let mut source = source_id.load(config, &HashSet::new())?;
+    if update_index {
+        source.invalidate_cache();
+    }
+    let pack = source
+        .download_now(id, config)
+        .with_context(|| format!("Could not download `{}` {}", version.name, version.version))?;
+    let archive = trawl_source::cached_crate_file(&pack).ok_or_else(|| {
+        anyhow!(
+            "Could not find the `.crate` file of `{}` {}",
+            version.name,
+            version.version
+        )
+    })?;
+    let unpacked = trawl_source::unpack(&archive)?;
+    super::scan_archive::scan(&unpacked, source_id, OnError::Fail, include_tests)
+}
+
+fn write_text(diff: &CrateDiff, out: &mut dyn Write) -> anyhow::Result<()> {
+    writeln!(
+        out,
+        "{} -> {}: {} -> {} unsafe items",
+        diff.base, diff.head, diff.base_unsafe_items, diff.head_unsafe_items
+    )?;
+    for (heading, findings) in [("Added", &diff.added), ("Removed", &diff.removed)] {
+        if findings.is_empty() {
+            continue;
+        }
+        writeln!(out)?;
+        writeln!(out, "{} ({})", heading, findings.len())?;
+        for finding in findings.iter() {
+            writeln!(
+                out,
+                "    {}:{}  {}  {}",
+                finding.file.display(),
+                finding.line,
+                finding.kind,
+                finding.item
+            )?;
+        }
+    }
+    if !diff.changed.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "Changed ({})", diff.changed.len())?;
+        for changed in &diff.changed {
+            let mut what = Vec::new();
+            if changed.moved {
+                what.push(format!(
+                    "moved from {}:{}",
+                    changed.base.file.display(),
+                    changed.base.line
+                ));
+            }
+            if changed.changed {
+                what.push("changed".to_string());
+            }
+            writeln!(
+                out,
+                "    {}:{}  {}  {}  ({})",
+                changed.head.file.display(),
+                changed.head.line,
+                changed.head.kind,
+                changed.head.item,
+                what.join(", ")
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+};
+
+use anyhow::{bail, Context};
+use cargo::core::{PackageId, SourceId};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use crate::{crates_io, output::Format, trawl_source};
+
+/// The most crates.io returns per page
+const PER_PAGE: usize = 100;
+
+#[derive(StructOpt, Debug)]
+pub struct DependentsOpts {
+    #[structopt(long = "top", value_name = "N", default_value = "10")]
+    /// Number of reverse dependencies to scan, most downloaded first
+    top: usize,
+
+    #[structopt(long = "include-tests")]
+    /// Count unsafe usage in tests
+    include_tests: bool,
+
+    #[structopt(long = "format", value_name = "FORMAT", default_value = "text")]
+    /// Output format: text, json or yaml
+    format: Format,
+
+    #[structopt(value_name = "CRATE")]
+    /// Crate on crates.io whose reverse dependencies to scan
+    krate: String,
+}
+
+#[derive(Deserialize)]
+struct ReverseDependencies {
+    dependencies: Vec<ReverseDependency>,
+    versions: Vec<Version>,
+}
+
+#[derive(Deserialize)]
+struct ReverseDependency {
+    version_id: u64,
+    downloads: u64,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    id: u64,
+    #[serde(rename = "crate")]
+    krate: String,
+    num: String,
+}
+
+/// One reverse dependency, scanned on its own
+#[derive(Debug, Serialize)]
+struct Dependent {
+    name: String,
+
+    /// The newest version that depends on the crate
+    version: String,
+
+    downloads: u64,
+
+    /// `None` if the package could not be downloaded or scanned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unsafe_items: Option<usize>,
+
+    /// Whether every crate root of the package has `#![forbid(unsafe_code)]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    forbids_unsafe_code: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Dependents {
+    #[serde(rename = "crate")]
+    krate: String,
+
+    /// By descending downloads
+    dependents: Vec<Dependent>,
+
+    /// The unsafe items of the dependents scanned, in total
+    unsafe_items: usize,
+}
+
+pub fn run(opts: &DependentsOpts) -> anyhow::Result<()> {
+    let mut dependents = fetch_dependents(&opts.krate, opts.top)?;
+    if dependents.is_empty() {
+        bail!("`{}` has no reverse dependencies on crates.io", opts.krate);
+    }
+
+    let config = cargo::Config::default()?;
+    let source_id = SourceId::crates_io(&config)?;
+    for (i, dependent) in dependents.iter_mut().enumerate() {
+        eprintln!("Scanning `{}` {}", dependent.name, dependent.version);
+        // The index only needs to be brought up to date once.
+        match scan(&config, source_id, dependent, i == 0, opts.include_tests) {
+            Ok(scan) => {
+                dependent.unsafe_items = Some(scan.unsafe_items);
+                dependent.forbids_unsafe_code = Some(scan.roots_without_forbid.is_empty());
+            }
+            Err(e) => {
+                eprintln!(
+                    "Warning: Could not scan `{}` {}: {:?}",
+                    dependent.name, dependent.version, e
+                );
+                dependent.error = Some(format!("{e:?}"));
+            }
+        }
+    }
+    let dependents = Dependents {
+        krate: opts.krate.clone(),
+        unsafe_items: dependents
+            .iter()
+            .filter_map(|dependent| dependent.unsafe_items)
+            .sum(),
+        dependents,
+    };
+
+    let mut out = io::stdout().lock();
+    match opts.format {
+        Format::Text => write_text(&dependents, &mut out)?,
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, &dependents)?;
+            writeln!(out)?;
+        }
+        Format::Yaml => serde_yaml::to_writer(out, &dependents)?,
+        format => bail!("The {format} format is only for reports"),
+    }
+    Ok(())
+}
+
+/// Downloads `dependent` from crates.io into the cargo cache and scans it
+fn scan(
+    config: &cargo::Config,
+    source_id: SourceId,
+    dependent: &Dependent,
+    update_index: bool,
+    include_tests: bool,
+) -> anyhow::Result<trawl_source::PackageScan> {
+    let id = PackageId::new(
+        dependent.name.as_str(),
+        dependent.version.as_str(),
+        source_id,
+    )?;
+    let mut source = source_id.load(config, &HashSet::new())?;
+    if update_index {
+        source.invalidate_cache();
+    }
+    let pack = source.download_now(id, config)?;
+    Ok(trawl_source::scan_package(&pack, include_tests)?)
+}
+
+/// The `top` most downloaded reverse dependencies of `krate`, not scanned yet. crates.io lists them
+/// by descending downloads.
+fn fetch_dependents(krate: &str, top: usize) -> anyhow::Result<Vec<Dependent>> {
+    let mut dependents = Vec::new();
+    let mut page = 1;
+    while dependents.len() < top {
+        let body = crates_io::get(&format!(
+            "crates/{krate}/reverse_dependencies?per_page={PER_PAGE}&page={page}"
+        ))?;
+        let response: ReverseDependencies = serde_json::from_slice(&body)
+            .with_context(|| format!("Could not parse the reverse dependencies of `{krate}`"))?;
+        if response.dependencies.is_empty() {
+            break;
+        }
+        let versions = response
+            .versions
+            .iter()
+            .map(|version| (version.id, version))
+            .collect::<HashMap<_, _>>();
+        for dependency in &response.dependencies {
+            let version = match versions.get(&dependency.version_id) {
+                Some(version) => version,
+                None => continue,
+            };
+            dependents.push(Dependent {
+                name: version.krate.clone(),
+                version: version.num.clone(),
+                downloads: dependency.downloads,
+                unsafe_items: None,
+                forbids_unsafe_code: None,
+                error: None,
+            });
+        }
+        page += 1;
+    }
+    dependents.truncate(top);
+    Ok(dependents)
+}
+
+fn write_text(dependents: &Dependents, out: &mut dyn Write) -> anyhow::Result<()> {
+    let scanned = dependents
+        .dependents
+        .iter()
+        .filter(|dependent| dependent.unsafe_items.is_some())
+        .collect::<Vec<_>>();
+    let forbidding = scanned
+        .iter()
+        .filter(|dependent| dependent.forbids_unsafe_code == Some(true))
+        .count();
+    writeln!(
+        out,
+        "{} of the most downloaded reverse dependencies of `{}` scanned: {} unsafe items, {} \
+         forbid unsafe code",
+        scanned.len(),
+        dependents.krate,
+        dependents.unsafe_items,
+        forbidding
+    )?;
+    writeln!(out)?;
+    writeln!(out, "   Downloads  Unsafe  Forbids  Crate")?;
+    for dependent in &dependents.dependents {
+        match (dependent.unsafe_items, dependent.forbids_unsafe_code) {
+            (Some(unsafe_items), Some(forbids)) => writeln!(
+                out,
+                "{:>12}  {:>6}  {:>7}  {} {}",
+                dependent.downloads,
+                unsafe_items,
+                if forbids { "yes" } else { "no" },
+                dependent.name,
+                dependent.version
+            )?,
+            _ => writeln!(
+                out,
+                "{:>12}  {:>6}  {:>7}  {} {} (not scanned)",
+                dependent.downloads, "-", "-", dependent.name, dependent.version
+            )?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use std::{path::Path, process::Command};
+
+use anyhow::{anyhow, bail};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct DoctorOpts {}
+
+/// The outcome of one check of the environment
+enum Check {
+    Ok(String),
+
+    /// Not needed for a basic run
+    Warn(String, &'static str),
+
+    Fail(String, &'static str),
+}
+
+pub fn run(_opts: &DoctorOpts) -> anyhow::Result<()> {
+    let checks = [
+        check_rustup(),
+        check_cargo(),
+        check_llvm(),
+        check_rust_src(),
+    ];
+
+    let mut failures = 0;
+    for check in &checks {
+        match check {
+            Check::Ok(message) => println!("[ok]   {message}"),
+            Check::Warn(message, remediation) => {
+                println!("[warn] {message}");
+                println!("       {remediation}");
+            }
+            Check::Fail(message, remediation) => {
+                failures += 1;
+                println!("[FAIL] {message}");
+                println!("       {remediation}");
+            }
+        }
+    }
+
+    if failures > 0 {
+        bail!("{} of {} checks failed", failures, checks.len());
+    }
+    Ok(())
+}
+
+/// Runs `program` with `args` and returns the first line of its stdout
+fn first_line(program: &str, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new(program).args(args).output()?;
+    if !output.status.success() {
+        bail!("`{} {}` failed", program, args.join(" "));
+    }
+    String::from_utf8(output.stdout)?
+        .lines()
+        .next()
+        .map(ToString::to_string)
+        .ok_or_else(|| anyhow!("`{} {}` printed nothing", program, args.join(" ")))
+}
+
+fn check_rustup() -> Check {
+    match first_line("rustup", &["show", "active-toolchain"]) {
+        Ok(toolchain) => Check::Ok(format!("rustup: active toolchain {toolchain}")),
+        Err(e) => Check::Fail(
+            format!("rustup: {e}"),
+            "Install rustup from https://rustup.rs; siderophile uses it to pick the toolchain.",
+        ),
+    }
+}
+
+fn check_cargo() -> Check {
+    match first_line("cargo", &["--version"]) {
+        Ok(version) => Check::Ok(format!("cargo: {version}")),
+        Err(e) => Check::Fail(
+            format!("cargo: {e}"),
+            "Make sure `cargo` is on the PATH, e.g., with `rustup default stable`.",
+        ),
+    }
+}
+
+/// The bitcode that rustc emits must be readable by the LLVM siderophile was built against
+fn check_llvm() -> Check {
+    let expected = llvm_ir::llvm_version();
+    let version = match rustc_version::version_meta() {
+        Ok(version) => version,
+        Err(e) => {
+            return Check::Fail(
+                format!("rustc: {e}"),
+                "Make sure `rustc` is on the PATH, e.g., with `rustup default stable`.",
+            )
+        }
+    };
+    match version.llvm_version {
+        Some(llvm) if llvm.major.to_string() == expected => Check::Ok(format!(
+            "rustc: {} emits LLVM {}, as siderophile expects",
+            version.semver, llvm.major
+        )),
+        Some(llvm) => Check::Fail(
+            format!(
+                "rustc: {} emits LLVM {}, but siderophile was built for LLVM {}",
+                version.semver, llvm.major, expected
+            ),
+            "Switch to a toolchain whose `rustc -vV` reports a matching LLVM version (e.g., with \
+             a `rust-toolchain` file), or rebuild siderophile against the LLVM that rustc uses.",
+        ),
+        None => Check::Fail(
+            format!("rustc: {} does not report its LLVM version", version.semver),
+            "Use an official rustup toolchain, which reports its LLVM version in `rustc -vV`.",
+        ),
+    }
+}
+
+fn check_rust_src() -> Check {
+    let sysroot = match first_line("rustc", &["--print", "sysroot"]) {
+        Ok(sysroot) => sysroot,
+        Err(e) => {
+            return Check::Warn(
+                format!("rust-src: could not find the sysroot: {e}"),
+                "Make sure `rustc` is on the PATH.",
+            )
+        }
+    };
+    if Path::new(&sysroot)
+        .join("lib/rustlib/src/rust/library")
+        .exists()
+    {
+        Check::Ok("rust-src: installed".to_string())
+    } else {
+        Check::Warn(
+            "rust-src: not installed (only needed to scan the standard library)".to_string(),
+            "Run `rustup component add rust-src`.",
+        )
+    }
+}
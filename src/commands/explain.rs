@@ -0,0 +1,122 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use anyhow::bail;
+use structopt::StructOpt;
+
+use crate::report::{Finding, Report};
+
+#[derive(StructOpt, Debug)]
+pub struct ExplainOpts {
+    #[structopt(long = "report", value_name = "PATH")]
+    /// JSON report containing the finding (by default, the current crate is analyzed)
+    report: Option<PathBuf>,
+
+    #[structopt(long = "context", value_name = "N", default_value = "3")]
+    /// Number of lines of source to show around the finding
+    context: usize,
+
+    #[structopt(value_name = "FINGERPRINT")]
+    /// Fingerprint of the finding, or a prefix of it
+    fingerprint: String,
+}
+
+pub fn run(opts: &ExplainOpts, analyze: &dyn Fn() -> anyhow::Result<Report>) -> anyhow::Result<()> {
+    let report = if let Some(path) = &opts.report {
+        Report::read(path)?
+    } else {
+        analyze()?
+    };
+
+    let findings = report
+        .findings
+        .iter()
+        .filter(|finding| finding.fingerprint.starts_with(&opts.fingerprint))
+        .collect::<Vec<_>>();
+    if findings.is_empty() {
+        bail!("No finding has fingerprint `{}`", opts.fingerprint);
+    }
+
+    let mut out = io::stdout().lock();
+    for (i, finding) in findings.into_iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        explain(&mut out, &report, finding, opts.context)?;
+    }
+    Ok(())
+}
+
+fn explain(
+    out: &mut dyn Write,
+    report: &Report,
+    finding: &Finding,
+    context: usize,
+) -> anyhow::Result<()> {
+    let provenance = &finding.provenance;
+    writeln!(out, "Finding   {}", finding.fingerprint)?;
+    writeln!(out, "Crate     {} {}", finding.krate, provenance.version)?;
+    if !provenance.source.is_empty() {
+        writeln!(out, "Source    {}", provenance.source)?;
+    }
+    if let Some(checksum) = &provenance.checksum {
+        writeln!(out, "Checksum  {}", checksum)?;
+    }
+    writeln!(out, "Item      {}", finding.item)?;
+    writeln!(out, "Kind      {}", finding.describe_kind())?;
+    writeln!(out, "Location  {}:{}", finding.file.display(), finding.line)?;
+    if let Some(entry) = report
+        .badness
+        .iter()
+        .find(|entry| entry.function == finding.item)
+    {
+        writeln!(out, "Badness   {}", entry.badness)?;
+    }
+
+    let source = match fs::read_to_string(&finding.file) {
+        Ok(source) => source,
+        Err(err) => {
+            writeln!(out)?;
+            writeln!(out, "Source is not available: {}", err)?;
+            return Ok(());
+        }
+    };
+    let lines = source.lines().collect::<Vec<_>>();
+
+    writeln!(out)?;
+    match safety_comment(&lines, finding.line) {
+        Some(comment) => writeln!(out, "{}", comment)?,
+        None => writeln!(out, "No SAFETY comment")?,
+    }
+
+    writeln!(out)?;
+    let first = finding.line.saturating_sub(context).max(1);
+    let last = (finding.line + context).min(lines.len());
+    for number in first..=last {
+        writeln!(
+            out,
+            "{} {:>5} | {}",
+            if number == finding.line { '>' } else { ' ' },
+            number,
+            lines[number - 1]
+        )?;
+    }
+    Ok(())
+}
+
+/// The comment immediately above `line` (1-based), if it contains `SAFETY`
+pub(super) fn safety_comment(lines: &[&str], line: usize) -> Option<String> {
+    let comment = lines[..line.saturating_sub(1).min(lines.len())]
+        .iter()
+        .rev()
+        .map(|line| line.trim())
+        .take_while(|line| line.starts_with("//"))
+        .collect::<Vec<_>>();
+    if !comment.iter().any(|line| line.contains("SAFETY")) {
+        return None;
+    }
+    Some(comment.into_iter().rev().collect::<Vec<_>>().join("\n"))
+}
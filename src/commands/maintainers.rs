@@ -0,0 +1,159 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use crate::{
+    crates_io,
+    output::Format,
+    report::{CrateSummary, Report},
+};
+
+/// What crates are grouped under when nothing is known about who maintains them
+const UNKNOWN: &str = "unknown";
+
+#[derive(StructOpt, Debug)]
+pub struct MaintainersOpts {
+    #[structopt(long = "report", value_name = "PATH")]
+    /// JSON report to group (by default, the current crate is analyzed)
+    report: Option<PathBuf>,
+
+    #[structopt(long = "owners")]
+    /// Also ask crates.io for the owners of each crate, and prefer the organization of a team
+    /// owner over the repository URL. Requires network access.
+    owners: bool,
+
+    #[structopt(long = "format", value_name = "FORMAT", default_value = "text")]
+    /// Output format: text, json or yaml
+    format: Format,
+}
+
+/// The crates maintained by one organization or individual
+#[derive(Debug, Serialize)]
+struct MaintainerGroup {
+    maintainer: String,
+
+    /// `name version`, sorted
+    crates: Vec<String>,
+
+    findings: usize,
+}
+
+pub fn run(
+    opts: &MaintainersOpts,
+    analyze: &dyn Fn() -> anyhow::Result<Report>,
+) -> anyhow::Result<()> {
+    let report = if let Some(path) = &opts.report {
+        Report::read(path)?
+    } else {
+        analyze()?
+    };
+
+    let mut groups = BTreeMap::<String, MaintainerGroup>::new();
+    for summary in &report.crates {
+        let maintainer = maintainer(summary, opts.owners);
+        let group = groups
+            .entry(maintainer.clone())
+            .or_insert_with(|| MaintainerGroup {
+                maintainer,
+                crates: Vec::new(),
+                findings: 0,
+            });
+        group
+            .crates
+            .push(format!("{} {}", summary.name, summary.version));
+        group.findings += summary.findings;
+    }
+    let mut groups = groups.into_values().collect::<Vec<_>>();
+    groups.sort_by(|a, b| {
+        b.findings
+            .cmp(&a.findings)
+            .then_with(|| a.maintainer.cmp(&b.maintainer))
+    });
+
+    let mut out = io::stdout().lock();
+    match opts.format {
+        Format::Text => {
+            writeln!(out, "Findings  Crates  Maintainer")?;
+            for group in &groups {
+                writeln!(
+                    out,
+                    "{:>8}  {:>6}  {}",
+                    group.findings,
+                    group.crates.len(),
+                    group.maintainer
+                )?;
+            }
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, &groups)?;
+            writeln!(out)?;
+        }
+        Format::Yaml => serde_yaml::to_writer(out, &groups)?,
+        format => bail!("The {format} format is only for reports"),
+    }
+    Ok(())
+}
+
+/// The organization maintaining a crate: the organization of a team owning it on crates.io (if
+/// `owners` is set), else the owner of its repository, else `UNKNOWN`
+fn maintainer(summary: &CrateSummary, owners: bool) -> String {
+    if owners && !summary.workspace_member {
+        match fetch_owners(&summary.name) {
+            Ok(logins) => {
+                if let Some(org) = logins.iter().find_map(|login| team_org(login)) {
+                    return org.to_string();
+                }
+            }
+            Err(err) => warn!(
+                "Could not fetch the owners of `{}`: {:?}",
+                summary.name, err
+            ),
+        }
+    }
+    summary
+        .repository
+        .as_deref()
+        .and_then(repository_org)
+        .unwrap_or_else(|| UNKNOWN.to_string())
+}
+
+/// `rust-lang` for `github:rust-lang:libs`
+fn team_org(login: &str) -> Option<&str> {
+    let mut parts = login.split(':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(_), Some(org), Some(_)) => Some(org),
+        _ => None,
+    }
+}
+
+/// `tokio-rs` for `https://github.com/tokio-rs/tokio`
+fn repository_org(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let mut segments = rest.split('/').filter(|segment| !segment.is_empty());
+    let _host = segments.next()?;
+    segments.next().map(str::to_lowercase)
+}
+
+#[derive(Deserialize)]
+struct Owners {
+    users: Vec<Owner>,
+}
+
+#[derive(Deserialize)]
+struct Owner {
+    /// `github:ORG:TEAM` for teams
+    login: String,
+}
+
+fn fetch_owners(name: &str) -> anyhow::Result<Vec<String>> {
+    let body = crates_io::get(&format!("crates/{name}/owners"))?;
+    let owners: Owners =
+        serde_json::from_slice(&body).with_context(|| "Could not parse the owners")?;
+    Ok(owners.users.into_iter().map(|owner| owner.login).collect())
+}
@@ -0,0 +1,208 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    io,
+    path::PathBuf,
+};
+
+use anyhow::bail;
+use structopt::StructOpt;
+
+use crate::{
+    category, dependency, diff,
+    output::{self, Format},
+    report::{self, CrateSummary, Report},
+    utils,
+};
+
+#[derive(StructOpt, Debug)]
+pub struct MergeOpts {
+    #[structopt(long = "format", value_name = "FORMAT", default_value = "json")]
+    /// Output format
+    format: Format,
+
+    #[structopt(value_name = "REPORT")]
+    /// JSON reports of the shards to merge
+    reports: Vec<PathBuf>,
+}
+
+pub fn run(opts: &MergeOpts) -> anyhow::Result<()> {
+    if opts.reports.is_empty() {
+        bail!("No reports to merge");
+    }
+    let reports = opts
+        .reports
+        .iter()
+        .map(|path| Report::read(path))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    output::write_report(
+        opts.format,
+        &output::html::HtmlOpts::default(),
+        &output::tree::TreeOpts::default(),
+        &output::TextOpts::default(),
+        &output::sqlite::SqliteOpts::default(),
+        &merge(reports),
+        &mut io::stdout().lock(),
+    )
+}
+
+/// Combines the reports of shards. Every shard summarizes every package, so the summaries are
+/// unioned, and since the shards scan disjoint packages, badness is summed.
+fn merge(reports: Vec<Report>) -> Report {
+    let mut crates = BTreeMap::<(String, String), CrateSummary>::new();
+    let mut findings = Vec::new();
+    let mut seen = HashSet::new();
+    let mut badness = BTreeMap::<String, report::BadnessEntry>::new();
+    let mut calls = BTreeSet::new();
+    let mut errors = Vec::new();
+    let mut inactive = BTreeSet::new();
+    let mut suggestions = BTreeMap::new();
+    let mut api = Vec::new();
+    let mut checksum_mismatches = Vec::new();
+    let mut generated = Vec::new();
+    let mut introduced = BTreeMap::new();
+    let mut stdlib = BTreeMap::new();
+    let mut skipped = BTreeSet::new();
+    let mut duplicates = BTreeMap::<String, report::DuplicateSource>::new();
+    let mut scenarios = Vec::new();
+    let mut targets = Vec::new();
+
+    // The shards should have been run identically.
+    let mut metadata = reports[0].metadata.clone();
+    // The digest is of one shard's report, not of the merged one.
+    metadata.inputs = None;
+    for report in &reports[1..] {
+        diff::warn_if_incomparable(&reports[0], report);
+    }
+
+    for report in reports {
+        for summary in report.crates {
+            crates
+                .entry((summary.name.clone(), summary.version.clone()))
+                .and_modify(|existing| {
+                    existing.workspace_member |= summary.workspace_member;
+                    existing.no_std |= summary.no_std;
+                    // Only the shard that scanned the package traced its unsafety
+                    existing.blast_radius = existing.blast_radius.max(summary.blast_radius);
+                    if existing.public_unsafe_fns.is_empty() {
+                        existing.public_unsafe_fns = summary.public_unsafe_fns.clone();
+                    }
+                    if existing.public_unsafe_traits.is_empty() {
+                        existing.public_unsafe_traits = summary.public_unsafe_traits.clone();
+                    }
+                })
+                .or_insert(summary);
+        }
+        for finding in report.findings {
+            if seen.insert((
+                finding.fingerprint.clone(),
+                utils::normalize_case(&finding.file),
+            )) {
+                findings.push(finding);
+            }
+        }
+        for entry in report.badness {
+            badness
+                .entry(entry.function.clone())
+                .and_modify(|existing| existing.badness += entry.badness)
+                .or_insert(entry);
+        }
+        calls.extend(report.calls);
+        errors.extend(report.errors);
+        inactive.extend(report.inactive);
+        api.extend(report.api);
+        checksum_mismatches.extend(report.checksum_mismatches);
+        generated.extend(report.generated);
+        for summary in report.stdlib {
+            stdlib
+                .entry((summary.name.clone(), summary.version.clone()))
+                .or_insert(summary);
+        }
+        skipped.extend(report.skipped);
+        for entry in report.introduced {
+            introduced.entry(entry.krate.clone()).or_insert(entry);
+        }
+        for suggestion in report.suggestions {
+            suggestions.insert(
+                (suggestion.krate.clone(), suggestion.version.clone()),
+                suggestion,
+            );
+        }
+        for duplicate in report.duplicates {
+            duplicates
+                .entry(duplicate.krate.clone())
+                .and_modify(|existing| {
+                    existing.versions.extend(duplicate.versions.iter().cloned());
+                    existing.versions.sort();
+                    existing.versions.dedup();
+                    existing.files += duplicate.files;
+                })
+                .or_insert(duplicate);
+        }
+        merge_scenarios(&mut scenarios, report.scenarios);
+        merge_scenarios(&mut targets, report.targets);
+    }
+
+    findings.sort_by(|a, b| a.location_key().cmp(&b.location_key()));
+
+    let mut crates = crates.into_values().collect::<Vec<_>>();
+    report::count_findings(&mut crates, &findings);
+    let categories = category::summarize(&crates);
+    let dependencies = dependency::summarize(&crates, &findings);
+
+    let mut badness = badness.into_values().collect::<Vec<_>>();
+    badness.sort_by(|a, b| {
+        b.badness
+            .cmp(&a.badness)
+            .then_with(|| a.function.cmp(&b.function))
+    });
+
+    // Badness is summed, so the API is ranked again
+    let api = report::rank_api(api, &badness);
+
+    Report {
+        metadata,
+        crates,
+        categories,
+        dependencies,
+        inactive: inactive.into_iter().collect(),
+        suggestions: suggestions.into_values().collect(),
+        api,
+        checksum_mismatches,
+        generated,
+        introduced: introduced.into_values().collect(),
+        stdlib: stdlib.into_values().collect(),
+        // Each shard only scans some of the files that the build compiled.
+        file_coverage: None,
+        skipped: skipped.into_iter().collect(),
+        findings,
+        badness,
+        calls: calls.into_iter().collect(),
+        errors,
+        duplicates: duplicates.into_values().collect(),
+        scenarios,
+        targets,
+    }
+}
+
+/// Combines the scenarios of shards. Like the main run, each scenario summarizes every package but
+/// only scans a shard, so the findings are summed.
+fn merge_scenarios(
+    merged: &mut Vec<report::ScenarioSummary>,
+    scenarios: Vec<report::ScenarioSummary>,
+) {
+    for scenario in scenarios {
+        match merged
+            .iter_mut()
+            .find(|existing| existing.name == scenario.name)
+        {
+            Some(existing) => {
+                existing.crates = existing.crates.max(scenario.crates);
+                existing.findings += scenario.findings;
+                for (krate, count) in scenario.by_crate {
+                    *existing.by_crate.entry(krate).or_default() += count;
+                }
+            }
+            None => merged.push(scenario),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+mod aggregate;
+mod api;
+mod attest;
+mod batch;
+mod checklist;
+mod comment;
+mod compare;
+mod convert;
+mod crate_diff;
+mod dependents;
+mod doctor;
+mod explain;
+mod maintainers;
+mod merge;
+mod packet;
+mod publish_check;
+mod scan_archive;
+mod trend;
+mod verify;
+
+use structopt::StructOpt;
+
+use crate::report::Report;
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    /// Combine the JSON reports of many projects into a view of the dependencies they share
+    Aggregate(aggregate::AggregateOpts),
+
+    /// Write a Markdown "API safety contract" of the public functions and the unsafety they reach
+    Api(api::ApiOpts),
+
+    /// Attest that the current package is free of unsafety, for its maintainers to publish
+    Attest(attest::AttestOpts),
+
+    /// Clone or update a list of repositories and analyze each, writing their reports and an index
+    Batch(batch::BatchOpts),
+
+    /// Write a Markdown checklist of the findings for auditors to tick off
+    Checklist(checklist::ChecklistOpts),
+
+    /// Summarize the unsafety introduced and removed between two JSON reports
+    Comment(comment::CommentOpts),
+
+    /// Analyze two git refs in temporary worktrees and summarize the difference
+    Compare(compare::CompareOpts),
+
+    /// Transcode a report to another format, e.g. a protobuf report to JSON
+    Convert(convert::ConvertOpts),
+
+    /// Download two versions of a crate from crates.io and show how their unsafe items differ
+    CrateDiff(crate_diff::CrateDiffOpts),
+
+    /// Scan the most downloaded reverse dependencies of a crate on crates.io
+    Dependents(dependents::DependentsOpts),
+
+    /// Check that the environment has what siderophile needs
+    Doctor(doctor::DoctorOpts),
+
+    /// Show the details of one finding: provenance, SAFETY comment and source
+    Explain(explain::ExplainOpts),
+
+    /// Group the crates by the organization maintaining them, with their numbers of findings
+    Maintainers(maintainers::MaintainersOpts),
+
+    /// Combine the JSON reports of runs with `--shard` into one report
+    Merge(merge::MergeOpts),
+
+    /// Bundle the findings, annotated sources, SAFETY comments, callgraph slice and checklist of
+    /// one crate into a directory for its audit
+    Packet(packet::PacketOpts),
+
+    /// Run `cargo package` and scan the files that will be published, not the working tree
+    PublishCheck(publish_check::PublishCheckOpts),
+
+    /// Scan a packaged `.crate` file as published, checking it against the registry index
+    ScanArchive(scan_archive::ScanArchiveOpts),
+
+    /// Draw a sparkline of the unsafe items over time, from dated reports, as an SVG
+    Trend(trend::TrendOpts),
+
+    /// Check that a report written with `--sign-report` was not edited since
+    VerifyReport(verify::VerifyOpts),
+}
+
+/// Runs `command`. `analyze` analyzes the current crate using the top-level options, for commands
+/// that need a report but were not given one.
+pub fn run(command: &Command, analyze: &dyn Fn() -> anyhow::Result<Report>) -> anyhow::Result<()> {
+    match command {
+        Command::Aggregate(opts) => aggregate::run(opts),
+        Command::Api(opts) => api::run(opts, analyze),
+        Command::Attest(opts) => attest::run(opts),
+        Command::Batch(opts) => batch::run(opts),
+        Command::Checklist(opts) => checklist::run(opts, analyze),
+        Command::Comment(opts) => comment::run(opts),
+        Command::Compare(opts) => compare::run(opts),
+        Command::Convert(opts) => convert::run(opts),
+        Command::CrateDiff(opts) => crate_diff::run(opts),
+        Command::Dependents(opts) => dependents::run(opts),
+        Command::Doctor(opts) => doctor::run(opts),
+        Command::Explain(opts) => explain::run(opts, analyze),
+        Command::Maintainers(opts) => maintainers::run(opts, analyze),
+        Command::Merge(opts) => merge::run(opts),
+        Command::Packet(opts) => packet::run(opts, analyze),
+        Command::PublishCheck(opts) => publish_check::run(opts),
+        Command::ScanArchive(opts) => scan_archive::run(opts),
+        Command::Trend(opts) => trend::run(opts),
+        Command::VerifyReport(opts) => verify::run(opts),
+    }
+}
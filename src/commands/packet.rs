@@ -0,0 +1,320 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use structopt::StructOpt;
+
+use super::{checklist, explain};
+use crate::{
+    report::{Finding, Report},
+    utils,
+};
+
+#[derive(StructOpt, Debug)]
+pub struct PacketOpts {
+    #[structopt(long = "report", value_name = "PATH")]
+    /// JSON report to make the packet from (by default, the current crate is analyzed)
+    report: Option<PathBuf>,
+
+    #[structopt(long = "output", short = "o", value_name = "DIR")]
+    /// Directory to write the packet to (by default, `packet-CRATE`)
+    output: Option<PathBuf>,
+
+    #[structopt(value_name = "CRATE")]
+    /// Crate to audit, as `NAME` or `NAME@VERSION`
+    krate: String,
+}
+
+pub fn run(opts: &PacketOpts, analyze: &dyn Fn() -> anyhow::Result<Report>) -> anyhow::Result<()> {
+    let report = if let Some(path) = &opts.report {
+        Report::read(path)?
+    } else {
+        analyze()?
+    };
+    let (name, version) = match opts.krate.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (opts.krate.as_str(), None),
+    };
+    let packet = slice(&report, name, version);
+    if packet.crates.is_empty() {
+        bail!("The report has no crate `{}`", opts.krate);
+    }
+
+    let dir = opts
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("packet-{name}")));
+    fs::create_dir_all(&dir).with_context(|| format!("Could not create `{}`", dir.display()))?;
+    let write = |file: &str, contents: Vec<u8>| {
+        let path = dir.join(file);
+        fs::write(&path, contents).with_context(|| format!("Could not write `{}`", path.display()))
+    };
+
+    let mut json = serde_json::to_vec_pretty(&packet)?;
+    json.push(b'\n');
+    write("report.json", json)?;
+
+    let mut checklist = Vec::new();
+    checklist::write_new_checklist(&mut checklist, &packet)?;
+    write("checklist.md", checklist)?;
+
+    let mut safety = Vec::new();
+    write_safety_comments(&mut safety, &packet)?;
+    write("safety.md", safety)?;
+
+    let mut callgraph = Vec::new();
+    write_callgraph(&mut callgraph, &packet)?;
+    write("callgraph.dot", callgraph)?;
+
+    let sources = write_sources(&dir.join("sources"), &packet)?;
+
+    let mut readme = Vec::new();
+    write_readme(&mut readme, &packet, &sources)?;
+    write("README.md", readme)?;
+
+    eprintln!(
+        "Wrote the review packet of `{}` ({} findings) to `{}`",
+        opts.krate,
+        packet.findings.len(),
+        dir.display()
+    );
+    Ok(())
+}
+
+/// The part of `report` about the crate `name`, of any version unless `version` is given: its
+/// summaries, its findings, and the calls through which the functions containing them are reached
+fn slice(report: &Report, name: &str, version: Option<&str>) -> Report {
+    let is_audited =
+        |krate: &str, v: &str| krate == name && version.map_or(true, |version| version == v);
+    let findings = report
+        .findings
+        .iter()
+        .filter(|finding| is_audited(&finding.krate, &finding.provenance.version))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    // Every function from which the findings can be reached
+    let mut callers = HashMap::<&str, Vec<&str>>::new();
+    for call in &report.calls {
+        callers
+            .entry(call.callee.as_str())
+            .or_default()
+            .push(call.caller.as_str());
+    }
+    let mut reaching = findings
+        .iter()
+        .map(|finding| finding.item.as_str())
+        .collect::<BTreeSet<_>>();
+    let mut stack = reaching.iter().copied().collect::<Vec<_>>();
+    while let Some(function) = stack.pop() {
+        for caller in callers.get(function).into_iter().flatten() {
+            if reaching.insert(caller) {
+                stack.push(caller);
+            }
+        }
+    }
+
+    Report {
+        metadata: report.metadata.clone(),
+        crates: report
+            .crates
+            .iter()
+            .filter(|summary| is_audited(&summary.name, &summary.version))
+            .cloned()
+            .collect(),
+        findings,
+        badness: report
+            .badness
+            .iter()
+            .filter(|entry| reaching.contains(entry.function.as_str()))
+            .cloned()
+            .collect(),
+        calls: report
+            .calls
+            .iter()
+            .filter(|call| reaching.contains(call.callee.as_str()))
+            .cloned()
+            .collect(),
+        ..Report::default()
+    }
+}
+
+/// The SAFETY comment above each finding, or that there is none
+fn write_safety_comments(out: &mut dyn Write, report: &Report) -> anyhow::Result<()> {
+    writeln!(out, "# SAFETY comments")?;
+    for finding in &report.findings {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "## `{}` `{}` ({})",
+            finding.fingerprint,
+            finding.item,
+            finding.describe_kind()
+        )?;
+        writeln!(out)?;
+        writeln!(out, "{}:{}", finding.file.display(), finding.line)?;
+        writeln!(out)?;
+        let comment = fs::read_to_string(&finding.file).ok().and_then(|source| {
+            explain::safety_comment(&source.lines().collect::<Vec<_>>(), finding.line)
+        });
+        match comment {
+            Some(comment) => {
+                writeln!(out, "```rust")?;
+                writeln!(out, "{comment}")?;
+                writeln!(out, "```")?;
+            }
+            None => writeln!(out, "No SAFETY comment")?,
+        }
+    }
+    Ok(())
+}
+
+/// The calls of the slice as a Graphviz graph, with the functions containing findings in boxes
+fn write_callgraph(out: &mut dyn Write, report: &Report) -> anyhow::Result<()> {
+    let quote = |function: &str| format!("\"{}\"", function.replace('"', "\\\""));
+    writeln!(out, "digraph callgraph {{")?;
+    let items = report
+        .findings
+        .iter()
+        .map(|finding| finding.item.as_str())
+        .collect::<BTreeSet<_>>();
+    for item in items {
+        writeln!(out, "    {} [shape=box];", quote(item))?;
+    }
+    for call in &report.calls {
+        writeln!(
+            out,
+            "    {} -> {};",
+            quote(&call.caller),
+            quote(&call.callee)
+        )?;
+    }
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// Copies the files with findings into `dir`, with a comment above each finding, and returns
+/// their paths relative to `dir` along with their findings
+fn write_sources<'a>(
+    dir: &Path,
+    report: &'a Report,
+) -> anyhow::Result<BTreeMap<PathBuf, Vec<&'a Finding>>> {
+    let mut by_file = BTreeMap::<_, Vec<_>>::new();
+    for finding in &report.findings {
+        by_file.entry(&*finding.file).or_default().push(finding);
+    }
+    let mut sources = BTreeMap::new();
+    for (file, findings) in by_file {
+        let source = match fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Warning: Could not read `{}`: {}", file.display(), e);
+                continue;
+            }
+        };
+        let mut annotated = String::new();
+        for (i, line) in source.lines().enumerate() {
+            for finding in findings.iter().filter(|finding| finding.line == i + 1) {
+                let indentation = &line[..line.len() - line.trim_start().len()];
+                annotated += &format!(
+                    "{}// siderophile: `{}` {}\n",
+                    indentation,
+                    finding.fingerprint,
+                    finding.describe_kind()
+                );
+            }
+            annotated += line;
+            annotated.push('\n');
+        }
+        let relative = utils::package_relative_path(
+            file,
+            &*findings[0].krate,
+            &findings[0].provenance.version,
+        );
+        let path = dir.join(&relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Could not create `{}`", parent.display()))?;
+        }
+        fs::write(&path, annotated)
+            .with_context(|| format!("Could not write `{}`", path.display()))?;
+        sources.insert(relative, findings);
+    }
+    Ok(sources)
+}
+
+fn write_readme(
+    out: &mut dyn Write,
+    report: &Report,
+    sources: &BTreeMap<PathBuf, Vec<&Finding>>,
+) -> anyhow::Result<()> {
+    for summary in &report.crates {
+        writeln!(
+            out,
+            "# Review packet: `{}` {}",
+            summary.name, summary.version
+        )?;
+        writeln!(out)?;
+        if !summary.source.is_empty() {
+            writeln!(out, "- Source: `{}`", summary.source)?;
+        }
+        if let Some(checksum) = &summary.checksum {
+            writeln!(out, "- Checksum: `{checksum}`")?;
+        }
+        if let Some(license) = &summary.license {
+            writeln!(out, "- License: `{license}`")?;
+        }
+        if let Some(repository) = &summary.repository {
+            writeln!(out, "- Repository: {repository}")?;
+        }
+        writeln!(
+            out,
+            "- Findings: {}",
+            report
+                .findings
+                .iter()
+                .filter(|finding| {
+                    *finding.krate == summary.name && finding.provenance.version == summary.version
+                })
+                .count()
+        )?;
+        writeln!(out)?;
+    }
+    writeln!(out, "## Contents")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "- [`report.json`](report.json): the findings, and the badness and calls of the functions \
+         reaching them"
+    )?;
+    writeln!(
+        out,
+        "- [`checklist.md`](checklist.md): one item per finding"
+    )?;
+    writeln!(
+        out,
+        "- [`safety.md`](safety.md): the SAFETY comment of each finding"
+    )?;
+    writeln!(
+        out,
+        "- [`callgraph.dot`](callgraph.dot): the calls reaching the findings, for Graphviz"
+    )?;
+    writeln!(
+        out,
+        "- `sources/`: the files with findings, each marked with a `// siderophile:` comment"
+    )?;
+    for (path, findings) in sources {
+        writeln!(
+            out,
+            "  - [`{}`](sources/{}) ({})",
+            path.display(),
+            path.display(),
+            findings.len()
+        )?;
+    }
+    Ok(())
+}
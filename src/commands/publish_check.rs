@@ -0,0 +1,132 @@
+use std::{
+    collections::BTreeSet,
+    io::{self, Write},
+    process::Command,
+};
+
+use anyhow::{bail, Context};
+use cargo::{
+    core::{SourceId, Workspace},
+    util::important_paths::find_root_manifest_for_wd,
+};
+use structopt::StructOpt;
+
+use crate::{
+    error::OnError,
+    filter::FilterOpts,
+    output::{self, Format},
+    trawl_source,
+};
+
+#[derive(StructOpt, Debug)]
+pub struct PublishCheckOpts {
+    #[structopt(long = "package", short = "p", value_name = "NAME")]
+    /// Workspace member to check (by default, the current package)
+    package: Option<String>,
+
+    #[structopt(long = "allow-dirty")]
+    /// Package the working tree even if it has uncommitted changes, as `cargo package
+    /// --allow-dirty` does
+    allow_dirty: bool,
+
+    #[structopt(long = "format", value_name = "FORMAT", default_value = "text")]
+    /// Output format
+    format: Format,
+
+    #[structopt(flatten)]
+    html_opts: output::html::HtmlOpts,
+
+    #[structopt(flatten)]
+    tree_opts: output::tree::TreeOpts,
+
+    #[structopt(flatten)]
+    text_opts: output::TextOpts,
+
+    #[structopt(flatten)]
+    sqlite_opts: output::sqlite::SqliteOpts,
+
+    #[structopt(flatten)]
+    filter_opts: FilterOpts,
+
+    #[structopt(long = "include-tests")]
+    /// Count unsafe usage in tests
+    include_tests: bool,
+
+    #[structopt(long = "on-error", value_name = "POLICY", default_value = "skip")]
+    /// What to do when a file cannot be scanned: skip (and list it in the report) or fail
+    on_error: OnError,
+}
+
+pub fn run(opts: &PublishCheckOpts) -> anyhow::Result<()> {
+    let config = cargo::Config::default()?;
+    let manifest = find_root_manifest_for_wd(config.cwd())?;
+    let ws = Workspace::new(&manifest, &config)?;
+    let pack = super::attest::find_package(&ws, opts.package.as_deref())?;
+
+    let mut command = Command::new("cargo");
+    command
+        .arg("package")
+        .arg("--no-verify")
+        .arg("--manifest-path")
+        .arg(pack.manifest_path());
+    if opts.allow_dirty {
+        command.arg("--allow-dirty");
+    }
+    let output = command.output().context("Could not run `cargo package`")?;
+    if !output.status.success() {
+        bail!(
+            "`cargo package` failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let archive = ws
+        .target_dir()
+        .as_path_unlocked()
+        .join("package")
+        .join(format!("{}-{}.crate", pack.name(), pack.version()));
+    let unpacked = trawl_source::unpack(&archive)?;
+    let report = super::scan_archive::scan(
+        &unpacked,
+        SourceId::crates_io(&config)?,
+        opts.on_error,
+        opts.include_tests,
+    )?;
+
+    // What `include` and `exclude` leave out of the package
+    let working_tree = trawl_source::scan_package(pack, opts.include_tests)?;
+    let left_out = working_tree
+        .files
+        .iter()
+        .map(|(path, _)| path.to_string_lossy().replace('\\', "/"))
+        .filter(|path| !unpacked.files.contains_key(path))
+        .collect::<BTreeSet<_>>();
+    let mut stderr = io::stderr().lock();
+    writeln!(
+        stderr,
+        "`{}` {} will ship {} unsafe items ({} in the working tree)",
+        pack.name(),
+        pack.version(),
+        report.findings.len(),
+        working_tree.unsafe_items
+    )?;
+    if !left_out.is_empty() {
+        writeln!(
+            stderr,
+            "Warning: {} source files of the working tree are not in the package:",
+            left_out.len()
+        )?;
+        for path in &left_out {
+            writeln!(stderr, "    {path}")?;
+        }
+    }
+
+    output::write_report(
+        opts.format,
+        &opts.html_opts,
+        &opts.tree_opts,
+        &opts.text_opts,
+        &opts.sqlite_opts,
+        &opts.filter_opts.apply(&report, None),
+        &mut io::stdout().lock(),
+    )
+}
@@ -0,0 +1,131 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    path::PathBuf,
+};
+
+use cargo::core::{PackageId, SourceId};
+use structopt::StructOpt;
+
+use crate::{
+    error::{OnError, ScanError},
+    exit::{Code, Failure},
+    filter::FilterOpts,
+    output::{self, Format},
+    report::{self, ChecksumMismatch, Report},
+    trawl_source,
+};
+
+#[derive(StructOpt, Debug)]
+pub struct ScanArchiveOpts {
+    #[structopt(long = "format", value_name = "FORMAT", default_value = "text")]
+    /// Output format
+    format: Format,
+
+    #[structopt(flatten)]
+    html_opts: output::html::HtmlOpts,
+
+    #[structopt(flatten)]
+    tree_opts: output::tree::TreeOpts,
+
+    #[structopt(flatten)]
+    text_opts: output::TextOpts,
+
+    #[structopt(flatten)]
+    sqlite_opts: output::sqlite::SqliteOpts,
+
+    #[structopt(flatten)]
+    filter_opts: FilterOpts,
+
+    #[structopt(long = "include-tests")]
+    /// Count unsafe usage in tests
+    include_tests: bool,
+
+    #[structopt(long = "on-error", value_name = "POLICY", default_value = "skip")]
+    /// What to do when a file cannot be scanned: skip (and list it in the report) or fail
+    on_error: OnError,
+
+    #[structopt(value_name = "CRATE_FILE")]
+    /// `.crate` file, e.g. one written by `cargo package`
+    archive: PathBuf,
+}
+
+/// The report of the package unpacked in `unpacked`, as published to the registry `source_id`
+pub(super) fn scan(
+    unpacked: &trawl_source::Unpacked,
+    source_id: SourceId,
+    on_error: OnError,
+    include_tests: bool,
+) -> anyhow::Result<Report> {
+    let trawl = trawl_source::trawl_archive(
+        unpacked,
+        &source_id.as_url().to_string(),
+        on_error,
+        include_tests,
+    )?;
+    let mut report = Report::new(trawl.crates, trawl.findings, &HashMap::new());
+    report.errors = trawl.issues;
+    report.skipped = trawl.skipped;
+    report.metadata = report::Metadata::collect(BTreeMap::new())?;
+    Ok(report)
+}
+
+pub fn run(opts: &ScanArchiveOpts) -> anyhow::Result<()> {
+    let unpacked = trawl_source::unpack(&opts.archive)?;
+    let config = cargo::Config::default()?;
+    let source_id = SourceId::crates_io(&config)?;
+    let mut report = scan(&unpacked, source_id, opts.on_error, opts.include_tests)?;
+
+    let Some(summary) = report.crates.first() else {
+        return Err(ScanError::Vendor(
+            "The archive contains no package".to_string(),
+            opts.archive.clone(),
+        )
+        .into());
+    };
+    let id = PackageId::new(summary.name.as_str(), summary.version.as_str(), source_id)?;
+    match trawl_source::published_checksum(&config, id) {
+        Ok(Some(published)) if published != unpacked.checksum => {
+            report.checksum_mismatches.push(ChecksumMismatch {
+                krate: summary.name.clone(),
+                version: summary.version.clone(),
+                file: None,
+                detail: format!(
+                    "The archive's checksum {} does not match {} in the registry index",
+                    unpacked.checksum, published
+                ),
+            });
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => eprintln!(
+            "Warning: `{}` {} is not in the local copy of the registry index, so its checksum was \
+             not verified",
+            summary.name, summary.version
+        ),
+        Err(e) => eprintln!(
+            "Warning: Could not read the registry index, so the checksum was not verified: {e:?}"
+        ),
+    }
+
+    output::write_report(
+        opts.format,
+        &opts.html_opts,
+        &opts.tree_opts,
+        &opts.text_opts,
+        &opts.sqlite_opts,
+        &opts.filter_opts.apply(&report, None),
+        &mut io::stdout().lock(),
+    )?;
+    if let Some(mismatch) = report.checksum_mismatches.first() {
+        return Err(Failure::new(
+            Code::ScanError,
+            format!(
+                "`{}` does not match the published `{}` {}",
+                opts.archive.display(),
+                mismatch.krate,
+                mismatch.version
+            ),
+        ));
+    }
+    Ok(())
+}
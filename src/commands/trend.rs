@@ -0,0 +1,205 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context};
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::Regex;
+use structopt::StructOpt;
+
+/// The color of the line, as on GitHub for removals
+const COLOR: &str = "#d73a49";
+
+/// Width of a digit of the label, in pixels at the font size used
+const DIGIT_WIDTH: usize = 7;
+
+const PADDING: f64 = 3.0;
+
+#[derive(StructOpt, Debug)]
+pub struct TrendOpts {
+    #[structopt(long = "svg", value_name = "PATH")]
+    /// Where to write the graph
+    svg: PathBuf,
+
+    #[structopt(long = "width", value_name = "PIXELS", default_value = "160")]
+    width: usize,
+
+    #[structopt(long = "height", value_name = "PIXELS", default_value = "32")]
+    height: usize,
+
+    #[structopt(value_name = "PATH", required = true)]
+    /// JSON reports or `--emit metrics-json` files, or directories of them. Each is dated by a
+    /// `YYYY-MM-DD` in its file name, else by when it was last modified.
+    paths: Vec<PathBuf>,
+}
+
+/// The unsafe items of the tree on one date
+#[derive(Debug)]
+struct Point {
+    date: NaiveDate,
+    unsafe_items: usize,
+}
+
+pub fn run(opts: &TrendOpts) -> anyhow::Result<()> {
+    let mut files = Vec::new();
+    for path in &opts.paths {
+        if path.is_dir() {
+            for entry in fs::read_dir(path)
+                .with_context(|| format!("Could not read `{}`", path.display()))?
+            {
+                let file = entry?.path();
+                if file
+                    .extension()
+                    .is_some_and(|extension| extension == "json")
+                {
+                    files.push(file);
+                }
+            }
+        } else {
+            files.push(path.clone());
+        }
+    }
+    // Files of the same day are kept in the order of their names.
+    files.sort();
+    let date_re = Regex::new(r"\d{4}-\d{2}-\d{2}")?;
+    let mut points = files
+        .iter()
+        .map(|file| read_point(file, &date_re))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    points.sort_by_key(|point| point.date);
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => bail!("No reports to draw a trend from"),
+    };
+
+    let mut svg = Vec::new();
+    write_svg(&points, opts.width, opts.height, &mut svg)?;
+    fs::write(&opts.svg, svg)
+        .with_context(|| format!("Could not write `{}`", opts.svg.display()))?;
+    println!(
+        "{} reports from {} to {}: {} -> {} unsafe items",
+        points.len(),
+        first.date,
+        last.date,
+        first.unsafe_items,
+        last.unsafe_items
+    );
+    Ok(())
+}
+
+/// The date and number of unsafe items of a report or metrics file
+fn read_point(file: &Path, date_re: &Regex) -> anyhow::Result<Point> {
+    let contents =
+        fs::read(file).with_context(|| format!("Could not read `{}`", file.display()))?;
+    let value: serde_json::Value = serde_json::from_slice(&contents)
+        .with_context(|| format!("Could not parse `{}`", file.display()))?;
+    // A report lists its findings, a metrics file counts them.
+    let unsafe_items = match &value["findings"] {
+        serde_json::Value::Array(findings) => findings.len(),
+        serde_json::Value::Number(findings) => findings
+            .as_u64()
+            .and_then(|findings| usize::try_from(findings).ok())
+            .ok_or_else(|| anyhow!("Invalid number of findings in `{}`", file.display()))?,
+        _ => bail!(
+            "`{}` is neither a report nor a metrics file",
+            file.display()
+        ),
+    };
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+    let date = match date_re.find(&name) {
+        Some(date) => date
+            .as_str()
+            .parse()
+            .with_context(|| format!("Invalid date in `{}`", file.display()))?,
+        None => {
+            let modified = fs::metadata(file)?.modified()?;
+            DateTime::<Utc>::from(modified).date_naive()
+        }
+    };
+    Ok(Point { date, unsafe_items })
+}
+
+/// A sparkline of `points`, which are sorted by date, labeled with the latest number of unsafe
+/// items
+fn write_svg(
+    points: &[Point],
+    width: usize,
+    height: usize,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let last = points[points.len() - 1].unsafe_items;
+    let label = last.to_string();
+    let label_width = DIGIT_WIDTH * label.len() + 2 * PADDING as usize;
+    let plot_width = width.saturating_sub(label_width).max(1) as f64 - 2.0 * PADDING;
+    let plot_height = height as f64 - 2.0 * PADDING;
+
+    let first_date = points[0].date;
+    let days = (points[points.len() - 1].date - first_date).num_days();
+    let min = points
+        .iter()
+        .map(|point| point.unsafe_items)
+        .min()
+        .unwrap_or_default();
+    let max = points
+        .iter()
+        .map(|point| point.unsafe_items)
+        .max()
+        .unwrap_or_default();
+    let coordinates = points
+        .iter()
+        .enumerate()
+        .map(|(i, point)| {
+            // Reports of a single day are spread out evenly.
+            let x = if days > 0 {
+                (point.date - first_date).num_days() as f64 / days as f64
+            } else if points.len() > 1 {
+                i as f64 / (points.len() - 1) as f64
+            } else {
+                1.0
+            };
+            let y = if max > min {
+                (point.unsafe_items - min) as f64 / (max - min) as f64
+            } else {
+                0.5
+            };
+            (PADDING + x * plot_width, PADDING + (1.0 - y) * plot_height)
+        })
+        .collect::<Vec<_>>();
+    let (last_x, last_y) = coordinates[coordinates.len() - 1];
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" role="img" aria-label="{label} unsafe items">"#
+    )?;
+    writeln!(
+        out,
+        "<title>Unsafe items from {} to {}: {} to {}</title>",
+        first_date,
+        points[points.len() - 1].date,
+        points[0].unsafe_items,
+        last
+    )?;
+    writeln!(
+        out,
+        r#"<polyline fill="none" stroke="{COLOR}" stroke-width="1.5" stroke-linejoin="round" points="{}"/>"#,
+        coordinates
+            .iter()
+            .map(|(x, y)| format!("{x:.1},{y:.1}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    )?;
+    writeln!(
+        out,
+        r#"<circle cx="{last_x:.1}" cy="{last_y:.1}" r="2" fill="{COLOR}"/>"#
+    )?;
+    writeln!(
+        out,
+        r#"<text x="{:.1}" y="{:.1}" font-family="sans-serif" font-size="11" dominant-baseline="middle">{label}</text>"#,
+        plot_width + 3.0 * PADDING,
+        height as f64 / 2.0
+    )?;
+    writeln!(out, "</svg>")?;
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{bail, Context};
+use structopt::StructOpt;
+
+use crate::{report::Report, sign};
+
+#[derive(StructOpt, Debug)]
+pub struct VerifyOpts {
+    #[structopt(long = "lockfile", value_name = "PATH")]
+    /// Also check that the report was produced from this `Cargo.lock`
+    lockfile: Option<PathBuf>,
+
+    #[structopt(value_name = "REPORT")]
+    /// JSON report written with `--sign-report`
+    report: PathBuf,
+}
+
+pub fn run(opts: &VerifyOpts) -> anyhow::Result<()> {
+    let report = Report::read(&opts.report)?;
+    let inputs = match &report.metadata.inputs {
+        Some(inputs) => inputs,
+        None => bail!(
+            "`{}` was not written with `--sign-report`",
+            opts.report.display()
+        ),
+    };
+    if sign::digest(&report)? != inputs.digest {
+        bail!(
+            "`{}` does not match its digest: it was edited after being written",
+            opts.report.display()
+        );
+    }
+    if let Some(lockfile) = &opts.lockfile {
+        let contents = fs::read(lockfile)
+            .with_context(|| format!("Could not read `{}`", lockfile.display()))?;
+        if sign::sha256(&contents) != inputs.lockfile {
+            bail!(
+                "`{}` was not produced from `{}`",
+                opts.report.display(),
+                lockfile.display()
+            );
+        }
+    }
+    println!("`{}` matches its digest", opts.report.display());
+    if let Some(commit) = &inputs.commit {
+        println!(
+            "Produced from commit {}{}",
+            commit,
+            if inputs.dirty {
+                ", with uncommitted changes"
+            } else {
+                ""
+            }
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,19 @@
+//! Requests to the crates.io API
+
+/// The body of the response to a GET request for `path`, e.g., `crates/libc/owners`
+pub fn get(path: &str) -> anyhow::Result<Vec<u8>> {
+    let mut handle = curl::easy::Easy::new();
+    handle.url(&format!("https://crates.io/api/v1/{path}"))?;
+    // crates.io rejects requests without a user agent
+    handle.useragent(concat!("siderophile/", env!("CARGO_PKG_VERSION")))?;
+    let mut body = Vec::new();
+    {
+        let mut transfer = handle.transfer();
+        transfer.write_function(|data| {
+            body.extend_from_slice(data);
+            Ok(data.len())
+        })?;
+        transfer.perform()?;
+    }
+    Ok(body)
+}
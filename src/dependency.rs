@@ -0,0 +1,263 @@
+//! Attributes the unsafety of the tree to the direct dependencies of the workspace that pull it in
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::report::{CrateSummary, DependencySummary, Finding};
+
+/// Enumerating every dependency chain can take exponentially long, so it stops after this many
+pub const MAX_CHAINS: usize = 1000;
+
+/// How a package is referred to in `CrateSummary::dependencies`
+pub fn id(name: &str, version: &str) -> String {
+    format!("{name}@{version}")
+}
+
+/// Whether `spec` refers to the package, by name or by `name@version`
+pub fn matches(summary: &CrateSummary, spec: &str) -> bool {
+    summary.name == spec || id(&summary.name, &summary.version) == spec
+}
+
+/// For each direct dependency of the workspace members, the crates and findings in the subtree it
+/// pulls in, sorted by descending number of findings. A crate in the subtrees of several direct
+/// dependencies counts toward the findings of each, but only toward the exclusive findings of a
+/// dependency whose subtree it is alone in.
+pub fn summarize(crates: &[CrateSummary], findings: &[Finding]) -> Vec<DependencySummary> {
+    let mut counts = HashMap::<(&str, &str), usize>::new();
+    for finding in findings {
+        *counts
+            .entry((&*finding.krate, finding.provenance.version.as_str()))
+            .or_default() += 1;
+    }
+    let by_id = crates
+        .iter()
+        .map(|summary| (id(&summary.name, &summary.version), summary))
+        .collect::<HashMap<_, _>>();
+    let findings_of = |id: &str| {
+        let summary = by_id[id];
+        counts
+            .get(&(summary.name.as_str(), summary.version.as_str()))
+            .copied()
+            .unwrap_or_default()
+    };
+
+    let direct = crates
+        .iter()
+        .filter(|summary| summary.workspace_member)
+        .flat_map(|summary| &summary.dependencies)
+        .filter(|dep| {
+            by_id
+                .get(dep.as_str())
+                .is_some_and(|summary| !summary.workspace_member)
+        })
+        .collect::<BTreeSet<_>>();
+    let subtrees = direct
+        .into_iter()
+        .map(|dep| (dep, subtree(&by_id, dep)))
+        .collect::<Vec<_>>();
+    let mut owners = HashMap::<&str, usize>::new();
+    for (_, subtree) in &subtrees {
+        for id in subtree {
+            *owners.entry(id).or_default() += 1;
+        }
+    }
+
+    let mut summaries = subtrees
+        .iter()
+        .map(|(dep, subtree)| {
+            let summary = by_id[dep.as_str()];
+            DependencySummary {
+                name: summary.name.clone(),
+                version: summary.version.clone(),
+                crates: subtree.len(),
+                findings: subtree.iter().map(|id| findings_of(id)).sum(),
+                exclusive_findings: subtree
+                    .iter()
+                    .filter(|id| owners[id.as_str()] == 1)
+                    .map(|id| findings_of(id))
+                    .sum(),
+            }
+        })
+        .collect::<Vec<_>>();
+    summaries.sort_by(|a, b| {
+        b.findings
+            .cmp(&a.findings)
+            .then_with(|| (&a.name, &a.version).cmp(&(&b.name, &b.version)))
+    });
+    summaries
+}
+
+/// The dependency chains through which the workspace members pull in `target`, each from a member
+/// to `target`, shortest first, and whether there were more than `MAX_CHAINS` of them. A chain
+/// stops at the first workspace member, so a member yields the chain of only itself.
+pub fn chains<'a>(
+    crates: &'a [CrateSummary],
+    target: &CrateSummary,
+) -> (Vec<Vec<&'a CrateSummary>>, bool) {
+    let index = crates
+        .iter()
+        .enumerate()
+        .map(|(i, summary)| (id(&summary.name, &summary.version), i))
+        .collect::<HashMap<_, _>>();
+    let mut dependents = vec![Vec::new(); crates.len()];
+    for (i, summary) in crates.iter().enumerate() {
+        for dep in &summary.dependencies {
+            if let Some(&j) = index.get(dep) {
+                dependents[j].push(i);
+            }
+        }
+    }
+
+    let mut walk = Walk {
+        crates,
+        dependents: &dependents,
+        path: Vec::new(),
+        chains: Vec::new(),
+        truncated: false,
+    };
+    if let Some(&target) = index.get(&id(&target.name, &target.version)) {
+        walk.visit(target);
+    }
+    let mut chains = walk.chains;
+    chains.sort_by_cached_key(|chain| {
+        (
+            chain.len(),
+            chain
+                .iter()
+                .map(|summary| id(&summary.name, &summary.version))
+                .collect::<Vec<_>>(),
+        )
+    });
+    (chains, walk.truncated)
+}
+
+/// The state of the walk up from a package to the workspace members
+struct Walk<'a, 'b> {
+    crates: &'a [CrateSummary],
+    dependents: &'b [Vec<usize>],
+
+    /// From the target up
+    path: Vec<usize>,
+
+    chains: Vec<Vec<&'a CrateSummary>>,
+    truncated: bool,
+}
+
+impl<'a, 'b> Walk<'a, 'b> {
+    fn visit(&mut self, i: usize) {
+        if self.truncated || self.path.contains(&i) {
+            return;
+        }
+        self.path.push(i);
+        if self.crates[i].workspace_member {
+            if self.chains.len() == MAX_CHAINS {
+                self.truncated = true;
+            } else {
+                let crates = self.crates;
+                self.chains
+                    .push(self.path.iter().rev().map(|&i| &crates[i]).collect());
+            }
+        } else {
+            for &dependent in &self.dependents[i] {
+                self.visit(dependent);
+            }
+        }
+        self.path.pop();
+    }
+}
+
+/// The packages reachable from `root`, including itself, up to the workspace members
+fn subtree(by_id: &HashMap<String, &CrateSummary>, root: &str) -> BTreeSet<String> {
+    let mut subtree = BTreeSet::new();
+    let mut stack = vec![root];
+    while let Some(id) = stack.pop() {
+        let summary = match by_id.get(id) {
+            Some(summary) if !summary.workspace_member => summary,
+            _ => continue,
+        };
+        if subtree.insert(id.to_string()) {
+            stack.extend(summary.dependencies.iter().map(String::as_str));
+        }
+    }
+    subtree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::{self, findings};
+
+    fn summary(name: &str, workspace_member: bool, dependencies: &[&str]) -> CrateSummary {
+        CrateSummary {
+            workspace_member,
+            dependencies: fixture::ids(dependencies),
+            ..fixture::summary(name)
+        }
+    }
+
+    #[test]
+    fn chains_end_at_workspace_members() {
+        let crates = vec![
+            summary("app", true, &["tokio", "hyper", "cli"]),
+            summary("cli", true, &["tokio"]),
+            summary("tokio", false, &["libc"]),
+            summary("hyper", false, &["tokio"]),
+            summary("libc", false, &[]),
+        ];
+        let (chains, truncated) = chains(&crates, &crates[4]);
+        let chains = chains
+            .into_iter()
+            .map(|chain| {
+                chain
+                    .into_iter()
+                    .map(|summary| summary.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            chains,
+            vec![
+                "app -> tokio -> libc",
+                "cli -> tokio -> libc",
+                "app -> hyper -> tokio -> libc",
+            ]
+        );
+        assert!(!truncated);
+    }
+
+    #[test]
+    fn shared_crates_are_not_exclusive() {
+        let crates = vec![
+            summary("app", true, &["tokio", "hyper"]),
+            summary("tokio", false, &["libc"]),
+            summary("hyper", false, &["tokio", "httparse"]),
+            summary("libc", false, &[]),
+            summary("httparse", false, &[]),
+        ];
+        let findings = [
+            findings("app", 1),
+            findings("tokio", 5),
+            findings("libc", 2),
+            findings("httparse", 3),
+        ]
+        .concat();
+        let summaries = summarize(&crates, &findings)
+            .into_iter()
+            .map(|summary| {
+                (
+                    summary.name,
+                    summary.crates,
+                    summary.findings,
+                    summary.exclusive_findings,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summaries,
+            vec![
+                ("hyper".to_string(), 4, 10, 3),
+                ("tokio".to_string(), 2, 7, 0),
+            ]
+        );
+    }
+}
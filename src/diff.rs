@@ -0,0 +1,184 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::report::{Finding, Report};
+
+/// The findings that differ between two reports, matched by fingerprint
+pub struct ReportDiff<'a> {
+    /// Findings in the head report but not in the base report
+    pub added: Vec<&'a Finding>,
+
+    /// Findings in the base report but not in the head report
+    pub removed: Vec<&'a Finding>,
+
+    /// Findings in both reports, but at another file or line in the head report, as
+    /// `(base, head)`: code that was moved around rather than changed
+    pub moved: Vec<(&'a Finding, &'a Finding)>,
+
+    /// Findings in both reports, but under other `cfg` conditions, with other `_unchecked` calls,
+    /// tags, kind or hook in the head report, as `(base, head)`
+    pub changed: Vec<(&'a Finding, &'a Finding)>,
+}
+
+pub fn diff<'a>(base: &'a Report, head: &'a Report) -> ReportDiff<'a> {
+    let base_fingerprints = fingerprints(base);
+    let head_fingerprints = fingerprints(head);
+    let mut base_by_fingerprint = HashMap::<_, Vec<_>>::new();
+    for finding in &base.findings {
+        base_by_fingerprint
+            .entry(finding.fingerprint.as_str())
+            .or_default()
+            .push(finding);
+    }
+    ReportDiff {
+        added: head
+            .findings
+            .iter()
+            .filter(|finding| !base_fingerprints.contains(finding.fingerprint.as_str()))
+            .collect(),
+        removed: base
+            .findings
+            .iter()
+            .filter(|finding| !head_fingerprints.contains(finding.fingerprint.as_str()))
+            .collect(),
+        moved: head
+            .findings
+            .iter()
+            .filter_map(|finding| {
+                let matches = base_by_fingerprint.get(finding.fingerprint.as_str())?;
+                // Each version of a crate in the tree has the same fingerprints.
+                if matches
+                    .iter()
+                    .any(|base| base.file == finding.file && base.line == finding.line)
+                {
+                    None
+                } else {
+                    Some((matches[0], finding))
+                }
+            })
+            .collect(),
+        changed: head
+            .findings
+            .iter()
+            .filter_map(|finding| {
+                let matches = base_by_fingerprint.get(finding.fingerprint.as_str())?;
+                if matches.iter().any(|base| same_facts(base, finding)) {
+                    None
+                } else {
+                    Some((matches[0], finding))
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Whether two findings with the same fingerprint say the same about the code, wherever it is
+fn same_facts(a: &Finding, b: &Finding) -> bool {
+    a.kind == b.kind
+        && a.cfg == b.cfg
+        && a.unchecked == b.unchecked
+        && a.tags == b.tags
+        && a.hook == b.hook
+}
+
+/// Reasons why the findings of two reports may differ other than changes to the code. Reports
+/// without metadata are not checked.
+pub fn incomparabilities(base: &Report, head: &Report) -> Vec<String> {
+    let (base, head) = (&base.metadata, &head.metadata);
+    let mut reasons = Vec::new();
+    for (what, base, head) in [
+        (
+            "siderophile version",
+            &base.siderophile_version,
+            &head.siderophile_version,
+        ),
+        ("rustc version", &base.rustc_version, &head.rustc_version),
+        ("target", &base.target, &head.target),
+    ] {
+        if !base.is_empty() && !head.is_empty() && base != head {
+            reasons.push(format!("{what} differs: {base} vs. {head}"));
+        }
+    }
+    if !base.features.is_empty() && !head.features.is_empty() && base.features != head.features {
+        reasons.push("enabled features differ".to_string());
+    }
+    reasons
+}
+
+/// Warns about each of the `incomparabilities`
+pub fn warn_if_incomparable(base: &Report, head: &Report) {
+    for reason in incomparabilities(base, head) {
+        eprintln!("Warning: The reports may not be comparable: {reason}");
+    }
+}
+
+fn fingerprints(report: &Report) -> HashSet<&str> {
+    report
+        .findings
+        .iter()
+        .map(|finding| finding.fingerprint.as_str())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+    use std::path::Path;
+
+    fn finding(fingerprint: &str, file: &str, line: usize) -> Finding {
+        Finding {
+            file: Path::new(file).into(),
+            fingerprint: fingerprint.to_string(),
+            ..fixture::finding("foo", line)
+        }
+    }
+
+    fn report(findings: Vec<Finding>) -> Report {
+        Report::new(Vec::new(), findings, &HashMap::new())
+    }
+
+    #[test]
+    fn tells_moved_findings_from_added_and_removed_ones() {
+        let base = report(vec![
+            finding("kept", "src/lib.rs", 10),
+            finding("moved", "src/lib.rs", 20),
+            finding("removed", "src/lib.rs", 30),
+        ]);
+        let head = report(vec![
+            finding("kept", "src/lib.rs", 10),
+            finding("moved", "src/util.rs", 5),
+            finding("added", "src/util.rs", 15),
+        ]);
+        let diff = diff(&base, &head);
+        let fingerprints = |findings: &[&Finding]| {
+            findings
+                .iter()
+                .map(|finding| finding.fingerprint.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(fingerprints(&diff.added), ["added"]);
+        assert_eq!(fingerprints(&diff.removed), ["removed"]);
+        assert_eq!(diff.moved.len(), 1);
+        let (from, to) = diff.moved[0];
+        assert_eq!((&*from.file, from.line), (Path::new("src/lib.rs"), 20));
+        assert_eq!((&*to.file, to.line), (Path::new("src/util.rs"), 5));
+        assert!(diff.changed.is_empty());
+    }
+
+    #[test]
+    fn tells_changed_findings() {
+        let base = report(vec![
+            finding("same", "src/lib.rs", 10),
+            finding("unchecked", "src/lib.rs", 20),
+        ]);
+        let mut unchecked = finding("unchecked", "src/lib.rs", 20);
+        unchecked.unchecked = vec!["get_unchecked".to_string()];
+        let head = report(vec![finding("same", "src/lib.rs", 12), unchecked]);
+        let diff = diff(&base, &head);
+        assert_eq!(diff.moved.len(), 1);
+        assert_eq!(diff.changed.len(), 1);
+        let (from, to) = diff.changed[0];
+        assert!(from.unchecked.is_empty());
+        assert_eq!(to.unchecked, ["get_unchecked"]);
+    }
+}
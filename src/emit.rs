@@ -0,0 +1,280 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Context};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use structopt::StructOpt;
+
+use crate::report::{FindingKind, Report};
+
+/// The companion of `Cargo.lock` written by `--emit lockfile-annotations`, next to it
+const LOCKFILE_ANNOTATIONS: &str = "Cargo.unsafe.toml";
+
+/// The compact metrics written by `--emit metrics-json`, next to `Cargo.lock`
+const METRICS_JSON: &str = "siderophile-metrics.json";
+
+const LOCKFILE_ANNOTATIONS_HEADER: &str = "\
+# The unsafe code in each package of Cargo.lock, written by siderophile.
+# Commit it along with Cargo.lock so that dependency changes show how the unsafe code changes.
+";
+
+#[derive(StructOpt, Debug)]
+pub struct EmitOpts {
+    #[structopt(long = "emit", value_name = "KIND", number_of_values = 1)]
+    /// Also write the given kind of file (may be given more than once): lockfile-annotations
+    /// writes `Cargo.unsafe.toml` next to `Cargo.lock`, and metrics-json writes per-crate counts
+    /// to `siderophile-metrics.json`
+    emit: Vec<Emit>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Emit {
+    LockfileAnnotations,
+    MetricsJson,
+}
+
+impl FromStr for Emit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "lockfile-annotations" => Ok(Self::LockfileAnnotations),
+            "metrics-json" => Ok(Self::MetricsJson),
+            _ => Err(anyhow!("Unknown kind of file `{}`", s)),
+        }
+    }
+}
+
+/// The packages of `Cargo.lock`, in its order
+#[derive(Debug, Serialize)]
+struct LockfileAnnotations {
+    package: Vec<PackageAnnotation>,
+}
+
+#[derive(Debug, Serialize)]
+struct PackageAnnotation {
+    name: String,
+
+    version: String,
+
+    /// As in `Cargo.lock`, which leaves it out for path dependencies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+
+    unsafe_functions: usize,
+
+    unsafe_blocks: usize,
+
+    /// Digest of the fingerprints of the package's findings, which changes whenever they do
+    fingerprint: String,
+}
+
+/// The numbers of a report without its findings, small enough to keep for every build
+#[derive(Debug, Serialize)]
+struct Metrics {
+    siderophile_version: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
+
+    findings: usize,
+
+    badness: BadnessMetrics,
+
+    /// Sorted by name and version
+    crates: Vec<CrateMetrics>,
+}
+
+#[derive(Debug, Serialize)]
+struct BadnessMetrics {
+    /// The functions of the analyzed crate that reach unsafe code
+    functions: usize,
+
+    max: u32,
+
+    total: u64,
+
+    mean: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct CrateMetrics {
+    name: String,
+
+    version: String,
+
+    findings: usize,
+
+    /// By kind, leaving out kinds without findings
+    kinds: BTreeMap<&'static str, usize>,
+
+    /// The source files with findings
+    files: usize,
+
+    /// Findings per file with findings, i.e., how concentrated the unsafe code is
+    #[serde(skip_serializing_if = "Option::is_none")]
+    density: Option<f64>,
+
+    /// The crate's share of the findings of the whole tree
+    share: f64,
+
+    /// See `CrateSummary::encapsulation`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encapsulation: Option<f64>,
+
+    blast_radius: usize,
+}
+
+/// Writes the files requested with `--emit` for the workspace at `root`
+pub fn emit(opts: &EmitOpts, root: &Path, report: &Report) -> anyhow::Result<()> {
+    for emit in &opts.emit {
+        match emit {
+            Emit::LockfileAnnotations => {
+                let path = root.join(LOCKFILE_ANNOTATIONS);
+                let contents = format!(
+                    "{}\n{}",
+                    LOCKFILE_ANNOTATIONS_HEADER,
+                    toml::to_string(&lockfile_annotations(report))?
+                );
+                fs::write(&path, contents)
+                    .with_context(|| format!("Could not write `{}`", path.display()))?;
+            }
+            Emit::MetricsJson => {
+                let path = root.join(METRICS_JSON);
+                let mut json = serde_json::to_vec(&metrics(report))?;
+                json.push(b'\n');
+                fs::write(&path, json)
+                    .with_context(|| format!("Could not write `{}`", path.display()))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn lockfile_annotations(report: &Report) -> LockfileAnnotations {
+    let mut findings = BTreeMap::<(&str, &str), Vec<_>>::new();
+    for finding in &report.findings {
+        findings
+            .entry((&*finding.krate, finding.provenance.version.as_str()))
+            .or_default()
+            .push(finding);
+    }
+    let mut package = report
+        .crates
+        .iter()
+        .map(|summary| {
+            let mut findings = findings
+                .remove(&(summary.name.as_str(), summary.version.as_str()))
+                .unwrap_or_default();
+            findings.sort_by(|a, b| a.fingerprint.cmp(&b.fingerprint));
+            let mut hasher = Sha256::new();
+            for finding in &findings {
+                hasher.update(finding.fingerprint.as_bytes());
+                hasher.update([0u8]);
+            }
+            let count = |kind| {
+                findings
+                    .iter()
+                    .filter(|finding| finding.kind == kind)
+                    .count()
+            };
+            PackageAnnotation {
+                name: summary.name.clone(),
+                version: summary.version.clone(),
+                source: Some(summary.source.clone())
+                    .filter(|source| !source.is_empty() && !source.starts_with("path+")),
+                unsafe_functions: count(FindingKind::Function),
+                unsafe_blocks: count(FindingKind::Block),
+                fingerprint: hasher
+                    .finalize()
+                    .iter()
+                    .take(8)
+                    .map(|b| format!("{b:02x}"))
+                    .collect(),
+            }
+        })
+        .collect::<Vec<_>>();
+    package.sort_by(|a, b| (&a.name, &a.version, &a.source).cmp(&(&b.name, &b.version, &b.source)));
+    LockfileAnnotations { package }
+}
+
+fn metrics(report: &Report) -> Metrics {
+    let mut findings = BTreeMap::<(&str, &str), Vec<_>>::new();
+    for finding in &report.findings {
+        findings
+            .entry((&*finding.krate, finding.provenance.version.as_str()))
+            .or_default()
+            .push(finding);
+    }
+    let mut crates = report
+        .crates
+        .iter()
+        .map(|summary| {
+            let findings = findings
+                .remove(&(summary.name.as_str(), summary.version.as_str()))
+                .unwrap_or_default();
+            let mut kinds = BTreeMap::new();
+            for finding in &findings {
+                *kinds.entry(finding.kind.as_str()).or_default() += 1;
+            }
+            let files = findings
+                .iter()
+                .map(|finding| &finding.file)
+                .collect::<BTreeSet<_>>()
+                .len();
+            CrateMetrics {
+                name: summary.name.clone(),
+                version: summary.version.clone(),
+                findings: findings.len(),
+                kinds,
+                files,
+                density: (files > 0).then(|| findings.len() as f64 / files as f64),
+                share: if report.findings.is_empty() {
+                    0.0
+                } else {
+                    findings.len() as f64 / report.findings.len() as f64
+                },
+                encapsulation: summary.encapsulation(),
+                blast_radius: summary.blast_radius,
+            }
+        })
+        .collect::<Vec<_>>();
+    crates.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+    let total = report
+        .badness
+        .iter()
+        .map(|entry| u64::from(entry.badness))
+        .sum::<u64>();
+    let badness = BadnessMetrics {
+        functions: report.badness.len(),
+        max: report
+            .badness
+            .iter()
+            .map(|entry| entry.badness)
+            .max()
+            .unwrap_or_default(),
+        total,
+        mean: if report.badness.is_empty() {
+            0.0
+        } else {
+            total as f64 / report.badness.len() as f64
+        },
+    };
+    Metrics {
+        siderophile_version: report.metadata.siderophile_version.clone(),
+        commit: report
+            .metadata
+            .inputs
+            .as_ref()
+            .and_then(|inputs| inputs.commit.clone()),
+        findings: report.findings.len(),
+        badness,
+        crates,
+    }
+}
@@ -0,0 +1,179 @@
+use std::{error::Error, fmt, io, path::PathBuf, str::FromStr, sync::PoisonError};
+
+use anyhow::anyhow;
+use cargo::core::PackageId;
+
+use crate::{
+    cancel::Cancelled,
+    report::{ScanIssue, SkipReason, SkippedCrate},
+    trawl_source::ScanFileError,
+};
+
+/// Everything that can go wrong while building and scanning the tree
+#[derive(Debug)]
+pub enum ScanError {
+    Walkdir(walkdir::Error),
+
+    /// Like io::Error but with the related path.
+    Io(io::Error, PathBuf),
+
+    /// Would like cargo::Error here, but it's private, why?
+    /// This is still way better than a panic though.
+    Cargo(String),
+
+    /// This should not happen unless incorrect assumptions have been made in
+    /// `siderophile` about how the cargo API works.
+    ArcUnwrap(),
+
+    /// Failed to get the inner context out of the mutex.
+    InnerContextMutex(String),
+
+    /// Failed to parse a .dep file.
+    DepParse(String, PathBuf),
+
+    /// Failed to read or parse a source file.
+    Scan(ScanFileError),
+
+    /// Failed to download a package.
+    Download(String),
+
+    /// A vendored package is missing, is not in the lockfile, or does not match its checksums, or
+    /// a `.crate` archive has no package in it.
+    Vendor(String, PathBuf),
+
+    /// The scan was cancelled, e.g., by `--timeout`.
+    Cancelled(Cancelled),
+}
+
+impl ScanError {
+    /// The file the error is about, if any
+    pub fn path(&self) -> Option<PathBuf> {
+        match self {
+            Self::Walkdir(e) => e.path().map(PathBuf::from),
+            Self::Io(_, path) | Self::DepParse(_, path) | Self::Vendor(_, path) => {
+                Some(path.clone())
+            }
+            Self::Scan(e) => Some(e.path().to_path_buf()),
+            Self::Cargo(_)
+            | Self::ArcUnwrap()
+            | Self::InnerContextMutex(_)
+            | Self::Download(_)
+            | Self::Cancelled(_) => None,
+        }
+    }
+}
+
+impl Error for ScanError {}
+
+/// Forward Display to Debug.
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl From<Cancelled> for ScanError {
+    fn from(e: Cancelled) -> Self {
+        Self::Cancelled(e)
+    }
+}
+
+impl<T> From<PoisonError<T>> for ScanError {
+    fn from(e: PoisonError<T>) -> Self {
+        Self::InnerContextMutex(e.to_string())
+    }
+}
+
+/// What to do when a single item (a package, a source file or a dep file) cannot be scanned
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnError {
+    /// Record the error in the report and carry on
+    Skip,
+
+    /// Abort the run
+    Fail,
+}
+
+impl FromStr for OnError {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "fail" => Ok(Self::Fail),
+            _ => Err(anyhow!("Unknown error policy `{}`", s)),
+        }
+    }
+}
+
+/// Applies the recovery policy to errors affecting single items, collecting the skipped ones
+pub struct Recovery {
+    on_error: OnError,
+    pub issues: Vec<ScanIssue>,
+
+    /// The packages that errors left unscanned
+    pub skipped: Vec<SkippedCrate>,
+}
+
+impl Recovery {
+    pub const fn new(on_error: OnError) -> Self {
+        Self {
+            on_error,
+            issues: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    pub fn recover(
+        &mut self,
+        package: Option<PackageId>,
+        error: ScanError,
+    ) -> Result<(), ScanError> {
+        self.recover_named(package.map(|id| id.name().to_string()), error)
+    }
+
+    /// Like `recover`, for a package cargo has no id for, e.g., a vendored one
+    pub fn recover_named(
+        &mut self,
+        package: Option<String>,
+        error: ScanError,
+    ) -> Result<(), ScanError> {
+        if self.on_error == OnError::Fail {
+            return Err(error);
+        }
+        warn!("Skipping after error: {}", error);
+        self.issues.push(ScanIssue {
+            package,
+            path: error.path(),
+            message: error.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Like `recover_named`, for an error that leaves a whole package unscanned, which is recorded
+    /// among the skipped packages rather than logged
+    pub fn skip(
+        &mut self,
+        name: String,
+        version: String,
+        reason: SkipReason,
+        error: ScanError,
+    ) -> Result<(), ScanError> {
+        if self.on_error == OnError::Fail {
+            return Err(error);
+        }
+        debug!("Skipping `{} v{}`: {}", name, version, error);
+        self.issues.push(ScanIssue {
+            package: Some(name.clone()),
+            path: error.path(),
+            message: error.to_string(),
+        });
+        self.skipped.push(SkippedCrate {
+            krate: name,
+            version,
+            reason,
+            detail: Some(error.to_string()),
+        });
+        Ok(())
+    }
+}
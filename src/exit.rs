@@ -0,0 +1,152 @@
+use std::{
+    error::Error,
+    fmt, fs,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use anyhow::anyhow;
+use serde::Serialize;
+use structopt::StructOpt;
+
+use crate::error::ScanError;
+
+/// The documented exit codes. Anything that is not one of the more specific failures is a scan
+/// error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Code {
+    Success = 0,
+    OverThreshold = 1,
+    PolicyViolation = 2,
+    ScanError = 10,
+    BuildFailure = 11,
+    Usage = 12,
+}
+
+impl Code {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::OverThreshold => "over-threshold",
+            Self::PolicyViolation => "policy-violation",
+            Self::ScanError => "scan-error",
+            Self::BuildFailure => "build-failure",
+            Self::Usage => "usage",
+        }
+    }
+}
+
+/// An error that should make the run exit with a specific code
+#[derive(Debug)]
+pub struct Failure {
+    pub code: Code,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn new(code: Code, message: impl Into<String>) -> anyhow::Error {
+        Self {
+            code,
+            message: message.into(),
+        }
+        .into()
+    }
+}
+
+impl Error for Failure {}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum ErrorFormat {
+    Human,
+    Json,
+}
+
+impl FromStr for ErrorFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow!("Unknown error format `{}`", s)),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ExitOpts {
+    #[structopt(long = "error-format", value_name = "FORMAT", default_value = "human")]
+    /// How the run status is reported: human or json
+    error_format: ErrorFormat,
+
+    #[structopt(long = "status-file", value_name = "PATH")]
+    /// With `--error-format json`, write the run status to PATH instead of stderr
+    status_file: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct Status<'a> {
+    code: i32,
+    status: &'a str,
+    message: Option<String>,
+}
+
+/// The exit code for the result of a run
+pub fn classify(result: &anyhow::Result<()>) -> Code {
+    let err = if let Err(err) = result {
+        err
+    } else {
+        return Code::Success;
+    };
+    err.chain()
+        .find_map(|cause| {
+            if let Some(failure) = cause.downcast_ref::<Failure>() {
+                Some(failure.code)
+            } else if let Some(ScanError::Cargo(_)) = cause.downcast_ref::<ScanError>() {
+                Some(Code::BuildFailure)
+            } else {
+                None
+            }
+        })
+        .unwrap_or(Code::ScanError)
+}
+
+/// Reports how the run went in the requested format and returns the process exit code
+pub fn report_status(opts: &ExitOpts, result: &anyhow::Result<()>) -> i32 {
+    let code = classify(result);
+    match opts.error_format {
+        ErrorFormat::Human => {
+            if let Err(err) = result {
+                eprintln!("Error: {err:?}");
+            }
+        }
+        ErrorFormat::Json => {
+            let status = Status {
+                code: code as i32,
+                status: code.as_str(),
+                message: result.as_ref().err().map(|err| format!("{err:#}")),
+            };
+            if let Err(err) = write_status(opts, &status) {
+                eprintln!("Error: could not write run status: {err:?}");
+            }
+        }
+    }
+    code as i32
+}
+
+fn write_status(opts: &ExitOpts, status: &Status) -> anyhow::Result<()> {
+    let json = serde_json::to_string_pretty(status)?;
+    if let Some(path) = &opts.status_file {
+        fs::write(path, json + "\n")?;
+    } else {
+        writeln!(io::stderr(), "{json}")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,806 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    iter::Peekable,
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail, Context};
+use regex::Regex;
+use structopt::StructOpt;
+
+use crate::{
+    category, dependency,
+    report::{self, CrateSummary, Finding, GeneratedCode, Report, SkipReason, SkippedCrate},
+    utils::LabelInfo,
+};
+
+#[derive(StructOpt, Debug)]
+pub struct FilterOpts {
+    #[structopt(long = "filter", value_name = "EXPR")]
+    /// Only report the findings for which EXPR holds, e.g., `crate =~ "sys$" && kind == "block"`
+    /// (see the README for the fields and operators)
+    filter: Option<Filter>,
+
+    #[structopt(long = "collapse-generated")]
+    /// Report the findings in files generated by bindgen or cbindgen as one entry per crate
+    collapse_generated: bool,
+
+    #[structopt(long = "exclude-stdlib")]
+    /// Leave the standard library crates and their replacements, like `compiler_builtins`, out of
+    /// the findings and totals, and list them separately
+    exclude_stdlib: bool,
+}
+
+impl FilterOpts {
+    pub const fn filter(&self) -> Option<&Filter> {
+        self.filter.as_ref()
+    }
+
+    /// Whether the findings are collapsed or left out depending on their crates
+    pub const fn reshapes_report(&self) -> bool {
+        self.collapse_generated || self.exclude_stdlib
+    }
+
+    /// The functions of the findings that the filter selects, for their badness to be traced, or
+    /// `None` if there is no filter
+    pub fn tainted(&self, report: &Report) -> Option<Vec<String>> {
+        let filter = self.filter.as_ref()?;
+        Some(
+            select(filter, report)
+                .map(|finding| finding.item.clone())
+                .collect(),
+        )
+    }
+
+    /// `report` with only the findings that the filter selects, or all of them if there is no
+    /// filter, and with those in generated files collapsed and the standard library left out if
+    /// requested. The totals are recounted from the findings kept, and the badness table is
+    /// replaced with `badness`, traced from the `tainted` functions. Without it, e.g., for a report
+    /// read back in, the badness of the whole report is kept, since there is no callgraph to trace
+    /// it through.
+    pub fn apply(
+        &self,
+        report: &Report,
+        badness: Option<&HashMap<String, (u32, LabelInfo)>>,
+    ) -> Report {
+        let mut report = report.clone();
+        if let Some(filter) = &self.filter {
+            let findings = select(filter, &report).cloned().collect::<Vec<_>>();
+            report::count_findings(&mut report.crates, &findings);
+            report.categories = category::summarize(&report.crates);
+            report.dependencies = dependency::summarize(&report.crates, &findings);
+            report.findings = findings;
+            if let Some(badness) = badness {
+                retrace(&mut report, badness);
+            }
+        }
+        if self.exclude_stdlib {
+            exclude_stdlib(&mut report);
+        }
+        if self.collapse_generated {
+            collapse_generated(&mut report);
+        }
+        report
+    }
+}
+
+/// The findings of `report` that `filter` selects
+fn select<'a>(filter: &'a Filter, report: &'a Report) -> impl Iterator<Item = &'a Finding> {
+    let crates = report
+        .crates
+        .iter()
+        .map(|summary| ((summary.name.as_str(), summary.version.as_str()), summary))
+        .collect::<HashMap<_, _>>();
+    report.findings.iter().filter(move |finding| {
+        let summary = crates
+            .get(&(&*finding.krate, finding.provenance.version.as_str()))
+            .copied();
+        filter.matches(finding, summary)
+    })
+}
+
+/// Replaces the badness of the functions in the badness table of `report` with `badness`, leaving
+/// out those that it no longer reaches, and keeps only the calls between the functions still in it
+fn retrace(report: &mut Report, badness: &HashMap<String, (u32, LabelInfo)>) {
+    let only_in = report
+        .badness
+        .iter()
+        .map(|entry| (entry.function.as_str(), entry.only_in))
+        .collect::<HashMap<_, _>>();
+    // The table may have been restricted already, e.g., to the changed files.
+    let mut entries = report::badness_entries(badness);
+    entries.retain(|entry| only_in.contains_key(entry.function.as_str()));
+    for entry in &mut entries {
+        entry.only_in = only_in[entry.function.as_str()];
+    }
+    let functions = entries
+        .iter()
+        .map(|entry| entry.function.as_str())
+        .collect::<BTreeSet<_>>();
+    report.calls.retain(|call| {
+        functions.contains(call.caller.as_str()) && functions.contains(call.callee.as_str())
+    });
+    report.api = report::rank_api(std::mem::take(&mut report.api), &entries);
+    report.badness = entries;
+}
+
+/// Moves the crates of the `stdlib` category to `report.stdlib`, and leaves their findings out.
+/// They are also listed as skipped.
+fn exclude_stdlib(report: &mut Report) {
+    let (stdlib, crates) = std::mem::take(&mut report.crates)
+        .into_iter()
+        .partition::<Vec<_>, _>(|summary| summary.category == category::STDLIB);
+    let excluded = stdlib
+        .iter()
+        .map(|summary| (summary.name.as_str(), summary.version.as_str()))
+        .collect::<BTreeSet<_>>();
+    report.findings.retain(|finding| {
+        !excluded.contains(&(&*finding.krate, finding.provenance.version.as_str()))
+    });
+    report.categories = category::summarize(&crates);
+    report.dependencies = dependency::summarize(&crates, &report.findings);
+    report.crates = crates;
+    report
+        .skipped
+        .extend(stdlib.iter().map(|summary| SkippedCrate {
+            krate: summary.name.clone(),
+            version: summary.version.clone(),
+            reason: SkipReason::Stdlib,
+            detail: None,
+        }));
+    report.skipped.sort();
+    report.stdlib = stdlib;
+}
+
+/// Moves the findings in generated files to `report.generated`, one entry per package. The counts
+/// of the crates still include them.
+fn collapse_generated(report: &mut Report) {
+    let mut generated = BTreeMap::<_, (BTreeSet<_>, usize)>::new();
+    for finding in report.findings.iter().filter(|finding| finding.generated) {
+        let (files, findings) = generated
+            .entry((
+                finding.krate.to_string(),
+                finding.provenance.version.clone(),
+            ))
+            .or_default();
+        files.insert(finding.file.clone());
+        *findings += 1;
+    }
+    report.findings.retain(|finding| !finding.generated);
+    report.generated = generated
+        .into_iter()
+        .map(|((krate, version), (files, findings))| GeneratedCode {
+            krate,
+            version,
+            files: files.len(),
+            findings,
+        })
+        .collect();
+}
+
+/// A boolean expression over the fields of a finding and of its crate
+#[derive(Clone, Debug)]
+pub struct Filter(Expr);
+
+impl Filter {
+    pub fn matches(&self, finding: &Finding, summary: Option<&CrateSummary>) -> bool {
+        self.0.eval(finding, summary)
+    }
+
+    /// Whether the filter refers to the fields of crates, which are only known once the crates
+    /// have been scanned
+    pub fn uses_crate_fields(&self) -> bool {
+        self.0.uses_crate_fields()
+    }
+}
+
+impl FromStr for Filter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut tokens = tokenize(s)?.into_iter().peekable();
+        let expr = parse_or(&mut tokens)?;
+        if let Some(token) = tokens.next() {
+            bail!("Unexpected {} in filter", token.describe());
+        }
+        Ok(Self(expr))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Crate,
+    File,
+    Line,
+    Item,
+    Kind,
+    Version,
+    Source,
+    Fingerprint,
+    Cfg,
+    Unchecked,
+    Tags,
+    Severity,
+    Hook,
+    Generated,
+    InTest,
+    Category,
+    License,
+    WorkspaceMember,
+    NoStd,
+    ProcMacro,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Type {
+    Str,
+    Int,
+    Bool,
+}
+
+impl Field {
+    fn parse(name: &str) -> anyhow::Result<Self> {
+        Ok(match name {
+            "crate" => Self::Crate,
+            "file" => Self::File,
+            "line" => Self::Line,
+            "item" => Self::Item,
+            "kind" => Self::Kind,
+            "version" => Self::Version,
+            "source" => Self::Source,
+            "fingerprint" => Self::Fingerprint,
+            "cfg" => Self::Cfg,
+            "unchecked" => Self::Unchecked,
+            "tags" => Self::Tags,
+            "severity" => Self::Severity,
+            "hook" => Self::Hook,
+            "generated" => Self::Generated,
+            "in_test" => Self::InTest,
+            "category" => Self::Category,
+            "license" => Self::License,
+            "workspace_member" => Self::WorkspaceMember,
+            "no_std" => Self::NoStd,
+            "proc_macro" => Self::ProcMacro,
+            _ => bail!("Unknown field `{}` in filter", name),
+        })
+    }
+
+    const fn ty(self) -> Type {
+        match self {
+            Self::Line => Type::Int,
+            Self::Generated
+            | Self::InTest
+            | Self::WorkspaceMember
+            | Self::NoStd
+            | Self::ProcMacro => Type::Bool,
+            _ => Type::Str,
+        }
+    }
+
+    const fn of_crate(self) -> bool {
+        matches!(
+            self,
+            Self::Category | Self::License | Self::WorkspaceMember | Self::NoStd | Self::ProcMacro
+        )
+    }
+
+    /// The field's value for `finding`. Fields of the crate are empty or false if the report has
+    /// no summary of it.
+    fn value(self, finding: &Finding, summary: Option<&CrateSummary>) -> Value {
+        match self {
+            Self::Crate => Value::Str(finding.krate.to_string()),
+            Self::File => Value::Str(finding.file.to_string_lossy().into_owned()),
+            Self::Line => Value::Int(finding.line as i64),
+            Self::Item => Value::Str(finding.item.clone()),
+            Self::Kind => Value::Str(finding.kind.as_str().to_string()),
+            Self::Version => Value::Str(finding.provenance.version.clone()),
+            Self::Source => Value::Str(finding.provenance.source.clone()),
+            Self::Fingerprint => Value::Str(finding.fingerprint.clone()),
+            Self::Cfg => Value::Str(finding.cfg.clone().unwrap_or_default()),
+            Self::Unchecked => Value::Str(finding.unchecked.join(",")),
+            Self::Tags => Value::Str(
+                finding
+                    .tags
+                    .iter()
+                    .map(|tag| tag.as_str())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            Self::Severity => Value::Str(finding.severity().as_str().to_string()),
+            Self::Hook => Value::Str(finding.hook.clone().unwrap_or_default()),
+            Self::Generated => Value::Bool(finding.generated),
+            Self::InTest => Value::Bool(finding.cfg.as_deref().is_some_and(requires_test)),
+            Self::Category => Value::Str(
+                summary
+                    .map(|summary| summary.category.clone())
+                    .unwrap_or_default(),
+            ),
+            Self::License => Value::Str(
+                summary
+                    .and_then(|summary| summary.license.clone())
+                    .unwrap_or_default(),
+            ),
+            Self::WorkspaceMember => {
+                Value::Bool(summary.is_some_and(|summary| summary.workspace_member))
+            }
+            Self::NoStd => Value::Bool(summary.is_some_and(|summary| summary.no_std)),
+            Self::ProcMacro => Value::Bool(summary.is_some_and(|summary| summary.proc_macro)),
+        }
+    }
+}
+
+/// Whether the `cfg` conditions of a finding include `test`, i.e., it is only compiled for tests.
+/// `#[test]` functions count as being under `cfg(test)`.
+fn requires_test(cfg: &str) -> bool {
+    let Some(conditions) = cfg
+        .strip_prefix("all(")
+        .and_then(|cfg| cfg.strip_suffix(')'))
+    else {
+        return cfg == "test";
+    };
+    // The conditions are separated by the commas outside of parentheses.
+    let mut depth = 0_usize;
+    let mut start = 0;
+    for (i, c) in conditions.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                if requires_test(conditions[start..i].trim()) {
+                    return true;
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    requires_test(conditions[start..].trim())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+}
+
+impl Value {
+    const fn ty(&self) -> Type {
+        match self {
+            Self::Str(_) => Type::Str,
+            Self::Int(_) => Type::Int,
+            Self::Bool(_) => Type::Bool,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Field, Comparison, Value),
+    Matches(Field, Regex),
+    /// A boolean field on its own
+    Field(Field),
+}
+
+impl Expr {
+    fn uses_crate_fields(&self) -> bool {
+        match self {
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.uses_crate_fields() || right.uses_crate_fields()
+            }
+            Self::Not(expr) => expr.uses_crate_fields(),
+            Self::Compare(field, ..) | Self::Matches(field, _) | Self::Field(field) => {
+                field.of_crate()
+            }
+        }
+    }
+
+    fn eval(&self, finding: &Finding, summary: Option<&CrateSummary>) -> bool {
+        match self {
+            Self::And(left, right) => left.eval(finding, summary) && right.eval(finding, summary),
+            Self::Or(left, right) => left.eval(finding, summary) || right.eval(finding, summary),
+            Self::Not(expr) => !expr.eval(finding, summary),
+            Self::Compare(field, comparison, value) => {
+                let actual = field.value(finding, summary);
+                match comparison {
+                    Comparison::Eq => actual == *value,
+                    Comparison::Ne => actual != *value,
+                    Comparison::Lt => actual < *value,
+                    Comparison::Le => actual <= *value,
+                    Comparison::Gt => actual > *value,
+                    Comparison::Ge => actual >= *value,
+                }
+            }
+            Self::Matches(field, regex) => match field.value(finding, summary) {
+                Value::Str(actual) => regex.is_match(&actual),
+                _ => false,
+            },
+            Self::Field(field) => field.value(finding, summary) == Value::Bool(true),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Int(i64),
+    Compare(Comparison),
+    Match,
+    NotMatch,
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Self::Ident(name) => format!("`{name}`"),
+            Self::Str(s) => format!("{s:?}"),
+            Self::Int(n) => format!("`{n}`"),
+            Self::Compare(comparison) => format!("`{}`", comparison_str(*comparison)),
+            Self::Match => "`=~`".to_string(),
+            Self::NotMatch => "`!~`".to_string(),
+            Self::And => "`&&`".to_string(),
+            Self::Or => "`||`".to_string(),
+            Self::Not => "`!`".to_string(),
+            Self::LParen => "`(`".to_string(),
+            Self::RParen => "`)`".to_string(),
+        }
+    }
+}
+
+const fn comparison_str(comparison: Comparison) -> &'static str {
+    match comparison {
+        Comparison::Eq => "==",
+        Comparison::Ne => "!=",
+        Comparison::Lt => "<",
+        Comparison::Le => "<=",
+        Comparison::Gt => ">",
+        Comparison::Ge => ">=",
+    }
+}
+
+fn tokenize(s: &str) -> anyhow::Result<Vec<Token>> {
+    const OPERATORS: &[(&str, Token)] = &[
+        ("==", Token::Compare(Comparison::Eq)),
+        ("!=", Token::Compare(Comparison::Ne)),
+        ("<=", Token::Compare(Comparison::Le)),
+        (">=", Token::Compare(Comparison::Ge)),
+        ("=~", Token::Match),
+        ("!~", Token::NotMatch),
+        ("&&", Token::And),
+        ("||", Token::Or),
+        ("<", Token::Compare(Comparison::Lt)),
+        (">", Token::Compare(Comparison::Gt)),
+        ("!", Token::Not),
+        ("(", Token::LParen),
+        (")", Token::RParen),
+    ];
+
+    let mut tokens = Vec::new();
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        let c = rest.chars().next().unwrap_or_default();
+        if let Some((op, token)) = OPERATORS.iter().find(|(op, _)| rest.starts_with(op)) {
+            tokens.push(token.clone());
+            rest = &rest[op.len()..];
+        } else if c == '"' {
+            let (s, after) = string_literal(&rest[1..])?;
+            tokens.push(Token::Str(s));
+            rest = after;
+        } else if c.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let n = rest[..end]
+                .parse()
+                .with_context(|| format!("Invalid number `{}` in filter", &rest[..end]))?;
+            tokens.push(Token::Int(n));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else {
+            bail!("Unexpected `{}` in filter", c);
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+/// Reads a string literal up to its closing quote, which has already been opened. `\"` and `\\`
+/// are the only escapes, so that regexes can be written as they would be anywhere else.
+fn string_literal(s: &str) -> anyhow::Result<(String, &str)> {
+    let mut literal = String::new();
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((literal, &s[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, c @ ('"' | '\\'))) => literal.push(c),
+                Some((_, c)) => {
+                    literal.push('\\');
+                    literal.push(c);
+                }
+                None => break,
+            },
+            _ => literal.push(c),
+        }
+    }
+    bail!("Unterminated string in filter")
+}
+
+type Tokens = Peekable<std::vec::IntoIter<Token>>;
+
+fn parse_or(tokens: &mut Tokens) -> anyhow::Result<Expr> {
+    let mut expr = parse_and(tokens)?;
+    while tokens.next_if_eq(&Token::Or).is_some() {
+        expr = Expr::Or(Box::new(expr), Box::new(parse_and(tokens)?));
+    }
+    Ok(expr)
+}
+
+fn parse_and(tokens: &mut Tokens) -> anyhow::Result<Expr> {
+    let mut expr = parse_unary(tokens)?;
+    while tokens.next_if_eq(&Token::And).is_some() {
+        expr = Expr::And(Box::new(expr), Box::new(parse_unary(tokens)?));
+    }
+    Ok(expr)
+}
+
+fn parse_unary(tokens: &mut Tokens) -> anyhow::Result<Expr> {
+    match tokens.next() {
+        Some(Token::Not) => Ok(Expr::Not(Box::new(parse_unary(tokens)?))),
+        Some(Token::LParen) => {
+            let expr = parse_or(tokens)?;
+            match tokens.next() {
+                Some(Token::RParen) => Ok(expr),
+                Some(token) => bail!("Expected `)` in filter, found {}", token.describe()),
+                None => bail!("Expected `)` at the end of filter"),
+            }
+        }
+        Some(Token::Ident(name)) => parse_predicate(&name, tokens),
+        Some(token) => bail!("Expected a field in filter, found {}", token.describe()),
+        None => bail!("Expected a field at the end of filter"),
+    }
+}
+
+/// Parses what follows the field `name`: a comparison with a value, a match against a regex, or
+/// nothing if the field is boolean
+fn parse_predicate(name: &str, tokens: &mut Tokens) -> anyhow::Result<Expr> {
+    let field = Field::parse(name)?;
+    match tokens.peek() {
+        Some(Token::Compare(comparison)) => {
+            let comparison = *comparison;
+            tokens.next();
+            let value = match tokens.next() {
+                Some(Token::Str(s)) => Value::Str(s),
+                Some(Token::Int(n)) => Value::Int(n),
+                Some(Token::Ident(ident)) if ident == "true" => Value::Bool(true),
+                Some(Token::Ident(ident)) if ident == "false" => Value::Bool(false),
+                Some(token) => bail!(
+                    "Expected a value after `{}` in filter, found {}",
+                    name,
+                    token.describe()
+                ),
+                None => bail!("Expected a value at the end of filter"),
+            };
+            if value.ty() != field.ty() {
+                bail!("`{}` cannot be compared with {:?}", name, value);
+            }
+            if field.ty() == Type::Bool && !matches!(comparison, Comparison::Eq | Comparison::Ne) {
+                bail!(
+                    "`{}` is true or false, so it cannot be compared with `{}`",
+                    name,
+                    comparison_str(comparison)
+                );
+            }
+            Ok(Expr::Compare(field, comparison, value))
+        }
+        Some(Token::Match | Token::NotMatch) => {
+            let negated = tokens.next() == Some(Token::NotMatch);
+            if field.ty() != Type::Str {
+                bail!("`{}` is not a string, so it cannot be matched", name);
+            }
+            let pattern = match tokens.next() {
+                Some(Token::Str(pattern)) => pattern,
+                _ => bail!("Expected a regex string after `{}` in filter", name),
+            };
+            let regex = Regex::new(&pattern)
+                .map_err(|error| anyhow!("Invalid regex {:?} in filter: {}", pattern, error))?;
+            let expr = Expr::Matches(field, regex);
+            Ok(if negated {
+                Expr::Not(Box::new(expr))
+            } else {
+                expr
+            })
+        }
+        _ if field.ty() == Type::Bool => Ok(Expr::Field(field)),
+        _ => bail!("Expected a comparison after `{}` in filter", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixture, report::FindingKind};
+
+    fn finding(krate: &str, line: usize, kind: FindingKind) -> Finding {
+        Finding {
+            kind,
+            ..fixture::finding(krate, line)
+        }
+    }
+
+    fn matches(filter: &str, finding: &Finding) -> bool {
+        filter.parse::<Filter>().unwrap().matches(finding, None)
+    }
+
+    #[test]
+    fn evaluate() {
+        let sys = finding("openssl-sys", 10, FindingKind::Block);
+        let fun = finding("libc", 200, FindingKind::Function);
+        let filter = r#"crate =~ "sys$" && kind == "block" && !workspace_member"#;
+        assert!(matches(filter, &sys));
+        assert!(!matches(filter, &fun));
+        assert!(matches(r#"line >= 100 || crate == "openssl-sys""#, &sys));
+        assert!(matches(r#"!(line < 100) && item !~ "^openssl""#, &fun));
+        assert!(!matches(r#"version != "1.0.0""#, &fun));
+        assert!(matches("!generated", &fun));
+    }
+
+    #[test]
+    fn in_test() {
+        let in_cfg = |cfg: &str| Finding {
+            cfg: Some(cfg.to_string()),
+            ..finding("libc", 1, FindingKind::Block)
+        };
+        assert!(matches("in_test", &in_cfg("test")));
+        assert!(matches("in_test", &in_cfg("all(unix, test)")));
+        assert!(matches("in_test", &in_cfg("all(any(unix, windows), test)")));
+        assert!(!matches("in_test", &in_cfg("not(test)")));
+        assert!(!matches("in_test", &in_cfg("any(test, debug_assertions)")));
+        assert!(!matches(
+            "in_test",
+            &in_cfg("all(unix, feature = \"test\")")
+        ));
+        assert!(matches("!in_test", &finding("libc", 1, FindingKind::Block)));
+    }
+
+    #[test]
+    fn crate_fields() {
+        let uses_crate_fields =
+            |filter: &str| filter.parse::<Filter>().unwrap().uses_crate_fields();
+        assert!(!uses_crate_fields(r#"crate =~ "sys$" && !in_test"#));
+        assert!(uses_crate_fields(
+            r#"kind == "block" || !(category =~ "ffi")"#
+        ));
+        assert!(uses_crate_fields("!workspace_member"));
+    }
+
+    #[test]
+    fn recounts_and_retraces_the_selected_findings() {
+        let app = CrateSummary {
+            workspace_member: true,
+            dependencies: fixture::ids(&["libc", "zstd-sys"]),
+            ..fixture::summary("app")
+        };
+        let crates = vec![app, fixture::summary("libc"), fixture::summary("zstd-sys")];
+        let mut findings = fixture::findings("libc", 2);
+        findings.extend(fixture::findings("zstd-sys", 1));
+        let badness = HashMap::from([
+            ("app::a".to_string(), (3, LabelInfo::default())),
+            ("app::b".to_string(), (1, LabelInfo::default())),
+        ]);
+        let mut report = Report::new(crates, findings, &badness);
+        report.calls = vec![report::Call {
+            caller: "app::a".to_string(),
+            callee: "app::b".to_string(),
+        }];
+        let opts = FilterOpts {
+            filter: Some(r#"crate == "libc""#.parse().unwrap()),
+            collapse_generated: false,
+            exclude_stdlib: false,
+        };
+        assert_eq!(opts.tainted(&report), Some(vec!["libc::f".to_string(); 2]));
+
+        let filtered_badness = HashMap::from([("app::a".to_string(), (2, LabelInfo::default()))]);
+        let filtered = opts.apply(&report, Some(&filtered_badness));
+        assert_eq!(filtered.findings.len(), 2);
+        let counts = filtered
+            .crates
+            .iter()
+            .map(|summary| (summary.name.as_str(), summary.findings))
+            .collect::<Vec<_>>();
+        assert_eq!(counts, [("app", 0), ("libc", 2), ("zstd-sys", 0)]);
+        let categorized = filtered
+            .categories
+            .iter()
+            .map(|category| category.findings)
+            .sum::<usize>();
+        assert_eq!(categorized, 2);
+        let dependencies = filtered
+            .dependencies
+            .iter()
+            .map(|dependency| (dependency.name.as_str(), dependency.findings))
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(dependencies["libc"], 2);
+        assert_eq!(dependencies["zstd-sys"], 0);
+        let badness = filtered
+            .badness
+            .iter()
+            .map(|entry| (entry.function.as_str(), entry.badness))
+            .collect::<Vec<_>>();
+        assert_eq!(badness, [("app::a", 2)]);
+        assert!(filtered.calls.is_empty());
+
+        // Without a callgraph, the badness is kept.
+        assert_eq!(opts.apply(&report, None).badness.len(), 2);
+    }
+
+    #[test]
+    fn collapses_generated_findings() {
+        let mut report = Report::new(Vec::new(), Vec::new(), &HashMap::new());
+        report.findings = vec![
+            finding("zstd-sys", 1, FindingKind::Function),
+            finding("zstd-sys", 2, FindingKind::Function),
+            finding("zstd-sys", 3, FindingKind::Block),
+        ];
+        report.findings[0].generated = true;
+        report.findings[1].generated = true;
+        let opts = FilterOpts {
+            filter: None,
+            collapse_generated: true,
+            exclude_stdlib: false,
+        };
+        let report = opts.apply(&report, None);
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(
+            report.generated,
+            [GeneratedCode {
+                krate: "zstd-sys".to_string(),
+                version: "1.0.0".to_string(),
+                files: 1,
+                findings: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        for filter in [
+            "",
+            "unknown == 1",
+            r#"line == "1""#,
+            "crate",
+            r#"crate =~ "(""#,
+            r#"crate == "a" &&"#,
+            r#"(crate == "a""#,
+            r#"crate == "a" )"#,
+            "no_std < true",
+            r#"crate == "a"#,
+        ] {
+            assert!(filter.parse::<Filter>().is_err(), "{filter}");
+        }
+    }
+}
@@ -0,0 +1,273 @@
+//! What a finding is: the construct it was found in, the patterns it is tagged with and where it
+//! was found. Shared by the binary and the library, whose `scan_dir` reports the same findings as
+//! the trawl.
+
+use std::{fmt, path::Path, str::FromStr, sync::Arc};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The syntactic construct a finding was found in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// An `unsafe fn`, either free-standing or a method
+    Function,
+
+    /// An `unsafe { ... }` expression
+    Block,
+
+    /// A manual `impl Unpin`, which lets pinned values be moved, and so has to agree with any
+    /// unsafe pin projections of the type
+    UnpinImpl,
+
+    /// A `#[global_allocator]` static, which replaces the allocator of the whole program
+    GlobalAllocator,
+
+    /// An item that claims another global hook, like `#[panic_handler]`, see `Finding::hook`
+    GlobalHook,
+
+    /// A function or static that runs before `main` or after it returns, through `#[ctor]`,
+    /// `#[dtor]` or a `#[link_section]` like `.init_array`
+    Constructor,
+
+    /// Another item placed in a section with `#[link_section]`, or kept with `#[used]`
+    LinkSection,
+
+    /// A function of an `extern "C++"` block, or of an `extern "Rust"` block of a
+    /// `#[cxx::bridge]` module, which crosses between Rust and C++ without an `unsafe` of its own
+    FfiBridge,
+
+    /// A JavaScript function imported by a `#[wasm_bindgen] extern "C"` block, which wasm-bindgen
+    /// generates the glue of
+    JsImport,
+}
+
+impl FindingKind {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Function => "function",
+            Self::Block => "block",
+            Self::UnpinImpl => "unpin_impl",
+            Self::GlobalAllocator => "global_allocator",
+            Self::GlobalHook => "global_hook",
+            Self::Constructor => "constructor",
+            Self::LinkSection => "link_section",
+            Self::FfiBridge => "ffi_bridge",
+            Self::JsImport => "js_import",
+        }
+    }
+}
+
+impl fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A pattern of unsafety that reviewers want to see listed apart from the rest
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tag {
+    /// Pinning: `Pin::new_unchecked`, `Pin::get_unchecked_mut` and the like, or an `impl Unpin`
+    Pin,
+
+    /// Unsafe code in a `Drop::drop` implementation, where double frees and uses after free lurk
+    Drop,
+
+    /// A `#[global_allocator]`, or a method of an impl of `GlobalAlloc`
+    Allocator,
+
+    /// Code that runs before `main`, see `FindingKind::Constructor`
+    BeforeMain,
+
+    /// Code in a build script's string literals, a `#[cxx::bridge]` module or a Markdown code
+    /// block, see `--scan-embedded`
+    Embedded,
+}
+
+impl Tag {
+    /// The severity of the findings with the tag
+    #[must_use]
+    pub const fn severity(self) -> Severity {
+        match self {
+            Self::Allocator | Self::BeforeMain => Severity::High,
+            Self::Pin | Self::Drop | Self::Embedded => Severity::Normal,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Pin => "pin",
+            Self::Drop => "drop",
+            Self::Allocator => "allocator",
+            Self::BeforeMain => "before_main",
+            Self::Embedded => "embedded",
+        }
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Tag {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "pin" => Ok(Self::Pin),
+            "drop" => Ok(Self::Drop),
+            "allocator" => Ok(Self::Allocator),
+            "before_main" => Ok(Self::BeforeMain),
+            "embedded" => Ok(Self::Embedded),
+            _ => Err(anyhow!("Unknown tag `{s}`")),
+        }
+    }
+}
+
+/// How much scrutiny a finding calls for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    Normal,
+
+    /// Security review must see it, whatever else it leaves out
+    High,
+}
+
+impl Severity {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::High => "high",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single piece of unsafety found while trawling the sources. The crate name and file are
+/// shared between the findings of a trawl, of which there can be very many.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    /// Name of the package the finding is in
+    #[serde(rename = "crate")]
+    pub krate: Arc<str>,
+
+    pub file: Arc<Path>,
+
+    pub line: usize,
+
+    /// Which build of the package was scanned
+    #[serde(flatten)]
+    pub provenance: Arc<Provenance>,
+
+    /// Path of the enclosing item, e.g. `parking_lot::raw_mutex::RawMutex::unlock_slow`
+    pub item: String,
+
+    pub kind: FindingKind,
+
+    /// See `fingerprint`
+    pub fingerprint: String,
+
+    /// The `cfg` conditions the finding is compiled under, e.g. `not(debug_assertions)`, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cfg: Option<String>,
+
+    /// The `_unchecked` functions the unsafe block or function calls, e.g. `get_unchecked`, which
+    /// often say more about what it does than the block itself
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unchecked: Vec<String>,
+
+    /// The patterns the finding is an instance of, see `Tag`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<Tag>,
+
+    /// The global hook the item claims, e.g. `panic_handler`, `lang = "eh_personality"` or
+    /// `global_allocator`. A program can only have one of each.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook: Option<String>,
+
+    /// Whether the finding is in a file that looks generated by bindgen or cbindgen (see
+    /// `trawl_source::generated`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub generated: bool,
+}
+
+/// Identifies the exact build of a package, as recorded in `Cargo.lock`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Provenance {
+    pub version: String,
+
+    /// The source id, e.g. `registry+https://github.com/rust-lang/crates.io-index`
+    pub source: String,
+
+    /// Only packages from registries have one
+    pub checksum: Option<String>,
+}
+
+impl Finding {
+    /// The order findings are reported in
+    #[must_use]
+    pub fn location_key(&self) -> (&str, &Path, usize) {
+        (&*self.krate, &*self.file, self.line)
+    }
+
+    /// The kind of the finding, followed by the unchecked functions it calls and its tags, if any
+    #[must_use]
+    pub fn describe_kind(&self) -> String {
+        let mut description = self.kind.to_string();
+        if !self.unchecked.is_empty() {
+            description = format!("{description} calling {}", self.unchecked.join(", "));
+        }
+        if !self.tags.is_empty() {
+            let tags = self.tags.iter().map(|tag| tag.as_str()).collect::<Vec<_>>();
+            description = format!("{description} [{}]", tags.join(", "));
+        }
+        description
+    }
+
+    #[must_use]
+    pub fn has_tag(&self, tag: Tag) -> bool {
+        self.tags.contains(&tag)
+    }
+
+    /// The highest severity of the finding's tags
+    #[must_use]
+    pub fn severity(&self) -> Severity {
+        self.tags
+            .iter()
+            .map(|tag| tag.severity())
+            .max()
+            .unwrap_or(Severity::Normal)
+    }
+}
+
+/// Identifies a finding independently of its file and line, so that it survives code being moved
+/// around. `ordinal` tells apart findings of the same kind within the same item.
+#[must_use]
+pub fn fingerprint(krate: &str, item: &str, kind: FindingKind, ordinal: usize) -> String {
+    let ordinal = ordinal.to_string();
+    let mut hasher = Sha256::new();
+    for part in [krate, item, kind.as_str(), ordinal.as_str()] {
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .take(8)
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
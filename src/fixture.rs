@@ -0,0 +1,79 @@
+//! Findings and crate summaries for tests. Every field is defaulted, so that tests only spell out
+//! the fields they are about, with struct update syntax, e.g.,
+//! `Finding { kind: FindingKind::Function, ..fixture::finding("libc", 10) }`.
+
+use std::{path::Path, sync::Arc};
+
+use crate::{
+    dependency,
+    report::{CrateSummary, Finding, FindingKind, Provenance},
+};
+
+/// The version of every fixture
+pub const VERSION: &str = "1.0.0";
+
+/// An unsafe block in `krate::f`, in `src/lib.rs` at `line`
+pub fn finding(krate: &str, line: usize) -> Finding {
+    Finding {
+        krate: krate.into(),
+        file: Path::new("src/lib.rs").into(),
+        line,
+        provenance: Arc::new(Provenance {
+            version: VERSION.to_string(),
+            ..Provenance::default()
+        }),
+        item: format!("{krate}::f"),
+        kind: FindingKind::Block,
+        fingerprint: format!("{krate}{line}"),
+        cfg: None,
+        unchecked: Vec::new(),
+        tags: Vec::new(),
+        hook: None,
+        generated: false,
+    }
+}
+
+/// `finding` in another version of its crate
+pub fn in_version(mut finding: Finding, version: &str) -> Finding {
+    finding.provenance = Arc::new(Provenance {
+        version: version.to_string(),
+        ..Provenance::clone(&finding.provenance)
+    });
+    finding
+}
+
+/// `n` findings in `krate`, on lines 0 to `n - 1`
+pub fn findings(krate: &str, n: usize) -> Vec<Finding> {
+    (0..n).map(|line| finding(krate, line)).collect()
+}
+
+/// A dependency without findings
+pub fn summary(name: &str) -> CrateSummary {
+    CrateSummary {
+        name: name.to_string(),
+        version: VERSION.to_string(),
+        source: String::new(),
+        checksum: None,
+        workspace_member: false,
+        no_std: false,
+        proc_macro: false,
+        repository: None,
+        license: None,
+        latest_version: None,
+        category: String::new(),
+        coverage: Default::default(),
+        dependencies: Vec::new(),
+        findings: 0,
+        blast_radius: 0,
+        public_unsafe_fns: Vec::new(),
+        public_unsafe_traits: Vec::new(),
+    }
+}
+
+/// The ids of the fixtures named `names`, for `CrateSummary::dependencies`
+pub fn ids(names: &[&str]) -> Vec<String> {
+    names
+        .iter()
+        .map(|name| dependency::id(name, VERSION))
+        .collect()
+}
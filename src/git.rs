@@ -0,0 +1,44 @@
+use std::{ffi::OsStr, path::Path, process::Command};
+
+use anyhow::bail;
+
+/// Runs git with `args` in the current directory and returns its stdout
+pub fn git(args: &[&OsStr]) -> anyhow::Result<String> {
+    let output = Command::new("git").args(args).output()?;
+    if !output.status.success() {
+        bail!(
+            "`git {}` failed:\n{}",
+            args.iter()
+                .map(|arg| arg.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// The commit checked out in the repository containing `dir` and whether `dir` has uncommitted
+/// changes, or `(None, false)` if `dir` is not in a git repository
+pub fn commit(dir: &Path) -> (Option<String>, bool) {
+    let dir = dir.as_os_str();
+    let head = match git(&[
+        OsStr::new("-C"),
+        dir,
+        OsStr::new("rev-parse"),
+        OsStr::new("HEAD"),
+    ]) {
+        Ok(head) => head,
+        Err(_) => return (None, false),
+    };
+    let status = git(&[
+        OsStr::new("-C"),
+        dir,
+        OsStr::new("status"),
+        OsStr::new("--porcelain"),
+        OsStr::new("--"),
+        OsStr::new("."),
+    ]);
+    let dirty = !status.is_ok_and(|status| status.trim().is_empty());
+    (Some(head.trim().to_string()), dirty)
+}
@@ -0,0 +1,153 @@
+//! With `--golden`, reports are normalized so that they only change when the unsafe footprint does,
+//! and can be committed as golden files that downstream test suites compare against
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use structopt::StructOpt;
+
+use crate::{
+    dependency,
+    report::{Metadata, Provenance, Report},
+};
+
+/// What versions are replaced with by `--elide-versions`
+const ELIDED: &str = "*";
+
+#[derive(StructOpt, Debug)]
+pub struct GoldenOpts {
+    #[structopt(long = "golden")]
+    /// Normalize the output for committing as a golden file: leave out the metadata and the
+    /// newest versions, make paths relative to the current directory, and sort everything
+    pub golden: bool,
+
+    #[structopt(long = "elide-versions", requires = "golden")]
+    /// With `--golden`, also replace the versions and checksums of the packages with `*`, so that
+    /// upgrades that do not change the findings leave the output alone
+    pub elide_versions: bool,
+}
+
+/// `report`, normalized if `--golden` was given
+pub fn normalize(opts: &GoldenOpts, mut report: Report) -> anyhow::Result<Report> {
+    if !opts.golden {
+        return Ok(report);
+    }
+    let root = std::env::current_dir()?;
+
+    // Everything but the features depends on the machine or the day
+    report.metadata = Metadata {
+        features: report.metadata.features,
+        ..Metadata::default()
+    };
+    for summary in report.crates.iter_mut().chain(&mut report.stdlib) {
+        summary.latest_version = None;
+        if let Some(path) = summary.source.strip_prefix("path+file://") {
+            summary.source = format!("path+{}", relative(Path::new(path), &root).display());
+        }
+    }
+    for entry in &mut report.badness {
+        entry.file = entry.file.as_deref().map(|file| relative(file, &root));
+    }
+    for issue in &mut report.errors {
+        issue.path = issue.path.as_deref().map(|path| relative(path, &root));
+    }
+    report
+        .errors
+        .sort_by(|a, b| (&a.package, &a.path, &a.message).cmp(&(&b.package, &b.path, &b.message)));
+    report.duplicates.sort_by(|a, b| a.krate.cmp(&b.krate));
+
+    if opts.elide_versions {
+        elide_versions(&mut report);
+    }
+    Ok(report)
+}
+
+/// `path` relative to `root` if it is below it
+fn relative(path: &Path, root: &Path) -> PathBuf {
+    path.strip_prefix(root).unwrap_or(path).to_path_buf()
+}
+
+fn elide_versions(report: &mut Report) {
+    for summary in report.crates.iter_mut().chain(&mut report.stdlib) {
+        summary.version = ELIDED.to_string();
+        summary.checksum = None;
+        for dependency in &mut summary.dependencies {
+            if let Some((name, _)) = dependency.split_once('@') {
+                *dependency = dependency::id(name, ELIDED);
+            }
+        }
+        summary.dependencies.dedup();
+    }
+    for finding in &mut report.findings {
+        finding.provenance = Arc::new(Provenance {
+            version: ELIDED.to_string(),
+            source: finding.provenance.source.clone(),
+            checksum: None,
+        });
+    }
+    for dependency in &mut report.dependencies {
+        dependency.version = ELIDED.to_string();
+    }
+    for id in &mut report.inactive {
+        if let Some((name, _)) = id.split_once('@') {
+            *id = dependency::id(name, ELIDED);
+        }
+    }
+    report.inactive.dedup();
+    for suggestion in &mut report.suggestions {
+        suggestion.version = ELIDED.to_string();
+    }
+    for mismatch in &mut report.checksum_mismatches {
+        mismatch.version = ELIDED.to_string();
+    }
+    for code in &mut report.generated {
+        code.version = ELIDED.to_string();
+    }
+    for skipped in &mut report.skipped {
+        skipped.version = ELIDED.to_string();
+    }
+    report.skipped.sort();
+    report.skipped.dedup();
+    for duplicate in &mut report.duplicates {
+        duplicate.versions.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixture, report::CrateSummary};
+
+    fn summary(name: &str, version: &str, dependencies: &[&str]) -> CrateSummary {
+        CrateSummary {
+            version: version.to_string(),
+            checksum: Some("0123".to_string()),
+            latest_version: Some("9.9.9".to_string()),
+            dependencies: dependencies.iter().map(ToString::to_string).collect(),
+            ..fixture::summary(name)
+        }
+    }
+
+    #[test]
+    fn elides_versions_on_request() {
+        let mut report = Report::new(
+            vec![summary("app", "0.1.0", &["libc@0.2.1", "libc@0.2.2"])],
+            Vec::new(),
+            &Default::default(),
+        );
+        report.metadata.rustc_version = "rustc 1.70.0".to_string();
+        let opts = GoldenOpts {
+            golden: true,
+            elide_versions: true,
+        };
+        let report = normalize(&opts, report).unwrap();
+        assert_eq!(report.metadata, Metadata::default());
+        let summary = &report.crates[0];
+        assert_eq!(summary.version, "*");
+        assert_eq!(summary.checksum, None);
+        assert_eq!(summary.latest_version, None);
+        assert_eq!(summary.dependencies, ["libc@*"]);
+    }
+}
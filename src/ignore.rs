@@ -0,0 +1,188 @@
+//! `.siderophileignore`, a gitignore-like file at the root of the workspace listing the files of
+//! the workspace that are not scanned, e.g. generated code or vendored third-party code
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use cargo::{core::Workspace, util::important_paths::find_root_manifest_for_wd};
+use glob::{MatchOptions, Pattern};
+
+const IGNORE_FILE: &str = ".siderophileignore";
+
+/// `*` and `?` do not match `/`, as in `.gitignore`
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug)]
+struct Rule {
+    pattern: Pattern,
+
+    /// Whether the pattern had a `/` other than a trailing one, and so is matched against the
+    /// path relative to the root rather than against the file name
+    anchored: bool,
+
+    /// `!pattern`: the files are scanned after all
+    negated: bool,
+
+    /// `pattern/`: only directories match
+    dir_only: bool,
+}
+
+/// The ignored files of a workspace
+#[derive(Debug, Default)]
+pub struct Ignore {
+    /// Canonicalized, like the paths of the files that are scanned
+    root: PathBuf,
+
+    rules: Vec<Rule>,
+}
+
+/// The `.siderophileignore` of the workspace in the current directory of `config`, which ignores
+/// nothing if there is none
+pub fn load(config: &cargo::Config) -> anyhow::Result<Ignore> {
+    let manifest = find_root_manifest_for_wd(config.cwd())?;
+    let ws = Workspace::new(&manifest, config)?;
+    Ignore::read(ws.root())
+}
+
+impl Ignore {
+    /// The `.siderophileignore` in `root`, which ignores nothing if there is none
+    pub fn read(root: &Path) -> anyhow::Result<Self> {
+        let path = root.join(IGNORE_FILE);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Could not read `{}`", path.display()))
+            }
+        };
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        Self::parse(root, &contents)
+            .with_context(|| format!("Could not parse `{}`", path.display()))
+    }
+
+    fn parse(root: PathBuf, contents: &str) -> anyhow::Result<Self> {
+        let mut rules = Vec::new();
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(line) => (true, line),
+                None => (false, line),
+            };
+            // `\#` and `\!` start patterns with a literal `#` or `!`
+            let line = line.strip_prefix('\\').unwrap_or(line);
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(line) => (true, line),
+                None => (false, line),
+            };
+            let anchored = line.contains('/');
+            let pattern = Pattern::new(line.strip_prefix('/').unwrap_or(line))
+                .with_context(|| format!("Invalid pattern on line {}", i + 1))?;
+            rules.push(Rule {
+                pattern,
+                anchored,
+                negated,
+                dir_only,
+            });
+        }
+        Ok(Self { root, rules })
+    }
+
+    /// Whether `path`, which is canonicalized, is ignored. Files outside of the workspace never
+    /// are.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+        let relative = match path.strip_prefix(&self.root) {
+            Ok(relative) => relative,
+            Err(_) => return false,
+        };
+        let components = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>();
+        // As with git, the files of an ignored directory cannot be unignored.
+        for end in 1..=components.len() {
+            let is_dir = end < components.len();
+            let ignored = self.matches(&components[..end].join("/"), &components[end - 1], is_dir);
+            if ignored || !is_dir {
+                return ignored;
+            }
+        }
+        false
+    }
+
+    /// Whether the last rule matching the file or directory at `relative`, named `name`, ignores
+    /// it
+    fn matches(&self, relative: &str, name: &str, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                (is_dir || !rule.dir_only)
+                    && if rule.anchored {
+                        rule.pattern.matches_with(relative, MATCH_OPTIONS)
+                    } else {
+                        rule.pattern.matches_with(name, MATCH_OPTIONS)
+                    }
+            })
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignore(contents: &str) -> Ignore {
+        Ignore::parse(PathBuf::from("/ws"), contents).unwrap()
+    }
+
+    fn ignored(ignore: &Ignore, path: &str) -> bool {
+        ignore.is_ignored(&Path::new("/ws").join(path))
+    }
+
+    #[test]
+    fn patterns() {
+        let ignore = ignore(
+            "\
+# Generated by build.rs
+generated/
+/third_party/*/src
+*_pb.rs
+!keep_pb.rs
+",
+        );
+        assert!(ignored(&ignore, "generated/foo.rs"));
+        assert!(ignored(&ignore, "crates/a/generated/foo.rs"));
+        assert!(!ignored(&ignore, "crates/generated.rs"));
+        assert!(ignored(&ignore, "third_party/zlib/src/lib.rs"));
+        assert!(!ignored(&ignore, "crates/third_party/zlib/src/lib.rs"));
+        assert!(!ignored(&ignore, "third_party/zlib/build.rs"));
+        assert!(ignored(&ignore, "src/proto/api_pb.rs"));
+        assert!(!ignored(&ignore, "src/proto/keep_pb.rs"));
+        assert!(!ignored(&ignore, "src/lib.rs"));
+        assert!(!ignore.is_ignored(Path::new("/registry/generated/foo.rs")));
+    }
+
+    #[test]
+    fn ignored_directories_stay_ignored() {
+        let ignore = ignore("generated/\n!generated/keep.rs\n");
+        assert!(ignored(&ignore, "generated/keep.rs"));
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        assert!(Ignore::parse(PathBuf::from("/ws"), "ok\n[\n").is_err());
+    }
+}
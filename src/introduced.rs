@@ -0,0 +1,120 @@
+//! When each dependency with findings was first added to `Cargo.lock`, and by whom, from the
+//! history of the lockfile
+
+use std::{collections::HashMap, ffi::OsStr, path::Path};
+
+use crate::{
+    git::git,
+    report::{CrateSummary, Introduced},
+};
+
+/// Marks the start of each commit in the log, which no line of a diff starts with
+const COMMIT: &str = "commit\t";
+
+/// The commit that first added each package with findings outside of the workspace to the
+/// lockfile at `lockfile`, sorted by package name. Empty if the lockfile is not in a git
+/// repository.
+pub fn introduced(lockfile: &Path, crates: &[CrateSummary]) -> Vec<Introduced> {
+    let dir = lockfile.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = lockfile.file_name().unwrap_or_else(|| lockfile.as_os_str());
+    let log = match git(&[
+        OsStr::new("-C"),
+        dir.as_os_str(),
+        OsStr::new("log"),
+        OsStr::new("--reverse"),
+        OsStr::new("--patch"),
+        OsStr::new("--unified=0"),
+        OsStr::new("--no-color"),
+        OsStr::new("--no-ext-diff"),
+        OsStr::new(&format!("--format={COMMIT}%H%x09%an%x09%aI%x09%s")),
+        OsStr::new("--"),
+        file_name,
+    ]) {
+        Ok(log) => log,
+        Err(e) => {
+            debug!(
+                "Could not read the history of `{}`: {}",
+                lockfile.display(),
+                e
+            );
+            return Vec::new();
+        }
+    };
+    let mut first = parse_log(&log);
+    let mut introduced = crates
+        .iter()
+        .filter(|summary| !summary.workspace_member && summary.findings > 0)
+        .filter_map(|summary| first.remove(summary.name.as_str()))
+        .collect::<Vec<_>>();
+    introduced.sort_by(|a, b| a.krate.cmp(&b.krate));
+    introduced
+}
+
+/// The first commit of `git log --reverse --patch` of a lockfile that adds each package name
+fn parse_log(log: &str) -> HashMap<&str, Introduced> {
+    let mut first = HashMap::new();
+    let mut commit = None;
+    for line in log.lines() {
+        if let Some(header) = line.strip_prefix(COMMIT) {
+            let mut fields = header.splitn(4, '\t');
+            commit = match (fields.next(), fields.next(), fields.next()) {
+                (Some(commit), Some(author), Some(date)) => {
+                    Some((commit, author, date, fields.next().unwrap_or_default()))
+                }
+                _ => None,
+            };
+        } else if let Some(name) = line
+            .strip_prefix("+name = \"")
+            .and_then(|name| name.strip_suffix('"'))
+        {
+            if let Some((commit, author, date, subject)) = commit {
+                first.entry(name).or_insert_with(|| Introduced {
+                    krate: name.to_string(),
+                    commit: commit.to_string(),
+                    author: author.to_string(),
+                    date: date.to_string(),
+                    subject: subject.to_string(),
+                });
+            }
+        }
+    }
+    first
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_addition_wins() {
+        let log = "\
+commit\taaaa\tAlice\t2021-03-01T10:00:00+01:00\tAdd libc
+diff --git a/Cargo.lock b/Cargo.lock
+--- a/Cargo.lock
++++ b/Cargo.lock
+@@ -10,0 +11,4 @@
++[[package]]
++name = \"libc\"
++version = \"0.2.80\"
++
+commit\tbbbb\tBob\t2022-05-01T10:00:00+02:00\tBump libc, add memchr
+diff --git a/Cargo.lock b/Cargo.lock
+--- a/Cargo.lock
++++ b/Cargo.lock
+@@ -12 +12 @@
+-version = \"0.2.80\"
++version = \"0.2.140\"
+@@ -20,0 +21,3 @@
++[[package]]
++name = \"memchr\"
++name = \"libc\"
+";
+        let first = parse_log(log);
+        assert_eq!(first.len(), 2);
+        assert_eq!(first["libc"].commit, "aaaa");
+        assert_eq!(first["libc"].author, "Alice");
+        assert_eq!(first["libc"].subject, "Add libc");
+        assert_eq!(first["memchr"].commit, "bbbb");
+        assert_eq!(first["memchr"].date, "2022-05-01T10:00:00+02:00");
+    }
+}
@@ -2,7 +2,31 @@
 #![deny(clippy::unwrap_used, clippy::panic, clippy::expect_used, warnings)]
 #![deny(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 
+#[macro_use]
+extern crate tracing;
+
+mod api;
 mod callgraph_gen;
+mod cancel;
+mod finding;
+mod pool;
 mod utils;
-pub use callgraph_gen::{gen_callgraph, trace_unsafety};
+
+/// The parts of the binary's trawl that `api` scans with
+mod trawl_source {
+    pub mod ast_walker;
+    pub mod file;
+    pub mod generated;
+}
+
+pub use api::{
+    scan_dir, scan_dirs_stream, FileSink, FindingStream, Progress, ReportSink, ScanDirError,
+    ScanOptions, Scanned, StdoutSink, VecSink,
+};
+pub use callgraph_gen::{calls_between, gen_callgraph, trace_unsafety};
+pub use cancel::{CancellationToken, Cancelled};
+pub use finding::{Finding, FindingKind, Provenance, Severity, Tag};
+pub use trawl_source::ast_walker::{
+    find_unsafe_in_file, PublicFn, ScanFileError, UnsafeItem, UnsafeItems,
+};
 pub use utils::{configure_rustup_toolchain, simplify_trait_paths, CallGraph};
@@ -0,0 +1,83 @@
+use structopt::StructOpt;
+
+use crate::{
+    exit::{Code, Failure},
+    report::{self, FileCoverage, Finding},
+};
+
+#[derive(StructOpt, Debug)]
+pub struct LimitOpts {
+    #[structopt(long = "max-unsafe-per-crate", value_name = "N")]
+    /// Fail if any crate that is not exempt has more than N findings
+    max_unsafe_per_crate: Option<usize>,
+
+    #[structopt(
+        long = "exempt",
+        value_name = "CRATE",
+        number_of_values = 1,
+        requires = "max-unsafe-per-crate"
+    )]
+    /// Exempt CRATE from `--max-unsafe-per-crate` (may be given more than once)
+    exempt: Vec<String>,
+
+    #[structopt(long = "min-coverage", value_name = "PERCENT")]
+    /// Fail if fewer than PERCENT of the `.rs` files that the build compiled were scanned
+    min_coverage: Option<f64>,
+}
+
+pub fn check(opts: &LimitOpts, findings: &[Finding]) -> anyhow::Result<()> {
+    let max = if let Some(max) = opts.max_unsafe_per_crate {
+        max
+    } else {
+        return Ok(());
+    };
+
+    let violations = report::counts_by_crate(findings)
+        .into_iter()
+        .filter(|(krate, count)| *count > max && !opts.exempt.iter().any(|exempt| exempt == krate))
+        .map(|(krate, count)| format!("crate `{krate}` has {count} findings"))
+        .collect::<Vec<_>>();
+    if !violations.is_empty() {
+        return Err(Failure::new(
+            Code::OverThreshold,
+            format!(
+                "Crates exceed the limit of {} findings:\n    {}",
+                max,
+                violations.join("\n    ")
+            ),
+        ));
+    }
+    Ok(())
+}
+
+pub fn check_coverage(opts: &LimitOpts, coverage: Option<FileCoverage>) -> anyhow::Result<()> {
+    let min = if let Some(min) = opts.min_coverage {
+        min
+    } else {
+        return Ok(());
+    };
+
+    let coverage = match coverage {
+        Some(coverage) => coverage,
+        None => {
+            eprintln!(
+                "Warning: How many of the compiled files were scanned is unknown without a build \
+                 or with `--shard`, so `--min-coverage` was not checked"
+            );
+            return Ok(());
+        }
+    };
+    if coverage.percent() < min {
+        return Err(Failure::new(
+            Code::ScanError,
+            format!(
+                "Only {} of the {} files compiled were scanned ({:.1}%, below {}%)",
+                coverage.scanned,
+                coverage.compiled,
+                coverage.percent(),
+                min
+            ),
+        ));
+    }
+    Ok(())
+}
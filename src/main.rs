@@ -1,14 +1,45 @@
 #![forbid(unsafe_code)]
 
 #[macro_use]
-extern crate log;
+extern crate tracing;
 
+mod alternative;
+mod baseline;
+mod blast;
 mod callgraph_gen;
+mod cancel;
+mod category;
+mod changed;
+mod commands;
+mod crates_io;
+mod dependency;
+mod diff;
+mod emit;
+mod error;
+mod exit;
+mod filter;
+mod finding;
+#[cfg(test)]
+mod fixture;
+mod git;
+mod golden;
+mod ignore;
+mod introduced;
+mod limit;
+mod logging;
 mod mark_source;
+mod output;
+mod policy;
+mod profile;
+mod ratchet;
+mod report;
+mod scenario;
+mod shard;
+mod sign;
 mod trawl_source;
 mod utils;
 
-use std::collections::HashMap;
+use std::{collections::HashMap, io, path::Path, process, time::Duration};
 
 use anyhow::{anyhow, bail};
 use cargo::{
@@ -29,18 +60,98 @@ pub struct Args {
     /// Package to be used as the root of the tree
     package: Option<String>,
 
-    #[structopt(long = "include-tests")]
-    /// Count unsafe usage in tests.
-    include_tests: bool,
+    #[structopt(flatten)]
+    trawl_opts: trawl_source::TrawlOpts,
+
+    #[structopt(long = "format", value_name = "FORMAT", default_value = "text")]
+    /// Output format: text, json, jsonl, yaml, protobuf, html, folded, tree, dot, mermaid,
+    /// defectdojo, csv, markdown, sql, sqlite, checkstyle or gitlab
+    format: output::Format,
+
+    #[structopt(flatten)]
+    html_opts: output::html::HtmlOpts,
+
+    #[structopt(flatten)]
+    tree_opts: output::tree::TreeOpts,
+
+    #[structopt(flatten)]
+    text_opts: output::TextOpts,
+
+    #[structopt(flatten)]
+    sqlite_opts: output::sqlite::SqliteOpts,
+
+    #[structopt(flatten)]
+    filter_opts: filter::FilterOpts,
+
+    #[structopt(flatten)]
+    scenario_opts: scenario::ScenarioOpts,
+
+    #[structopt(flatten)]
+    emit_opts: emit::EmitOpts,
+
+    #[structopt(flatten)]
+    baseline_opts: baseline::BaselineOpts,
+
+    #[structopt(flatten)]
+    ratchet_opts: ratchet::RatchetOpts,
+
+    #[structopt(flatten)]
+    limit_opts: limit::LimitOpts,
+
+    #[structopt(flatten)]
+    policy_opts: policy::PolicyOpts,
+
+    #[structopt(flatten)]
+    alternative_opts: alternative::AlternativeOpts,
+
+    #[structopt(flatten)]
+    golden_opts: golden::GoldenOpts,
+
+    #[structopt(flatten)]
+    sign_opts: sign::SignOpts,
+
+    #[structopt(flatten)]
+    changed_opts: changed::ChangedOpts,
 
     #[structopt(flatten)]
     mark_opts: mark_source::MarkOpts,
+
+    #[structopt(flatten)]
+    exit_opts: exit::ExitOpts,
+
+    #[structopt(long = "log-format", value_name = "FORMAT", default_value = "text")]
+    /// Log format: text or json
+    log_format: logging::LogFormat,
+
+    #[structopt(subcommand)]
+    command: Option<commands::Command>,
+}
+
+struct Analysis {
+    /// The badness of every function, which the sources are marked with
+    badness: HashMap<String, (u32, utils::LabelInfo)>,
+
+    report: report::Report,
+
+    /// With `--filter`, the badness traced from the selected findings alone, for the report shown
+    filtered_badness: Option<HashMap<String, (u32, utils::LabelInfo)>>,
 }
 
-fn real_main(args: &Args) -> anyhow::Result<HashMap<String, (u32, utils::LabelInfo)>> {
-    let config = cargo::Config::default()?;
+fn real_main(args: &Args, policy: Option<&policy::Policy>) -> anyhow::Result<Analysis> {
+    let alternatives = alternative::load(&args.alternative_opts)?;
+    if let (Some(vendor_dir), Some(lockfile)) =
+        (&args.trawl_opts.vendor_dir, &args.trawl_opts.lockfile)
+    {
+        return analyze_vendored(args, policy, &alternatives, vendor_dir, lockfile);
+    }
+    let mut config = cargo::Config::default()?;
+    if args.trawl_opts.hermetic {
+        trawl_source::configure_offline(&mut config)?;
+    }
     let workspace_root = cargo::util::important_paths::find_root_manifest_for_wd(config.cwd())?;
     let ws = cargo::core::Workspace::new(&workspace_root, &config)?;
+    // Where `Cargo.lock` is, even when only one package is analyzed
+    let lock_root = ws.root().to_path_buf();
 
     let mut ws = if let Some(name) = &args.package {
         let package =
@@ -67,23 +178,233 @@ fn real_main(args: &Args) -> anyhow::Result<HashMap<String, (u32, utils::LabelIn
     }
 
     // new language, same horrible horrible hack. see PR#22 and related issues, this makes me sad....
-    utils::configure_rustup_toolchain();
+    utils::configure_rustup_toolchain()?;
 
+    // Shared with the scans of the scenarios and targets, so that they only rebuild what differs
+    let builds = trawl_source::BuildCache::default();
     // smoelius: `trawl_source::get_tainted` must be called before `callgraph_gen::gen_callgraph`
     // because `get_tainted` performs the build.
-    let tainted = trawl_source::get_tainted(&config, &ws, &args.package, args.include_tests)?;
-    let callgraph = callgraph_gen::gen_callgraph(&ws, &crate_name)?;
-    Ok(callgraph_gen::trace_unsafety(
-        &callgraph,
-        &crate_name,
-        &tainted,
-    ))
+    let trawl = info_span!("trawl").in_scope(|| {
+        trawl_source::get_tainted(&config, &ws, &args.package, &args.trawl_opts, None, &builds)
+    })?;
+    let findings = baseline::apply(&args.baseline_opts, trawl.findings)?;
+    let findings = if let Some(policy) = policy {
+        policy.waive(findings, chrono::Local::now().date_naive())
+    } else {
+        findings
+    };
+    let tainted = findings
+        .iter()
+        .map(|finding| finding.item.clone())
+        .collect::<Vec<_>>();
+    let benches = if args.trawl_opts.trace_benches {
+        bench_crate_names(&ws, &crate_name)
+    } else {
+        Vec::new()
+    };
+    let callgraph = info_span!("callgraph").in_scope(|| -> anyhow::Result<_> {
+        let mut callgraph = callgraph_gen::gen_callgraph(&ws, &crate_name)?;
+        for bench in &benches {
+            callgraph.merge(callgraph_gen::gen_callgraph(&ws, bench)?);
+        }
+        Ok(callgraph)
+    });
+    // A failed `-sys` build script stops the build before the crate's bitcode is emitted, but the
+    // findings are still worth reporting.
+    let callgraph = match callgraph {
+        Ok(callgraph) => callgraph,
+        Err(e)
+            if trawl
+                .crates
+                .iter()
+                .any(|summary| summary.coverage.build_failed) =>
+        {
+            eprintln!(
+                "Warning: The build failed, so there is no callgraph to compute badness from: {e}"
+            );
+            utils::CallGraph {
+                label_to_label_info: HashMap::new(),
+                short_label_to_labels: HashMap::new(),
+            }
+        }
+        Err(e) => return Err(e),
+    };
+    let cancel = &args.trawl_opts.cancel;
+    let trace = |tainted: &[String]| -> anyhow::Result<_> {
+        let mut badness = callgraph_gen::trace_unsafety(&callgraph, &crate_name, tainted, cancel)?;
+        for bench in &benches {
+            badness.extend(callgraph_gen::trace_unsafety(
+                &callgraph, bench, tainted, cancel,
+            )?);
+        }
+        Ok(badness)
+    };
+    let badness = info_span!("trace").in_scope(|| trace(&tainted))?;
+    // The scenarios are built after the callgraph is generated, since their builds replace the
+    // bitcode it is generated from.
+    let comparison = info_span!("scenarios").in_scope(|| {
+        scenario::compare(&args.scenario_opts, |scope| {
+            trawl_source::get_tainted(
+                &config,
+                &ws,
+                &args.package,
+                &args.trawl_opts,
+                Some(scope),
+                &builds,
+            )
+        })
+    })?;
+    let crate_names = std::iter::once(crate_name.as_str())
+        .chain(benches.iter().map(String::as_str))
+        .collect::<Vec<_>>();
+    let only_in = info_span!("profiles")
+        .in_scope(|| profile::only_in(&callgraph, &crate_names, &findings, &badness, cancel))?;
+    let blast_radius = info_span!("blast_radius")
+        .in_scope(|| blast::blast_radius(&callgraph, &crate_names, &findings, &badness, cancel))?;
+    let mut report = report::Report::new(trawl.crates, findings, &badness);
+    for entry in &mut report.badness {
+        entry.only_in = only_in.get(&entry.function).copied();
+    }
+    for summary in &mut report.crates {
+        summary.blast_radius = blast_radius
+            .get(&(summary.name.clone(), summary.version.clone()))
+            .copied()
+            .unwrap_or_default();
+    }
+    let functions = badness.keys().map(String::as_str).collect();
+    report.calls = callgraph_gen::calls_between(&callgraph, &functions)
+        .into_iter()
+        .map(|(caller, callee)| report::Call { caller, callee })
+        .collect();
+    report.errors = trawl.issues;
+    report.duplicates = trawl.duplicates;
+    report.inactive = trawl.inactive;
+    report.checksum_mismatches = trawl.checksum_mismatches;
+    report.file_coverage = trawl.file_coverage;
+    report.skipped = trawl.skipped;
+    if let Some(policy) = policy {
+        report
+            .skipped
+            .extend(policy.waived_crates(&report.crates, chrono::Local::now().date_naive()));
+        report.skipped.sort();
+    }
+    if !report.checksum_mismatches.is_empty() {
+        eprintln!(
+            "Warning: The sources of some registry packages do not match their checksums (see \
+             `checksum_mismatches` in the report)"
+        );
+    }
+    report.suggestions = alternatives.suggest(&report.crates);
+    report.introduced = introduced::introduced(&lock_root.join("Cargo.lock"), &report.crates);
+    report.api = report::rank_api(
+        trawl
+            .api
+            .into_iter()
+            .filter(|function| function.krate == crate_name)
+            .collect(),
+        &report.badness,
+    );
+    report.metadata = report::Metadata::collect(trawl.features)?;
+    report.metadata.inputs = sign::inputs(&args.sign_opts, &lock_root.join("Cargo.lock"))?;
+    report.scenarios = comparison.scenarios;
+    report.targets = comparison.targets;
+    emit::emit(&args.emit_opts, &lock_root, &report)?;
+    let filtered_badness = args
+        .filter_opts
+        .tainted(&report)
+        .map(|tainted| info_span!("trace").in_scope(|| trace(&tainted)))
+        .transpose()?;
+    Ok(Analysis {
+        badness,
+        report,
+        filtered_badness,
+    })
+}
+
+/// Like `real_main`, for the packages of a `cargo vendor` directory. Nothing is built, so there is
+/// no callgraph, and the report has findings but no badness.
+fn analyze_vendored(
+    args: &Args,
+    policy: Option<&policy::Policy>,
+    alternatives: &alternative::Alternatives,
+    vendor_dir: &Path,
+    lockfile: &Path,
+) -> anyhow::Result<Analysis> {
+    let trawl = info_span!("trawl")
+        .in_scope(|| trawl_source::trawl_vendored(vendor_dir, lockfile, &args.trawl_opts))?;
+    let findings = baseline::apply(&args.baseline_opts, trawl.findings)?;
+    let findings = if let Some(policy) = policy {
+        policy.waive(findings, chrono::Local::now().date_naive())
+    } else {
+        findings
+    };
+    let mut report = report::Report::new(trawl.crates, findings, &HashMap::new());
+    report.errors = trawl.issues;
+    report.skipped = trawl.skipped;
+    if let Some(policy) = policy {
+        report
+            .skipped
+            .extend(policy.waived_crates(&report.crates, chrono::Local::now().date_naive()));
+        report.skipped.sort();
+    }
+    report.suggestions = alternatives.suggest(&report.crates);
+    report.introduced = introduced::introduced(lockfile, &report.crates);
+    report.metadata = report::Metadata::collect(trawl.features)?;
+    report.metadata.inputs = sign::inputs(&args.sign_opts, lockfile)?;
+    let lock_root = lockfile.parent().unwrap_or_else(|| Path::new("."));
+    emit::emit(&args.emit_opts, lock_root, &report)?;
+    Ok(Analysis {
+        badness: HashMap::new(),
+        report,
+        filtered_badness: None,
+    })
+}
+
+/// Writes `report` as filtered and normalized for the output, unless its findings were streamed
+/// already, and marks the sources with `badness`
+fn show(
+    args: &Args,
+    report: &report::Report,
+    filtered_badness: Option<&HashMap<String, (u32, utils::LabelInfo)>>,
+    badness: &HashMap<String, (u32, utils::LabelInfo)>,
+) -> anyhow::Result<()> {
+    let mut shown = golden::normalize(
+        &args.golden_opts,
+        args.filter_opts.apply(report, filtered_badness),
+    )?;
+    sign::sign(&mut shown)?;
+    if !args.trawl_opts.stream_findings {
+        output::write_report(
+            args.format,
+            &args.html_opts,
+            &args.tree_opts,
+            &args.text_opts,
+            &args.sqlite_opts,
+            &shown,
+            &mut io::stdout().lock(),
+        )?;
+    }
+    mark_source::mark_source(&args.mark_opts, badness)
 }
 
 fn find_package<'ws>(ws: &'ws Workspace, name: &str) -> Option<&'ws Package> {
     ws.members().find(|package| package.name() == name)
 }
 
+/// The crate names of the benchmarks of the package `crate_name`
+fn bench_crate_names(ws: &Workspace, crate_name: &str) -> Vec<String> {
+    find_package(ws, crate_name)
+        .map(|package| {
+            package
+                .targets()
+                .iter()
+                .filter(|target| target.is_bench())
+                .map(|target| target.crate_name())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 fn crate_name(ws: &Workspace, package: &Option<String>) -> anyhow::Result<String> {
     package.as_ref().cloned().map_or_else(
         || ws.current().map(|package| package.name().to_string()),
@@ -91,18 +412,81 @@ fn crate_name(ws: &Workspace, package: &Option<String>) -> anyhow::Result<String
     )
 }
 
-fn main() -> anyhow::Result<()> {
-    env_logger::init();
-    let args = Args::from_args();
-    real_main(&args).and_then(|badness| {
-        println!("Badness  Function");
-        let mut badness_out_list: Vec<(&str, &u32)> =
-            badness.iter().map(|(a, (b, _))| (a as &str, b)).collect();
-        badness_out_list.sort_by_key(|(a, b)| (std::u32::MAX - *b, *a));
-        for (label, badness) in badness_out_list {
-            println!("    {badness:03}  {label}");
+fn main() {
+    let mut args = match Args::from_args_safe() {
+        Ok(args) => args,
+        Err(err)
+            if matches!(
+                err.kind,
+                clap::ErrorKind::HelpDisplayed | clap::ErrorKind::VersionDisplayed
+            ) =>
+        {
+            err.exit()
+        }
+        Err(err) => {
+            eprintln!("{}", err.message);
+            process::exit(exit::Code::Usage as i32);
+        }
+    };
+    logging::init(args.log_format);
+    // The findings are written as they are found, rather than in the report at the end, unless
+    // nothing is built, in which case the scan is quick anyway, or unless which findings are
+    // written, or how, depends on more than each finding alone.
+    args.trawl_opts.stream_findings = args.command.is_none()
+        && args.trawl_opts.vendor_dir.is_none()
+        && args.format == output::Format::Jsonl
+        && !args
+            .filter_opts
+            .filter()
+            .is_some_and(filter::Filter::uses_crate_fields)
+        && !args.filter_opts.reshapes_report()
+        && !args.baseline_opts.is_enabled()
+        && !args.policy_opts.is_enabled()
+        && !args.changed_opts.is_enabled()
+        && !args.golden_opts.golden;
+    args.trawl_opts.stream_filter = args.filter_opts.filter().cloned();
+    if let Some(timeout) = args.trawl_opts.timeout {
+        args.trawl_opts
+            .cancel
+            .cancel_after(Duration::from_secs(timeout));
+    }
+    let result = run(&args);
+    process::exit(exit::report_status(&args.exit_opts, &result));
+}
+
+fn run(args: &Args) -> anyhow::Result<()> {
+    if let Some(command) = &args.command {
+        return commands::run(command, &|| {
+            let policy = policy::load(&args.policy_opts)?;
+            real_main(args, policy.as_ref()).map(|analysis| analysis.report)
+        });
+    }
+    let policy = policy::load(&args.policy_opts)?;
+    let changed = changed::ChangedLines::load(&args.changed_opts)?;
+    real_main(args, policy.as_ref()).and_then(|analysis| {
+        let Analysis {
+            badness,
+            report,
+            filtered_badness,
+        } = analysis;
+        // The full report is still used for the ratchet, the limit and the policy, whatever the
+        // filter.
+        if let Some(changed) = &changed {
+            show(
+                args,
+                &changed.restrict(&report),
+                filtered_badness.as_ref(),
+                &changed.restrict_badness(&badness),
+            )?;
+        } else {
+            show(args, &report, filtered_badness.as_ref(), &badness)?;
+        }
+        ratchet::check(&args.ratchet_opts, &report.findings)?;
+        limit::check(&args.limit_opts, &report.findings)?;
+        limit::check_coverage(&args.limit_opts, report.file_coverage)?;
+        if let Some(policy) = &policy {
+            policy.check(&report)?;
         }
-        mark_source::mark_source(&args.mark_opts, &badness)?;
         Ok(())
     })
 }
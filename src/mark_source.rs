@@ -8,7 +8,7 @@ use regex::Regex;
 use structopt::StructOpt;
 use tempfile::NamedTempFile;
 
-use crate::utils::LabelInfo;
+use crate::utils::{self, LabelInfo};
 
 type BadnessMap = HashMap<String, (u32, LabelInfo)>;
 
@@ -47,14 +47,8 @@ fn group_by_path(badness: &BadnessMap) -> HashMap<PathBuf, BadnessMap> {
     let mut grouped = HashMap::new();
     for entry in badness {
         if let Some(debugloc) = &entry.1 .1.debugloc {
-            let path = debugloc
-                .directory
-                .as_ref()
-                .map_or(PathBuf::from(&debugloc.filename), |directory| {
-                    PathBuf::from(directory).join(&debugloc.filename)
-                });
             grouped
-                .entry(path)
+                .entry(utils::debugloc_path(debugloc))
                 .or_insert_with(HashMap::new)
                 .insert(entry.0.clone(), entry.1.clone());
         }
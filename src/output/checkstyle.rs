@@ -0,0 +1,42 @@
+//! Checkstyle XML, which CI systems turn into annotations of the lines of the findings. Each finding
+//! is an `error` element of its file, whose message is the path of its item.
+
+use std::{collections::BTreeMap, io::Write, path::Path};
+
+use super::html::escape;
+use crate::report::{Finding, Report, Severity};
+
+pub fn write(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    let mut by_file = BTreeMap::<&Path, Vec<&Finding>>::new();
+    for finding in &report.findings {
+        by_file.entry(&finding.file).or_default().push(finding);
+    }
+
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<checkstyle version="4.3">"#)?;
+    for (file, findings) in by_file {
+        writeln!(
+            out,
+            r#"  <file name="{}">"#,
+            escape(&file.display().to_string())
+        )?;
+        for finding in findings {
+            // Checkstyle's `error` is the most severe, for high severity findings.
+            let severity = match finding.severity() {
+                Severity::High => "error",
+                Severity::Normal => "warning",
+            };
+            writeln!(
+                out,
+                r#"    <error line="{}" severity="{}" message="{}" source="siderophile.{}"/>"#,
+                finding.line,
+                severity,
+                escape(&finding.item),
+                finding.kind
+            )?;
+        }
+        writeln!(out, "  </file>")?;
+    }
+    writeln!(out, "</checkstyle>")?;
+    Ok(())
+}
@@ -0,0 +1,62 @@
+//! One row per finding, for spreadsheets and dashboards
+
+use std::io::Write;
+
+use crate::report::Report;
+
+const HEADER: [&str; 7] = [
+    "crate",
+    "version",
+    "file",
+    "line",
+    "item",
+    "kind",
+    "fingerprint",
+];
+
+pub fn write(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    write_row(out, &HEADER)?;
+    for finding in &report.findings {
+        write_row(
+            out,
+            &[
+                &*finding.krate,
+                &finding.provenance.version,
+                &finding.file.to_string_lossy(),
+                &finding.line.to_string(),
+                &finding.item,
+                &finding.kind.to_string(),
+                &finding.fingerprint,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn write_row(out: &mut dyn Write, fields: &[&str]) -> anyhow::Result<()> {
+    let fields = fields.iter().map(|field| quote(field)).collect::<Vec<_>>();
+    write!(out, "{}\r\n", fields.join(","))?;
+    Ok(())
+}
+
+/// Quotes `field` as RFC 4180 requires if it contains a comma, quote or line break, as item
+/// paths such as `<[u8; 4] as Foo<A, B>>::foo` do
+fn quote(field: &str) -> String {
+    if field.contains(|c| matches!(c, ',' | '"' | '\r' | '\n')) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::quote;
+
+    #[test]
+    fn quotes_fields_when_needed() {
+        assert_eq!(quote("c::raw"), "c::raw");
+        assert_eq!(quote("<c::Foo<A, B>>::new"), "\"<c::Foo<A, B>>::new\"");
+        assert_eq!(quote("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
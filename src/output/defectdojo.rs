@@ -0,0 +1,109 @@
+//! The generic findings import format of DefectDojo, so that findings can be imported into it
+//! alongside the results of other scanners
+
+use std::{collections::HashMap, io::Write};
+
+use serde::Serialize;
+
+use crate::{
+    report::{Finding, Report, Severity},
+    utils,
+};
+
+#[derive(Debug, Serialize)]
+struct Findings<'a> {
+    findings: Vec<DojoFinding<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct DojoFinding<'a> {
+    title: String,
+
+    /// `High` for high severity findings, `Low` for the others
+    severity: &'static str,
+
+    /// Markdown
+    description: String,
+
+    mitigation: &'static str,
+
+    file_path: String,
+
+    line: usize,
+
+    component_name: &'a str,
+
+    component_version: &'a str,
+
+    /// The fingerprint, which DefectDojo deduplicates on
+    unique_id_from_tool: &'a str,
+
+    /// The kind of the finding
+    vuln_id_from_tool: &'static str,
+
+    static_finding: bool,
+
+    dynamic_finding: bool,
+}
+
+const MITIGATION: &str = "Check that the safety conditions of the unsafe code are upheld, and \
+                          document them in a `SAFETY` comment, or replace the code with a safe \
+                          alternative.";
+
+pub fn write(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    let badness = report
+        .badness
+        .iter()
+        .map(|entry| (entry.function.as_str(), entry.badness))
+        .collect::<HashMap<_, _>>();
+    let findings = Findings {
+        findings: report
+            .findings
+            .iter()
+            .map(|finding| {
+                let badness = badness
+                    .get(utils::simplify_trait_paths(&finding.item).as_str())
+                    .copied();
+                to_dojo(finding, badness)
+            })
+            .collect(),
+    };
+    serde_json::to_writer_pretty(&mut *out, &findings)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+fn to_dojo(finding: &Finding, badness: Option<u32>) -> DojoFinding {
+    let mut description = format!(
+        "Unsafe {} in `{}` of `{} {}`.",
+        finding.describe_kind(),
+        finding.item,
+        finding.krate,
+        finding.provenance.version
+    );
+    if let Some(cfg) = &finding.cfg {
+        description.push_str(&format!("\n\nOnly compiled under `cfg({cfg})`."));
+    }
+    if let Some(badness) = badness {
+        description.push_str(&format!(
+            "\n\nThe enclosing function has a badness of {badness}."
+        ));
+    }
+    DojoFinding {
+        title: format!("Unsafe {} in {}", finding.kind, finding.item),
+        severity: match finding.severity() {
+            Severity::High => "High",
+            Severity::Normal => "Low",
+        },
+        description,
+        mitigation: MITIGATION,
+        file_path: finding.file.display().to_string(),
+        line: finding.line,
+        component_name: &finding.krate,
+        component_version: &finding.provenance.version,
+        unique_id_from_tool: &finding.fingerprint,
+        vuln_id_from_tool: finding.kind.as_str(),
+        static_finding: true,
+        dynamic_finding: false,
+    }
+}
@@ -0,0 +1,171 @@
+//! Folded stacks of the callgraph, as consumed by `inferno-flamegraph` and `flamegraph.pl`, so that
+//! badness can be viewed as a flamegraph
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    io::Write,
+};
+
+use crate::report::Report;
+
+/// Enumerating every call path can take exponentially long, so it stops after this many stacks
+const MAX_STACKS: usize = 100_000;
+
+/// Writes one line per call path from an entry point (a function that none of the others call) to
+/// a function that calls none of the others, i.e., the function closest to the unsafe code it
+/// reaches. Each path is weighted by that function's badness.
+pub fn write(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    let (stacks, truncated) = stacks(report);
+    if truncated {
+        eprintln!(
+            "Warning: The callgraph has more than {} call paths; only the first {} were written",
+            MAX_STACKS, MAX_STACKS
+        );
+    }
+    for (stack, weight) in stacks {
+        writeln!(out, "{} {}", stack.join(";"), weight)?;
+    }
+    Ok(())
+}
+
+/// The call paths, and whether there were too many to enumerate them all
+fn stacks(report: &Report) -> (Vec<(Vec<String>, u32)>, bool) {
+    let badness = report
+        .badness
+        .iter()
+        .map(|entry| (entry.function.as_str(), entry.badness))
+        .collect::<BTreeMap<_, _>>();
+    let mut callees = BTreeMap::<&str, Vec<&str>>::new();
+    let mut called = HashSet::new();
+    for call in &report.calls {
+        if badness.contains_key(call.caller.as_str()) && badness.contains_key(call.callee.as_str())
+        {
+            callees.entry(&call.caller).or_default().push(&call.callee);
+            called.insert(call.callee.as_str());
+        }
+    }
+
+    let mut walk = Walk {
+        badness: &badness,
+        callees: &callees,
+        path: Vec::new(),
+        visited: BTreeSet::new(),
+        stacks: Vec::new(),
+        truncated: false,
+    };
+    // Functions that only call each other in a cycle have no entry point, so they are walked from
+    // once the entry points are done.
+    let roots = report
+        .badness
+        .iter()
+        .map(|entry| entry.function.as_str())
+        .filter(|function| !called.contains(function))
+        .chain(report.badness.iter().map(|entry| entry.function.as_str()))
+        .collect::<Vec<_>>();
+    for root in roots {
+        if !walk.visited.contains(root) {
+            walk.visit(root);
+        }
+    }
+    (walk.stacks, walk.truncated)
+}
+
+struct Walk<'a> {
+    badness: &'a BTreeMap<&'a str, u32>,
+    callees: &'a BTreeMap<&'a str, Vec<&'a str>>,
+    path: Vec<&'a str>,
+    visited: BTreeSet<&'a str>,
+    stacks: Vec<(Vec<String>, u32)>,
+    truncated: bool,
+}
+
+impl<'a> Walk<'a> {
+    fn visit(&mut self, function: &'a str) {
+        if self.stacks.len() >= MAX_STACKS {
+            self.truncated = true;
+            return;
+        }
+        self.visited.insert(function);
+        self.path.push(function);
+        let callees = self
+            .callees
+            .get(function)
+            .map(|callees| {
+                callees
+                    .iter()
+                    .copied()
+                    .filter(|callee| !self.path.contains(callee))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if callees.is_empty() {
+            let stack = self.path.iter().map(|function| frame(function)).collect();
+            self.stacks.push((stack, self.badness[function]));
+        }
+        for callee in callees {
+            self.visit(callee);
+        }
+        self.path.pop();
+    }
+}
+
+/// `;` separates frames, but appears in function names such as `<[u8; 4] as Foo>::foo`
+fn frame(function: &str) -> String {
+    function.replace(';', ",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::stacks;
+    use crate::report::{BadnessEntry, Call, Report};
+
+    fn entry(function: &str, badness: u32) -> BadnessEntry {
+        BadnessEntry {
+            function: function.to_string(),
+            badness,
+            file: None,
+            line: None,
+            only_in: None,
+        }
+    }
+
+    fn call(caller: &str, callee: &str) -> Call {
+        Call {
+            caller: caller.to_string(),
+            callee: callee.to_string(),
+        }
+    }
+
+    #[test]
+    fn paths_from_entry_points() {
+        let mut report = Report::new(Vec::new(), Vec::new(), &Default::default());
+        report.badness = vec![
+            entry("c::api", 3),
+            entry("c::helper", 2),
+            entry("c::raw", 1),
+            entry("c::ping", 1),
+            entry("c::pong", 1),
+        ];
+        report.calls = vec![
+            call("c::api", "c::helper"),
+            call("c::api", "c::raw"),
+            call("c::helper", "c::raw"),
+            call("c::ping", "c::pong"),
+            call("c::pong", "c::ping"),
+        ];
+        let (stacks, truncated) = stacks(&report);
+        assert!(!truncated);
+        let stacks = stacks
+            .into_iter()
+            .map(|(stack, weight)| (stack.join(";"), weight))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            stacks,
+            vec![
+                ("c::api;c::helper;c::raw".to_string(), 1),
+                ("c::api;c::raw".to_string(), 1),
+                ("c::ping;c::pong".to_string(), 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! GitLab's Code Quality report format, so that findings are shown on the lines of merge requests
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::report::{Finding, Report, Severity};
+
+#[derive(Debug, Serialize)]
+struct Issue<'a> {
+    description: String,
+
+    /// The kind of the finding
+    check_name: &'static str,
+
+    /// GitLab compares the issues of the source and target branches by fingerprint
+    fingerprint: &'a str,
+
+    /// `major` for high severity findings, `minor` for the others
+    severity: &'static str,
+
+    location: Location,
+}
+
+#[derive(Debug, Serialize)]
+struct Location {
+    /// Relative to the root of the repository
+    path: PathBuf,
+
+    lines: Lines,
+}
+
+#[derive(Debug, Serialize)]
+struct Lines {
+    begin: usize,
+}
+
+pub fn write(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    // CI jobs run in the root of the repository. The scanned files are canonical. The files of
+    // dependencies are left absolute, and are not shown on merge requests.
+    let root = Path::new(".").canonicalize()?;
+    let issues = report
+        .findings
+        .iter()
+        .map(|finding| to_issue(finding, &root))
+        .collect::<Vec<_>>();
+    serde_json::to_writer_pretty(&mut *out, &issues)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+fn to_issue<'a>(finding: &'a Finding, root: &Path) -> Issue<'a> {
+    Issue {
+        description: format!(
+            "Unsafe {} in {} ({} {})",
+            finding.describe_kind(),
+            finding.item,
+            finding.krate,
+            finding.provenance.version
+        ),
+        check_name: finding.kind.as_str(),
+        fingerprint: &finding.fingerprint,
+        severity: match finding.severity() {
+            Severity::High => "major",
+            Severity::Normal => "minor",
+        },
+        location: Location {
+            path: relative_path(&finding.file, root),
+            lines: Lines {
+                begin: finding.line,
+            },
+        },
+    }
+}
+
+fn relative_path(file: &Path, root: &Path) -> PathBuf {
+    file.strip_prefix(root).unwrap_or(file).to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::relative_path;
+    use std::path::Path;
+
+    #[test]
+    fn paths_are_relative_to_the_root() {
+        let root = Path::new("/builds/group/project");
+        assert_eq!(
+            relative_path(Path::new("/builds/group/project/src/lib.rs"), root),
+            Path::new("src/lib.rs")
+        );
+        assert_eq!(
+            relative_path(Path::new("/root/.cargo/registry/src/libc/src/lib.rs"), root),
+            Path::new("/root/.cargo/registry/src/libc/src/lib.rs")
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! A small Rust syntax highlighter for the sources bundled into HTML reports. It only tells apart
+//! keywords, comments, strings and numbers, which is all the reports style.
+
+use super::html::escape;
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type", "union",
+    "unsafe", "use", "where", "while",
+];
+
+/// What a line starts in, carried over from the previous line
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Code,
+
+    /// Block comments nest, so this is the depth
+    Comment(usize),
+
+    Str,
+}
+
+/// The lines of `source` as HTML, with `span`s of the classes `kw`, `unsafe`, `comment`, `str` and
+/// `num`
+pub fn highlight(source: &str) -> Vec<String> {
+    let mut state = State::Code;
+    source
+        .lines()
+        .map(|line| {
+            let (html, next) = highlight_line(line, state);
+            state = next;
+            html
+        })
+        .collect()
+}
+
+fn highlight_line(line: &str, state: State) -> (String, State) {
+    let chars = line.chars().collect::<Vec<_>>();
+    let mut html = String::new();
+
+    // Finish the comment or string that the previous line left open
+    let mut i = 0;
+    let mut state = state;
+    match state {
+        State::Code => {}
+        State::Comment(depth) => {
+            (i, state) = skip_comment(&chars, 0, depth);
+            span(&mut html, "comment", &chars[..i]);
+        }
+        State::Str => {
+            (i, state) = skip_str(&chars, 0);
+            span(&mut html, "str", &chars[..i]);
+        }
+    }
+
+    while i < chars.len() && state == State::Code {
+        let start = i;
+        let c = chars[i];
+        if chars[i..].starts_with(&['/', '/']) {
+            i = chars.len();
+            span(&mut html, "comment", &chars[start..i]);
+        } else if chars[i..].starts_with(&['/', '*']) {
+            (i, state) = skip_comment(&chars, i + 2, 1);
+            span(&mut html, "comment", &chars[start..i]);
+        } else if c == '"' {
+            (i, state) = skip_str(&chars, i + 1);
+            span(&mut html, "str", &chars[start..i]);
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word = chars[start..i].iter().collect::<String>();
+            if word == "unsafe" {
+                span(&mut html, "kw unsafe", &chars[start..i]);
+            } else if KEYWORDS.contains(&word.as_str()) {
+                span(&mut html, "kw", &chars[start..i]);
+            } else {
+                html += &escape(&word);
+            }
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            span(&mut html, "num", &chars[start..i]);
+        } else {
+            i += 1;
+            html += &escape(&c.to_string());
+        }
+    }
+    (html, state)
+}
+
+/// Skips a block comment from `i`, at the given nesting depth, up to its end or the end of the
+/// line
+fn skip_comment(chars: &[char], mut i: usize, mut depth: usize) -> (usize, State) {
+    while i < chars.len() && depth > 0 {
+        if chars[i..].starts_with(&['/', '*']) {
+            depth += 1;
+            i += 2;
+        } else if chars[i..].starts_with(&['*', '/']) {
+            depth -= 1;
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    let state = if depth == 0 {
+        State::Code
+    } else {
+        State::Comment(depth)
+    };
+    (i, state)
+}
+
+/// Skips a string literal from `i` up to and including its closing quote, or to the end of the
+/// line
+fn skip_str(chars: &[char], mut i: usize) -> (usize, State) {
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '"' => return (i + 1, State::Code),
+            _ => i += 1,
+        }
+    }
+    (chars.len(), State::Str)
+}
+
+fn span(html: &mut String, class: &str, chars: &[char]) {
+    let text = chars.iter().collect::<String>();
+    *html += &format!("<span class=\"{}\">{}</span>", class, escape(&text));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::highlight;
+
+    #[test]
+    fn highlight_lines() {
+        assert_eq!(
+            highlight("unsafe fn f() -> u8 { 1 } // <done>\n/* a\nb */ \"s\\\"\" x"),
+            vec![
+                "<span class=\"kw unsafe\">unsafe</span> <span class=\"kw\">fn</span> f() -&gt; \
+                 u8 { <span class=\"num\">1</span> } <span class=\"comment\">// \
+                 &lt;done&gt;</span>",
+                "<span class=\"comment\">/* a</span>",
+                "<span class=\"comment\">b */</span> <span class=\"str\">&quot;s\\&quot;&quot;</span> x",
+            ]
+        );
+    }
+}
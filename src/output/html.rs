@@ -0,0 +1,688 @@
+//! Self-contained HTML reports, for reviewers who do not use the command line
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::Write,
+    ops::RangeInclusive,
+    path::Path,
+};
+
+use serde_json::json;
+use structopt::StructOpt;
+
+use super::highlight::highlight;
+use crate::report::{Finding, Report};
+
+/// Bundled sources larger than this (in bytes) are worth a warning, since every copy of the report
+/// carries them
+const LARGE_BUNDLE: usize = 10 << 20;
+
+/// Lines of context shown before and after the unsafe code of an excerpt
+const EXCERPT_CONTEXT: usize = 2;
+
+/// Unsafe code longer than this many lines is cut short in excerpts
+const EXCERPT_LINES: usize = 30;
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.5em; text-align: left; }
+td.number { text-align: right; }
+code, pre { font-family: monospace; }
+pre { background: #f8f8f8; padding: 0.5em; overflow-x: auto; }
+.line { display: block; }
+.line.unsafe { background: #fde2e2; }
+.line .number { display: inline-block; width: 4em; color: #999; text-decoration: none; }
+.kw { color: #7c3aed; }
+.kw.unsafe { color: #dc2626; font-weight: bold; }
+.comment { color: #6b7280; }
+.str { color: #059669; }
+.num { color: #b45309; }
+:target { outline: 2px solid #2563eb; }
+";
+
+/// Draws a force-directed graph of the `callgraph-data`, colored by badness, in `#callgraph`, and
+/// dims the functions that do not match `#callgraph-search`. The graph is laid out by the script
+/// itself, so that the report works offline and loads no code from elsewhere.
+const CALLGRAPH_SCRIPT: &str = r##"
+const data = JSON.parse(document.getElementById("callgraph-data").textContent);
+const width = 960, height = 600, ns = "http://www.w3.org/2000/svg";
+const max = Math.max(...data.nodes.map(d => d.badness)) || 1;
+const color = b => `rgb(255,${Math.round(235 - 200 * b / max)},${Math.round(225 - 210 * b / max)})`;
+const byId = new Map(data.nodes.map((d, i) => {
+  const angle = i * 2.39996, radius = 10 * Math.sqrt(i);
+  Object.assign(d, { x: width / 2 + radius * Math.cos(angle), y: height / 2 + radius * Math.sin(angle), vx: 0, vy: 0 });
+  return [d.id, d];
+}));
+const links = data.links.filter(l => byId.has(l.source) && byId.has(l.target))
+  .map(l => ({ source: byId.get(l.source), target: byId.get(l.target) }));
+const element = (name, parent, attrs) => {
+  const e = document.createElementNS(ns, name);
+  for (const [k, v] of Object.entries(attrs)) e.setAttribute(k, v);
+  return parent.appendChild(e);
+};
+const svg = element("svg", document.getElementById("callgraph"), { viewBox: `0 0 ${width} ${height}` });
+const g = element("g", svg, {});
+const linkGroup = element("g", g, { stroke: "#999" }), nodeGroup = element("g", g, { stroke: "#333" });
+for (const l of links) l.el = element("line", linkGroup, {});
+for (const d of data.nodes) {
+  d.el = element("circle", nodeGroup, { r: 4 + 8 * Math.sqrt(d.badness / max), fill: color(d.badness) });
+  element("title", d.el, {}).textContent = `${d.id} (badness ${d.badness})`;
+}
+let view = { x: 0, y: 0, k: 1 }, alpha = 1, dragged = null, panning = null;
+const point = e => {
+  const p = svg.createSVGPoint();
+  p.x = e.clientX; p.y = e.clientY;
+  const q = p.matrixTransform(svg.getScreenCTM().inverse());
+  return { x: (q.x - view.x) / view.k, y: (q.y - view.y) / view.k, sx: q.x, sy: q.y };
+};
+const tick = () => {
+  for (const a of data.nodes) for (const b of data.nodes) {
+    if (a === b) continue;
+    const dx = a.x - b.x || 0.1, dy = a.y - b.y || 0.1, d2 = dx * dx + dy * dy;
+    a.vx += 60 * alpha * dx / d2; a.vy += 60 * alpha * dy / d2;
+  }
+  for (const { source: s, target: t } of links) {
+    const dx = t.x - s.x, dy = t.y - s.y, d = Math.sqrt(dx * dx + dy * dy) || 1, f = 0.1 * alpha * (d - 40) / d;
+    s.vx += f * dx; s.vy += f * dy; t.vx -= f * dx; t.vy -= f * dy;
+  }
+  for (const d of data.nodes) {
+    d.vx += 0.01 * alpha * (width / 2 - d.x); d.vy += 0.01 * alpha * (height / 2 - d.y);
+    if (d !== dragged) { d.x += d.vx; d.y += d.vy; }
+    d.vx *= 0.6; d.vy *= 0.6;
+  }
+};
+const draw = () => {
+  for (const l of links) {
+    l.el.setAttribute("x1", l.source.x); l.el.setAttribute("y1", l.source.y);
+    l.el.setAttribute("x2", l.target.x); l.el.setAttribute("y2", l.target.y);
+  }
+  for (const d of data.nodes) { d.el.setAttribute("cx", d.x); d.el.setAttribute("cy", d.y); }
+  g.setAttribute("transform", `translate(${view.x},${view.y}) scale(${view.k})`);
+};
+const frame = () => {
+  tick(); draw();
+  alpha *= 0.99;
+  if (alpha > 0.005 || dragged) requestAnimationFrame(frame);
+};
+const restart = () => { if (alpha <= 0.005) { alpha = 0.3; requestAnimationFrame(frame); } else alpha = Math.max(alpha, 0.3); };
+requestAnimationFrame(frame);
+svg.addEventListener("wheel", e => {
+  e.preventDefault();
+  const p = point(e), k = Math.min(10, Math.max(0.1, view.k * Math.exp(-e.deltaY / 500)));
+  view = { x: p.sx - p.x * k, y: p.sy - p.y * k, k };
+  draw();
+});
+svg.addEventListener("pointerdown", e => {
+  dragged = data.nodes.find(d => d.el === e.target) || null;
+  if (!dragged) panning = { x: e.clientX - view.x, y: e.clientY - view.y };
+  else restart();
+  svg.setPointerCapture(e.pointerId);
+});
+svg.addEventListener("pointermove", e => {
+  if (dragged) { const p = point(e); dragged.x = p.x; dragged.y = p.y; draw(); }
+  else if (panning) { view.x = e.clientX - panning.x; view.y = e.clientY - panning.y; draw(); }
+});
+svg.addEventListener("pointerup", () => { dragged = null; panning = null; });
+document.getElementById("callgraph-search").addEventListener("input", e => {
+  const query = e.target.value.toLowerCase();
+  const matches = d => !query || d.id.toLowerCase().includes(query);
+  for (const d of data.nodes) d.el.setAttribute("opacity", matches(d) ? 1 : 0.15);
+  for (const l of links) l.el.setAttribute("opacity", matches(l.source) || matches(l.target) ? 1 : 0.05);
+});
+"##;
+
+#[derive(StructOpt, Debug, Default)]
+pub struct HtmlOpts {
+    #[structopt(long = "bundle-sources")]
+    /// With `--format html`, embed the source files that contain findings in the report, with
+    /// the unsafe code highlighted, so that it can be reviewed without the sources at hand. This
+    /// can make the report large.
+    pub bundle_sources: bool,
+}
+
+/// A source file embedded in the report
+struct BundledSource<'a> {
+    /// Highlighted
+    lines: Vec<String>,
+
+    /// 1-based
+    unsafe_lines: HashSet<usize>,
+
+    /// The findings starting on each line
+    findings: BTreeMap<usize, Vec<&'a Finding>>,
+}
+
+/// The unsafe code of a finding with a few lines around it
+struct Excerpt {
+    /// 1-based number of the first line
+    first: usize,
+
+    /// Highlighted
+    lines: Vec<String>,
+
+    /// 1-based
+    unsafe_lines: RangeInclusive<usize>,
+}
+
+/// Escapes `s` for use in HTML text and attribute values
+pub fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn write(report: &Report, opts: &HtmlOpts, out: &mut dyn Write) -> anyhow::Result<()> {
+    let sources = if opts.bundle_sources {
+        bundle(report)
+    } else {
+        BTreeMap::new()
+    };
+    // Files are referred to by their position among the bundled sources.
+    let ids = sources
+        .keys()
+        .enumerate()
+        .map(|(id, file)| (*file, id))
+        .collect::<BTreeMap<_, _>>();
+    let excerpts = excerpts(report);
+
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\">")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Siderophile report</title>")?;
+    writeln!(out, "<style>{}</style>", STYLE)?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "<h1>Siderophile report</h1>")?;
+    let metadata = &report.metadata;
+    if !metadata.siderophile_version.is_empty() {
+        writeln!(
+            out,
+            "<p>siderophile {}, {}, {}</p>",
+            escape(&metadata.siderophile_version),
+            escape(&metadata.rustc_version),
+            escape(&metadata.target)
+        )?;
+    }
+
+    write_crates(report, out)?;
+    write_build_time(report, out)?;
+    write_hooks(report, out)?;
+    write_findings(report, &ids, &excerpts, out)?;
+    write_badness(report, out)?;
+    write_callgraph(report, out)?;
+    write_sources(&sources, out)?;
+
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")?;
+    Ok(())
+}
+
+fn write_crates(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    writeln!(out, "<h2>Crates</h2>")?;
+    writeln!(out, "<table>")?;
+    writeln!(
+        out,
+        "<tr><th>Crate</th><th>Version</th><th>Latest</th><th>License</th><th>Category</th>\
+         <th>Findings</th></tr>"
+    )?;
+    for summary in &report.crates {
+        let name = match &summary.repository {
+            Some(repository) if is_web_url(repository) => format!(
+                "<a href=\"{}\">{}</a>",
+                escape(repository),
+                escape(&summary.name)
+            ),
+            _ => escape(&summary.name),
+        };
+        let findings = if summary.findings == 0 {
+            summary.findings.to_string()
+        } else {
+            format!(
+                "<a href=\"#{}\">{}</a>",
+                crate_id(&summary.name, &summary.version),
+                summary.findings
+            )
+        };
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+             <td class=\"number\">{}</td></tr>",
+            name,
+            escape(&summary.version),
+            escape(summary.latest_version.as_deref().unwrap_or_default()),
+            escape(summary.license.as_deref().unwrap_or_default()),
+            escape(&summary.category),
+            findings
+        )?;
+    }
+    writeln!(out, "</table>")?;
+    Ok(())
+}
+
+/// Whether `url` is safe to link to. The repository field of a manifest is whatever the author
+/// wrote, e.g., a `javascript:` URL.
+fn is_web_url(url: &str) -> bool {
+    let url = url.trim_start().to_ascii_lowercase();
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+/// The proc-macro crates, whose code runs inside rustc at build time
+fn write_build_time(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    let proc_macros = report
+        .crates
+        .iter()
+        .filter(|summary| summary.proc_macro)
+        .collect::<Vec<_>>();
+    if proc_macros.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "<h2>Build-time execution</h2>")?;
+    writeln!(out, "<table>")?;
+    writeln!(
+        out,
+        "<tr><th>Proc-macro crate</th><th>Version</th><th>Findings</th></tr>"
+    )?;
+    for summary in proc_macros {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td class=\"number\">{}</td></tr>",
+            escape(&summary.name),
+            escape(&summary.version),
+            summary.findings
+        )?;
+    }
+    writeln!(out, "</table>")?;
+    Ok(())
+}
+
+/// The items that claim global hooks, linked to their findings
+fn write_hooks(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    let hooks = report
+        .findings
+        .iter()
+        .filter_map(|finding| Some((finding.hook.as_deref()?, finding)))
+        .collect::<Vec<_>>();
+    if hooks.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "<h2>Global hooks</h2>")?;
+    writeln!(out, "<table>")?;
+    writeln!(out, "<tr><th>Hook</th><th>Crate</th><th>Item</th></tr>")?;
+    for (hook, finding) in hooks {
+        writeln!(
+            out,
+            "<tr><td><code>{}</code></td><td>{} {}</td>\
+             <td><a href=\"#finding-{}\"><code>{}</code></a></td></tr>",
+            escape(hook),
+            escape(&finding.krate),
+            escape(&finding.provenance.version),
+            escape(&finding.fingerprint),
+            escape(&finding.item)
+        )?;
+    }
+    writeln!(out, "</table>")?;
+    Ok(())
+}
+
+/// The findings of each crate, each linked to its excerpt, or to its bundled source if it has no
+/// excerpt
+fn write_findings(
+    report: &Report,
+    ids: &BTreeMap<&Path, usize>,
+    excerpts: &HashMap<&str, Excerpt>,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    writeln!(out, "<h2>Findings</h2>")?;
+    let mut by_crate = BTreeMap::<_, Vec<_>>::new();
+    for finding in &report.findings {
+        by_crate
+            .entry((&*finding.krate, finding.provenance.version.as_str()))
+            .or_default()
+            .push(finding);
+    }
+    for ((krate, version), findings) in by_crate {
+        writeln!(
+            out,
+            "<h3 id=\"{}\">{} {}</h3>",
+            crate_id(krate, version),
+            escape(krate),
+            escape(version)
+        )?;
+        writeln!(out, "<table>")?;
+        writeln!(
+            out,
+            "<tr><th>Fingerprint</th><th>Item</th><th>Kind</th><th>Location</th></tr>"
+        )?;
+        for finding in &findings {
+            let location = escape(&format!("{}:{}", finding.file.display(), finding.line));
+            let location = if excerpts.contains_key(finding.fingerprint.as_str()) {
+                format!(
+                    "<a href=\"#excerpt-{}\">{}</a>",
+                    escape(&finding.fingerprint),
+                    location
+                )
+            } else if let Some(id) = ids.get(&*finding.file) {
+                format!(
+                    "<a href=\"#source-{}-L{}\">{}</a>",
+                    id, finding.line, location
+                )
+            } else {
+                location
+            };
+            writeln!(
+                out,
+                "<tr id=\"finding-{}\"><td><code>{}</code></td><td><code>{}</code></td>\
+                 <td>{}</td><td>{}</td></tr>",
+                escape(&finding.fingerprint),
+                escape(&finding.fingerprint),
+                escape(&finding.item),
+                escape(&finding.describe_kind()),
+                location
+            )?;
+        }
+        writeln!(out, "</table>")?;
+        for finding in findings {
+            if let Some(excerpt) = excerpts.get(finding.fingerprint.as_str()) {
+                write_excerpt(finding, excerpt, ids, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The `id` of the section of the findings of a crate
+fn crate_id(krate: &str, version: &str) -> String {
+    escape(&format!("crate-{krate}-{version}"))
+}
+
+fn write_excerpt(
+    finding: &Finding,
+    excerpt: &Excerpt,
+    ids: &BTreeMap<&Path, usize>,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let fingerprint = escape(&finding.fingerprint);
+    let location = escape(&format!("{}:{}", finding.file.display(), finding.line));
+    // With `--bundle-sources`, the excerpt leads on to the whole file.
+    let location = match ids.get(&*finding.file) {
+        Some(id) => format!(
+            "<a href=\"#source-{}-L{}\">{}</a>",
+            id, finding.line, location
+        ),
+        None => location,
+    };
+    writeln!(
+        out,
+        "<div id=\"excerpt-{}\"><p><a href=\"#finding-{}\"><code>{}</code></a> {} ({})</p>",
+        fingerprint,
+        fingerprint,
+        escape(&finding.item),
+        location,
+        escape(&finding.describe_kind())
+    )?;
+    write!(out, "<pre><code>")?;
+    for (i, line) in excerpt.lines.iter().enumerate() {
+        let number = excerpt.first + i;
+        write!(
+            out,
+            "<span class=\"line{}\"><span class=\"number\">{}</span>{}</span>",
+            if excerpt.unsafe_lines.contains(&number) {
+                " unsafe"
+            } else {
+                ""
+            },
+            number,
+            line
+        )?;
+    }
+    writeln!(out, "</code></pre>")?;
+    writeln!(out, "</div>")?;
+    Ok(())
+}
+
+fn write_badness(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    writeln!(out, "<h2>Badness</h2>")?;
+    writeln!(out, "<table>")?;
+    writeln!(out, "<tr><th>Badness</th><th>Function</th></tr>")?;
+    for entry in &report.badness {
+        writeln!(
+            out,
+            "<tr><td class=\"number\">{}</td><td><code>{}</code></td></tr>",
+            entry.badness,
+            escape(&entry.function)
+        )?;
+    }
+    writeln!(out, "</table>")?;
+    Ok(())
+}
+
+/// An interactive view of the calls between the functions in the badness table
+fn write_callgraph(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    if report.badness.is_empty() {
+        return Ok(());
+    }
+    let data = json!({
+        "nodes": report
+            .badness
+            .iter()
+            .map(|entry| json!({ "id": entry.function, "badness": entry.badness }))
+            .collect::<Vec<_>>(),
+        "links": report
+            .calls
+            .iter()
+            .map(|call| json!({ "source": call.caller, "target": call.callee }))
+            .collect::<Vec<_>>(),
+    });
+    writeln!(out, "<h2>Callgraph</h2>")?;
+    writeln!(
+        out,
+        "<p><input id=\"callgraph-search\" type=\"search\" placeholder=\"Search functions\"></p>"
+    )?;
+    writeln!(out, "<div id=\"callgraph\"></div>")?;
+    // `</` would end the script element early.
+    writeln!(
+        out,
+        "<script type=\"application/json\" id=\"callgraph-data\">{}</script>",
+        data.to_string().replace("</", "<\\/")
+    )?;
+    writeln!(out, "<script>{}</script>", CALLGRAPH_SCRIPT)?;
+    Ok(())
+}
+
+fn write_sources(
+    sources: &BTreeMap<&Path, BundledSource>,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    if sources.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "<h2>Sources</h2>")?;
+    for (id, (file, source)) in sources.iter().enumerate() {
+        writeln!(
+            out,
+            "<details id=\"source-{}\"><summary><code>{}</code></summary>",
+            id,
+            escape(&file.display().to_string())
+        )?;
+        write!(out, "<pre><code>")?;
+        for (i, line) in source.lines.iter().enumerate() {
+            let number = i + 1;
+            // A line's number links to the first finding on it, if any.
+            let href = source
+                .findings
+                .get(&number)
+                .and_then(|findings| findings.first())
+                .map_or_else(
+                    || format!("#source-{id}-L{number}"),
+                    |finding| format!("#finding-{}", escape(&finding.fingerprint)),
+                );
+            write!(
+                out,
+                "<span class=\"line{}\" id=\"source-{}-L{}\">\
+                 <a class=\"number\" href=\"{}\">{}</a>{}</span>",
+                if source.unsafe_lines.contains(&number) {
+                    " unsafe"
+                } else {
+                    ""
+                },
+                id,
+                number,
+                href,
+                number,
+                line
+            )?;
+        }
+        writeln!(out, "</code></pre>")?;
+        writeln!(out, "</details>")?;
+    }
+    Ok(())
+}
+
+/// Reads and highlights the files that contain findings. Files that cannot be read are left out.
+fn bundle(report: &Report) -> BTreeMap<&Path, BundledSource> {
+    let mut by_file = BTreeMap::<&Path, Vec<&Finding>>::new();
+    for finding in &report.findings {
+        by_file.entry(&finding.file).or_default().push(finding);
+    }
+
+    let mut size = 0;
+    let mut sources = BTreeMap::new();
+    for (file, findings) in by_file {
+        let contents = match fs::read_to_string(file) {
+            Ok(contents) => contents,
+            Err(err) => {
+                eprintln!("Warning: Could not bundle `{}`: {}", file.display(), err);
+                continue;
+            }
+        };
+        size += contents.len();
+        let raw_lines = contents.lines().collect::<Vec<_>>();
+        let mut source = BundledSource {
+            lines: highlight(&contents),
+            unsafe_lines: HashSet::new(),
+            findings: BTreeMap::new(),
+        };
+        for finding in findings {
+            source
+                .unsafe_lines
+                .extend(unsafe_region(&raw_lines, finding.line));
+            source
+                .findings
+                .entry(finding.line)
+                .or_default()
+                .push(finding);
+        }
+        sources.insert(file, source);
+    }
+
+    if size > LARGE_BUNDLE {
+        eprintln!(
+            "Warning: The bundled sources make the report over {} MiB",
+            size >> 20
+        );
+    }
+    sources
+}
+
+/// Reads the files of the findings and cuts out the excerpt of each finding, by fingerprint. Files
+/// that cannot be read are left out silently, since the report may have been made elsewhere.
+fn excerpts(report: &Report) -> HashMap<&str, Excerpt> {
+    let mut by_file = BTreeMap::<&Path, Vec<&Finding>>::new();
+    for finding in &report.findings {
+        by_file.entry(&finding.file).or_default().push(finding);
+    }
+
+    let mut excerpts = HashMap::new();
+    for (file, findings) in by_file {
+        let contents = match fs::read_to_string(file) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+        let raw_lines = contents.lines().collect::<Vec<_>>();
+        // The whole file is highlighted, since a line can start inside a comment or string.
+        let lines = highlight(&contents);
+        for finding in findings {
+            if finding.line == 0 || finding.line > lines.len() {
+                continue;
+            }
+            let end = (*unsafe_region(&raw_lines, finding.line).end())
+                .min(finding.line + EXCERPT_LINES - 1);
+            let first = finding.line.saturating_sub(EXCERPT_CONTEXT).max(1);
+            let last = (end + EXCERPT_CONTEXT).min(lines.len());
+            excerpts.insert(
+                finding.fingerprint.as_str(),
+                Excerpt {
+                    first,
+                    lines: lines[first - 1..last].to_vec(),
+                    unsafe_lines: finding.line..=end,
+                },
+            );
+        }
+    }
+    excerpts
+}
+
+/// The lines (1-based) of the unsafe code starting at `line`: up to the brace that closes the first
+/// one opened, or just `line` if there is no such brace, e.g., for `unsafe fn f();` in a trait
+fn unsafe_region(lines: &[&str], line: usize) -> RangeInclusive<usize> {
+    let mut depth = 0usize;
+    let mut opened = false;
+    for (i, text) in lines.iter().enumerate().skip(line.saturating_sub(1)) {
+        for c in text.chars() {
+            match c {
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        if opened && depth == 0 {
+            return line..=i + 1;
+        }
+        if !opened && text.trim_end().ends_with(';') {
+            break;
+        }
+    }
+    line..=line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_crates;
+    use crate::{
+        fixture,
+        report::{CrateSummary, Report},
+    };
+
+    fn crates_table(repository: &str) -> String {
+        let report = Report {
+            crates: vec![CrateSummary {
+                repository: Some(repository.to_string()),
+                ..fixture::summary("libc")
+            }],
+            ..Report::default()
+        };
+        let mut out = Vec::new();
+        write_crates(&report, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn links_web_repositories() {
+        assert!(crates_table("https://github.com/rust-lang/libc")
+            .contains("<a href=\"https://github.com/rust-lang/libc\">libc</a>"));
+    }
+
+    #[test]
+    fn does_not_link_other_repositories() {
+        let table = crates_table("javascript:alert(1)");
+        assert!(!table.contains("href=\"javascript"));
+        assert!(table.contains("<td>libc</td>"));
+    }
+}
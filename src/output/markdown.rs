@@ -0,0 +1,107 @@
+//! A summary of the findings to paste into wikis and issues: a table of the counts of each crate,
+//! and the findings of each file in collapsible sections
+
+use std::{collections::BTreeMap, io::Write, path::PathBuf};
+
+use super::html::escape;
+use crate::{
+    report::{Finding, FindingKind, Report},
+    utils,
+};
+
+pub fn write(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    let mut by_crate = BTreeMap::<_, Vec<_>>::new();
+    for finding in &report.findings {
+        by_crate
+            .entry((&*finding.krate, finding.provenance.version.as_str()))
+            .or_default()
+            .push(finding);
+    }
+
+    writeln!(out, "# Unsafe code summary")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{} findings in {} of {} crates.",
+        report.findings.len(),
+        by_crate.len(),
+        report.crates.len()
+    )?;
+    if by_crate.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(
+        out,
+        "| Crate | Version | Functions | Blocks | Other | Total |"
+    )?;
+    writeln!(out, "| --- | --- | ---: | ---: | ---: | ---: |")?;
+    let mut rows = by_crate.iter().collect::<Vec<_>>();
+    // Most findings first
+    rows.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(b.0)));
+    for ((krate, version), findings) in rows {
+        let count = |kind| {
+            findings
+                .iter()
+                .filter(|finding| finding.kind == kind)
+                .count()
+        };
+        let functions = count(FindingKind::Function);
+        let blocks = count(FindingKind::Block);
+        writeln!(
+            out,
+            "| `{}` | {} | {} | {} | {} | {} |",
+            krate,
+            version,
+            functions,
+            blocks,
+            findings.len() - functions - blocks,
+            findings.len()
+        )?;
+    }
+
+    writeln!(out)?;
+    writeln!(out, "## Findings by file")?;
+    for ((krate, version), findings) in &by_crate {
+        writeln!(out)?;
+        writeln!(out, "### `{krate}` {version}")?;
+        let mut by_file = BTreeMap::<PathBuf, Vec<&Finding>>::new();
+        for finding in findings {
+            by_file
+                .entry(utils::package_relative_path(&finding.file, krate, version))
+                .or_default()
+                .push(finding);
+        }
+        for (file, findings) in by_file {
+            writeln!(out)?;
+            writeln!(out, "<details>")?;
+            writeln!(
+                out,
+                "<summary><code>{}</code> ({})</summary>",
+                escape(&file.display().to_string()),
+                findings.len()
+            )?;
+            // Markdown is only rendered inside `details` after a blank line.
+            writeln!(out)?;
+            writeln!(out, "| Line | Item | Kind |")?;
+            writeln!(out, "| ---: | --- | --- |")?;
+            for finding in findings {
+                writeln!(
+                    out,
+                    "| {} | `{}` | {} |",
+                    finding.line,
+                    cell(&finding.item),
+                    cell(&finding.describe_kind())
+                )?;
+            }
+            writeln!(out)?;
+            writeln!(out, "</details>")?;
+        }
+    }
+    Ok(())
+}
+
+/// `|` ends a table cell even in code spans, e.g., in the paths of closures
+fn cell(s: &str) -> String {
+    s.replace('|', "\\|")
+}
@@ -0,0 +1,806 @@
+pub mod checkstyle;
+pub mod csv;
+pub mod defectdojo;
+pub mod folded;
+pub mod gitlab;
+mod highlight;
+pub mod html;
+pub mod markdown;
+pub mod protobuf;
+pub mod sql;
+pub mod sqlite;
+pub mod tree;
+
+use std::{collections::BTreeMap, fmt, io::Write, str::FromStr};
+
+use anyhow::{anyhow, bail};
+use structopt::StructOpt;
+
+use crate::{
+    category, dependency,
+    report::{
+        ChecksumMismatch, DependencySummary, Finding, FindingKind, GeneratedCode, Introduced,
+        Report, ScenarioSummary, Severity, SkipReason, SkippedCrate, Suggestion, Tag,
+    },
+};
+
+/// The sections of the text format that are only written on request
+#[derive(StructOpt, Debug, Default)]
+pub struct TextOpts {
+    #[structopt(long = "show-dependencies")]
+    /// List the direct dependencies of the workspace by the findings they pull in, in the text
+    /// format
+    pub show_dependencies: bool,
+
+    #[structopt(long = "show-blast-radius")]
+    /// Rank the dependencies by the functions that only their unsafety reaches, in the text format
+    pub show_blast_radius: bool,
+
+    #[structopt(long = "show-suggestions")]
+    /// Name the safer alternatives to the dependencies that have them, in the text format
+    pub show_suggestions: bool,
+
+    #[structopt(long = "show-unsafe-api")]
+    /// List the dependencies with unsafe public functions, in the text format
+    pub show_unsafe_api: bool,
+
+    #[structopt(long = "show-encapsulation")]
+    /// Score how much of the unsafe code of each dependency is internal, in the text format
+    pub show_encapsulation: bool,
+
+    #[structopt(long = "show-introduced")]
+    /// List the commits that first added the packages with findings, in the text format
+    pub show_introduced: bool,
+}
+
+/// How the results of a run are written to stdout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// The human-readable badness table
+    Text,
+
+    /// A `Report`, which can be read back in by the subcommands
+    Json,
+
+    /// The same as `Json`, for tools that consume YAML
+    Yaml,
+
+    /// A compact binary encoding of a `Report` (see `schema/report.proto`), which `convert`
+    /// transcodes to the other formats
+    Protobuf,
+
+    /// A self-contained page for reviewers, optionally with the sources of the findings
+    Html,
+
+    /// Folded stacks of the callgraph, for flamegraph tools
+    Folded,
+
+    /// The dependency tree, annotated with the findings of each subtree
+    Tree,
+
+    /// The dependency graph in Graphviz's DOT language, shaded by the findings of each package
+    Dot,
+
+    /// The same graph as a Mermaid flowchart, for Markdown documents
+    Mermaid,
+
+    /// The findings in DefectDojo's generic findings import format
+    DefectDojo,
+
+    /// One row per finding, for spreadsheets
+    Csv,
+
+    /// One finding per line of JSON. The findings of a scan are written as they are found.
+    Jsonl,
+
+    /// A table of the counts of each crate and the findings of each file, for wikis
+    Markdown,
+
+    /// A SQL script appending the run to a SQLite database, for querying the history of runs
+    Sql,
+
+    /// The run appended to a SQLite database directly, with the same schema as `Sql`
+    Sqlite,
+
+    /// Checkstyle XML, for CI systems that annotate the lines of the findings
+    Checkstyle,
+
+    /// GitLab's Code Quality report, to show the findings on merge requests
+    Gitlab,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "yaml" => Ok(Self::Yaml),
+            "protobuf" => Ok(Self::Protobuf),
+            "html" => Ok(Self::Html),
+            "folded" => Ok(Self::Folded),
+            "tree" => Ok(Self::Tree),
+            "dot" => Ok(Self::Dot),
+            "mermaid" => Ok(Self::Mermaid),
+            "defectdojo" => Ok(Self::DefectDojo),
+            "csv" => Ok(Self::Csv),
+            "jsonl" => Ok(Self::Jsonl),
+            "markdown" => Ok(Self::Markdown),
+            "sql" => Ok(Self::Sql),
+            "sqlite" => Ok(Self::Sqlite),
+            "checkstyle" => Ok(Self::Checkstyle),
+            "gitlab" => Ok(Self::Gitlab),
+            _ => Err(anyhow!("Unknown format `{}`", s)),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Text => "text",
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+            Self::Protobuf => "protobuf",
+            Self::Html => "html",
+            Self::Folded => "folded",
+            Self::Tree => "tree",
+            Self::Dot => "dot",
+            Self::Mermaid => "mermaid",
+            Self::DefectDojo => "defectdojo",
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+            Self::Markdown => "markdown",
+            Self::Sql => "sql",
+            Self::Sqlite => "sqlite",
+            Self::Checkstyle => "checkstyle",
+            Self::Gitlab => "gitlab",
+        })
+    }
+}
+
+pub fn write_report(
+    format: Format,
+    html_opts: &html::HtmlOpts,
+    tree_opts: &tree::TreeOpts,
+    text_opts: &TextOpts,
+    sqlite_opts: &sqlite::SqliteOpts,
+    report: &Report,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    match format {
+        Format::Text => write_text(report, text_opts, tree_opts.invert.as_deref(), out),
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, report)?;
+            writeln!(out)?;
+            Ok(())
+        }
+        Format::Yaml => {
+            serde_yaml::to_writer(out, report)?;
+            Ok(())
+        }
+        Format::Protobuf => {
+            out.write_all(&protobuf::encode(report))?;
+            Ok(())
+        }
+        Format::Html => html::write(report, html_opts, out),
+        Format::Folded => folded::write(report, out),
+        Format::Tree => tree::write(report, tree_opts, out),
+        Format::Dot => tree::write_dot(report, tree_opts, out),
+        Format::Mermaid => tree::write_mermaid(report, tree_opts, out),
+        Format::DefectDojo => defectdojo::write(report, out),
+        Format::Csv => csv::write(report, out),
+        Format::Jsonl => {
+            for finding in &report.findings {
+                serde_json::to_writer(&mut *out, finding)?;
+                writeln!(out)?;
+            }
+            Ok(())
+        }
+        Format::Markdown => markdown::write(report, out),
+        Format::Sql => sql::write(report, out),
+        Format::Sqlite => sqlite::write(report, sqlite_opts),
+        Format::Checkstyle => checkstyle::write(report, out),
+        Format::Gitlab => gitlab::write(report, out),
+    }
+}
+
+fn write_text(
+    report: &Report,
+    opts: &TextOpts,
+    invert: Option<&str>,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    write_checksum_mismatches(&report.checksum_mismatches, out)?;
+    writeln!(out, "Badness  Function")?;
+    for entry in &report.badness {
+        match entry.only_in {
+            Some(profile) => writeln!(
+                out,
+                "    {:03}  {} ({} builds only)",
+                entry.badness, entry.function, profile
+            )?,
+            None => writeln!(out, "    {:03}  {}", entry.badness, entry.function)?,
+        }
+    }
+    write_findings(
+        "High severity",
+        report
+            .findings
+            .iter()
+            .filter(|finding| finding.severity() == Severity::High),
+        out,
+    )?;
+    write_hooks(report, out)?;
+    write_boundary("FFI bridges", FindingKind::FfiBridge, report, out)?;
+    write_boundary("JavaScript imports", FindingKind::JsImport, report, out)?;
+    write_findings(
+        "Pinning",
+        report
+            .findings
+            .iter()
+            .filter(|finding| finding.has_tag(Tag::Pin)),
+        out,
+    )?;
+    write_generated(&report.generated, out)?;
+    write_stdlib(report, out)?;
+    if opts.show_dependencies {
+        write_dependencies(&report.dependencies, out)?;
+    }
+    if opts.show_introduced {
+        write_introduced(&report.introduced, out)?;
+    }
+    if opts.show_blast_radius {
+        write_blast_radius(report, out)?;
+    }
+    if opts.show_unsafe_api {
+        write_unsafe_api(report, out)?;
+    }
+    if opts.show_encapsulation {
+        write_encapsulation(report, out)?;
+    }
+    if opts.show_suggestions {
+        write_suggestions(&report.suggestions, out)?;
+    }
+    if let Some(spec) = invert {
+        write_chains(report, spec, out)?;
+    }
+    write_build_time(report, out)?;
+    write_file_coverage(report, out)?;
+    write_inactive(&report.inactive, out)?;
+    write_skipped(&report.skipped, out)?;
+    write_scenarios("Scenario", &report.scenarios, out)?;
+    write_scenarios("Target", &report.targets, out)
+}
+
+/// Sources that do not match what the lockfile pins, first, since the rest of the report cannot be
+/// trusted for them
+fn write_checksum_mismatches(
+    mismatches: &[ChecksumMismatch],
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "CHECKSUM MISMATCHES")?;
+    for mismatch in mismatches {
+        match &mismatch.file {
+            Some(file) => writeln!(
+                out,
+                "    {} {}: {}: {}",
+                mismatch.krate,
+                mismatch.version,
+                file.display(),
+                mismatch.detail
+            )?,
+            None => writeln!(
+                out,
+                "    {} {}: {}",
+                mismatch.krate, mismatch.version, mismatch.detail
+            )?,
+        }
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Lists `findings` under `heading`, if there are any. Reviewers go through high severity and
+/// pinning findings on their own.
+fn write_findings<'a>(
+    heading: &str,
+    findings: impl Iterator<Item = &'a Finding>,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let mut findings = findings.peekable();
+    if findings.peek().is_none() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "{heading}")?;
+    for finding in findings {
+        writeln!(
+            out,
+            "    {} {}  {}:{}  {} ({})",
+            finding.krate,
+            finding.provenance.version,
+            finding.file.display(),
+            finding.line,
+            finding.item,
+            finding.describe_kind()
+        )?;
+    }
+    Ok(())
+}
+
+/// The items that claim global hooks, of which a program can only have one each
+fn write_hooks(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    let hooks = report
+        .findings
+        .iter()
+        .filter_map(|finding| Some((finding.hook.as_deref()?, finding)))
+        .collect::<Vec<_>>();
+    if hooks.is_empty() {
+        return Ok(());
+    }
+    let heading = "Global hook";
+    let width = hooks
+        .iter()
+        .map(|(hook, _)| hook.len())
+        .max()
+        .unwrap_or_default()
+        .max(heading.len());
+    writeln!(out)?;
+    writeln!(out, "{:width$}  Crate", heading)?;
+    for (hook, finding) in hooks {
+        writeln!(
+            out,
+            "{:width$}  {} {}  {}:{}  {}",
+            hook,
+            finding.krate,
+            finding.provenance.version,
+            finding.file.display(),
+            finding.line,
+            finding.item
+        )?;
+    }
+    Ok(())
+}
+
+/// The functions that cross to another language without an `unsafe` of their own, i.e., those of
+/// the findings of `kind`, by crate
+fn write_boundary(
+    heading: &str,
+    kind: FindingKind,
+    report: &Report,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let mut by_crate = BTreeMap::<_, Vec<_>>::new();
+    for finding in &report.findings {
+        if finding.kind == kind {
+            by_crate
+                .entry((&*finding.krate, finding.provenance.version.as_str()))
+                .or_default()
+                .push(&finding.item);
+        }
+    }
+    if by_crate.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "{heading}")?;
+    for ((krate, version), functions) in by_crate {
+        writeln!(
+            out,
+            "    {} {}: {} functions",
+            krate,
+            version,
+            functions.len()
+        )?;
+        for function in functions {
+            writeln!(out, "        {function}")?;
+        }
+    }
+    Ok(())
+}
+
+/// The findings pulled in by each direct dependency
+fn write_dependencies(
+    dependencies: &[DependencySummary],
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    if dependencies.is_empty() {
+        return Ok(());
+    }
+    let heading = "Direct dependency";
+    let names = dependencies
+        .iter()
+        .map(|dependency| format!("{} {}", dependency.name, dependency.version))
+        .collect::<Vec<_>>();
+    let width = names
+        .iter()
+        .map(String::len)
+        .max()
+        .unwrap_or_default()
+        .max(heading.len());
+    writeln!(out)?;
+    writeln!(out, "{:width$}  Crates  Findings  Exclusive", heading)?;
+    for (name, dependency) in names.iter().zip(dependencies) {
+        writeln!(
+            out,
+            "{:width$}  {:>6}  {:>8}  {:>9}",
+            name, dependency.crates, dependency.findings, dependency.exclusive_findings
+        )?;
+    }
+    Ok(())
+}
+
+/// Who first added each package with findings to the lockfile, and when
+fn write_introduced(introduced: &[Introduced], out: &mut dyn Write) -> anyhow::Result<()> {
+    if introduced.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "First introduced")?;
+    for entry in introduced {
+        writeln!(
+            out,
+            "    {}: {} by {} on {}: {}",
+            entry.krate,
+            &entry.commit[..entry.commit.len().min(12)],
+            entry.author,
+            entry.date.get(..10).unwrap_or(&entry.date),
+            entry.subject
+        )?;
+    }
+    Ok(())
+}
+
+/// The dependencies ranked by how many functions only their unsafety reaches, i.e., which would be
+/// most worth replacing
+fn write_blast_radius(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    let mut dependencies = report
+        .crates
+        .iter()
+        .filter(|summary| !summary.workspace_member && summary.blast_radius > 0)
+        .map(|summary| {
+            (
+                format!("{} {}", summary.name, summary.version),
+                summary.blast_radius,
+            )
+        })
+        .collect::<Vec<_>>();
+    if dependencies.is_empty() {
+        return Ok(());
+    }
+    dependencies.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let heading = "Blast radius";
+    let width = dependencies
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or_default()
+        .max(heading.len());
+    writeln!(out)?;
+    writeln!(out, "{:width$}  Functions", heading)?;
+    for (name, functions) in dependencies {
+        writeln!(out, "{:width$}  {:>9}", name, functions)?;
+    }
+    Ok(())
+}
+
+/// The dependencies whose public API is unsafe, which their users have to uphold the safety
+/// contracts of, apart from those that keep their unsafety internal
+fn write_unsafe_api(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    let dependencies = report
+        .crates
+        .iter()
+        .filter(|summary| !summary.workspace_member && !summary.public_unsafe_fns.is_empty())
+        .map(|summary| (format!("{} {}", summary.name, summary.version), summary))
+        .collect::<Vec<_>>();
+    if dependencies.is_empty() {
+        return Ok(());
+    }
+    let heading = "Unsafe public API";
+    let width = dependencies
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or_default()
+        .max(heading.len());
+    writeln!(out)?;
+    writeln!(out, "{:width$}  pub unsafe fns  Other findings", heading)?;
+    for (name, summary) in dependencies {
+        writeln!(
+            out,
+            "{:width$}  {:>14}  {:>14}",
+            name,
+            summary.public_unsafe_fns.len(),
+            summary
+                .findings
+                .saturating_sub(summary.public_unsafe_fns.len())
+        )?;
+    }
+    Ok(())
+}
+
+/// The dependencies with unsafety, the least encapsulated first (see
+/// `CrateSummary::encapsulation`)
+fn write_encapsulation(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    let mut dependencies = report
+        .crates
+        .iter()
+        .filter(|summary| !summary.workspace_member)
+        .filter_map(|summary| {
+            Some((
+                format!("{} {}", summary.name, summary.version),
+                summary,
+                summary.encapsulation()?,
+            ))
+        })
+        .collect::<Vec<_>>();
+    if dependencies.is_empty() {
+        return Ok(());
+    }
+    dependencies.sort_by(|a, b| a.2.total_cmp(&b.2).then_with(|| a.0.cmp(&b.0)));
+    let heading = "Encapsulation";
+    let width = dependencies
+        .iter()
+        .map(|(name, _, _)| name.len())
+        .max()
+        .unwrap_or_default()
+        .max(heading.len());
+    writeln!(out)?;
+    writeln!(out, "{:width$}  Score  Internal  Exported", heading)?;
+    for (name, summary, score) in dependencies {
+        let exported = summary.public_unsafe_fns.len() + summary.public_unsafe_traits.len();
+        writeln!(
+            out,
+            "{:width$}  {:>5.2}  {:>8}  {:>8}",
+            name,
+            score,
+            summary
+                .findings
+                .saturating_sub(summary.public_unsafe_fns.len()),
+            exported
+        )?;
+    }
+    Ok(())
+}
+
+/// Safer alternatives to the dependencies with findings
+fn write_suggestions(suggestions: &[Suggestion], out: &mut dyn Write) -> anyhow::Result<()> {
+    if suggestions.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "Suggested alternatives")?;
+    for suggestion in suggestions {
+        write!(
+            out,
+            "    {} {} ({} findings): {}",
+            suggestion.krate,
+            suggestion.version,
+            suggestion.findings,
+            suggestion.alternatives.join(", ")
+        )?;
+        match &suggestion.note {
+            Some(note) => writeln!(out, " ({note})")?,
+            None => writeln!(out)?,
+        }
+    }
+    Ok(())
+}
+
+/// With `--invert`, the dependency chains through which the workspace members pull in the packages
+/// `spec` refers to
+fn write_chains(report: &Report, spec: &str, out: &mut dyn Write) -> anyhow::Result<()> {
+    let targets = report
+        .crates
+        .iter()
+        .filter(|summary| dependency::matches(summary, spec))
+        .collect::<Vec<_>>();
+    if targets.is_empty() {
+        bail!("No package in the tree matches `{}`", spec);
+    }
+    for target in targets {
+        let (chains, truncated) = dependency::chains(&report.crates, target);
+        writeln!(out)?;
+        writeln!(
+            out,
+            "Pulled in by  {} {} ({} findings)",
+            target.name, target.version, target.findings
+        )?;
+        for chain in chains {
+            let chain = chain
+                .iter()
+                .map(|summary| format!("{} {}", summary.name, summary.version))
+                .collect::<Vec<_>>();
+            writeln!(out, "    {}", chain.join(" -> "))?;
+        }
+        if truncated {
+            writeln!(
+                out,
+                "    ... (only the first {} chains are listed)",
+                dependency::MAX_CHAINS
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// The files that the build compiled but that were not scanned, if any
+fn write_file_coverage(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    let coverage = match report.file_coverage {
+        Some(coverage) if coverage.scanned < coverage.compiled => coverage,
+        _ => return Ok(()),
+    };
+    writeln!(out)?;
+    writeln!(
+        out,
+        "Scanned {} of the {} files compiled ({:.1}%)",
+        coverage.scanned,
+        coverage.compiled,
+        coverage.percent()
+    )?;
+    Ok(())
+}
+
+/// The findings of the proc-macro crates, whose code runs inside rustc at build time rather than in
+/// the program, and so calls for a different kind of trust
+fn write_build_time(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    let proc_macros = report
+        .crates
+        .iter()
+        .filter(|summary| summary.proc_macro)
+        .map(|summary| {
+            (
+                format!("{} {}", summary.name, summary.version),
+                summary.findings,
+            )
+        })
+        .collect::<Vec<_>>();
+    if proc_macros.is_empty() {
+        return Ok(());
+    }
+    let heading = "Build-time execution";
+    let width = proc_macros
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or_default()
+        .max(heading.len());
+    writeln!(out)?;
+    writeln!(out, "{:width$}  Findings", heading)?;
+    for (name, findings) in proc_macros {
+        writeln!(out, "{:width$}  {:>8}", name, findings)?;
+    }
+    Ok(())
+}
+
+/// The standard library crates and their replacements, apart since desktop trees do not have them,
+/// and whether they count towards the totals
+fn write_stdlib(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    let included = report
+        .crates
+        .iter()
+        .filter(|summary| summary.category == category::STDLIB)
+        .collect::<Vec<_>>();
+    if included.is_empty() && report.stdlib.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "Standard library crates")?;
+    for summary in included {
+        writeln!(
+            out,
+            "    {} {}: {} findings",
+            summary.name, summary.version, summary.findings
+        )?;
+    }
+    for summary in &report.stdlib {
+        writeln!(
+            out,
+            "    {} {}: {} findings (excluded)",
+            summary.name, summary.version, summary.findings
+        )?;
+    }
+    Ok(())
+}
+
+/// With `--collapse-generated`, the findings in generated files, one line per package
+fn write_generated(generated: &[GeneratedCode], out: &mut dyn Write) -> anyhow::Result<()> {
+    if generated.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "Generated code")?;
+    for code in generated {
+        writeln!(
+            out,
+            "    {} {}: {} findings in {} files",
+            code.krate, code.version, code.findings, code.files
+        )?;
+    }
+    Ok(())
+}
+
+/// The optional dependencies left out with `--no-optional-deps`
+fn write_inactive(inactive: &[String], out: &mut dyn Write) -> anyhow::Result<()> {
+    if inactive.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "Present but inactive")?;
+    for id in inactive {
+        writeln!(out, "    {id}")?;
+    }
+    Ok(())
+}
+
+/// The packages left out, failures first. The inactive ones and the standard library have their own
+/// sections.
+fn write_skipped(skipped: &[SkippedCrate], out: &mut dyn Write) -> anyhow::Result<()> {
+    let mut skipped = skipped
+        .iter()
+        .filter(|skipped| !matches!(skipped.reason, SkipReason::Inactive | SkipReason::Stdlib))
+        .collect::<Vec<_>>();
+    if skipped.is_empty() {
+        return Ok(());
+    }
+    skipped.sort_by_key(|skipped| skipped.reason.is_intentional());
+    writeln!(out)?;
+    writeln!(out, "Skipped crates")?;
+    for skipped in skipped {
+        match &skipped.detail {
+            Some(detail) => writeln!(
+                out,
+                "    {} {}: {}: {}",
+                skipped.krate, skipped.version, skipped.reason, detail
+            )?,
+            None => writeln!(
+                out,
+                "    {} {}: {}",
+                skipped.krate, skipped.version, skipped.reason
+            )?,
+        }
+    }
+    Ok(())
+}
+
+/// The findings of each scenario, and how they compare to those of the first
+fn write_scenarios(
+    heading: &str,
+    scenarios: &[ScenarioSummary],
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let first = match scenarios.first() {
+        Some(first) => first,
+        None => return Ok(()),
+    };
+    let width = scenarios
+        .iter()
+        .map(|scenario| scenario.name.len())
+        .max()
+        .unwrap_or_default()
+        .max(heading.len());
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{:width$}  Crates  Findings  Change  Features",
+        heading
+    )?;
+    for scenario in scenarios {
+        let change = scenario.findings as i64 - first.findings as i64;
+        writeln!(
+            out,
+            "{:width$}  {:>6}  {:>8}  {:>+6}  {}",
+            scenario.name,
+            scenario.crates,
+            scenario.findings,
+            change,
+            scenario.features.join(",")
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,1089 @@
+//! The compact binary report format. The messages mirror `schema/report.proto`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+use prost::Message;
+
+use crate::{
+    profile::Profile,
+    report::{self, Provenance},
+};
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Report {
+    #[prost(message, repeated, tag = "1")]
+    pub crates: Vec<CrateSummary>,
+
+    #[prost(message, repeated, tag = "2")]
+    pub findings: Vec<Finding>,
+
+    #[prost(message, repeated, tag = "3")]
+    pub badness: Vec<BadnessEntry>,
+
+    #[prost(message, repeated, tag = "4")]
+    pub errors: Vec<ScanIssue>,
+
+    #[prost(message, repeated, tag = "5")]
+    pub duplicates: Vec<DuplicateSource>,
+
+    #[prost(string, repeated, tag = "6")]
+    pub strings: Vec<String>,
+
+    #[prost(message, repeated, tag = "7")]
+    pub categories: Vec<CategorySummary>,
+
+    #[prost(message, optional, tag = "8")]
+    pub metadata: Option<Metadata>,
+
+    #[prost(message, repeated, tag = "9")]
+    pub scenarios: Vec<Scenario>,
+
+    #[prost(message, repeated, tag = "10")]
+    pub targets: Vec<Scenario>,
+
+    #[prost(message, repeated, tag = "11")]
+    pub calls: Vec<Call>,
+
+    #[prost(message, repeated, tag = "12")]
+    pub dependencies: Vec<DependencySummary>,
+
+    #[prost(string, repeated, tag = "13")]
+    pub inactive: Vec<String>,
+
+    #[prost(message, repeated, tag = "14")]
+    pub suggestions: Vec<Suggestion>,
+
+    #[prost(message, repeated, tag = "15")]
+    pub api: Vec<ApiFunction>,
+
+    #[prost(message, repeated, tag = "16")]
+    pub checksum_mismatches: Vec<ChecksumMismatch>,
+
+    #[prost(message, repeated, tag = "17")]
+    pub generated: Vec<GeneratedCode>,
+
+    #[prost(message, repeated, tag = "18")]
+    pub introduced: Vec<Introduced>,
+
+    #[prost(message, repeated, tag = "19")]
+    pub stdlib: Vec<CrateSummary>,
+
+    #[prost(message, optional, tag = "20")]
+    pub file_coverage: Option<FileCoverage>,
+
+    #[prost(message, repeated, tag = "21")]
+    pub skipped: Vec<SkippedCrate>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FileCoverage {
+    #[prost(uint64, tag = "1")]
+    pub compiled: u64,
+
+    #[prost(uint64, tag = "2")]
+    pub scanned: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Scenario {
+    #[prost(string, tag = "1")]
+    pub name: String,
+
+    #[prost(string, repeated, tag = "2")]
+    pub features: Vec<String>,
+
+    #[prost(uint64, tag = "3")]
+    pub crates: u64,
+
+    #[prost(uint64, tag = "4")]
+    pub findings: u64,
+
+    #[prost(btree_map = "string, uint64", tag = "5")]
+    pub by_crate: BTreeMap<String, u64>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Metadata {
+    #[prost(string, tag = "1")]
+    pub siderophile_version: String,
+
+    #[prost(string, tag = "2")]
+    pub rustc_version: String,
+
+    #[prost(string, tag = "3")]
+    pub target: String,
+
+    #[prost(btree_map = "string, message", tag = "4")]
+    pub features: BTreeMap<String, FeatureList>,
+
+    #[prost(string, repeated, tag = "5")]
+    pub arguments: Vec<String>,
+
+    #[prost(message, optional, tag = "6")]
+    pub inputs: Option<Inputs>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Inputs {
+    #[prost(string, tag = "1")]
+    pub lockfile: String,
+
+    #[prost(string, optional, tag = "2")]
+    pub commit: Option<String>,
+
+    #[prost(bool, tag = "3")]
+    pub dirty: bool,
+
+    #[prost(string, tag = "4")]
+    pub digest: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FeatureList {
+    #[prost(string, repeated, tag = "1")]
+    pub features: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct CrateSummary {
+    #[prost(string, tag = "1")]
+    pub name: String,
+
+    #[prost(string, tag = "2")]
+    pub version: String,
+
+    #[prost(string, tag = "3")]
+    pub source: String,
+
+    #[prost(string, optional, tag = "4")]
+    pub checksum: Option<String>,
+
+    #[prost(bool, tag = "5")]
+    pub workspace_member: bool,
+
+    #[prost(bool, tag = "6")]
+    pub no_std: bool,
+
+    #[prost(string, optional, tag = "7")]
+    pub repository: Option<String>,
+
+    #[prost(uint64, tag = "8")]
+    pub findings: u64,
+
+    #[prost(string, tag = "9")]
+    pub category: String,
+
+    #[prost(message, optional, tag = "10")]
+    pub coverage: Option<Coverage>,
+
+    #[prost(string, optional, tag = "11")]
+    pub license: Option<String>,
+
+    #[prost(string, optional, tag = "12")]
+    pub latest_version: Option<String>,
+
+    #[prost(string, repeated, tag = "13")]
+    pub dependencies: Vec<String>,
+
+    #[prost(bool, tag = "14")]
+    pub proc_macro: bool,
+
+    #[prost(uint64, tag = "15")]
+    pub blast_radius: u64,
+
+    #[prost(string, repeated, tag = "16")]
+    pub public_unsafe_fns: Vec<String>,
+
+    #[prost(string, repeated, tag = "17")]
+    pub public_unsafe_traits: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Coverage {
+    #[prost(string, repeated, tag = "1")]
+    pub missing_targets: Vec<String>,
+
+    #[prost(string, optional, tag = "2")]
+    pub fetched_from: Option<String>,
+
+    #[prost(bool, tag = "3")]
+    pub build_failed: bool,
+
+    #[prost(string, optional, tag = "4")]
+    pub cached_copy: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct DependencySummary {
+    #[prost(string, tag = "1")]
+    pub name: String,
+
+    #[prost(string, tag = "2")]
+    pub version: String,
+
+    #[prost(uint64, tag = "3")]
+    pub crates: u64,
+
+    #[prost(uint64, tag = "4")]
+    pub findings: u64,
+
+    #[prost(uint64, tag = "5")]
+    pub exclusive_findings: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct CategorySummary {
+    #[prost(string, tag = "1")]
+    pub category: String,
+
+    #[prost(uint64, tag = "2")]
+    pub crates: u64,
+
+    #[prost(uint64, tag = "3")]
+    pub findings: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum FindingKind {
+    Function = 0,
+    Block = 1,
+    UnpinImpl = 2,
+    GlobalAllocator = 3,
+    GlobalHook = 4,
+    Constructor = 5,
+    LinkSection = 6,
+    FfiBridge = 7,
+    JsImport = 8,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Finding {
+    #[prost(uint32, tag = "1")]
+    pub krate: u32,
+
+    #[prost(uint32, tag = "2")]
+    pub file: u32,
+
+    #[prost(uint32, tag = "3")]
+    pub version: u32,
+
+    #[prost(uint32, tag = "4")]
+    pub source: u32,
+
+    #[prost(uint32, optional, tag = "5")]
+    pub checksum: Option<u32>,
+
+    #[prost(uint64, tag = "6")]
+    pub line: u64,
+
+    #[prost(string, tag = "7")]
+    pub item: String,
+
+    #[prost(enumeration = "FindingKind", tag = "8")]
+    pub kind: i32,
+
+    #[prost(string, tag = "9")]
+    pub fingerprint: String,
+
+    #[prost(string, optional, tag = "10")]
+    pub cfg: Option<String>,
+
+    #[prost(string, repeated, tag = "11")]
+    pub unchecked: Vec<String>,
+
+    #[prost(string, repeated, tag = "12")]
+    pub tags: Vec<String>,
+
+    #[prost(string, optional, tag = "13")]
+    pub hook: Option<String>,
+
+    #[prost(bool, tag = "14")]
+    pub generated: bool,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BadnessEntry {
+    #[prost(string, tag = "1")]
+    pub function: String,
+
+    #[prost(uint32, tag = "2")]
+    pub badness: u32,
+
+    #[prost(string, optional, tag = "3")]
+    pub file: Option<String>,
+
+    #[prost(uint32, optional, tag = "4")]
+    pub line: Option<u32>,
+
+    #[prost(string, optional, tag = "5")]
+    pub only_in: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Call {
+    #[prost(string, tag = "1")]
+    pub caller: String,
+
+    #[prost(string, tag = "2")]
+    pub callee: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ScanIssue {
+    #[prost(string, optional, tag = "1")]
+    pub package: Option<String>,
+
+    #[prost(string, optional, tag = "2")]
+    pub path: Option<String>,
+
+    #[prost(string, tag = "3")]
+    pub message: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct DuplicateSource {
+    #[prost(string, tag = "1")]
+    pub krate: String,
+
+    #[prost(string, repeated, tag = "2")]
+    pub versions: Vec<String>,
+
+    #[prost(uint64, tag = "3")]
+    pub files: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ApiFunction {
+    #[prost(string, tag = "1")]
+    pub function: String,
+
+    #[prost(string, tag = "2")]
+    pub krate: String,
+
+    #[prost(string, tag = "3")]
+    pub file: String,
+
+    #[prost(uint64, tag = "4")]
+    pub line: u64,
+
+    #[prost(bool, tag = "5")]
+    pub unsafe_fn: bool,
+
+    #[prost(uint32, tag = "6")]
+    pub badness: u32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ChecksumMismatch {
+    #[prost(string, tag = "1")]
+    pub krate: String,
+
+    #[prost(string, tag = "2")]
+    pub version: String,
+
+    #[prost(string, optional, tag = "3")]
+    pub file: Option<String>,
+
+    #[prost(string, tag = "4")]
+    pub detail: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum SkipReason {
+    DownloadFailed = 0,
+    NotVendored = 1,
+    ChecksumFailed = 2,
+    BuildFailed = 3,
+    Inactive = 4,
+    Waived = 5,
+    Stdlib = 6,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct SkippedCrate {
+    #[prost(string, tag = "1")]
+    pub krate: String,
+
+    #[prost(string, tag = "2")]
+    pub version: String,
+
+    #[prost(enumeration = "SkipReason", tag = "3")]
+    pub reason: i32,
+
+    #[prost(string, optional, tag = "4")]
+    pub detail: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Introduced {
+    #[prost(string, tag = "1")]
+    pub krate: String,
+
+    #[prost(string, tag = "2")]
+    pub commit: String,
+
+    #[prost(string, tag = "3")]
+    pub author: String,
+
+    #[prost(string, tag = "4")]
+    pub date: String,
+
+    #[prost(string, tag = "5")]
+    pub subject: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct GeneratedCode {
+    #[prost(string, tag = "1")]
+    pub krate: String,
+
+    #[prost(string, tag = "2")]
+    pub version: String,
+
+    #[prost(uint64, tag = "3")]
+    pub files: u64,
+
+    #[prost(uint64, tag = "4")]
+    pub findings: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Suggestion {
+    #[prost(string, tag = "1")]
+    pub krate: String,
+
+    #[prost(string, tag = "2")]
+    pub version: String,
+
+    #[prost(uint64, tag = "3")]
+    pub findings: u64,
+
+    #[prost(string, repeated, tag = "4")]
+    pub alternatives: Vec<String>,
+
+    #[prost(string, optional, tag = "5")]
+    pub note: Option<String>,
+}
+
+impl From<report::FindingKind> for FindingKind {
+    fn from(kind: report::FindingKind) -> Self {
+        match kind {
+            report::FindingKind::Function => Self::Function,
+            report::FindingKind::Block => Self::Block,
+            report::FindingKind::UnpinImpl => Self::UnpinImpl,
+            report::FindingKind::GlobalAllocator => Self::GlobalAllocator,
+            report::FindingKind::GlobalHook => Self::GlobalHook,
+            report::FindingKind::Constructor => Self::Constructor,
+            report::FindingKind::LinkSection => Self::LinkSection,
+            report::FindingKind::FfiBridge => Self::FfiBridge,
+            report::FindingKind::JsImport => Self::JsImport,
+        }
+    }
+}
+
+impl From<report::SkipReason> for SkipReason {
+    fn from(reason: report::SkipReason) -> Self {
+        match reason {
+            report::SkipReason::DownloadFailed => Self::DownloadFailed,
+            report::SkipReason::NotVendored => Self::NotVendored,
+            report::SkipReason::ChecksumFailed => Self::ChecksumFailed,
+            report::SkipReason::BuildFailed => Self::BuildFailed,
+            report::SkipReason::Inactive => Self::Inactive,
+            report::SkipReason::Waived => Self::Waived,
+            report::SkipReason::Stdlib => Self::Stdlib,
+        }
+    }
+}
+
+/// Builds `Report.strings`
+#[derive(Default)]
+struct Strings {
+    indices: HashMap<String, u32>,
+    strings: Vec<String>,
+}
+
+impl Strings {
+    fn index(&mut self, s: &str) -> u32 {
+        if let Some(index) = self.indices.get(s) {
+            return *index;
+        }
+        let index = self.strings.len() as u32;
+        self.indices.insert(s.to_string(), index);
+        self.strings.push(s.to_string());
+        index
+    }
+}
+
+/// Paths are stored as strings, lossily on platforms where they need not be Unicode
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+pub fn encode(report: &report::Report) -> Vec<u8> {
+    let mut strings = Strings::default();
+    let findings = report
+        .findings
+        .iter()
+        .map(|finding| Finding {
+            krate: strings.index(&finding.krate),
+            file: strings.index(&path_string(&finding.file)),
+            version: strings.index(&finding.provenance.version),
+            source: strings.index(&finding.provenance.source),
+            checksum: finding
+                .provenance
+                .checksum
+                .as_ref()
+                .map(|checksum| strings.index(checksum)),
+            line: finding.line as u64,
+            item: finding.item.clone(),
+            kind: FindingKind::from(finding.kind) as i32,
+            fingerprint: finding.fingerprint.clone(),
+            cfg: finding.cfg.clone(),
+            unchecked: finding.unchecked.clone(),
+            tags: finding.tags.iter().map(ToString::to_string).collect(),
+            hook: finding.hook.clone(),
+            generated: finding.generated,
+        })
+        .collect();
+    let metadata = &report.metadata;
+    Report {
+        metadata: Some(Metadata {
+            siderophile_version: metadata.siderophile_version.clone(),
+            rustc_version: metadata.rustc_version.clone(),
+            target: metadata.target.clone(),
+            features: metadata
+                .features
+                .iter()
+                .map(|(member, features)| {
+                    (
+                        member.clone(),
+                        FeatureList {
+                            features: features.clone(),
+                        },
+                    )
+                })
+                .collect(),
+            arguments: metadata.arguments.clone(),
+            inputs: metadata.inputs.as_ref().map(|inputs| Inputs {
+                lockfile: inputs.lockfile.clone(),
+                commit: inputs.commit.clone(),
+                dirty: inputs.dirty,
+                digest: inputs.digest.clone(),
+            }),
+        }),
+        crates: report.crates.iter().map(encode_crate).collect(),
+        categories: report
+            .categories
+            .iter()
+            .map(|category| CategorySummary {
+                category: category.category.clone(),
+                crates: category.crates as u64,
+                findings: category.findings as u64,
+            })
+            .collect(),
+        dependencies: report
+            .dependencies
+            .iter()
+            .map(|dependency| DependencySummary {
+                name: dependency.name.clone(),
+                version: dependency.version.clone(),
+                crates: dependency.crates as u64,
+                findings: dependency.findings as u64,
+                exclusive_findings: dependency.exclusive_findings as u64,
+            })
+            .collect(),
+        inactive: report.inactive.clone(),
+        suggestions: report
+            .suggestions
+            .iter()
+            .map(|suggestion| Suggestion {
+                krate: suggestion.krate.clone(),
+                version: suggestion.version.clone(),
+                findings: suggestion.findings as u64,
+                alternatives: suggestion.alternatives.clone(),
+                note: suggestion.note.clone(),
+            })
+            .collect(),
+        checksum_mismatches: report
+            .checksum_mismatches
+            .iter()
+            .map(|mismatch| ChecksumMismatch {
+                krate: mismatch.krate.clone(),
+                version: mismatch.version.clone(),
+                file: mismatch.file.as_deref().map(path_string),
+                detail: mismatch.detail.clone(),
+            })
+            .collect(),
+        generated: report
+            .generated
+            .iter()
+            .map(|code| GeneratedCode {
+                krate: code.krate.clone(),
+                version: code.version.clone(),
+                files: code.files as u64,
+                findings: code.findings as u64,
+            })
+            .collect(),
+        introduced: report
+            .introduced
+            .iter()
+            .map(|entry| Introduced {
+                krate: entry.krate.clone(),
+                commit: entry.commit.clone(),
+                author: entry.author.clone(),
+                date: entry.date.clone(),
+                subject: entry.subject.clone(),
+            })
+            .collect(),
+        stdlib: report.stdlib.iter().map(encode_crate).collect(),
+        file_coverage: report.file_coverage.map(|coverage| FileCoverage {
+            compiled: coverage.compiled as u64,
+            scanned: coverage.scanned as u64,
+        }),
+        skipped: report
+            .skipped
+            .iter()
+            .map(|skipped| SkippedCrate {
+                krate: skipped.krate.clone(),
+                version: skipped.version.clone(),
+                reason: SkipReason::from(skipped.reason) as i32,
+                detail: skipped.detail.clone(),
+            })
+            .collect(),
+        api: report
+            .api
+            .iter()
+            .map(|function| ApiFunction {
+                function: function.function.clone(),
+                krate: function.krate.clone(),
+                file: path_string(&function.file),
+                line: function.line as u64,
+                unsafe_fn: function.unsafe_fn,
+                badness: function.badness,
+            })
+            .collect(),
+        findings,
+        badness: report
+            .badness
+            .iter()
+            .map(|entry| BadnessEntry {
+                function: entry.function.clone(),
+                badness: entry.badness,
+                file: entry.file.as_deref().map(path_string),
+                line: entry.line,
+                only_in: entry.only_in.map(|profile| profile.as_str().to_string()),
+            })
+            .collect(),
+        calls: report
+            .calls
+            .iter()
+            .map(|call| Call {
+                caller: call.caller.clone(),
+                callee: call.callee.clone(),
+            })
+            .collect(),
+        errors: report
+            .errors
+            .iter()
+            .map(|issue| ScanIssue {
+                package: issue.package.clone(),
+                path: issue.path.as_deref().map(path_string),
+                message: issue.message.clone(),
+            })
+            .collect(),
+        duplicates: report
+            .duplicates
+            .iter()
+            .map(|duplicate| DuplicateSource {
+                krate: duplicate.krate.clone(),
+                versions: duplicate.versions.clone(),
+                files: duplicate.files as u64,
+            })
+            .collect(),
+        strings: strings.strings,
+        scenarios: report.scenarios.iter().map(encode_scenario).collect(),
+        targets: report.targets.iter().map(encode_scenario).collect(),
+    }
+    .encode_to_vec()
+}
+
+fn encode_crate(summary: &report::CrateSummary) -> CrateSummary {
+    CrateSummary {
+        name: summary.name.clone(),
+        version: summary.version.clone(),
+        source: summary.source.clone(),
+        checksum: summary.checksum.clone(),
+        workspace_member: summary.workspace_member,
+        no_std: summary.no_std,
+        proc_macro: summary.proc_macro,
+        repository: summary.repository.clone(),
+        license: summary.license.clone(),
+        latest_version: summary.latest_version.clone(),
+        findings: summary.findings as u64,
+        blast_radius: summary.blast_radius as u64,
+        public_unsafe_fns: summary.public_unsafe_fns.clone(),
+        public_unsafe_traits: summary.public_unsafe_traits.clone(),
+        category: summary.category.clone(),
+        coverage: Some(Coverage {
+            missing_targets: summary
+                .coverage
+                .missing_targets
+                .iter()
+                .map(|path| path_string(path))
+                .collect(),
+            fetched_from: summary.coverage.fetched_from.clone(),
+            build_failed: summary.coverage.build_failed,
+            cached_copy: summary.coverage.cached_copy.as_deref().map(path_string),
+        }),
+        dependencies: summary.dependencies.clone(),
+    }
+}
+
+fn encode_scenario(scenario: &report::ScenarioSummary) -> Scenario {
+    Scenario {
+        name: scenario.name.clone(),
+        features: scenario.features.clone(),
+        crates: scenario.crates as u64,
+        findings: scenario.findings as u64,
+        by_crate: scenario
+            .by_crate
+            .iter()
+            .map(|(krate, count)| (krate.clone(), *count as u64))
+            .collect(),
+    }
+}
+
+pub fn decode(bytes: &[u8]) -> anyhow::Result<report::Report> {
+    let message = Report::decode(bytes)?;
+    let string = |index: u32| {
+        message
+            .strings
+            .get(index as usize)
+            .ok_or_else(|| anyhow!("String index {} is out of range", index))
+    };
+
+    // Share crate names, files and provenances between findings, as a trawl does
+    let mut krates = HashMap::<u32, Arc<str>>::new();
+    let mut files = HashMap::<u32, Arc<Path>>::new();
+    let mut provenances = HashMap::<(u32, u32, Option<u32>), Arc<Provenance>>::new();
+    let mut findings = Vec::with_capacity(message.findings.len());
+    for finding in &message.findings {
+        let krate = match krates.get(&finding.krate) {
+            Some(krate) => krate.clone(),
+            None => {
+                let krate = Arc::<str>::from(string(finding.krate)?.as_str());
+                krates.insert(finding.krate, krate.clone());
+                krate
+            }
+        };
+        let file = match files.get(&finding.file) {
+            Some(file) => file.clone(),
+            None => {
+                let file = Arc::<Path>::from(Path::new(string(finding.file)?));
+                files.insert(finding.file, file.clone());
+                file
+            }
+        };
+        let key = (finding.version, finding.source, finding.checksum);
+        let provenance = match provenances.get(&key) {
+            Some(provenance) => provenance.clone(),
+            None => {
+                let provenance = Arc::new(Provenance {
+                    version: string(finding.version)?.clone(),
+                    source: string(finding.source)?.clone(),
+                    checksum: finding.checksum.map(string).transpose()?.cloned(),
+                });
+                provenances.insert(key, provenance.clone());
+                provenance
+            }
+        };
+        findings.push(report::Finding {
+            krate,
+            file,
+            line: usize::try_from(finding.line)?,
+            provenance,
+            item: finding.item.clone(),
+            kind: match FindingKind::from_i32(finding.kind) {
+                Some(FindingKind::Function) => report::FindingKind::Function,
+                Some(FindingKind::Block) => report::FindingKind::Block,
+                Some(FindingKind::UnpinImpl) => report::FindingKind::UnpinImpl,
+                Some(FindingKind::GlobalAllocator) => report::FindingKind::GlobalAllocator,
+                Some(FindingKind::GlobalHook) => report::FindingKind::GlobalHook,
+                Some(FindingKind::Constructor) => report::FindingKind::Constructor,
+                Some(FindingKind::LinkSection) => report::FindingKind::LinkSection,
+                Some(FindingKind::FfiBridge) => report::FindingKind::FfiBridge,
+                Some(FindingKind::JsImport) => report::FindingKind::JsImport,
+                None => return Err(anyhow!("Unknown finding kind {}", finding.kind)),
+            },
+            fingerprint: finding.fingerprint.clone(),
+            cfg: finding.cfg.clone(),
+            unchecked: finding.unchecked.clone(),
+            tags: finding
+                .tags
+                .iter()
+                .map(|tag| tag.parse())
+                .collect::<anyhow::Result<_>>()?,
+            hook: finding.hook.clone(),
+            generated: finding.generated,
+        });
+    }
+
+    Ok(report::Report {
+        metadata: message
+            .metadata
+            .as_ref()
+            .map(|metadata| report::Metadata {
+                siderophile_version: metadata.siderophile_version.clone(),
+                rustc_version: metadata.rustc_version.clone(),
+                target: metadata.target.clone(),
+                features: metadata
+                    .features
+                    .iter()
+                    .map(|(member, list)| (member.clone(), list.features.clone()))
+                    .collect(),
+                arguments: metadata.arguments.clone(),
+                inputs: metadata.inputs.as_ref().map(|inputs| report::Inputs {
+                    lockfile: inputs.lockfile.clone(),
+                    commit: inputs.commit.clone(),
+                    dirty: inputs.dirty,
+                    digest: inputs.digest.clone(),
+                }),
+            })
+            .unwrap_or_default(),
+        crates: message
+            .crates
+            .iter()
+            .map(decode_crate)
+            .collect::<anyhow::Result<_>>()?,
+        categories: message
+            .categories
+            .iter()
+            .map(|category| -> anyhow::Result<_> {
+                Ok(report::CategorySummary {
+                    category: category.category.clone(),
+                    crates: usize::try_from(category.crates)?,
+                    findings: usize::try_from(category.findings)?,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?,
+        dependencies: message
+            .dependencies
+            .iter()
+            .map(|dependency| -> anyhow::Result<_> {
+                Ok(report::DependencySummary {
+                    name: dependency.name.clone(),
+                    version: dependency.version.clone(),
+                    crates: usize::try_from(dependency.crates)?,
+                    findings: usize::try_from(dependency.findings)?,
+                    exclusive_findings: usize::try_from(dependency.exclusive_findings)?,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?,
+        inactive: message.inactive.clone(),
+        suggestions: message
+            .suggestions
+            .iter()
+            .map(|suggestion| -> anyhow::Result<_> {
+                Ok(report::Suggestion {
+                    krate: suggestion.krate.clone(),
+                    version: suggestion.version.clone(),
+                    findings: usize::try_from(suggestion.findings)?,
+                    alternatives: suggestion.alternatives.clone(),
+                    note: suggestion.note.clone(),
+                })
+            })
+            .collect::<anyhow::Result<_>>()?,
+        checksum_mismatches: message
+            .checksum_mismatches
+            .iter()
+            .map(|mismatch| report::ChecksumMismatch {
+                krate: mismatch.krate.clone(),
+                version: mismatch.version.clone(),
+                file: mismatch.file.as_ref().map(PathBuf::from),
+                detail: mismatch.detail.clone(),
+            })
+            .collect(),
+        generated: message
+            .generated
+            .iter()
+            .map(|code| -> anyhow::Result<_> {
+                Ok(report::GeneratedCode {
+                    krate: code.krate.clone(),
+                    version: code.version.clone(),
+                    files: usize::try_from(code.files)?,
+                    findings: usize::try_from(code.findings)?,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?,
+        introduced: message
+            .introduced
+            .iter()
+            .map(|entry| report::Introduced {
+                krate: entry.krate.clone(),
+                commit: entry.commit.clone(),
+                author: entry.author.clone(),
+                date: entry.date.clone(),
+                subject: entry.subject.clone(),
+            })
+            .collect(),
+        stdlib: message
+            .stdlib
+            .iter()
+            .map(decode_crate)
+            .collect::<anyhow::Result<_>>()?,
+        file_coverage: message
+            .file_coverage
+            .as_ref()
+            .map(|coverage| -> anyhow::Result<_> {
+                Ok(report::FileCoverage {
+                    compiled: usize::try_from(coverage.compiled)?,
+                    scanned: usize::try_from(coverage.scanned)?,
+                })
+            })
+            .transpose()?,
+        skipped: message
+            .skipped
+            .iter()
+            .map(|skipped| -> anyhow::Result<_> {
+                Ok(report::SkippedCrate {
+                    krate: skipped.krate.clone(),
+                    version: skipped.version.clone(),
+                    reason: match SkipReason::from_i32(skipped.reason) {
+                        Some(SkipReason::DownloadFailed) => report::SkipReason::DownloadFailed,
+                        Some(SkipReason::NotVendored) => report::SkipReason::NotVendored,
+                        Some(SkipReason::ChecksumFailed) => report::SkipReason::ChecksumFailed,
+                        Some(SkipReason::BuildFailed) => report::SkipReason::BuildFailed,
+                        Some(SkipReason::Inactive) => report::SkipReason::Inactive,
+                        Some(SkipReason::Waived) => report::SkipReason::Waived,
+                        Some(SkipReason::Stdlib) => report::SkipReason::Stdlib,
+                        None => return Err(anyhow!("Unknown skip reason {}", skipped.reason)),
+                    },
+                    detail: skipped.detail.clone(),
+                })
+            })
+            .collect::<anyhow::Result<_>>()?,
+        api: message
+            .api
+            .iter()
+            .map(|function| -> anyhow::Result<_> {
+                Ok(report::ApiFunction {
+                    function: function.function.clone(),
+                    krate: function.krate.clone(),
+                    file: PathBuf::from(&function.file),
+                    line: usize::try_from(function.line)?,
+                    unsafe_fn: function.unsafe_fn,
+                    badness: function.badness,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?,
+        findings,
+        badness: message
+            .badness
+            .iter()
+            .map(|entry| -> anyhow::Result<_> {
+                Ok(report::BadnessEntry {
+                    function: entry.function.clone(),
+                    badness: entry.badness,
+                    file: entry.file.as_ref().map(PathBuf::from),
+                    line: entry.line,
+                    only_in: match entry.only_in.as_deref() {
+                        None => None,
+                        Some("debug") => Some(Profile::Debug),
+                        Some("release") => Some(Profile::Release),
+                        Some(profile) => return Err(anyhow!("Unknown profile `{}`", profile)),
+                    },
+                })
+            })
+            .collect::<anyhow::Result<_>>()?,
+        calls: message
+            .calls
+            .iter()
+            .map(|call| report::Call {
+                caller: call.caller.clone(),
+                callee: call.callee.clone(),
+            })
+            .collect(),
+        errors: message
+            .errors
+            .iter()
+            .map(|issue| report::ScanIssue {
+                package: issue.package.clone(),
+                path: issue.path.as_ref().map(PathBuf::from),
+                message: issue.message.clone(),
+            })
+            .collect(),
+        duplicates: message
+            .duplicates
+            .iter()
+            .map(|duplicate| -> anyhow::Result<_> {
+                Ok(report::DuplicateSource {
+                    krate: duplicate.krate.clone(),
+                    versions: duplicate.versions.clone(),
+                    files: usize::try_from(duplicate.files)?,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?,
+        scenarios: message
+            .scenarios
+            .iter()
+            .map(decode_scenario)
+            .collect::<anyhow::Result<_>>()?,
+        targets: message
+            .targets
+            .iter()
+            .map(decode_scenario)
+            .collect::<anyhow::Result<_>>()?,
+    })
+}
+
+fn decode_crate(summary: &CrateSummary) -> anyhow::Result<report::CrateSummary> {
+    Ok(report::CrateSummary {
+        name: summary.name.clone(),
+        version: summary.version.clone(),
+        source: summary.source.clone(),
+        checksum: summary.checksum.clone(),
+        workspace_member: summary.workspace_member,
+        no_std: summary.no_std,
+        proc_macro: summary.proc_macro,
+        repository: summary.repository.clone(),
+        license: summary.license.clone(),
+        latest_version: summary.latest_version.clone(),
+        findings: usize::try_from(summary.findings)?,
+        blast_radius: usize::try_from(summary.blast_radius)?,
+        public_unsafe_fns: summary.public_unsafe_fns.clone(),
+        public_unsafe_traits: summary.public_unsafe_traits.clone(),
+        category: summary.category.clone(),
+        coverage: summary
+            .coverage
+            .as_ref()
+            .map(|coverage| report::Coverage {
+                missing_targets: coverage.missing_targets.iter().map(PathBuf::from).collect(),
+                fetched_from: coverage.fetched_from.clone(),
+                build_failed: coverage.build_failed,
+                cached_copy: coverage.cached_copy.as_ref().map(PathBuf::from),
+            })
+            .unwrap_or_default(),
+        dependencies: summary.dependencies.clone(),
+    })
+}
+
+fn decode_scenario(scenario: &Scenario) -> anyhow::Result<report::ScenarioSummary> {
+    Ok(report::ScenarioSummary {
+        name: scenario.name.clone(),
+        features: scenario.features.clone(),
+        crates: usize::try_from(scenario.crates)?,
+        findings: usize::try_from(scenario.findings)?,
+        by_crate: scenario
+            .by_crate
+            .iter()
+            .map(|(krate, count)| Ok((krate.clone(), usize::try_from(*count)?)))
+            .collect::<anyhow::Result<_>>()?,
+    })
+}
@@ -0,0 +1,123 @@
+//! A SQL script that records a run in a SQLite database, for keeping the history of scheduled runs
+//! and querying it. Each run is appended with its metadata, the summary of each crate and its
+//! findings, all keyed by the id of the run, e.g.,
+//! `siderophile --format sql | sqlite3 history.db`.
+
+use std::io::Write;
+
+use crate::report::Report;
+
+/// Only creates what is missing, so that the script can be run against the database of earlier runs
+pub(super) const SCHEMA: &str = "\
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    recorded TEXT NOT NULL,
+    siderophile_version TEXT NOT NULL,
+    rustc_version TEXT NOT NULL,
+    target TEXT NOT NULL,
+    arguments TEXT NOT NULL,
+    crates INTEGER NOT NULL,
+    findings INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS crates (
+    run INTEGER NOT NULL REFERENCES runs (id),
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    source TEXT NOT NULL,
+    workspace_member INTEGER NOT NULL,
+    category TEXT NOT NULL,
+    license TEXT,
+    findings INTEGER NOT NULL,
+    blast_radius INTEGER NOT NULL,
+    public_unsafe_fns INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS findings (
+    run INTEGER NOT NULL REFERENCES runs (id),
+    crate TEXT NOT NULL,
+    version TEXT NOT NULL,
+    file TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    item TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    fingerprint TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS crates_run ON crates (run);
+CREATE INDEX IF NOT EXISTS findings_run ON findings (run);
+CREATE INDEX IF NOT EXISTS findings_fingerprint ON findings (fingerprint);
+";
+
+/// The id of the run being inserted, which `last_insert_rowid` only gives until the next insert
+const RUN: &str = "(SELECT id FROM temp.this_run)";
+
+pub fn write(report: &Report, out: &mut dyn Write) -> anyhow::Result<()> {
+    let metadata = &report.metadata;
+    write!(out, "{SCHEMA}")?;
+    writeln!(out, "BEGIN;")?;
+    writeln!(
+        out,
+        "INSERT INTO runs (recorded, siderophile_version, rustc_version, target, arguments, \
+         crates, findings) VALUES (datetime('now'), {}, {}, {}, {}, {}, {});",
+        literal(&metadata.siderophile_version),
+        literal(&metadata.rustc_version),
+        literal(&metadata.target),
+        literal(&serde_json::to_string(&metadata.arguments)?),
+        report.crates.len(),
+        report.findings.len()
+    )?;
+    writeln!(
+        out,
+        "CREATE TEMP TABLE this_run AS SELECT last_insert_rowid() AS id;"
+    )?;
+    for summary in &report.crates {
+        writeln!(
+            out,
+            "INSERT INTO crates VALUES ({RUN}, {}, {}, {}, {}, {}, {}, {}, {}, {});",
+            literal(&summary.name),
+            literal(&summary.version),
+            literal(&summary.source),
+            u8::from(summary.workspace_member),
+            literal(&summary.category),
+            summary
+                .license
+                .as_deref()
+                .map_or_else(|| "NULL".to_string(), literal),
+            summary.findings,
+            summary.blast_radius,
+            summary.public_unsafe_fns.len()
+        )?;
+    }
+    for finding in &report.findings {
+        writeln!(
+            out,
+            "INSERT INTO findings VALUES ({RUN}, {}, {}, {}, {}, {}, {}, {}, {});",
+            literal(&finding.krate),
+            literal(&finding.provenance.version),
+            literal(&finding.file.to_string_lossy()),
+            finding.line,
+            literal(&finding.item),
+            literal(&finding.kind.to_string()),
+            literal(finding.severity().as_str()),
+            literal(&finding.fingerprint)
+        )?;
+    }
+    writeln!(out, "DROP TABLE temp.this_run;")?;
+    writeln!(out, "COMMIT;")?;
+    Ok(())
+}
+
+/// A SQL string literal, in which only quotes need escaping
+fn literal(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::literal;
+
+    #[test]
+    fn doubles_quotes() {
+        assert_eq!(literal("c::raw"), "'c::raw'");
+        assert_eq!(literal("<&'a T as Foo>::foo"), "'<&''a T as Foo>::foo'");
+    }
+}
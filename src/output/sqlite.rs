@@ -0,0 +1,120 @@
+//! Records a run in a SQLite database, with the same schema as the script of `--format sql`, e.g.,
+//! `siderophile --format sqlite --database history.db`. The database is created if it is missing,
+//! and each run is appended to it.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context};
+use rusqlite::{params, Connection};
+use structopt::StructOpt;
+
+use super::sql::SCHEMA;
+use crate::report::Report;
+
+#[derive(StructOpt, Debug, Default)]
+pub struct SqliteOpts {
+    #[structopt(long = "database", value_name = "PATH")]
+    /// With `--format sqlite`, the SQLite database to append the run to, which is created if it
+    /// does not exist
+    pub database: Option<PathBuf>,
+}
+
+pub fn write(report: &Report, opts: &SqliteOpts) -> anyhow::Result<()> {
+    let Some(path) = &opts.database else {
+        bail!("`--format sqlite` needs the database to write to, with `--database`");
+    };
+    let mut connection = Connection::open(path)
+        .with_context(|| format!("Could not open the database `{}`", path.display()))?;
+    insert(&mut connection, report)
+        .with_context(|| format!("Could not record the run in `{}`", path.display()))
+}
+
+/// Appends the run of `report` in one transaction, so that a failed run leaves no partial rows
+fn insert(connection: &mut Connection, report: &Report) -> anyhow::Result<()> {
+    connection.execute_batch(SCHEMA)?;
+    let transaction = connection.transaction()?;
+    let metadata = &report.metadata;
+    transaction.execute(
+        "INSERT INTO runs (recorded, siderophile_version, rustc_version, target, arguments, \
+         crates, findings) VALUES (datetime('now'), ?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            metadata.siderophile_version,
+            metadata.rustc_version,
+            metadata.target,
+            serde_json::to_string(&metadata.arguments)?,
+            report.crates.len(),
+            report.findings.len()
+        ],
+    )?;
+    let run = transaction.last_insert_rowid();
+    {
+        let mut crates = transaction
+            .prepare("INSERT INTO crates VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)")?;
+        for summary in &report.crates {
+            crates.execute(params![
+                run,
+                summary.name,
+                summary.version,
+                summary.source,
+                summary.workspace_member,
+                summary.category,
+                summary.license,
+                summary.findings,
+                summary.blast_radius,
+                summary.public_unsafe_fns.len()
+            ])?;
+        }
+        let mut findings = transaction
+            .prepare("INSERT INTO findings VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)")?;
+        for finding in &report.findings {
+            findings.execute(params![
+                run,
+                &*finding.krate,
+                finding.provenance.version,
+                finding.file.to_string_lossy(),
+                finding.line,
+                finding.item,
+                finding.kind.to_string(),
+                finding.severity().as_str(),
+                finding.fingerprint
+            ])?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use super::insert;
+    use crate::{fixture, report::Report};
+
+    #[test]
+    fn appends_each_run() -> anyhow::Result<()> {
+        let report = Report {
+            crates: vec![fixture::summary("libc")],
+            findings: fixture::findings("libc", 2),
+            ..Report::default()
+        };
+        let mut connection = Connection::open_in_memory()?;
+        insert(&mut connection, &report)?;
+        insert(&mut connection, &report)?;
+        let count = |table: &str| -> rusqlite::Result<i64> {
+            connection.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+        };
+        assert_eq!(count("runs")?, 2);
+        assert_eq!(count("crates")?, 2);
+        assert_eq!(count("findings")?, 4);
+        let runs: i64 = connection.query_row(
+            "SELECT COUNT(DISTINCT run) FROM findings WHERE crate = 'libc'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(runs, 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,456 @@
+//! The dependency tree in the style of `cargo tree`, with each package annotated with its findings
+//! and those of the packages it pulls in, so that it shows at a glance which branch of the tree
+//! carries the unsafety
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io::Write,
+};
+
+use anyhow::bail;
+use structopt::StructOpt;
+
+use crate::{
+    dependency,
+    report::{CrateSummary, Report},
+};
+
+#[derive(StructOpt, Debug, Default)]
+pub struct TreeOpts {
+    #[structopt(long = "invert", short = "i", value_name = "SPEC")]
+    /// Show why the package SPEC (`NAME` or `NAME@VERSION`) is in the tree: the dependency chains
+    /// through which the workspace members pull it in, or with `--format tree`, `dot` or
+    /// `mermaid`, the tree of the packages that depend on it
+    pub invert: Option<String>,
+}
+
+/// Writes the tree of each workspace member, or with `--invert`, the inverted tree of each
+/// matching package
+pub fn write(report: &Report, opts: &TreeOpts, out: &mut dyn Write) -> anyhow::Result<()> {
+    let graph = Graph::new(report);
+    let roots = graph.roots(opts)?;
+    for (i, root) in roots.into_iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "{}", graph.node(root))?;
+        let mut expanded = BTreeSet::from([root]);
+        graph.write_children(root, opts.invert.is_some(), "", &mut expanded, out)?;
+    }
+    Ok(())
+}
+
+/// Writes the same graph as `write` in Graphviz's DOT language, with each package labeled with
+/// its findings and filled in a shade of red that deepens with their number. Packages reached
+/// more than once are drawn once, with all of their edges.
+pub fn write_dot(report: &Report, opts: &TreeOpts, out: &mut dyn Write) -> anyhow::Result<()> {
+    let graph = Graph::new(report);
+    let invert = opts.invert.is_some();
+    let roots = graph.roots(opts)?;
+    let reached = graph.reached(&roots, invert);
+    let shades = graph.shades(&reached);
+
+    writeln!(out, "digraph dependencies {{")?;
+    writeln!(out, "    node [style=filled, colorscheme=reds9];")?;
+    for &id in &reached {
+        let shade = shades[id];
+        writeln!(
+            out,
+            "    {} [label={}, fillcolor={}{}{}];",
+            quote(id),
+            quote(&graph.label(id, "\\n")),
+            shade + 1,
+            if shade > 4 { ", fontcolor=white" } else { "" },
+            if roots.contains(&id) {
+                ", shape=box"
+            } else {
+                ""
+            }
+        )?;
+    }
+    for &id in &reached {
+        for child in graph.children(id, invert) {
+            writeln!(out, "    {} -> {};", quote(id), quote(child))?;
+        }
+    }
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// The colors of Graphviz's `reds9` scheme, for Mermaid
+const REDS: [&str; 9] = [
+    "#fff5f0", "#fee0d2", "#fcbba1", "#fc9272", "#fb6a4a", "#ef3b2c", "#cb181d", "#a50f15",
+    "#67000d",
+];
+
+/// Writes the same graph as `write_dot` as a Mermaid flowchart in a fenced code block, which GitHub
+/// and GitLab render in Markdown documents
+pub fn write_mermaid(report: &Report, opts: &TreeOpts, out: &mut dyn Write) -> anyhow::Result<()> {
+    let graph = Graph::new(report);
+    let invert = opts.invert.is_some();
+    let roots = graph.roots(opts)?;
+    let reached = graph.reached(&roots, invert);
+    let shades = graph.shades(&reached);
+    // Mermaid ids cannot contain `@` or `.`, so packages are numbered.
+    let numbers = reached
+        .iter()
+        .enumerate()
+        .map(|(number, id)| (*id, number))
+        .collect::<HashMap<_, _>>();
+
+    writeln!(out, "```mermaid")?;
+    writeln!(out, "graph TD")?;
+    for &id in &reached {
+        let label = graph.label(id, "<br>").replace('"', "#quot;");
+        if roots.contains(&id) {
+            writeln!(out, "    p{}[\"{}\"]", numbers[id], label)?;
+        } else {
+            writeln!(out, "    p{}(\"{}\")", numbers[id], label)?;
+        }
+    }
+    for &id in &reached {
+        for child in graph.children(id, invert) {
+            writeln!(out, "    p{} --> p{}", numbers[id], numbers[child])?;
+        }
+    }
+    for &id in &reached {
+        let shade = shades[id];
+        writeln!(
+            out,
+            "    style p{} fill:{},color:{}",
+            numbers[id],
+            REDS[shade],
+            if shade > 4 { "#fff" } else { "#000" }
+        )?;
+    }
+    writeln!(out, "```")?;
+    Ok(())
+}
+
+/// `s` as a DOT string. `\n` escapes are left alone, so that labels can span lines.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\\\""))
+}
+
+struct Graph<'a> {
+    by_id: BTreeMap<String, &'a CrateSummary>,
+
+    /// The packages each package depends on, and is depended on by
+    dependencies: HashMap<String, BTreeSet<String>>,
+    dependents: HashMap<String, BTreeSet<String>>,
+
+    /// Number of findings in each package
+    findings: HashMap<(&'a str, &'a str), usize>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(report: &'a Report) -> Self {
+        let by_id = report
+            .crates
+            .iter()
+            .map(|summary| (dependency::id(&summary.name, &summary.version), summary))
+            .collect::<BTreeMap<_, _>>();
+        let mut dependencies = HashMap::<_, BTreeSet<_>>::new();
+        let mut dependents = HashMap::<_, BTreeSet<_>>::new();
+        for (id, summary) in &by_id {
+            for dep in &summary.dependencies {
+                // Only the packages the report summarizes are drawn
+                if by_id.contains_key(dep) {
+                    dependencies
+                        .entry(id.clone())
+                        .or_default()
+                        .insert(dep.clone());
+                    dependents
+                        .entry(dep.clone())
+                        .or_default()
+                        .insert(id.clone());
+                }
+            }
+        }
+        let mut findings = HashMap::new();
+        for finding in &report.findings {
+            *findings
+                .entry((&*finding.krate, finding.provenance.version.as_str()))
+                .or_default() += 1;
+        }
+        Self {
+            by_id,
+            dependencies,
+            dependents,
+            findings,
+        }
+    }
+
+    /// Each workspace member, or with `--invert`, each matching package
+    fn roots(&self, opts: &TreeOpts) -> anyhow::Result<Vec<&str>> {
+        match &opts.invert {
+            Some(spec) => {
+                let roots = self.matching(spec);
+                if roots.is_empty() {
+                    bail!("No package in the tree matches `{}`", spec);
+                }
+                Ok(roots)
+            }
+            None => Ok(self.members()),
+        }
+    }
+
+    /// The packages `id` depends on, or with `invert`, the packages that depend on it
+    fn children(&self, id: &str, invert: bool) -> Vec<&str> {
+        let edges = if invert {
+            &self.dependents
+        } else {
+            &self.dependencies
+        };
+        edges
+            .get(id)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// The packages reached from `roots`, including them
+    fn reached<'g>(&'g self, roots: &[&'g str], invert: bool) -> BTreeSet<&'g str> {
+        let mut reached = BTreeSet::new();
+        let mut stack = roots.to_vec();
+        while let Some(id) = stack.pop() {
+            if reached.insert(id) {
+                stack.extend(self.children(id, invert));
+            }
+        }
+        reached
+    }
+
+    /// The shade of red of each of `ids`, from 0 for packages without findings to 8 for those with
+    /// the most
+    fn shades<'i>(&self, ids: &BTreeSet<&'i str>) -> HashMap<&'i str, usize> {
+        let max = ids
+            .iter()
+            .map(|id| self.findings_of(id))
+            .max()
+            .unwrap_or_default();
+        ids.iter()
+            .map(|&id| {
+                let findings = self.findings_of(id);
+                let shade = if max == 0 {
+                    0
+                } else {
+                    (8 * findings + max - 1) / max
+                };
+                (id, shade)
+            })
+            .collect()
+    }
+
+    /// The name and version of the package, and its findings on a second line, after `break_`
+    fn label(&self, id: &str, break_: &str) -> String {
+        let summary = self.by_id[id];
+        format!(
+            "{} v{}{}{} unsafe, {} in subtree",
+            summary.name,
+            summary.version,
+            break_,
+            self.findings_of(id),
+            self.subtree_findings(id)
+        )
+    }
+
+    fn members(&self) -> Vec<&str> {
+        self.by_id
+            .iter()
+            .filter(|(_, summary)| summary.workspace_member)
+            .map(|(id, _)| id.as_str())
+            .collect()
+    }
+
+    /// The packages `spec` refers to, by name or by `name@version`
+    fn matching(&self, spec: &str) -> Vec<&str> {
+        self.by_id
+            .iter()
+            .filter(|(_, summary)| dependency::matches(summary, spec))
+            .map(|(id, _)| id.as_str())
+            .collect()
+    }
+
+    fn findings_of(&self, id: &str) -> usize {
+        let summary = self.by_id[id];
+        self.findings
+            .get(&(summary.name.as_str(), summary.version.as_str()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The findings of the package and of every package it pulls in, each counted once
+    fn subtree_findings(&self, root: &str) -> usize {
+        let mut subtree = BTreeSet::new();
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            if subtree.insert(id) {
+                stack.extend(
+                    self.dependencies
+                        .get(id)
+                        .into_iter()
+                        .flatten()
+                        .map(String::as_str),
+                );
+            }
+        }
+        subtree.into_iter().map(|id| self.findings_of(id)).sum()
+    }
+
+    fn node(&self, id: &str) -> String {
+        let summary = self.by_id[id];
+        format!(
+            "{} v{} [{} unsafe, {} in subtree]",
+            summary.name,
+            summary.version,
+            self.findings_of(id),
+            self.subtree_findings(id)
+        )
+    }
+
+    /// Like `cargo tree`, a package already expanded is marked `(*)` rather than expanded again
+    fn write_children<'g>(
+        &'g self,
+        id: &str,
+        invert: bool,
+        prefix: &str,
+        expanded: &mut BTreeSet<&'g str>,
+        out: &mut dyn Write,
+    ) -> anyhow::Result<()> {
+        let children = self.children(id, invert);
+        for (i, &child) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            let branch = if last { "└── " } else { "├── " };
+            if expanded.insert(child) {
+                writeln!(out, "{prefix}{branch}{}", self.node(child))?;
+                let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+                self.write_children(child, invert, &prefix, expanded, out)?;
+            } else {
+                writeln!(out, "{prefix}{branch}{} (*)", self.node(child))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    fn summary(name: &str, workspace_member: bool, dependencies: &[&str]) -> CrateSummary {
+        CrateSummary {
+            workspace_member,
+            dependencies: fixture::ids(dependencies),
+            ..fixture::summary(name)
+        }
+    }
+
+    fn report() -> Report {
+        let crates = vec![
+            summary("app", true, &["hyper", "tokio"]),
+            summary("hyper", false, &["httparse", "tokio"]),
+            summary("tokio", false, &["libc"]),
+            summary("httparse", false, &[]),
+            summary("libc", false, &[]),
+        ];
+        let findings = [fixture::findings("tokio", 5), fixture::findings("libc", 2)].concat();
+        Report::new(crates, findings, &Default::default())
+    }
+
+    fn tree(opts: &TreeOpts) -> String {
+        let mut out = Vec::new();
+        write(&report(), opts, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn annotates_subtree_totals() {
+        assert_eq!(
+            tree(&TreeOpts::default()),
+            "\
+app v1.0.0 [0 unsafe, 7 in subtree]
+├── hyper v1.0.0 [0 unsafe, 7 in subtree]
+│   ├── httparse v1.0.0 [0 unsafe, 0 in subtree]
+│   └── tokio v1.0.0 [5 unsafe, 7 in subtree]
+│       └── libc v1.0.0 [2 unsafe, 2 in subtree]
+└── tokio v1.0.0 [5 unsafe, 7 in subtree] (*)
+"
+        );
+    }
+
+    #[test]
+    fn dot_shades_by_findings() {
+        let mut out = Vec::new();
+        write_dot(
+            &report(),
+            &TreeOpts {
+                invert: Some("tokio".to_string()),
+            },
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+digraph dependencies {
+    node [style=filled, colorscheme=reds9];
+    \"app@1.0.0\" [label=\"app v1.0.0\\n0 unsafe, 7 in subtree\", fillcolor=1];
+    \"hyper@1.0.0\" [label=\"hyper v1.0.0\\n0 unsafe, 7 in subtree\", fillcolor=1];
+    \"tokio@1.0.0\" [label=\"tokio v1.0.0\\n5 unsafe, 7 in subtree\", fillcolor=9, fontcolor=white, shape=box];
+    \"hyper@1.0.0\" -> \"app@1.0.0\";
+    \"tokio@1.0.0\" -> \"app@1.0.0\";
+    \"tokio@1.0.0\" -> \"hyper@1.0.0\";
+}
+"
+        );
+    }
+
+    #[test]
+    fn mermaid_numbers_packages() {
+        let mut out = Vec::new();
+        write_mermaid(
+            &report(),
+            &TreeOpts {
+                invert: Some("tokio".to_string()),
+            },
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+```mermaid
+graph TD
+    p0(\"app v1.0.0<br>0 unsafe, 7 in subtree\")
+    p1(\"hyper v1.0.0<br>0 unsafe, 7 in subtree\")
+    p2[\"tokio v1.0.0<br>5 unsafe, 7 in subtree\"]
+    p1 --> p0
+    p2 --> p0
+    p2 --> p1
+    style p0 fill:#fff5f0,color:#000
+    style p1 fill:#fff5f0,color:#000
+    style p2 fill:#67000d,color:#fff
+```
+"
+        );
+    }
+
+    #[test]
+    fn inverts() {
+        assert_eq!(
+            tree(&TreeOpts {
+                invert: Some("libc".to_string()),
+            }),
+            "\
+libc v1.0.0 [2 unsafe, 2 in subtree]
+└── tokio v1.0.0 [5 unsafe, 7 in subtree]
+    ├── app v1.0.0 [0 unsafe, 7 in subtree]
+    └── hyper v1.0.0 [0 unsafe, 7 in subtree]
+        └── app v1.0.0 [0 unsafe, 7 in subtree] (*)
+"
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use structopt::StructOpt;
+
+use crate::{
+    exit::{Code, Failure},
+    report::{CrateSummary, Finding, Report, SkipReason, SkippedCrate},
+};
+
+#[derive(StructOpt, Debug)]
+pub struct PolicyOpts {
+    #[structopt(long = "policy", value_name = "PATH")]
+    /// Fail if the findings violate the policy file at PATH
+    policy: Option<PathBuf>,
+}
+
+impl PolicyOpts {
+    pub const fn is_enabled(&self) -> bool {
+        self.policy.is_some()
+    }
+}
+
+/// Rules about how much unsafety each crate in the tree may contain
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Policy {
+    /// Maximum number of findings in crates without their own budget (unlimited if omitted)
+    default_budget: Option<usize>,
+
+    /// Whether workspace members without their own budget must be free of unsafety
+    #[serde(default)]
+    workspace_unsafe_free: bool,
+
+    /// Crates with more findings than this must be marked as audited
+    audit_threshold: Option<usize>,
+
+    /// Crates that may not appear in the tree at all
+    #[serde(default)]
+    deny: BTreeSet<String>,
+
+    #[serde(default)]
+    crates: BTreeMap<String, CratePolicy>,
+
+    #[serde(default)]
+    waivers: Vec<Waiver>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct CratePolicy {
+    /// Maximum number of findings in the crate
+    budget: Option<usize>,
+
+    /// Whether the crate may contain any number of findings
+    #[serde(default)]
+    unlimited: bool,
+
+    /// Whether the crate's unsafety has been audited
+    #[serde(default)]
+    audit: bool,
+}
+
+/// Suppresses the findings with a fingerprint, or all of the findings in a crate, until the waiver
+/// expires
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Waiver {
+    fingerprint: Option<String>,
+
+    #[serde(rename = "crate")]
+    krate: Option<String>,
+
+    /// Who is responsible for the waived unsafety
+    owner: Option<String>,
+
+    /// Last day on which the waiver applies
+    expires: Option<NaiveDate>,
+}
+
+impl Waiver {
+    fn matches(&self, finding: &Finding) -> bool {
+        self.fingerprint
+            .as_ref()
+            .map_or(true, |fingerprint| *fingerprint == finding.fingerprint)
+            && self
+                .krate
+                .as_ref()
+                .map_or(true, |krate| **krate == *finding.krate)
+    }
+
+    fn is_expired(&self, today: NaiveDate) -> bool {
+        self.expires.is_some_and(|expires| expires < today)
+    }
+
+    fn describe(&self) -> String {
+        let mut description = match (&self.fingerprint, &self.krate) {
+            (Some(fingerprint), _) => format!("waiver for `{fingerprint}`"),
+            (None, Some(krate)) => format!("waiver for crate `{krate}`"),
+            (None, None) => "waiver".to_string(),
+        };
+        if let Some(owner) = &self.owner {
+            description.push_str(&format!(" (owner: {owner})"));
+        }
+        description
+    }
+}
+
+/// Reads the policy file, if one was given
+pub fn load(opts: &PolicyOpts) -> anyhow::Result<Option<Policy>> {
+    opts.policy.as_deref().map(Policy::read).transpose()
+}
+
+impl Policy {
+    fn read(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Could not read `{}`", path.display()))?;
+        let policy: Self = toml::from_str(&contents)
+            .with_context(|| format!("Could not parse policy `{}`", path.display()))?;
+        if let Some(waiver) = policy
+            .waivers
+            .iter()
+            .find(|waiver| waiver.fingerprint.is_none() && waiver.krate.is_none())
+        {
+            bail!(
+                "{} in `{}` needs a `fingerprint` or a `crate`",
+                waiver.describe(),
+                path.display()
+            );
+        }
+        Ok(policy)
+    }
+
+    /// Removes the findings covered by a waiver that has not expired. Expired waivers are reported
+    /// and their findings are kept.
+    pub fn waive(&self, findings: Vec<Finding>, today: NaiveDate) -> Vec<Finding> {
+        let (expired, active): (Vec<_>, Vec<_>) = self
+            .waivers
+            .iter()
+            .partition(|waiver| waiver.is_expired(today));
+        for waiver in expired {
+            if let Some(expires) = waiver.expires {
+                warn!("{} expired on {}", waiver.describe(), expires);
+            }
+        }
+        findings
+            .into_iter()
+            .filter(|finding| !active.iter().any(|waiver| waiver.matches(finding)))
+            .collect()
+    }
+
+    /// The packages among `crates` whose findings an unexpired waiver of the whole crate covers
+    pub fn waived_crates(&self, crates: &[CrateSummary], today: NaiveDate) -> Vec<SkippedCrate> {
+        crates
+            .iter()
+            .filter_map(|summary| {
+                let waiver = self.waivers.iter().find(|waiver| {
+                    waiver.fingerprint.is_none()
+                        && waiver.krate.as_ref() == Some(&summary.name)
+                        && !waiver.is_expired(today)
+                })?;
+                Some(SkippedCrate {
+                    krate: summary.name.clone(),
+                    version: summary.version.clone(),
+                    reason: SkipReason::Waived,
+                    detail: Some(waiver.describe()),
+                })
+            })
+            .collect()
+    }
+
+    /// The maximum number of findings allowed in `krate`, or `None` if unlimited
+    fn budget(&self, krate: &CrateSummary) -> Option<usize> {
+        match self.crates.get(&krate.name) {
+            Some(CratePolicy {
+                unlimited: true, ..
+            }) => None,
+            Some(CratePolicy {
+                budget: Some(budget),
+                ..
+            }) => Some(*budget),
+            _ if krate.workspace_member && self.workspace_unsafe_free => Some(0),
+            _ => self.default_budget,
+        }
+    }
+
+    fn violations(&self, krate: &CrateSummary) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        if self.deny.contains(&krate.name) {
+            violations.push(format!("crate `{}` is denied", krate.name));
+        }
+
+        if let Some(budget) = self.budget(krate) {
+            if krate.findings > budget {
+                violations.push(format!(
+                    "crate `{}` has {} findings, but its budget is {}",
+                    krate.name, krate.findings, budget
+                ));
+            }
+        }
+
+        if let Some(threshold) = self.audit_threshold {
+            let audited = self.crates.get(&krate.name).is_some_and(|c| c.audit);
+            if krate.findings > threshold && !audited {
+                violations.push(format!(
+                    "crate `{}` has {} findings, more than {}, and requires `audit = true`",
+                    krate.name, krate.findings, threshold
+                ));
+            }
+        }
+
+        violations
+    }
+
+    /// Evaluates the policy against the report, failing with the list of violations if there are
+    /// any
+    pub fn check(&self, report: &Report) -> anyhow::Result<()> {
+        let violations = report
+            .crates
+            .iter()
+            .flat_map(|krate| self.violations(krate))
+            .collect::<Vec<_>>();
+
+        if !violations.is_empty() {
+            return Err(Failure::new(
+                Code::PolicyViolation,
+                format!("Policy violated:\n    {}", violations.join("\n    ")),
+            ));
+        }
+
+        Ok(())
+    }
+}
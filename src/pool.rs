@@ -0,0 +1,61 @@
+//! The threads the library's streams scan on. They are started on first use and shared by all the
+//! scans of the process, so that a service does not start threads for each request.
+
+use std::{
+    num::NonZeroUsize,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex, MutexGuard, OnceLock, PoisonError,
+    },
+    thread,
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+pub struct Pool {
+    jobs: Mutex<Sender<Job>>,
+}
+
+impl Pool {
+    /// The pool of the process, with a thread for each core
+    pub fn global() -> &'static Self {
+        static POOL: OnceLock<Pool> = OnceLock::new();
+        POOL.get_or_init(|| Self::new(thread::available_parallelism().map_or(1, NonZeroUsize::get)))
+    }
+
+    fn new(threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..threads {
+            let receiver = Arc::clone(&receiver);
+            thread::spawn(move || work(&receiver));
+        }
+        Self {
+            jobs: Mutex::new(sender),
+        }
+    }
+
+    /// Runs `job` on one of the threads once they are done with the jobs spawned before it
+    pub fn spawn(&self, job: impl FnOnce() + Send + 'static) {
+        // The threads only stop with the process
+        let _ = lock(&self.jobs).send(Box::new(job));
+    }
+}
+
+fn work(jobs: &Mutex<Receiver<Job>>) {
+    loop {
+        // Not `while let`, which would hold the lock while the job runs
+        let job = lock(jobs).recv();
+        let Ok(job) = job else {
+            return;
+        };
+        // A job that panics takes down neither the thread nor the jobs queued after it
+        let _ = panic::catch_unwind(AssertUnwindSafe(job));
+    }
+}
+
+/// A thread that panicked while holding the lock left nothing half-done
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,145 @@
+//! Tells apart the unsafety that only debug or only release builds contain, judging by the
+//! `debug_assertions` in the cfg conditions of findings
+
+use std::{collections::HashMap, fmt};
+
+use serde::{Deserialize, Serialize};
+use syn::{Meta, NestedMeta};
+
+use crate::{
+    callgraph_gen,
+    cancel::{CancellationToken, Cancelled},
+    report::Finding,
+    utils,
+};
+
+/// A build profile, as far as `debug_assertions` goes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Profile {
+    /// `debug_assertions` is enabled, as in the `dev` and `test` profiles
+    Debug,
+
+    /// `debug_assertions` is disabled, as in the `release` and `bench` profiles
+    Release,
+}
+
+impl Profile {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Release => "release",
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Whether a finding with the cfg condition `cfg` can be compiled in `profile`. Predicates other
+/// than `debug_assertions` are assumed to hold or not as needed.
+pub fn can_hold(cfg: &str, profile: Profile) -> bool {
+    !syn::parse_str::<Meta>(cfg).is_ok_and(|meta| eval(&meta, profile) == Some(false))
+}
+
+/// Evaluates `meta` in `profile`, or `None` if that depends on more than `debug_assertions`
+fn eval(meta: &Meta, profile: Profile) -> Option<bool> {
+    match meta {
+        Meta::Path(path) if path.is_ident("debug_assertions") => Some(profile == Profile::Debug),
+        Meta::List(list) => {
+            let mut values = list.nested.iter().map(|nested| match nested {
+                NestedMeta::Meta(meta) => eval(meta, profile),
+                NestedMeta::Lit(_) => None,
+            });
+            if list.path.is_ident("not") {
+                values.next().flatten().map(|value| !value)
+            } else if list.path.is_ident("all") {
+                values.fold(Some(true), |all, value| match (all, value) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                })
+            } else if list.path.is_ident("any") {
+                values.fold(Some(false), |any, value| match (any, value) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                })
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The functions of `badness` that only unsafety compiled in one profile reaches, with that
+/// profile. `crate_names` are those `badness` was traced for.
+pub fn only_in(
+    callgraph: &utils::CallGraph,
+    crate_names: &[&str],
+    findings: &[Finding],
+    badness: &HashMap<String, (u32, utils::LabelInfo)>,
+    cancel: &CancellationToken,
+) -> Result<HashMap<String, Profile>, Cancelled> {
+    let mut only_in = HashMap::new();
+    if findings.iter().all(|finding| finding.cfg.is_none()) {
+        return Ok(only_in);
+    }
+    let trace = |profile| -> Result<_, Cancelled> {
+        let tainted = findings
+            .iter()
+            .filter(|finding| {
+                finding
+                    .cfg
+                    .as_deref()
+                    .map_or(true, |cfg| can_hold(cfg, profile))
+            })
+            .map(|finding| finding.item.clone())
+            .collect::<Vec<_>>();
+        let mut badness = HashMap::new();
+        for crate_name in crate_names {
+            badness.extend(callgraph_gen::trace_unsafety(
+                callgraph, crate_name, &tainted, cancel,
+            )?);
+        }
+        Ok(badness)
+    };
+    let debug = trace(Profile::Debug)?;
+    let release = trace(Profile::Release)?;
+    for function in badness.keys() {
+        match (debug.contains_key(function), release.contains_key(function)) {
+            (true, false) => {
+                only_in.insert(function.clone(), Profile::Debug);
+            }
+            (false, true) => {
+                only_in.insert(function.clone(), Profile::Release);
+            }
+            _ => {}
+        }
+    }
+    Ok(only_in)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_assertions() {
+        assert!(can_hold("debug_assertions", Profile::Debug));
+        assert!(!can_hold("debug_assertions", Profile::Release));
+        assert!(!can_hold("not(debug_assertions)", Profile::Debug));
+        assert!(can_hold("not(debug_assertions)", Profile::Release));
+        assert!(!can_hold(
+            "all(unix, not(debug_assertions))",
+            Profile::Debug
+        ));
+        assert!(can_hold("any(unix, debug_assertions)", Profile::Release));
+        assert!(can_hold("feature = \"fast\"", Profile::Debug));
+        assert!(can_hold("feature = \"fast\"", Profile::Release));
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use crate::{
+    exit::{Code, Failure},
+    report::{self, Finding},
+};
+
+#[derive(StructOpt, Debug)]
+pub struct RatchetOpts {
+    #[structopt(long = "ratchet", value_name = "PATH")]
+    /// Fail if any crate has more findings than recorded in the ratchet file at PATH
+    ratchet: Option<PathBuf>,
+
+    #[structopt(long = "ratchet-update", requires = "ratchet")]
+    /// Lower the counts in the ratchet file for crates whose findings dropped
+    ratchet_update: bool,
+}
+
+/// Maximum number of findings allowed in each crate. Crates that are not listed are allowed none.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Ratchet {
+    crates: BTreeMap<String, usize>,
+}
+
+impl Ratchet {
+    fn read(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Could not read `{}`", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Could not parse ratchet `{}`", path.display()))
+    }
+
+    fn write(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("Could not write `{}`", path.display()))
+    }
+}
+
+pub fn check(opts: &RatchetOpts, findings: &[Finding]) -> anyhow::Result<()> {
+    let path = if let Some(path) = &opts.ratchet {
+        path
+    } else {
+        return Ok(());
+    };
+
+    let counts = report::counts_by_crate(findings);
+
+    if !path.exists() {
+        if !opts.ratchet_update {
+            bail!("Ratchet `{}` does not exist", path.display());
+        }
+        // A new ratchet simply records the current counts.
+        let crates = counts
+            .iter()
+            .map(|(krate, count)| ((*krate).to_string(), *count))
+            .collect();
+        return Ratchet { crates }.write(path);
+    }
+
+    let mut ratchet = Ratchet::read(path)?;
+
+    let mut violations = Vec::new();
+    for (krate, count) in &counts {
+        let max = ratchet.crates.get(*krate).copied().unwrap_or_default();
+        if *count > max {
+            violations.push(format!(
+                "crate `{krate}` has {count} findings, but the ratchet allows {max}"
+            ));
+        }
+    }
+
+    if opts.ratchet_update {
+        for (krate, max) in &mut ratchet.crates {
+            let count = counts.get(krate.as_str()).copied().unwrap_or_default();
+            if count < *max {
+                *max = count;
+            }
+        }
+        ratchet.write(path)?;
+    }
+
+    if !violations.is_empty() {
+        return Err(Failure::new(
+            Code::OverThreshold,
+            format!("Unsafety increased:\n    {}", violations.join("\n    ")),
+        ));
+    }
+
+    Ok(())
+}
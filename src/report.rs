@@ -0,0 +1,657 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+pub use crate::finding::{Finding, FindingKind, Provenance, Severity, Tag};
+use crate::{
+    category, dependency,
+    profile::Profile,
+    utils::{self, LabelInfo},
+};
+
+/// Number of findings in each crate that has any
+pub fn counts_by_crate(findings: &[Finding]) -> BTreeMap<&str, usize> {
+    let mut counts = BTreeMap::new();
+    for finding in findings {
+        *counts.entry(&*finding.krate).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Sets the number of findings of each crate. The versions of a crate in the tree are counted apart.
+pub fn count_findings(crates: &mut [CrateSummary], findings: &[Finding]) {
+    let mut counts = HashMap::<_, usize>::new();
+    for finding in findings {
+        *counts
+            .entry((&*finding.krate, finding.provenance.version.as_str()))
+            .or_default() += 1;
+    }
+    for summary in crates {
+        summary.findings = counts
+            .get(&(summary.name.as_str(), summary.version.as_str()))
+            .copied()
+            .unwrap_or_default();
+    }
+}
+
+/// A package in the tree
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateSummary {
+    pub name: String,
+
+    pub version: String,
+
+    /// See `Provenance`
+    #[serde(default)]
+    pub source: String,
+
+    #[serde(default)]
+    pub checksum: Option<String>,
+
+    pub workspace_member: bool,
+
+    /// Whether the library root declares `#![no_std]`, possibly conditionally
+    #[serde(default)]
+    pub no_std: bool,
+
+    /// Whether the package is a procedural macro, whose code runs inside rustc at build time
+    #[serde(default)]
+    pub proc_macro: bool,
+
+    /// The `repository` of the package's manifest
+    #[serde(default)]
+    pub repository: Option<String>,
+
+    /// The `license` of the package's manifest, an SPDX expression
+    #[serde(default)]
+    pub license: Option<String>,
+
+    /// The newest version of the package in the local copy of its registry's index
+    #[serde(default)]
+    pub latest_version: Option<String>,
+
+    /// See `category::categorize`
+    #[serde(default)]
+    pub category: String,
+
+    #[serde(default)]
+    pub coverage: Coverage,
+
+    /// The packages this one depends on, as `name@version`
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+
+    /// Number of findings in the package
+    pub findings: usize,
+
+    /// Number of functions of the crate that no unsafety outside of the package reaches, and so
+    /// would have no badness without it (see `blast::blast_radius`)
+    #[serde(default)]
+    pub blast_radius: usize,
+
+    /// The `pub unsafe fn`s of the package, which push a safety contract onto its users rather
+    /// than encapsulating the unsafety
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub public_unsafe_fns: Vec<String>,
+
+    /// The `pub unsafe trait`s of the package, whose implementors have to uphold their contracts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub public_unsafe_traits: Vec<String>,
+}
+
+impl CrateSummary {
+    /// The share of the package's unsafety that it keeps internal, behind safe APIs, rather than
+    /// exports as `pub unsafe fn`s and `pub unsafe trait`s: 1 for a crate that encapsulates all
+    /// of it, like `parking_lot`, and close to 0 for a thin unsafe wrapper. `None` without any
+    /// unsafety.
+    pub fn encapsulation(&self) -> Option<f64> {
+        let exported = self.public_unsafe_fns.len() + self.public_unsafe_traits.len();
+        // Every `pub unsafe fn` is also a finding
+        let internal = self.findings.saturating_sub(self.public_unsafe_fns.len());
+        if internal + exported == 0 {
+            return None;
+        }
+        Some(internal as f64 / (internal + exported) as f64)
+    }
+}
+
+/// How much of a package's source could be scanned
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Coverage {
+    /// Source files of targets that the published package leaves out, relative to its root
+    pub missing_targets: Vec<PathBuf>,
+
+    /// `URL@TAG` of the repository that the missing files were fetched from
+    pub fetched_from: Option<String>,
+
+    /// Whether the package is a `-sys` crate whose build script failed, e.g. for lack of native
+    /// headers. Its files were still found in its directory, but which of them the build uses is
+    /// unknown.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub build_failed: bool,
+
+    /// With `--offline-fallback`, the copy in the cargo cache that was scanned because the package
+    /// could not be downloaded. It may have been modified since it was extracted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached_copy: Option<PathBuf>,
+}
+
+/// The crates of one category, e.g. `cryptography`, and their findings
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategorySummary {
+    pub category: String,
+
+    pub crates: usize,
+
+    pub findings: usize,
+}
+
+/// A direct dependency of the workspace and the unsafety in the subtree of packages it pulls in
+/// (see `dependency::summarize`)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencySummary {
+    pub name: String,
+
+    pub version: String,
+
+    /// Number of packages in the subtree, including the dependency itself
+    pub crates: usize,
+
+    pub findings: usize,
+
+    /// The findings in packages that no other direct dependency pulls in
+    pub exclusive_findings: usize,
+}
+
+/// How many of the `.rs` files that the build compiled were scanned
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileCoverage {
+    pub compiled: usize,
+
+    pub scanned: usize,
+}
+
+impl FileCoverage {
+    /// The share of the compiled files that were scanned, in percent
+    pub fn percent(self) -> f64 {
+        if self.compiled == 0 {
+            100.0
+        } else {
+            self.scanned as f64 * 100.0 / self.compiled as f64
+        }
+    }
+}
+
+/// The commit that first added a package to `Cargo.lock` (see `introduced::introduced`)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Introduced {
+    #[serde(rename = "crate")]
+    pub krate: String,
+
+    pub commit: String,
+
+    pub author: String,
+
+    /// The author date, in strict ISO 8601
+    pub date: String,
+
+    /// The first line of the commit message
+    pub subject: String,
+}
+
+/// Why a package was left out of the scan, in whole or in part
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// It could not be downloaded
+    DownloadFailed,
+
+    /// With `--vendor-dir`, it is in the lockfile but not vendored, or the other way around
+    NotVendored,
+
+    /// With `--vendor-dir`, its sources could not be verified against the checksums of the
+    /// lockfile and of `cargo vendor`
+    ChecksumFailed,
+
+    /// Its build script failed, so all of its files were scanned rather than only those the build
+    /// used
+    BuildFailed,
+
+    /// With `--no-optional-deps`, it is an optional dependency that the default features do not
+    /// enable
+    Inactive,
+
+    /// A waiver of the policy covers all of its findings
+    Waived,
+
+    /// With `--exclude-stdlib`, it is a standard library crate
+    Stdlib,
+}
+
+impl SkipReason {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::DownloadFailed => "download_failed",
+            Self::NotVendored => "not_vendored",
+            Self::ChecksumFailed => "checksum_failed",
+            Self::BuildFailed => "build_failed",
+            Self::Inactive => "inactive",
+            Self::Waived => "waived",
+            Self::Stdlib => "stdlib",
+        }
+    }
+
+    /// Whether the package was left out on purpose, rather than because something went wrong
+    pub const fn is_intentional(self) -> bool {
+        matches!(self, Self::Inactive | Self::Waived | Self::Stdlib)
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A package left out of the scan, in whole or in part
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SkippedCrate {
+    #[serde(rename = "crate")]
+    pub krate: String,
+
+    pub version: String,
+
+    pub reason: SkipReason,
+
+    /// The error, or what the package was left out by
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Alternatives to a dependency, suggested because of its findings
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Suggestion {
+    #[serde(rename = "crate")]
+    pub krate: String,
+
+    pub version: String,
+
+    pub findings: usize,
+
+    /// Crate names, or `std`
+    pub alternatives: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// A difference between the sources of a registry package and what the lockfile pins
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecksumMismatch {
+    #[serde(rename = "crate")]
+    pub krate: String,
+
+    pub version: String,
+
+    /// Relative to the package root, or `None` when the `.crate` file itself does not match the
+    /// lockfile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+
+    pub detail: String,
+}
+
+/// The findings of a package in generated files, collapsed into one entry with
+/// `--collapse-generated` (see `filter::FilterOpts`)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeneratedCode {
+    #[serde(rename = "crate")]
+    pub krate: String,
+
+    pub version: String,
+
+    /// The generated files with findings
+    pub files: usize,
+
+    pub findings: usize,
+}
+
+/// The findings of the tree with one feature configuration or for one target (see
+/// `scenario::ScenarioOpts`)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScenarioSummary {
+    pub name: String,
+
+    /// As given on the command line, e.g. `default` or `std,serde`
+    pub features: Vec<String>,
+
+    /// Number of packages in the tree
+    pub crates: usize,
+
+    pub findings: usize,
+
+    /// Number of findings in each crate that has any
+    pub by_crate: BTreeMap<String, usize>,
+}
+
+/// Something that could not be scanned and was skipped
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanIssue {
+    pub package: Option<String>,
+
+    pub path: Option<PathBuf>,
+
+    pub message: String,
+}
+
+/// Source files that are identical in several versions of the same crate, which were therefore
+/// scanned once
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuplicateSource {
+    #[serde(rename = "crate")]
+    pub krate: String,
+
+    pub versions: Vec<String>,
+
+    /// Number of files shared
+    pub files: usize,
+}
+
+/// A function of the analyzed crate along with its badness
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BadnessEntry {
+    pub function: String,
+
+    pub badness: u32,
+
+    pub file: Option<PathBuf>,
+
+    pub line: Option<u32>,
+
+    /// The profile of the only builds that contain the unsafety reaching the function, if it is
+    /// not in every build (see `profile::only_in`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub only_in: Option<Profile>,
+}
+
+/// The entries of the badness table, sorted by descending badness, then by function name
+pub fn badness_entries(badness: &HashMap<String, (u32, LabelInfo)>) -> Vec<BadnessEntry> {
+    let mut entries = badness
+        .iter()
+        .map(|(function, (badness, label_info))| BadnessEntry {
+            function: function.clone(),
+            badness: *badness,
+            file: label_info.debugloc.as_ref().map(utils::debugloc_path),
+            line: label_info.debugloc.as_ref().map(|debugloc| debugloc.line),
+            only_in: None,
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| {
+        b.badness
+            .cmp(&a.badness)
+            .then_with(|| a.function.cmp(&b.function))
+    });
+    entries
+}
+
+/// A public function of a workspace member, and how much unsafety it reaches
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiFunction {
+    pub function: String,
+
+    #[serde(rename = "crate")]
+    pub krate: String,
+
+    pub file: PathBuf,
+
+    pub line: usize,
+
+    /// Whether the function is an `unsafe fn`, i.e., puts a safety contract on its callers
+    pub unsafe_fn: bool,
+
+    /// See `BadnessEntry`. Zero if no unsafety is reached.
+    pub badness: u32,
+}
+
+/// Gives the functions of `api` their badness from `badness`, and sorts them by descending badness
+pub fn rank_api(mut api: Vec<ApiFunction>, badness: &[BadnessEntry]) -> Vec<ApiFunction> {
+    let badness = badness
+        .iter()
+        .map(|entry| (entry.function.as_str(), entry.badness))
+        .collect::<HashMap<_, _>>();
+    for function in &mut api {
+        function.badness = badness
+            .get(utils::simplify_trait_paths(&function.function).as_str())
+            .copied()
+            .unwrap_or_default();
+    }
+    api.sort_by(|a, b| {
+        b.badness
+            .cmp(&a.badness)
+            .then_with(|| a.function.cmp(&b.function))
+    });
+    api.dedup_by(|a, b| a.function == b.function);
+    api
+}
+
+/// A call from one function in the badness table to another
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Call {
+    pub caller: String,
+
+    pub callee: String,
+}
+
+/// How a report was produced, so that reports can be checked for comparability
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    pub siderophile_version: String,
+
+    pub rustc_version: String,
+
+    /// The host triple, which the crate was built for
+    pub target: String,
+
+    /// The features enabled on each workspace member
+    pub features: BTreeMap<String, Vec<String>>,
+
+    /// The command line arguments, without the program name
+    pub arguments: Vec<String>,
+
+    /// With `--sign-report`, what the report was produced from (see `sign`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<Inputs>,
+}
+
+/// The source state a report was produced from, and a digest of the report
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Inputs {
+    /// `sha256:` digest of `Cargo.lock`
+    pub lockfile: String,
+
+    /// The commit checked out, if the workspace is in a git repository
+    pub commit: Option<String>,
+
+    /// Whether the workspace had uncommitted changes
+    pub dirty: bool,
+
+    /// `sha256:` digest of the JSON serialization of the report, with this field empty
+    pub digest: String,
+}
+
+impl Metadata {
+    pub fn collect(features: BTreeMap<String, Vec<String>>) -> anyhow::Result<Self> {
+        let rustc = rustc_version::version_meta()?;
+        Ok(Self {
+            siderophile_version: env!("CARGO_PKG_VERSION").to_string(),
+            rustc_version: rustc.short_version_string,
+            target: rustc.host,
+            features,
+            arguments: std::env::args().skip(1).collect(),
+            inputs: None,
+        })
+    }
+}
+
+/// Everything a run produces, in a form that can be written out and read back in
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Report {
+    #[serde(default)]
+    pub metadata: Metadata,
+
+    /// Sorted by name, then by version
+    pub crates: Vec<CrateSummary>,
+
+    /// Sorted by descending number of findings
+    #[serde(default)]
+    pub categories: Vec<CategorySummary>,
+
+    /// Sorted by descending number of findings
+    #[serde(default)]
+    pub dependencies: Vec<DependencySummary>,
+
+    /// With `--no-optional-deps`, the optional dependencies in the tree that the default features
+    /// do not enable, as `name@version`, sorted. They are neither summarized nor scanned.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inactive: Vec<String>,
+
+    /// Safer alternatives to the dependencies with findings (see `alternative`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<Suggestion>,
+
+    /// The public functions of the analyzed crate, by descending badness (see `rank_api`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub api: Vec<ApiFunction>,
+
+    /// How the sources of the registry packages scanned differ from the `.crate` files they were
+    /// extracted from, or those from the checksums of the lockfile
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checksum_mismatches: Vec<ChecksumMismatch>,
+
+    /// With `--collapse-generated`, the findings in generated files, which are left out of
+    /// `findings`, by package
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub generated: Vec<GeneratedCode>,
+
+    /// When the packages with findings outside of the workspace were first added to `Cargo.lock`,
+    /// if it is in a git repository, sorted by package name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub introduced: Vec<Introduced>,
+
+    /// With `--exclude-stdlib`, the standard library crates and their replacements, which are left
+    /// out of `crates`, `findings` and the totals (see `category::STDLIB`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stdlib: Vec<CrateSummary>,
+
+    /// How many of the files that the build compiled were scanned. `None` when nothing was built,
+    /// or with `--shard`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_coverage: Option<FileCoverage>,
+
+    /// The packages that were left out of the scan, in whole or in part, and why, sorted by name,
+    /// then by version
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedCrate>,
+
+    pub findings: Vec<Finding>,
+
+    /// Sorted by descending badness, then by function name
+    pub badness: Vec<BadnessEntry>,
+
+    /// The calls between the functions in `badness`, sorted
+    #[serde(default)]
+    pub calls: Vec<Call>,
+
+    /// What was skipped because of errors
+    #[serde(default)]
+    pub errors: Vec<ScanIssue>,
+
+    /// Crates whose versions in the tree share source files
+    #[serde(default)]
+    pub duplicates: Vec<DuplicateSource>,
+
+    /// The same tree under other feature configurations, in the order they were given
+    #[serde(default)]
+    pub scenarios: Vec<ScenarioSummary>,
+
+    /// The same tree for other targets, named by their triples, in the order they were given
+    #[serde(default)]
+    pub targets: Vec<ScenarioSummary>,
+}
+
+impl Report {
+    pub fn new(
+        mut crates: Vec<CrateSummary>,
+        findings: Vec<Finding>,
+        badness: &HashMap<String, (u32, LabelInfo)>,
+    ) -> Self {
+        count_findings(&mut crates, &findings);
+        crates.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+        let categories = category::summarize(&crates);
+        let dependencies = dependency::summarize(&crates, &findings);
+
+        let badness = badness_entries(badness);
+        Self {
+            metadata: Metadata::default(),
+            crates,
+            categories,
+            dependencies,
+            inactive: Vec::new(),
+            suggestions: Vec::new(),
+            api: Vec::new(),
+            checksum_mismatches: Vec::new(),
+            generated: Vec::new(),
+            introduced: Vec::new(),
+            stdlib: Vec::new(),
+            file_coverage: None,
+            skipped: Vec::new(),
+            findings,
+            badness,
+            calls: Vec::new(),
+            errors: Vec::new(),
+            duplicates: Vec::new(),
+            scenarios: Vec::new(),
+            targets: Vec::new(),
+        }
+    }
+
+    /// Reads a report previously written with `--format json`
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Could not open `{}`", path.display()))?;
+        serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Could not parse report `{}`", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{count_findings, CrateSummary};
+    use crate::fixture;
+
+    #[test]
+    fn versions_of_a_crate_are_counted_apart() {
+        let mut crates = vec![
+            fixture::summary("libc"),
+            CrateSummary {
+                version: "2.0.0".to_string(),
+                ..fixture::summary("libc")
+            },
+        ];
+        let mut findings = fixture::findings("libc", 3);
+        findings.push(fixture::in_version(fixture::finding("libc", 3), "2.0.0"));
+        count_findings(&mut crates, &findings);
+        assert_eq!(crates[0].findings, 3);
+        assert_eq!(crates[1].findings, 1);
+    }
+}
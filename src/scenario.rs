@@ -0,0 +1,159 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use cargo::core::resolver::features::CliFeatures;
+use structopt::StructOpt;
+
+use crate::{
+    report::{self, ScenarioSummary},
+    trawl_source::{Scope, Trawl},
+};
+
+/// Stands for the default features in a scenario's feature list
+const DEFAULT: &str = "default";
+
+/// Stands for all features in a scenario's feature list
+const ALL: &str = "all";
+
+#[derive(StructOpt, Debug)]
+pub struct ScenarioOpts {
+    #[structopt(long = "scenario", value_name = "NAME=FEATURES", number_of_values = 1)]
+    /// Also scan the tree with only the comma-separated FEATURES enabled, and compare the findings
+    /// of each scenario (may be given more than once). `default` stands for the default features
+    /// and `all` for all features.
+    scenarios: Vec<Scenario>,
+
+    #[structopt(long = "targets", value_name = "TRIPLES", use_delimiter = true)]
+    /// Also scan the tree for each of the comma-separated target triples, with the default
+    /// features, and compare their findings. The targets must be installed, e.g., with `rustup
+    /// target add`.
+    targets: Vec<String>,
+}
+
+/// A named feature configuration of the workspace
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scenario {
+    name: String,
+    features: Vec<String>,
+}
+
+impl Scenario {
+    fn cli_features(&self) -> anyhow::Result<CliFeatures> {
+        let features = self
+            .features
+            .iter()
+            .filter(|feature| *feature != DEFAULT && *feature != ALL)
+            .cloned()
+            .collect::<Vec<_>>();
+        Ok(CliFeatures::from_command_line(
+            &features,
+            self.features.iter().any(|feature| feature == ALL),
+            self.features.iter().any(|feature| feature == DEFAULT),
+        )?)
+    }
+}
+
+impl FromStr for Scenario {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (name, features) = s.split_once('=').ok_or_else(|| {
+            anyhow!(
+                "Expected a scenario of the form `NAME=FEATURES`, found `{}`",
+                s
+            )
+        })?;
+        if name.is_empty() {
+            bail!("Scenario `{}` has no name", s);
+        }
+        Ok(Self {
+            name: name.to_string(),
+            features: features
+                .split(',')
+                .map(str::trim)
+                .filter(|feature| !feature.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+        })
+    }
+}
+
+/// What scanning the tree under each scenario and for each target found
+pub struct Comparison {
+    pub scenarios: Vec<ScenarioSummary>,
+
+    pub targets: Vec<ScenarioSummary>,
+}
+
+/// Scans the tree with `trawl` under each scenario and for each target, in the order they were
+/// given
+pub fn compare(
+    opts: &ScenarioOpts,
+    trawl: impl Fn(&Scope) -> anyhow::Result<Trawl>,
+) -> anyhow::Result<Comparison> {
+    // One after another: the builds share the target directory, which cargo locks for the
+    // duration of each build, so only what they find out is shared, not their time.
+    let scenarios = opts
+        .scenarios
+        .iter()
+        .map(|scenario| {
+            let _span = info_span!("scenario", name = %scenario.name).entered();
+            let trawl = trawl(&Scope {
+                features: scenario.cli_features()?,
+                target: None,
+            })?;
+            Ok(summarize(&scenario.name, &scenario.features, &trawl))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let targets = opts
+        .targets
+        .iter()
+        .map(|target| {
+            let _span = info_span!("target", name = %target).entered();
+            let trawl = trawl(&Scope {
+                features: CliFeatures::new_all(false),
+                target: Some(target.clone()),
+            })?;
+            Ok(summarize(target, &[DEFAULT.to_string()], &trawl))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Comparison { scenarios, targets })
+}
+
+fn summarize(name: &str, features: &[String], trawl: &Trawl) -> ScenarioSummary {
+    ScenarioSummary {
+        name: name.to_string(),
+        features: features.to_vec(),
+        crates: trawl.crates.len(),
+        findings: trawl.findings.len(),
+        by_crate: report::counts_by_crate(&trawl.findings)
+            .into_iter()
+            .map(|(krate, count)| (krate.to_string(), count))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Scenario;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "minimal=".parse::<Scenario>().unwrap(),
+            Scenario {
+                name: "minimal".to_string(),
+                features: Vec::new(),
+            }
+        );
+        assert_eq!(
+            "full=default, serde".parse::<Scenario>().unwrap(),
+            Scenario {
+                name: "full".to_string(),
+                features: vec!["default".to_string(), "serde".to_string()],
+            }
+        );
+        assert!("default".parse::<Scenario>().is_err());
+        assert!("=serde".parse::<Scenario>().is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+
+/// One of `count` disjoint parts of the package set, numbered from 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shard {
+    index: usize,
+    count: usize,
+}
+
+impl Shard {
+    /// Whether the package at `position` in the sorted package set belongs to this shard
+    pub const fn contains(self, position: usize) -> bool {
+        position % self.count == self.index - 1
+    }
+}
+
+impl FromStr for Shard {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (index, count) = s
+            .split_once('/')
+            .ok_or_else(|| anyhow!("Expected a shard of the form `I/N`, found `{}`", s))?;
+        let index = index.parse()?;
+        let count = count.parse()?;
+        if index == 0 || index > count {
+            bail!(
+                "Shard index must be between 1 and {}, found {}",
+                count,
+                index
+            );
+        }
+        Ok(Self { index, count })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Shard;
+
+    #[test]
+    fn partition() {
+        let shards = ["1/3", "2/3", "3/3"]
+            .iter()
+            .map(|s| s.parse::<Shard>().unwrap())
+            .collect::<Vec<_>>();
+        for position in 0..10 {
+            assert_eq!(
+                shards
+                    .iter()
+                    .filter(|shard| shard.contains(position))
+                    .count(),
+                1
+            );
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        assert!("0/3".parse::<Shard>().is_err());
+        assert!("4/3".parse::<Shard>().is_err());
+        assert!("3".parse::<Shard>().is_err());
+    }
+}
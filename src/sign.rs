@@ -0,0 +1,72 @@
+//! With `--sign-report`, what a report was produced from and a digest of the report itself are
+//! embedded in its metadata, so that downstream consumers can check that it corresponds to a
+//! specific source state and was not edited since
+
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use sha2::{Digest, Sha256};
+use structopt::StructOpt;
+
+use crate::{
+    git,
+    report::{Inputs, Report},
+};
+
+#[derive(StructOpt, Debug)]
+pub struct SignOpts {
+    #[structopt(long = "sign-report")]
+    /// Embed the digests of the lockfile and of the report, and the commit analyzed, in the
+    /// report's metadata (see the `verify-report` subcommand)
+    sign_report: bool,
+}
+
+/// With `--sign-report`, what the report is produced from, with no digest yet
+pub fn inputs(opts: &SignOpts, lockfile: &Path) -> anyhow::Result<Option<Inputs>> {
+    if !opts.sign_report {
+        return Ok(None);
+    }
+    let contents =
+        fs::read(lockfile).with_context(|| format!("Could not read `{}`", lockfile.display()))?;
+    let (commit, dirty) = git::commit(lockfile.parent().unwrap_or_else(|| Path::new(".")));
+    if dirty {
+        eprintln!(
+            "Warning: There are uncommitted changes, so the report does not match its commit"
+        );
+    }
+    Ok(Some(Inputs {
+        lockfile: sha256(&contents),
+        commit,
+        dirty,
+        digest: String::new(),
+    }))
+}
+
+/// Sets the digest of `report`, if it has inputs
+pub fn sign(report: &mut Report) -> anyhow::Result<()> {
+    if report.metadata.inputs.is_none() {
+        return Ok(());
+    }
+    let digest = digest(report)?;
+    if let Some(inputs) = &mut report.metadata.inputs {
+        inputs.digest = digest;
+    }
+    Ok(())
+}
+
+/// `sha256:` digest of the JSON serialization of `report`, with the digest left empty
+pub fn digest(report: &Report) -> anyhow::Result<String> {
+    let mut report = report.clone();
+    if let Some(inputs) = &mut report.metadata.inputs {
+        inputs.digest.clear();
+    }
+    Ok(sha256(&serde_json::to_vec(&report)?))
+}
+
+pub fn sha256(contents: &[u8]) -> String {
+    let hex = Sha256::digest(contents)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    format!("sha256:{hex}")
+}
@@ -0,0 +1,95 @@
+//! Unpacking a packaged `.crate` file, e.g. the one `cargo package` wrote, so that exactly the
+//! files that are published can be scanned
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+use tempfile::{tempdir, TempDir};
+
+/// A `.crate` file unpacked into a temporary directory
+pub struct Unpacked {
+    /// Deleted when the unpacked archive is dropped
+    _tempdir: TempDir,
+
+    /// The package's directory, `NAME-VERSION`
+    pub root: PathBuf,
+
+    /// The SHA-256 digest of the `.crate` file, as recorded in lockfiles and registry indexes
+    pub checksum: String,
+
+    /// The SHA-256 digest of each file, by path relative to `root`, as in a
+    /// `.cargo-checksum.json`
+    pub files: BTreeMap<String, String>,
+}
+
+/// Unpacks the `.crate` file at `archive`, which must hold a single package directory
+pub fn unpack(archive: &Path) -> anyhow::Result<Unpacked> {
+    let contents =
+        fs::read(archive).with_context(|| format!("Could not read `{}`", archive.display()))?;
+    let tempdir = tempdir()?;
+    let mut package_dir = None::<OsString>;
+    let mut files = BTreeMap::new();
+    let mut tar = tar::Archive::new(GzDecoder::new(contents.as_slice()));
+    let entries = tar
+        .entries()
+        .with_context(|| format!("Could not read `{}`", archive.display()))?;
+    for entry in entries {
+        let mut entry = entry.with_context(|| format!("Could not read `{}`", archive.display()))?;
+        let path = entry.path()?.into_owned();
+        let mut components = path.components();
+        let top = match components.next() {
+            Some(Component::Normal(top)) => top.to_os_string(),
+            _ => bail!(
+                "`{}` has an entry outside of a package directory: `{}`",
+                archive.display(),
+                path.display()
+            ),
+        };
+        match &package_dir {
+            Some(package_dir) if *package_dir != top => {
+                bail!("`{}` holds more than one package", archive.display())
+            }
+            Some(_) => {}
+            None => package_dir = Some(top),
+        }
+        // `unpack_in` skips the entries that would be written outside of the directory.
+        if !entry.unpack_in(tempdir.path())? {
+            bail!(
+                "`{}` has an entry that escapes its directory: `{}`",
+                archive.display(),
+                path.display()
+            );
+        }
+        if entry.header().entry_type().is_file() {
+            let unpacked = tempdir.path().join(&path);
+            let contents = fs::read(&unpacked)
+                .with_context(|| format!("Could not read `{}`", unpacked.display()))?;
+            let relative = components
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.insert(relative, hex(&Sha256::digest(&contents)));
+        }
+    }
+    let root = match package_dir {
+        Some(package_dir) => tempdir.path().join(package_dir),
+        None => bail!("`{}` is empty", archive.display()),
+    };
+    Ok(Unpacked {
+        _tempdir: tempdir,
+        root,
+        checksum: hex(&Sha256::digest(&contents)),
+        files,
+    })
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{b:02x}")).collect()
+}
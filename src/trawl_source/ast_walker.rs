@@ -3,29 +3,95 @@
 use std::{
     collections::VecDeque,
     error::Error,
-    fmt,
-    fs::File,
-    io::{self, Read},
+    fmt, io, mem,
     path::{Path, PathBuf},
-    string::FromUtf8Error,
+    str::Utf8Error,
 };
 
+use proc_macro2::{Span, TokenStream, TokenTree};
 use quote::ToTokens;
+use serde::{Deserialize, Serialize};
 use syn::{
-    punctuated::Punctuated, visit, Attribute, Expr, GenericArgument, ImplItemMethod, ItemFn,
-    ItemImpl, ItemMod, ItemTrait, PathArguments, TraitItemMethod,
+    punctuated::Punctuated, visit, Attribute, Expr, ForeignItem, GenericArgument, ImplItemMethod,
+    Item, ItemFn, ItemForeignMod, ItemImpl, ItemMod, ItemStatic, ItemTrait, PathArguments,
+    TraitItemMethod, Visibility,
 };
 
-/// A formatted list of Rust items that are unsafe
-pub struct UnsafeItems(pub(crate) Vec<String>);
+use crate::finding::{FindingKind, Tag};
+
+/// A piece of unsafety along with the formatted path of its enclosing item
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UnsafeItem {
+    pub path: String,
+    pub kind: FindingKind,
+    pub line: usize,
+
+    /// The `cfg` conditions of the enclosing items and expressions, combined with `all` if there
+    /// are several
+    pub cfg: Option<String>,
+
+    /// The `_unchecked` functions called in the unsafe block or function, e.g. `get_unchecked`
+    pub unchecked: Vec<String>,
+
+    /// The patterns of unsafety the item is an instance of, e.g. pinning
+    pub tags: Vec<Tag>,
+
+    /// The global hook the item claims, see `global_hook`
+    pub hook: Option<String>,
+}
+
+/// A `pub` free function or inherent method, nested in no function
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PublicFn {
+    pub path: String,
+    pub line: usize,
+    pub unsafe_fn: bool,
+}
+
+/// A list of Rust items that are unsafe
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UnsafeItems {
+    pub items: Vec<UnsafeItem>,
+
+    /// Whether the file has a `#![no_std]` attribute, possibly behind a `cfg_attr`. Only
+    /// meaningful for crate roots.
+    pub no_std: bool,
+
+    /// Whether the file has an unconditional `#![forbid(unsafe_code)]` attribute. Only meaningful
+    /// for crate roots.
+    pub forbids_unsafe_code: bool,
+
+    /// The files of the `mod` declarations with a `#[path]` attribute, relative to the directory
+    /// of the file. They can be outside of the package.
+    pub path_mods: Vec<PathBuf>,
+
+    /// The `pub` functions of the file. Those in modules of `private_mods`, of this file or of
+    /// another, are not actually public.
+    pub public_fns: Vec<PublicFn>,
+
+    /// The `pub unsafe trait`s of the file, like `public_fns`
+    pub public_unsafe_traits: Vec<String>,
+
+    /// The paths of the modules the file declares without `pub`
+    pub private_mods: Vec<String>,
+}
 
 #[derive(Debug)]
 pub enum ScanFileError {
     Io(io::Error, PathBuf),
-    Utf8(FromUtf8Error, PathBuf),
+    Utf8(Utf8Error, PathBuf),
     Syn(syn::Error, PathBuf),
 }
 
+impl ScanFileError {
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Io(_, path) | Self::Utf8(_, path) | Self::Syn(_, path) => path,
+        }
+    }
+}
+
 impl Error for ScanFileError {}
 
 /// Forward Display to Debug. See the crate root documentation.
@@ -37,13 +103,41 @@ impl fmt::Display for ScanFileError {
 
 struct SiderophileSynVisitor {
     /// Where we log all the findings
-    buf: Vec<String>,
+    buf: Vec<UnsafeItem>,
 
     /// Keeps track of what the current module path is (this includes trait defs and impls)
     cur_mod_path: VecDeque<String>,
 
     /// Count unsafe usage inside tests
     include_tests: bool,
+
+    /// The `cfg` conditions of the enclosing items and expressions, formatted
+    cfgs: Vec<String>,
+
+    /// Indexes into `buf` of the enclosing unsafe blocks and functions, innermost last
+    enclosing: Vec<usize>,
+
+    /// The trait of the enclosing trait impl, e.g. `Drop`
+    impl_trait: Option<String>,
+
+    /// The tags the enclosing method gives the findings in it, see `method_tags`
+    item_tags: Vec<Tag>,
+
+    /// The enclosing inline modules (`mod foo { .. }`), against which `#[path]`s are resolved
+    inline_mods: Vec<String>,
+
+    /// The `#[path]`s of `mod` declarations, with the inline modules they are in
+    path_mods: Vec<(Vec<String>, String)>,
+
+    /// Whether a function or method encloses the current node
+    in_fn: bool,
+
+    /// Whether the current node is in a `#[cxx::bridge]` module
+    in_bridge: bool,
+
+    public_fns: Vec<PublicFn>,
+    public_unsafe_traits: Vec<String>,
+    private_mods: Vec<String>,
 }
 
 impl SiderophileSynVisitor {
@@ -56,8 +150,353 @@ impl SiderophileSynVisitor {
             buf,
             cur_mod_path,
             include_tests,
+            cfgs: Vec::new(),
+            enclosing: Vec::new(),
+            impl_trait: None,
+            item_tags: Vec::new(),
+            inline_mods: Vec::new(),
+            path_mods: Vec::new(),
+            in_fn: false,
+            in_bridge: false,
+            public_fns: Vec::new(),
+            public_unsafe_traits: Vec::new(),
+            private_mods: Vec::new(),
         }
     }
+
+    /// Notes the function or method with signature `sig` in the current module path if it is `pub`
+    /// and not nested in another
+    fn record_public_fn(&mut self, vis: &Visibility, sig: &syn::Signature) {
+        if !self.in_fn && matches!(vis, Visibility::Public(_)) {
+            self.public_fns.push(PublicFn {
+                path: fmt_mod_path(&self.cur_mod_path),
+                line: sig.fn_token.span.start().line,
+                unsafe_fn: sig.unsafety.is_some(),
+            });
+        }
+    }
+
+    /// Logs a finding at `span` in the current module path. The unchecked calls that follow are
+    /// attributed to it until it is popped off `self.enclosing`.
+    fn record(&mut self, kind: FindingKind, span: Span) {
+        self.enclosing.push(self.buf.len());
+        self.buf.push(UnsafeItem {
+            path: fmt_mod_path(&self.cur_mod_path),
+            kind,
+            line: span.start().line,
+            cfg: match self.cfgs.as_slice() {
+                [] => None,
+                [cfg] => Some(cfg.clone()),
+                cfgs => Some(format!("all({})", cfgs.join(", "))),
+            },
+            unchecked: Vec::new(),
+            tags: self.item_tags.clone(),
+            hook: None,
+        });
+    }
+
+    /// Logs an item at `span` in the current module path that the linker is told to treat
+    /// specially by `attrs`, see `link_kind`
+    fn record_link_attrs(&mut self, attrs: &[Attribute], span: Span) {
+        let Some(kind) = link_kind(attrs) else {
+            return;
+        };
+        let tags = if kind == FindingKind::Constructor {
+            vec![Tag::BeforeMain]
+        } else {
+            Vec::new()
+        };
+        let item_tags = mem::replace(&mut self.item_tags, tags);
+        self.record(kind, span);
+        self.enclosing.pop();
+        self.item_tags = item_tags;
+    }
+
+    /// Logs an item at `span` in the current module path that claims the global hook `hook`
+    fn record_hook(&mut self, kind: FindingKind, hook: String, span: Span) {
+        self.record(kind, span);
+        if let Some(index) = self.enclosing.pop() {
+            self.buf[index].hook = Some(hook);
+        }
+    }
+
+    /// Attributes a call to the `_unchecked` function `callee` to the innermost unsafe block or
+    /// function
+    fn record_unchecked(&mut self, callee: String) {
+        if let Some(item) = self.enclosing.last().map(|&index| &mut self.buf[index]) {
+            if !item.unchecked.contains(&callee) {
+                item.unchecked.push(callee);
+            }
+        }
+    }
+
+    /// Tags the innermost unsafe block or function with `tag`
+    fn record_tag(&mut self, tag: Tag) {
+        if let Some(item) = self.enclosing.last().map(|&index| &mut self.buf[index]) {
+            if !item.tags.contains(&tag) {
+                item.tags.push(tag);
+            }
+        }
+    }
+
+    /// Puts `cfgs` in effect when entering a node. Returns what to truncate `self.cfgs` to when
+    /// leaving it.
+    fn enter_cfgs(&mut self, cfgs: impl IntoIterator<Item = String>) -> usize {
+        let outer = self.cfgs.len();
+        self.cfgs.extend(cfgs);
+        outer
+    }
+}
+
+/// The conditions of the `#[cfg(...)]` attributes among `attrs`, formatted
+fn cfgs(attrs: &[Attribute]) -> Vec<String> {
+    use syn::{Meta, NestedMeta};
+    attrs
+        .iter()
+        .flat_map(Attribute::parse_meta)
+        .filter_map(|m| match m {
+            Meta::List(ml) if ml.path.is_ident("cfg") && ml.nested.len() == 1 => {
+                match ml.nested.first() {
+                    Some(NestedMeta::Meta(meta)) => Some(fmt_meta(meta)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether `name` is that of an unchecked variant of a function, like `get_unchecked`, whose
+/// caller is responsible for what the checked variant would verify
+fn is_unchecked(name: &str) -> bool {
+    name.ends_with("_unchecked")
+}
+
+/// The tags of the findings in the method `method` of an impl of `impl_trait`: `drop` of `Drop`
+/// runs during destruction, and the methods of `GlobalAlloc` allocate for the whole program once
+/// it is made the `#[global_allocator]`
+fn method_tags(impl_trait: Option<&str>, method: &str) -> Vec<Tag> {
+    match (impl_trait, method) {
+        (Some("Drop"), "drop") => vec![Tag::Drop],
+        (Some("GlobalAlloc"), _) => vec![Tag::Allocator],
+        _ => Vec::new(),
+    }
+}
+
+/// The unsafe methods of `Pin`, which let pinned values be moved if misused
+const PIN_UNCHECKED: &[&str] = &[
+    "new_unchecked",
+    "get_unchecked_mut",
+    "map_unchecked",
+    "map_unchecked_mut",
+    "into_inner_unchecked",
+];
+
+/// Whether a call to the method `method` with `args` arguments is one of `PIN_UNCHECKED`. Slices
+/// have a `get_unchecked_mut` too, but it takes an index.
+fn is_pin_method_call(method: &str, args: usize) -> bool {
+    match method {
+        "get_unchecked_mut" => args == 0,
+        "map_unchecked" | "map_unchecked_mut" => true,
+        _ => false,
+    }
+}
+
+/// Whether a call to the function at `path` is one of `PIN_UNCHECKED`, e.g. `Pin::new_unchecked`
+fn is_pin_call(path: &syn::Path) -> bool {
+    let mut segments = path.segments.iter().rev();
+    match (segments.next(), segments.next()) {
+        (Some(function), Some(ty)) => {
+            ty.ident == "Pin" && PIN_UNCHECKED.iter().any(|name| function.ident == *name)
+        }
+        _ => false,
+    }
+}
+
+/// The attributes that claim a hook of which a program can only have one
+const GLOBAL_HOOKS: &[&str] = &["panic_handler", "alloc_error_handler", "lang"];
+
+/// The global hook an item with `attrs` claims, e.g. `panic_handler` or
+/// `lang = "eh_personality"`
+fn global_hook(attrs: &[Attribute]) -> Option<String> {
+    attrs
+        .iter()
+        .filter(|attr| GLOBAL_HOOKS.iter().any(|hook| attr.path.is_ident(hook)))
+        .find_map(|attr| attr.parse_meta().ok())
+        .map(|meta| fmt_meta(&meta))
+}
+
+/// The prefixes of the sections whose function pointers are called by the loader or the C runtime
+/// before `main`, or after it returns
+const CONSTRUCTOR_SECTIONS: &[&str] = &[
+    ".init_array",
+    ".preinit_array",
+    ".fini_array",
+    ".ctors",
+    ".dtors",
+    "__DATA,__mod_init_func",
+    "__DATA,__mod_term_func",
+    ".CRT$X",
+];
+
+/// Whether `section` is one of `CONSTRUCTOR_SECTIONS`, possibly with a priority, like
+/// `.init_array.00100`. The MSVC sections are named by the letters after `$X`, e.g., `.CRT$XCU`.
+fn is_constructor_section(section: &str) -> bool {
+    CONSTRUCTOR_SECTIONS.iter().any(|prefix| {
+        section
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.') || prefix.ends_with("$X"))
+    })
+}
+
+/// `Constructor` for items that run before `main` or after it returns, through `#[ctor]`,
+/// `#[dtor]` or a `#[link_section]` like `.init_array`, and `LinkSection` for other items
+/// placed in a section with `#[link_section]` or kept with `#[used]`
+fn link_kind(attrs: &[Attribute]) -> Option<FindingKind> {
+    use syn::{Lit, Meta};
+    let mut kind = None;
+    for attr in attrs {
+        let is_ctor = attr
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "ctor" || segment.ident == "dtor");
+        let is_constructor_section = match attr.parse_meta() {
+            Ok(Meta::NameValue(nv)) if nv.path.is_ident("link_section") => match nv.lit {
+                Lit::Str(section) => is_constructor_section(&section.value()),
+                _ => false,
+            },
+            _ => false,
+        };
+        if is_ctor || is_constructor_section {
+            return Some(FindingKind::Constructor);
+        }
+        if attr.path.is_ident("link_section") || attr.path.is_ident("used") {
+            kind = Some(FindingKind::LinkSection);
+        }
+    }
+    kind
+}
+
+/// The condition of `cfg!(...)`, or of `!cfg!(...)` negated
+fn cfg_macro(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Macro(mac) if mac.mac.path.is_ident("cfg") => mac
+            .mac
+            .parse_body::<syn::Meta>()
+            .ok()
+            .map(|meta| fmt_meta(&meta)),
+        Expr::Unary(unary) if matches!(unary.op, syn::UnOp::Not(_)) => {
+            cfg_macro(&unary.expr).map(|cfg| format!("not({cfg})"))
+        }
+        Expr::Paren(paren) => cfg_macro(&paren.expr),
+        _ => None,
+    }
+}
+
+/// Formats a cfg predicate like `all(unix, not(debug_assertions))`
+fn fmt_meta(meta: &syn::Meta) -> String {
+    use syn::{Meta, NestedMeta};
+    let path = |path: &syn::Path| fmt_syn_path(path.clone());
+    match meta {
+        Meta::Path(p) => path(p),
+        Meta::List(ml) => {
+            let nested = ml
+                .nested
+                .iter()
+                .map(|nested| match nested {
+                    NestedMeta::Meta(meta) => fmt_meta(meta),
+                    NestedMeta::Lit(lit) => lit.to_token_stream().to_string(),
+                })
+                .collect::<Vec<_>>();
+            format!("{}({})", path(&ml.path), nested.join(", "))
+        }
+        Meta::NameValue(nv) => format!("{} = {}", path(&nv.path), nv.lit.to_token_stream()),
+    }
+}
+
+/// The value of a `#[path = "..."]` attribute
+fn path_attr(attrs: &[Attribute]) -> Option<String> {
+    use syn::{Lit, Meta};
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("path"))
+        .find_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(syn::MetaNameValue {
+                lit: Lit::Str(path),
+                ..
+            })) => Some(path.value()),
+            _ => None,
+        })
+}
+
+/// Resolves the `#[path]` of a `mod` declaration in `file`, relative to the directory of `file`.
+/// Outside of inline modules, it is relative to that directory. Inside them, it is relative to
+/// the directory of their own files, which for a file that is not a `mod.rs` or crate root is
+/// named after it.
+fn resolve_path_mod(file: &Path, inline_mods: &[String], path: &str) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    if !inline_mods.is_empty() {
+        let is_mod_rs = file
+            .file_name()
+            .is_some_and(|name| name == "mod.rs" || name == "lib.rs" || name == "main.rs");
+        if !is_mod_rs {
+            if let Some(stem) = file.file_stem() {
+                resolved.push(stem);
+            }
+        }
+        resolved.extend(inline_mods);
+    }
+    resolved.push(path);
+    resolved
+}
+
+/// Whether `i` is a `#[cxx::bridge]` module, from which cxx generates the bindings of the functions
+/// of its `extern` blocks
+fn is_cxx_bridge(i: &ItemMod) -> bool {
+    i.attrs.iter().any(|attr| {
+        let segments = attr
+            .path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<_>>();
+        segments == ["cxx", "bridge"]
+    })
+}
+
+/// Whether `attrs` have `#[wasm_bindgen]`, which on an `extern "C"` block imports its functions from
+/// JavaScript
+fn is_wasm_bindgen(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "wasm_bindgen")
+    })
+}
+
+/// An `unsafe extern` block, which syn only keeps the tokens of, parsed as if it were not `unsafe`
+fn parse_unsafe_foreign_mod(tokens: &TokenStream) -> Option<ItemForeignMod> {
+    let mut tokens = tokens.clone().into_iter().peekable();
+    let mut without_unsafe = TokenStream::new();
+    let mut is_unsafe = false;
+    while let Some(token) = tokens.next() {
+        match &token {
+            TokenTree::Ident(ident)
+                if ident == "unsafe"
+                    && matches!(tokens.peek(), Some(TokenTree::Ident(next)) if next == "extern") =>
+            {
+                is_unsafe = true;
+            }
+            _ => without_unsafe.extend([token]),
+        }
+    }
+    if is_unsafe {
+        syn::parse2(without_unsafe).ok()
+    } else {
+        None
+    }
 }
 
 /// Will return true for #[cfg(test)] decodated modules.
@@ -119,6 +558,40 @@ fn is_test_fn(i: &ItemFn) -> bool {
         .any(|m| meta_is_word_test(&m))
 }
 
+/// Will return true for `#![no_std]` and `#![cfg_attr(..., no_std)]`. The latter is how most
+/// crates that support `no_std` declare it, so the condition is not evaluated.
+fn is_no_std(file: &syn::File) -> bool {
+    use syn::{Meta, NestedMeta};
+    file.attrs
+        .iter()
+        .flat_map(Attribute::parse_meta)
+        .any(|m| match m {
+            Meta::Path(p) => p.is_ident("no_std"),
+            Meta::List(ml) if ml.path.is_ident("cfg_attr") => ml
+                .nested
+                .iter()
+                .skip(1)
+                .any(|n| matches!(n, NestedMeta::Meta(Meta::Path(p)) if p.is_ident("no_std"))),
+            _ => false,
+        })
+}
+
+/// Will return true for `#![forbid(unsafe_code)]`, but not behind a `cfg_attr`, which would not
+/// hold for every build.
+fn forbids_unsafe_code(file: &syn::File) -> bool {
+    use syn::{Meta, NestedMeta};
+    file.attrs
+        .iter()
+        .flat_map(Attribute::parse_meta)
+        .any(|m| match m {
+            Meta::List(ml) if ml.path.is_ident("forbid") => ml
+                .nested
+                .iter()
+                .any(|n| matches!(n, NestedMeta::Meta(Meta::Path(p)) if p.is_ident("unsafe_code"))),
+            _ => false,
+        })
+}
+
 impl<'ast> visit::Visit<'ast> for SiderophileSynVisitor {
     fn visit_file(&mut self, i: &'ast syn::File) {
         syn::visit::visit_file(self, i);
@@ -132,26 +605,90 @@ impl<'ast> visit::Visit<'ast> for SiderophileSynVisitor {
         }
 
         self.cur_mod_path.push_back(i.sig.ident.to_string());
+        // `#[test]` functions are only compiled under `cfg(test)`.
+        let test = is_test_fn(i).then(|| "test".to_string());
+        let outer = self.enter_cfgs(cfgs(&i.attrs).into_iter().chain(test));
+        // A function nested in a method is not run by it unless called
+        let item_tags = mem::take(&mut self.item_tags);
+
+        if let Some(hook) = global_hook(&i.attrs) {
+            self.record_hook(FindingKind::GlobalHook, hook, i.sig.fn_token.span);
+        }
+        self.record_link_attrs(&i.attrs, i.sig.fn_token.span);
+        self.record_public_fn(&i.vis, &i.sig);
 
         // See if this function is marked unsafe
-        if i.sig.unsafety.is_some() {
-            let pp = fmt_mod_path(&self.cur_mod_path);
-            self.buf.push(pp);
+        let enclosing = self.enclosing.len();
+        if let Some(unsafety) = i.sig.unsafety {
+            self.record(FindingKind::Function, unsafety.span);
         }
 
         trace!("entering function {:?}", i.sig.ident);
+        let in_fn = mem::replace(&mut self.in_fn, true);
         visit::visit_item_fn(self, i);
+        self.in_fn = in_fn;
 
+        self.enclosing.truncate(enclosing);
+        self.item_tags = item_tags;
+        self.cfgs.truncate(outer);
         self.cur_mod_path.pop_back();
     }
 
     fn visit_expr(&mut self, i: &Expr) {
         match i {
             Expr::Unsafe(i) => {
-                let pp = fmt_mod_path(&self.cur_mod_path);
-                self.buf.push(pp);
+                let outer = self.enter_cfgs(cfgs(&i.attrs));
+                self.record(FindingKind::Block, i.unsafe_token.span);
                 visit::visit_expr_unsafe(self, i);
+                self.enclosing.pop();
+                self.cfgs.truncate(outer);
+            }
+            Expr::MethodCall(i) => {
+                let method = i.method.to_string();
+                if is_pin_method_call(&method, i.args.len()) {
+                    self.record_tag(Tag::Pin);
+                }
+                if is_unchecked(&method) {
+                    self.record_unchecked(method);
+                }
+                visit::visit_expr_method_call(self, i);
+            }
+            Expr::Call(i) => {
+                if let Expr::Path(func) = &*i.func {
+                    if is_pin_call(&func.path) {
+                        self.record_tag(Tag::Pin);
+                    }
+                    if func
+                        .path
+                        .segments
+                        .last()
+                        .is_some_and(|segment| is_unchecked(&segment.ident.to_string()))
+                    {
+                        self.record_unchecked(fmt_syn_path(func.path.clone()));
+                    }
+                }
+                visit::visit_expr_call(self, i);
+            }
+            Expr::Block(i) => {
+                let outer = self.enter_cfgs(cfgs(&i.attrs));
+                visit::visit_expr_block(self, i);
+                self.cfgs.truncate(outer);
             }
+            // `if cfg!(debug_assertions) { .. } else { unsafe { .. } }` is how unchecked code is
+            // often swapped in for release builds.
+            Expr::If(i) => match cfg_macro(&i.cond) {
+                Some(cfg) => {
+                    let outer = self.enter_cfgs([cfg.clone()]);
+                    visit::visit_block(self, &i.then_branch);
+                    self.cfgs.truncate(outer);
+                    if let Some((_, else_branch)) = &i.else_branch {
+                        let outer = self.enter_cfgs([format!("not({cfg})")]);
+                        visit::Visit::visit_expr(self, else_branch);
+                        self.cfgs.truncate(outer);
+                    }
+                }
+                None => visit::visit_expr_if(self, i),
+            },
             Expr::Closure(expr_closure) => {
                 self.cur_mod_path.push_back("{{closure}}".to_string());
                 visit::visit_expr_closure(self, expr_closure);
@@ -167,17 +704,120 @@ impl<'ast> visit::Visit<'ast> for SiderophileSynVisitor {
         }
     }
 
+    fn visit_item_static(&mut self, i: &ItemStatic) {
+        let outer = self.enter_cfgs(cfgs(&i.attrs));
+        self.cur_mod_path.push_back(i.ident.to_string());
+        if i.attrs
+            .iter()
+            .any(|attr| attr.path.is_ident("global_allocator"))
+        {
+            let item_tags = mem::replace(&mut self.item_tags, vec![Tag::Allocator]);
+            self.record_hook(
+                FindingKind::GlobalAllocator,
+                "global_allocator".to_string(),
+                i.static_token.span,
+            );
+            self.item_tags = item_tags;
+        } else if let Some(hook) = global_hook(&i.attrs) {
+            self.record_hook(FindingKind::GlobalHook, hook, i.static_token.span);
+        }
+        self.record_link_attrs(&i.attrs, i.static_token.span);
+        self.cur_mod_path.pop_back();
+        visit::visit_item_static(self, i);
+        self.cfgs.truncate(outer);
+    }
+
     fn visit_item_mod(&mut self, i: &ItemMod) {
         if !self.include_tests && is_test_mod(i) {
             return;
         }
 
         self.cur_mod_path.push_back(i.ident.to_string());
-        visit::visit_item_mod(self, i);
+        let outer = self.enter_cfgs(cfgs(&i.attrs));
+        if !matches!(i.vis, Visibility::Public(_)) {
+            self.private_mods.push(fmt_mod_path(&self.cur_mod_path));
+        }
+        if i.content.is_some() {
+            self.inline_mods.push(i.ident.to_string());
+            let in_bridge = mem::replace(&mut self.in_bridge, is_cxx_bridge(i));
+            visit::visit_item_mod(self, i);
+            self.in_bridge = in_bridge;
+            self.inline_mods.pop();
+        } else {
+            if let Some(path) = path_attr(&i.attrs) {
+                self.path_mods.push((self.inline_mods.clone(), path));
+            }
+            visit::visit_item_mod(self, i);
+        }
+        self.cfgs.truncate(outer);
         self.cur_mod_path.pop_back();
     }
 
+    fn visit_item(&mut self, i: &Item) {
+        // cxx asserts that the functions of an `unsafe extern "C++"` block are safe to call.
+        match i {
+            Item::Verbatim(tokens) => {
+                if let Some(foreign_mod) = parse_unsafe_foreign_mod(tokens) {
+                    self.visit_item_foreign_mod(&foreign_mod);
+                }
+            }
+            other => visit::visit_item(self, other),
+        }
+    }
+
+    /// The functions of `extern "C++"` blocks, and of the `extern "Rust"` blocks of bridge modules,
+    /// which are the boundary between Rust and C++ without being declared `unsafe`, and the
+    /// JavaScript functions imported with `#[wasm_bindgen]`
+    fn visit_item_foreign_mod(&mut self, i: &ItemForeignMod) {
+        let abi = i.abi.name.as_ref().map(syn::LitStr::value);
+        let kind = if is_wasm_bindgen(&i.attrs) {
+            Some(FindingKind::JsImport)
+        } else {
+            match abi.as_deref() {
+                Some("C++") => Some(FindingKind::FfiBridge),
+                Some("Rust") if self.in_bridge => Some(FindingKind::FfiBridge),
+                _ => None,
+            }
+        };
+        if let Some(kind) = kind {
+            let outer = self.enter_cfgs(cfgs(&i.attrs));
+            for item in &i.items {
+                if let ForeignItem::Fn(f) = item {
+                    let outer = self.enter_cfgs(cfgs(&f.attrs));
+                    self.cur_mod_path.push_back(f.sig.ident.to_string());
+                    self.record(kind, f.sig.fn_token.span);
+                    self.enclosing.pop();
+                    self.cur_mod_path.pop_back();
+                    self.cfgs.truncate(outer);
+                }
+            }
+            self.cfgs.truncate(outer);
+        }
+        visit::visit_item_foreign_mod(self, i);
+    }
+
+    fn visit_local(&mut self, i: &syn::Local) {
+        let outer = self.enter_cfgs(cfgs(&i.attrs));
+        visit::visit_local(self, i);
+        self.cfgs.truncate(outer);
+    }
+
+    fn visit_arm(&mut self, i: &syn::Arm) {
+        let outer = self.enter_cfgs(cfgs(&i.attrs));
+        visit::visit_arm(self, i);
+        self.cfgs.truncate(outer);
+    }
+
     fn visit_item_impl(&mut self, i: &ItemImpl) {
+        let outer = self.enter_cfgs(cfgs(&i.attrs));
+        let impl_trait = mem::replace(
+            &mut self.impl_trait,
+            i.trait_
+                .as_ref()
+                .and_then(|(_, path, _)| path.segments.last())
+                .map(|segment| segment.ident.to_string()),
+        );
+        let item_tags = mem::take(&mut self.item_tags);
         // unsafe trait impl's
         if let syn::Type::Path(ref for_path) = &*i.self_ty {
             let for_path = fmt_syn_path(for_path.path.clone());
@@ -197,6 +837,16 @@ impl<'ast> visit::Visit<'ast> for SiderophileSynVisitor {
                 self.cur_mod_path.clear();
                 self.cur_mod_path.push_back(full_impl_path);
 
+                // Negative impls (`impl !Unpin for ..`) only take guarantees away
+                let is_unpin = i.trait_.as_ref().is_some_and(|(bang, path, _)| {
+                    bang.is_none() && path.segments.last().is_some_and(|s| s.ident == "Unpin")
+                });
+                if is_unpin {
+                    self.record(FindingKind::UnpinImpl, i.impl_token.span);
+                    self.record_tag(Tag::Pin);
+                    self.enclosing.pop();
+                }
+
                 // Recurse
                 visit::visit_item_impl(self, i);
 
@@ -217,34 +867,63 @@ impl<'ast> visit::Visit<'ast> for SiderophileSynVisitor {
             // I don't know what this case represents
             visit::visit_item_impl(self, i);
         }
+        self.item_tags = item_tags;
+        self.impl_trait = impl_trait;
+        self.cfgs.truncate(outer);
     }
 
     fn visit_item_trait(&mut self, i: &ItemTrait) {
         // Unsafe traits
         self.cur_mod_path.push_back(i.ident.to_string());
+        let outer = self.enter_cfgs(cfgs(&i.attrs));
+        if let Some(hook) = global_hook(&i.attrs) {
+            self.record_hook(FindingKind::GlobalHook, hook, i.trait_token.span);
+        }
+        if !self.in_fn && i.unsafety.is_some() && matches!(i.vis, Visibility::Public(_)) {
+            self.public_unsafe_traits
+                .push(fmt_mod_path(&self.cur_mod_path));
+        }
         visit::visit_item_trait(self, i);
+        self.cfgs.truncate(outer);
         self.cur_mod_path.pop_back();
     }
 
     fn visit_trait_item_method(&mut self, i: &TraitItemMethod) {
         // Unsafe default-implemented trait methods
         self.cur_mod_path.push_back(i.sig.ident.to_string());
+        let outer = self.enter_cfgs(cfgs(&i.attrs));
         visit::visit_trait_item_method(self, i);
+        self.cfgs.truncate(outer);
         self.cur_mod_path.pop_back();
     }
 
     fn visit_impl_item_method(&mut self, i: &ImplItemMethod) {
         self.cur_mod_path.push_back(i.sig.ident.to_string());
+        let outer = self.enter_cfgs(cfgs(&i.attrs));
+        let item_tags = mem::replace(
+            &mut self.item_tags,
+            method_tags(self.impl_trait.as_deref(), &i.sig.ident.to_string()),
+        );
+
+        // The methods of trait impls have no visibility of their own
+        if self.impl_trait.is_none() {
+            self.record_public_fn(&i.vis, &i.sig);
+        }
 
         // See if this method is unsafe
-        if i.sig.unsafety.is_some() {
-            let pp = fmt_mod_path(&self.cur_mod_path);
-            self.buf.push(pp);
+        let enclosing = self.enclosing.len();
+        if let Some(unsafety) = i.sig.unsafety {
+            self.record(FindingKind::Function, unsafety.span);
         }
 
         trace!("entering method {:?}", i.sig.ident);
+        let in_fn = mem::replace(&mut self.in_fn, true);
         visit::visit_impl_item_method(self, i);
+        self.in_fn = in_fn;
 
+        self.enclosing.truncate(enclosing);
+        self.item_tags = item_tags;
+        self.cfgs.truncate(outer);
         self.cur_mod_path.pop_back();
     }
 }
@@ -302,16 +981,48 @@ fn fmt_mod_path(mod_path: &VecDeque<String>) -> String {
     submods.as_slice().join("::")
 }
 
-/// Scan a single file for `unsafe` usage.
-#[allow(clippy::unwrap_used)]
+/// Scan a single file for `unsafe` usage. `contents` are the bytes of `file_to_scan`, whose path
+/// determines the module path of the items found.
+///
+/// # Errors
+///
+/// If `contents` are not valid UTF-8 or Rust
 pub fn find_unsafe_in_file(
     crate_name: &str,
     file_to_scan: &Path,
+    contents: &[u8],
     include_tests: bool,
 ) -> Result<UnsafeItems, ScanFileError> {
     use syn::visit::Visit;
     trace!("in crate {}", crate_name);
     trace!("in file {:?}", file_to_scan);
+    let full_prefix = module_path(crate_name, file_to_scan);
+
+    let src = std::str::from_utf8(contents)
+        .map_err(|e| ScanFileError::Utf8(e, file_to_scan.to_path_buf()))?;
+    let syntax =
+        syn::parse_file(src).map_err(|e| ScanFileError::Syn(e, file_to_scan.to_path_buf()))?;
+
+    let mut vis = SiderophileSynVisitor::new(full_prefix, include_tests);
+    vis.visit_file(&syntax);
+
+    Ok(UnsafeItems {
+        items: vis.buf,
+        no_std: is_no_std(&syntax),
+        forbids_unsafe_code: forbids_unsafe_code(&syntax),
+        path_mods: vis
+            .path_mods
+            .iter()
+            .map(|(inline_mods, path)| resolve_path_mod(file_to_scan, inline_mods, path))
+            .collect(),
+        public_fns: vis.public_fns,
+        public_unsafe_traits: vis.public_unsafe_traits,
+        private_mods: vis.private_mods,
+    })
+}
+
+/// The module path of the items of `file_to_scan`, e.g., `parking_lot_core::thread_parker::unix`
+pub fn module_path(crate_name: &str, file_to_scan: &Path) -> String {
     let src = std::ffi::OsString::from("src");
     let src_cpt = std::path::Component::Normal(&src);
 
@@ -321,7 +1032,7 @@ pub fn find_unsafe_in_file(
             .components()
             .rev()
             .take_while(|c| c != &src_cpt)
-            .map(|c| c.as_os_str().to_os_string().into_string().unwrap())
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
             .map(|c| c.replace('-', "_"))
             .filter(|c| c != "lib.rs" && c != "mod.rs")
             .map(|mut c| {
@@ -337,26 +1048,364 @@ pub fn find_unsafe_in_file(
         String::new()
     };
 
-    // This looks like `parking_lot_core::thread_parker::unix`
-    let full_prefix = if prefix_module_path.is_empty() {
+    if prefix_module_path.is_empty() {
         crate_name.to_string()
     } else {
         [crate_name, &prefix_module_path].join("::")
-    };
+    }
+}
 
-    let mut in_file =
-        File::open(file_to_scan).map_err(|e| ScanFileError::Io(e, file_to_scan.to_path_buf()))?;
-    let mut src = vec![];
-    in_file
-        .read_to_end(&mut src)
-        .map_err(|e| ScanFileError::Io(e, file_to_scan.to_path_buf()))?;
-    let src =
-        String::from_utf8(src).map_err(|e| ScanFileError::Utf8(e, file_to_scan.to_path_buf()))?;
-    let syntax =
-        syn::parse_file(&src).map_err(|e| ScanFileError::Syn(e, file_to_scan.to_path_buf()))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let mut vis = SiderophileSynVisitor::new(full_prefix, include_tests);
-    vis.visit_file(&syntax);
+    /// The items found in `src`, scanned as the `src/lib.rs` of `krate`
+    #[allow(clippy::unwrap_used)]
+    fn scan(src: &str) -> Vec<UnsafeItem> {
+        find_unsafe_in_file("krate", Path::new("src/lib.rs"), src.as_bytes(), false)
+            .unwrap()
+            .items
+    }
+
+    /// The kinds of the items found in `src`, with their paths
+    fn kinds(src: &str) -> Vec<(String, FindingKind)> {
+        scan(src)
+            .into_iter()
+            .map(|item| (item.path, item.kind))
+            .collect()
+    }
+
+    #[test]
+    fn unchecked_calls_go_to_the_innermost_unsafe_block() {
+        let items = scan(
+            "unsafe fn f(v: &[u8]) {
+                v.get_unchecked(0);
+                unsafe {
+                    std::str::from_utf8_unchecked(v);
+                    v.get_unchecked(1);
+                    v.get_unchecked(2);
+                }
+                v.get(3);
+            }",
+        );
+        let unchecked = items
+            .iter()
+            .map(|item| (item.kind, item.unchecked.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            unchecked,
+            [
+                (FindingKind::Function, vec!["get_unchecked".to_string()]),
+                (
+                    FindingKind::Block,
+                    vec![
+                        "std::str::from_utf8_unchecked".to_string(),
+                        "get_unchecked".to_string()
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unchecked_calls_outside_unsafe_code_are_not_recorded() {
+        let items = scan(
+            "fn f(v: &[u8]) {
+                unsafe { v.len() };
+                v.get_unchecked(0);
+            }",
+        );
+        assert_eq!(items.len(), 1);
+        assert!(items[0].unchecked.is_empty());
+    }
+
+    #[test]
+    fn pinning_is_tagged() {
+        let tags = scan(
+            "fn f(x: &mut Fut, v: &mut [u8], p: Pin<&mut Fut>) {
+                unsafe { Pin::new_unchecked(x) };
+                unsafe { p.get_unchecked_mut() };
+                unsafe { p.map_unchecked_mut(|x| x) };
+                unsafe { v.get_unchecked_mut(0) };
+            }",
+        )
+        .into_iter()
+        .map(|item| item.tags)
+        .collect::<Vec<_>>();
+        assert_eq!(
+            tags,
+            [vec![Tag::Pin], vec![Tag::Pin], vec![Tag::Pin], Vec::new()]
+        );
+    }
+
+    #[test]
+    fn unpin_impls_are_reported() {
+        let items = scan("impl Unpin for Fut {}");
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, "<krate::Fut as Unpin>");
+        assert_eq!(items[0].kind, FindingKind::UnpinImpl);
+        assert_eq!(items[0].tags, [Tag::Pin]);
+        assert!(kinds("impl !Unpin for Fut {}").is_empty());
+        assert!(kinds("impl Send for Fut {}").is_empty());
+    }
+
+    #[test]
+    fn unsafe_code_in_drop_is_tagged() {
+        let tags = scan(
+            "impl Drop for Buf {
+                fn drop(&mut self) {
+                    unsafe { free(self.ptr) };
+                    fn helper() {
+                        unsafe { free(PTR) };
+                    }
+                }
+            }
+            impl Buf {
+                fn drop(&mut self) {
+                    unsafe { free(self.ptr) };
+                }
+            }",
+        )
+        .into_iter()
+        .map(|item| (item.path, item.tags))
+        .collect::<Vec<_>>();
+        assert_eq!(
+            tags,
+            [
+                ("<krate::Buf as Drop>::drop".to_string(), vec![Tag::Drop]),
+                ("<krate::Buf as Drop>::drop::helper".to_string(), Vec::new()),
+                ("krate::Buf::drop".to_string(), Vec::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn global_allocators_are_reported() {
+        let items = scan(
+            "#[global_allocator]
+            static A: MyAlloc = MyAlloc;
+            static B: MyAlloc = MyAlloc;
+            unsafe impl GlobalAlloc for MyAlloc {
+                unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                    System.alloc(layout)
+                }
+            }
+            impl Allocator for MyAlloc {
+                unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                    System.alloc(layout)
+                }
+            }",
+        );
+        let found = items
+            .iter()
+            .map(|item| (item.path.as_str(), item.kind, item.tags.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (
+                    "krate::A",
+                    FindingKind::GlobalAllocator,
+                    vec![Tag::Allocator]
+                ),
+                (
+                    "<krate::MyAlloc as GlobalAlloc>::alloc",
+                    FindingKind::Function,
+                    vec![Tag::Allocator]
+                ),
+                (
+                    "<krate::MyAlloc as Allocator>::alloc",
+                    FindingKind::Function,
+                    Vec::new()
+                ),
+            ]
+        );
+        assert_eq!(items[0].hook.as_deref(), Some("global_allocator"));
+    }
+
+    #[test]
+    fn global_hooks_are_reported() {
+        let hooks = scan(
+            r#"#[panic_handler]
+            fn panic(info: &PanicInfo) -> ! {
+                loop {}
+            }
+            #[alloc_error_handler]
+            fn oom(layout: Layout) -> ! {
+                loop {}
+            }
+            #[lang = "eh_personality"]
+            extern "C" fn eh_personality() {}
+            #[inline]
+            fn f() {}
+            #[language = "eh_personality"]
+            fn g() {}"#,
+        )
+        .into_iter()
+        .map(|item| (item.path, item.kind, item.hook))
+        .collect::<Vec<_>>();
+        let hook = |path: &str, hook: &str| {
+            (
+                path.to_string(),
+                FindingKind::GlobalHook,
+                Some(hook.to_string()),
+            )
+        };
+        assert_eq!(
+            hooks,
+            [
+                hook("krate::panic", "panic_handler"),
+                hook("krate::oom", "alloc_error_handler"),
+                hook("krate::eh_personality", r#"lang = "eh_personality""#),
+            ]
+        );
+    }
+
+    #[test]
+    fn constructors_are_reported() {
+        let found = scan(
+            r#"#[ctor]
+            fn init() {}
+            #[ctor::dtor]
+            fn fini() {}
+            #[used]
+            #[link_section = ".init_array"]
+            static INIT: extern "C" fn() = init;
+            #[link_section = ".init_array.00099"]
+            static EARLY: extern "C" fn() = init;
+            #[link_section = ".CRT$XCU"]
+            static MSVC: extern "C" fn() = init;
+            #[link_section = ".ctorsx"]
+            static NOT_CTORS: u8 = 0;
+            #[link_section = ".data.rel"]
+            static DATA: u8 = 0;
+            #[used]
+            static USED: u8 = 0;
+            static PLAIN: u8 = 0;
+            #[no_mangle]
+            fn exported() {}"#,
+        )
+        .into_iter()
+        .map(|item| (item.path, item.kind, item.tags))
+        .collect::<Vec<_>>();
+        let constructor = |path: &str| {
+            (
+                format!("krate::{path}"),
+                FindingKind::Constructor,
+                vec![Tag::BeforeMain],
+            )
+        };
+        let link_section = |path: &str| {
+            (
+                format!("krate::{path}"),
+                FindingKind::LinkSection,
+                Vec::new(),
+            )
+        };
+        assert_eq!(
+            found,
+            [
+                constructor("init"),
+                constructor("fini"),
+                constructor("INIT"),
+                constructor("EARLY"),
+                constructor("MSVC"),
+                link_section("NOT_CTORS"),
+                link_section("DATA"),
+                link_section("USED"),
+            ]
+        );
+    }
 
-    Ok(UnsafeItems(vis.buf))
+    #[test]
+    fn cxx_bridges_are_reported() {
+        let found = scan(
+            r#"#[cxx::bridge]
+            mod ffi {
+                unsafe extern "C++" {
+                    fn new_client() -> UniquePtr<Client>;
+                }
+                extern "Rust" {
+                    fn callback(n: u32);
+                }
+            }
+            extern "Rust" {
+                fn not_bridged();
+            }
+            extern "C" {
+                fn abs(n: i32) -> i32;
+            }"#,
+        )
+        .into_iter()
+        .map(|item| (item.path, item.kind, item.line))
+        .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (
+                    "krate::ffi::new_client".to_string(),
+                    FindingKind::FfiBridge,
+                    4
+                ),
+                (
+                    "krate::ffi::callback".to_string(),
+                    FindingKind::FfiBridge,
+                    7
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unsafe_extern_blocks_are_reparsed() -> Result<(), Box<dyn Error>> {
+        let tokens = quote::quote! {
+            #[namespace = "client"]
+            unsafe extern "C++" {
+                fn connect();
+            }
+        };
+        let foreign_mod = parse_unsafe_foreign_mod(&tokens).ok_or("Not reparsed")?;
+        assert_eq!(foreign_mod.attrs.len(), 1);
+        assert_eq!(foreign_mod.items.len(), 1);
+        let tokens = quote::quote! { extern "C++" { fn connect(); } };
+        assert!(parse_unsafe_foreign_mod(&tokens).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn js_imports_are_reported() {
+        let found = kinds(
+            r#"#[wasm_bindgen]
+            extern "C" {
+                fn alert(s: &str);
+                #[wasm_bindgen(js_namespace = console)]
+                fn log(s: &str);
+            }
+            #[wasm_bindgen(module = "/js/greet.js")]
+            extern "C" {
+                fn greet();
+            }
+            extern "C" {
+                fn abs(n: i32) -> i32;
+            }
+            #[wasm_bindgen(module = "/js/greet.js")]
+            pub fn exported() {}
+            #[wasm_bindgen]
+            pub struct Counter;
+            #[wasm_bindgen]
+            impl Counter {
+                pub fn new() -> Self {
+                    Self
+                }
+            }"#,
+        );
+        assert_eq!(
+            found,
+            [
+                ("krate::alert".to_string(), FindingKind::JsImport),
+                ("krate::log".to_string(), FindingKind::JsImport),
+                ("krate::greet".to_string(), FindingKind::JsImport),
+            ]
+        );
+    }
 }
@@ -0,0 +1,83 @@
+//! What the builds of earlier scans found out about each unit, so that scanning the tree again
+//! under another scenario or for another target only rebuilds the units that differ, instead of
+//! rebuilding the whole tree every time
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use cargo::{
+    core::{
+        compiler::{CompileKind, CompileMode, Unit},
+        manifest::TargetKind,
+        profiles::Profile,
+        PackageId,
+    },
+    util::interning::InternedString,
+};
+
+/// Everything that tells one unit of a build from another, which, unlike `Unit` itself, can be
+/// compared across builds
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UnitKey {
+    id: PackageId,
+    target: String,
+    target_kind: TargetKind,
+    mode: CompileMode,
+    kind: CompileKind,
+    profile: Profile,
+    features: Vec<InternedString>,
+    dep_hash: u64,
+}
+
+impl UnitKey {
+    pub fn new(unit: &Unit) -> Self {
+        Self {
+            id: unit.pkg.package_id(),
+            target: unit.target.name().to_string(),
+            target_kind: unit.target.kind().clone(),
+            mode: unit.mode,
+            kind: unit.kind,
+            profile: unit.profile.clone(),
+            features: unit.features.clone(),
+            dep_hash: unit.dep_hash,
+        }
+    }
+
+    /// What the executor is told about the unit it runs rustc for, which is all it has to match
+    /// the invocation with the unit
+    pub fn invocation(&self) -> (PackageId, &str, CompileMode) {
+        (self.id, &self.target, self.mode)
+    }
+}
+
+/// The files rustc was passed and the directory it wrote to when it built a unit
+#[derive(Clone, Debug)]
+pub struct BuiltUnit {
+    pub rs_files: HashSet<PathBuf>,
+    pub out_dir: PathBuf,
+}
+
+/// Shared by the scans of one run. Cloning it shares the same units.
+#[derive(Clone, Debug, Default)]
+pub struct BuildCache {
+    units: Arc<Mutex<HashMap<UnitKey, BuiltUnit>>>,
+}
+
+impl BuildCache {
+    pub fn get(&self, key: &UnitKey) -> Option<BuiltUnit> {
+        self.units
+            .lock()
+            .ok()
+            .and_then(|units| units.get(key).cloned())
+    }
+
+    pub fn insert(&self, key: UnitKey, unit: BuiltUnit) {
+        // A poisoned cache only means the unit is rebuilt next time.
+        if let Ok(mut units) = self.units.lock() {
+            units.insert(key, unit);
+        }
+    }
+}
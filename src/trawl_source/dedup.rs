@@ -0,0 +1,125 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use sha2::{Digest, Sha256};
+
+use super::ast_walker::UnsafeItems;
+use crate::report::DuplicateSource;
+
+/// A source file that may be shared, byte for byte, by several versions of a crate
+#[derive(PartialEq, Eq, Hash)]
+struct SourceKey {
+    krate: Arc<str>,
+
+    /// Relative to the package root, since it determines the module path of the items found
+    relative: PathBuf,
+
+    hash: Vec<u8>,
+}
+
+/// Remembers what was found in each source file, so that a file that is identical in two versions
+/// of a crate is scanned only once
+#[derive(Default)]
+pub struct ScannedSources {
+    /// The version that was scanned, and what was found
+    scanned: HashMap<SourceKey, (String, UnsafeItems)>,
+
+    /// For each crate, the versions sharing files, and the number of files shared
+    duplicates: BTreeMap<Arc<str>, (BTreeSet<String>, usize)>,
+}
+
+impl ScannedSources {
+    /// The unsafe items of a file, calling `scan` only if an identical file was not already scanned
+    pub fn scan<E>(
+        &mut self,
+        krate: &Arc<str>,
+        version: &str,
+        relative: &Path,
+        contents: &[u8],
+        scan: impl FnOnce() -> Result<UnsafeItems, E>,
+    ) -> Result<UnsafeItems, E> {
+        let key = SourceKey {
+            krate: krate.clone(),
+            relative: relative.to_path_buf(),
+            hash: Sha256::digest(contents).to_vec(),
+        };
+        if let Some((scanned_version, items)) = self.scanned.get(&key) {
+            if scanned_version != version {
+                debug!(
+                    "`{}` is identical in versions {} and {} of `{}`",
+                    relative.display(),
+                    scanned_version,
+                    version,
+                    krate
+                );
+                let (versions, files) = self.duplicates.entry(krate.clone()).or_default();
+                versions.insert(scanned_version.clone());
+                versions.insert(version.to_string());
+                *files += 1;
+            }
+            return Ok(items.clone());
+        }
+        let items = scan()?;
+        self.scanned
+            .insert(key, (version.to_string(), items.clone()));
+        Ok(items)
+    }
+
+    pub fn into_duplicates(self) -> Vec<DuplicateSource> {
+        self.duplicates
+            .into_iter()
+            .map(|(krate, (versions, files))| DuplicateSource {
+                krate: krate.to_string(),
+                versions: versions.into_iter().collect(),
+                files,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    #[test]
+    fn identical_files_are_scanned_once() {
+        let krate = Arc::<str>::from("foo");
+        let relative = Path::new("src/lib.rs");
+        let mut sources = ScannedSources::default();
+        let mut scans = 0;
+        for (version, contents) in [
+            ("1.0.0", "a"),
+            ("1.0.0", "a"),
+            ("2.0.0", "a"),
+            ("3.0.0", "b"),
+        ] {
+            sources
+                .scan(&krate, version, relative, contents.as_bytes(), || {
+                    scans += 1;
+                    Ok::<_, Infallible>(UnsafeItems {
+                        items: Vec::new(),
+                        no_std: false,
+                        forbids_unsafe_code: false,
+                        path_mods: Vec::new(),
+                        public_fns: Vec::new(),
+                        public_unsafe_traits: Vec::new(),
+                        private_mods: Vec::new(),
+                    })
+                })
+                .unwrap();
+        }
+        assert_eq!(scans, 2);
+        assert_eq!(
+            sources.into_duplicates(),
+            vec![DuplicateSource {
+                krate: "foo".to_string(),
+                versions: vec!["1.0.0".to_string(), "2.0.0".to_string()],
+                files: 1,
+            }]
+        );
+    }
+}
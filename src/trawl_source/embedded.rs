@@ -0,0 +1,278 @@
+//! With `--scan-embedded`, Rust code that the build does not compile as it is in the package is
+//! scanned too: the code in the string literals of build scripts, which they write to `OUT_DIR` to
+//! be `include!`d, the `unsafe` of `#[cxx::bridge]` modules, which cxx expands into the actual
+//! bindings, and the `rust` code blocks of Markdown files. Bridging crates hide much of their
+//! unsafety in these. The findings are tagged `embedded`, and put in an `{{embedded}}` module so
+//! that their fingerprints do not collide with those of the items around them.
+
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{visit, ItemMod, LitStr};
+use walkdir::WalkDir;
+
+use super::ast_walker::{self, UnsafeItem};
+use crate::report::{FindingKind, Tag};
+
+/// The findings of the code embedded in the Rust source `contents` of `file`. The literals are only
+/// scanned if it is a build script.
+pub fn scan_rust(
+    crate_name: &str,
+    file: &Path,
+    contents: &[u8],
+    build_script: bool,
+    include_tests: bool,
+) -> Vec<UnsafeItem> {
+    // A file that does not parse is reported by the regular scan.
+    let syntax = match std::str::from_utf8(contents)
+        .ok()
+        .and_then(|src| syn::parse_file(src).ok())
+    {
+        Some(syntax) => syntax,
+        None => return Vec::new(),
+    };
+    let mut visitor = EmbeddedVisitor {
+        mod_path: vec![ast_walker::module_path(crate_name, file)],
+        build_script,
+        literals: Vec::new(),
+        bridges: Vec::new(),
+    };
+    visit::Visit::visit_file(&mut visitor, &syntax);
+    let mut items = embed(crate_name, visitor.bridges, 0);
+    for (code, line) in visitor.literals {
+        // Most literals are not code, and templates with `{}` placeholders do not parse.
+        if !code.contains("unsafe") {
+            continue;
+        }
+        if let Ok(found) =
+            ast_walker::find_unsafe_in_file(crate_name, file, code.as_bytes(), include_tests)
+        {
+            items.extend(embed(crate_name, found.items, line - 1));
+        }
+    }
+    items
+}
+
+/// The findings of the `rust` code blocks of the Markdown `contents` of `file`. Like rustdoc, a
+/// block that is not a whole file is scanned as the body of `main`.
+pub fn scan_markdown(
+    crate_name: &str,
+    file: &Path,
+    contents: &str,
+    include_tests: bool,
+) -> Vec<UnsafeItem> {
+    let mut items = Vec::new();
+    for (first_line, code) in rust_blocks(contents) {
+        if !code.contains("unsafe") {
+            continue;
+        }
+        let found =
+            ast_walker::find_unsafe_in_file(crate_name, file, code.as_bytes(), include_tests)
+                .map(|found| (found, first_line - 1))
+                .or_else(|_| {
+                    let wrapped = format!("fn main() {{\n{code}\n}}\n");
+                    ast_walker::find_unsafe_in_file(
+                        crate_name,
+                        file,
+                        wrapped.as_bytes(),
+                        include_tests,
+                    )
+                    .map(|found| (found, first_line - 2))
+                });
+        match found {
+            Ok((found, offset)) => items.extend(embed(crate_name, found.items, offset)),
+            Err(e) => debug!(
+                "Could not parse the code block at `{}:{}`: {}",
+                file.display(),
+                first_line,
+                e
+            ),
+        }
+    }
+    items
+}
+
+/// The Markdown files of the package in `root`, leaving out the build directories and hidden ones
+pub fn markdown_files(root: &Path) -> Vec<PathBuf> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0 || !(name.starts_with('.') || name == "target")
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| super::is_file_with_ext(entry, "md"))
+        .map(walkdir::DirEntry::into_path)
+        .collect()
+}
+
+struct EmbeddedVisitor {
+    mod_path: Vec<String>,
+    build_script: bool,
+
+    /// The values of the string literals, with the lines they start on
+    literals: Vec<(String, usize)>,
+
+    /// The `unsafe` of the `#[cxx::bridge]` modules
+    bridges: Vec<UnsafeItem>,
+}
+
+impl<'ast> visit::Visit<'ast> for EmbeddedVisitor {
+    fn visit_item_mod(&mut self, i: &'ast ItemMod) {
+        self.mod_path.push(i.ident.to_string());
+        if is_cxx_bridge(i) {
+            if let Some((_, content)) = &i.content {
+                let path = self.mod_path.join("::");
+                for item in content {
+                    bridge_unsafety(&path, item.to_token_stream(), &mut self.bridges);
+                }
+            }
+        }
+        visit::visit_item_mod(self, i);
+        self.mod_path.pop();
+    }
+
+    fn visit_lit_str(&mut self, i: &'ast LitStr) {
+        if self.build_script {
+            self.literals.push((i.value(), i.span().start().line));
+        }
+    }
+}
+
+/// Whether `i` is a `#[cxx::bridge]` module, whose `unsafe` cxx expands into the bindings
+fn is_cxx_bridge(i: &ItemMod) -> bool {
+    i.attrs.iter().any(|attr| {
+        let mut segments = attr.path.segments.iter();
+        matches!(
+            (segments.next(), segments.next(), segments.next()),
+            (Some(cxx), Some(bridge), None) if cxx.ident == "cxx" && bridge.ident == "bridge"
+        )
+    })
+}
+
+/// Records each `unsafe` in the tokens of a bridge module item. An `unsafe extern "C++"` block
+/// asserts that every function it declares is safe to call, and an `unsafe fn` is one that callers
+/// have to uphold the contract of.
+fn bridge_unsafety(path: &str, tokens: TokenStream, items: &mut Vec<UnsafeItem>) {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident) if ident == "unsafe" => {
+                let line = ident.span().start().line;
+                let (kind, path) = match tokens.peek() {
+                    Some(TokenTree::Ident(next)) if next == "fn" => {
+                        tokens.next();
+                        match tokens.peek() {
+                            Some(TokenTree::Ident(name)) => {
+                                (FindingKind::Function, format!("{path}::{name}"))
+                            }
+                            _ => (FindingKind::Function, path.to_string()),
+                        }
+                    }
+                    _ => (FindingKind::Block, path.to_string()),
+                };
+                items.push(UnsafeItem {
+                    path,
+                    kind,
+                    line,
+                    cfg: None,
+                    unchecked: Vec::new(),
+                    tags: Vec::new(),
+                    hook: None,
+                });
+            }
+            TokenTree::Group(group) => bridge_unsafety(path, group.stream(), items),
+            _ => {}
+        }
+    }
+}
+
+/// Moves the findings of embedded code into the `{{embedded}}` module of the crate, tags them and
+/// adds `offset` to their lines
+fn embed(crate_name: &str, items: Vec<UnsafeItem>, offset: usize) -> Vec<UnsafeItem> {
+    items
+        .into_iter()
+        .map(|mut item| {
+            item.path = match item.path.strip_prefix(crate_name) {
+                Some(rest) => format!("{crate_name}::{{{{embedded}}}}{rest}"),
+                None => format!("{{{{embedded}}}}::{}", item.path),
+            };
+            item.line += offset;
+            if !item.tags.contains(&Tag::Embedded) {
+                item.tags.push(Tag::Embedded);
+            }
+            item
+        })
+        .collect()
+}
+
+/// The code blocks of `markdown` whose info string starts with `rust`, e.g., `rust,ignore`, with
+/// the lines they start on. The lines that rustdoc hides are kept, since they are compiled.
+fn rust_blocks(markdown: &str) -> Vec<(usize, String)> {
+    let mut blocks = Vec::new();
+    // The fence of the open block, the line its code starts on and, if it is Rust, its code
+    let mut open: Option<(&str, usize, Option<Vec<&str>>)> = None;
+    for (index, line) in markdown.lines().enumerate() {
+        let trimmed = line.trim();
+        if let Some((fence, first_line, code)) = &mut open {
+            if trimmed.starts_with(*fence) && trimmed.chars().all(|c| fence.starts_with(c)) {
+                if let Some(code) = code.take() {
+                    blocks.push((*first_line, code.join("\n")));
+                }
+                open = None;
+            } else if let Some(code) = code {
+                code.push(unhidden(line));
+            }
+        } else if let Some(fence) = ["```", "~~~"]
+            .into_iter()
+            .find(|fence| trimmed.starts_with(fence))
+        {
+            let info = trimmed
+                .trim_start_matches(|c| fence.starts_with(c))
+                .trim_start();
+            let is_rust =
+                info.split(|c: char| c == ',' || c.is_whitespace()).next() == Some("rust");
+            open = Some((fence, index + 2, is_rust.then(Vec::new)));
+        }
+    }
+    blocks
+}
+
+/// A line of a code block, without the `# ` that makes rustdoc hide it
+fn unhidden(line: &str) -> &str {
+    match line.trim_start().strip_prefix('#') {
+        Some("") => "",
+        Some(rest) if rest.starts_with(' ') => &rest[1..],
+        _ => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rust_blocks;
+
+    #[test]
+    fn finds_rust_blocks() {
+        let markdown = "# Example\n\
+                        \n\
+                        ```rust,no_run\n\
+                        # use foo::Bar;\n\
+                        unsafe { Bar::new() };\n\
+                        ```\n\
+                        \n\
+                        ```toml\n\
+                        foo = \"1\"\n\
+                        ```\n\
+                        ~~~rust\n\
+                        let x = 1;\n\
+                        ~~~\n";
+        assert_eq!(
+            rust_blocks(markdown),
+            [
+                (4, "use foo::Bar;\nunsafe { Bar::new() };".to_string()),
+                (12, "let x = 1;".to_string())
+            ]
+        );
+    }
+}
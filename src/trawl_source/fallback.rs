@@ -0,0 +1,38 @@
+//! With `--offline-fallback`, a registry package that could not be downloaded is scanned from a
+//! copy of the same version that cargo extracted earlier, e.g. from another registry or before its
+//! `.crate` file was cleaned from the cache
+
+use std::{fs, path::PathBuf};
+
+use cargo::core::{Package, PackageId};
+
+/// A copy of the package `id` extracted in the `src` directory of any registry in the cargo
+/// cache, if there is one
+pub fn extracted(config: &cargo::Config, id: PackageId) -> Option<Package> {
+    let src = config
+        .home()
+        .as_path_unlocked()
+        .join("registry")
+        .join("src");
+    let mut index_dirs = fs::read_dir(&src)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect::<Vec<PathBuf>>();
+    // The same copy is found on every run
+    index_dirs.sort();
+    let dir_name = format!("{}-{}", id.name(), id.version());
+    index_dirs.into_iter().find_map(|index_dir| {
+        let manifest = index_dir.join(&dir_name).join("Cargo.toml");
+        if !manifest.is_file() {
+            return None;
+        }
+        match cargo::ops::read_package(&manifest, id.source_id(), config) {
+            Ok((pack, _)) if pack.package_id() == id => Some(pack),
+            Ok(_) => None,
+            Err(e) => {
+                debug!("Could not read `{}`: {:?}", manifest.display(), e);
+                None
+            }
+        }
+    })
+}
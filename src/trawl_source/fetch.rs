@@ -0,0 +1,115 @@
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context};
+use cargo::core::Package;
+use tempfile::{tempdir, TempDir};
+use walkdir::WalkDir;
+
+use crate::git::git;
+
+/// A clone of a package's repository at the tag of the package's version
+pub struct Checkout {
+    /// Deleted when the checkout is dropped
+    _tempdir: TempDir,
+
+    /// The package's directory within the clone
+    pub root: PathBuf,
+
+    /// `URL@TAG`
+    pub origin: String,
+}
+
+/// The source files of `pack`'s targets that the published package does not include, relative to
+/// the package root
+pub fn missing_targets(pack: &Package) -> Vec<PathBuf> {
+    let mut missing = pack
+        .targets()
+        .iter()
+        .filter_map(|target| target.src_path().path())
+        .filter(|path| !path.exists())
+        .map(|path| path.strip_prefix(pack.root()).unwrap_or(path).to_path_buf())
+        .collect::<Vec<_>>();
+    missing.sort();
+    missing.dedup();
+    missing
+}
+
+/// Clones the repository of `pack` at the tag of its version. Since there is no convention for
+/// tags, the common forms `vX.Y.Z`, `X.Y.Z`, `NAME-vX.Y.Z` and `NAME-X.Y.Z` are tried in turn.
+pub fn fetch(pack: &Package) -> anyhow::Result<Checkout> {
+    let name = pack.name();
+    let version = pack.version();
+    let url = pack
+        .manifest()
+        .metadata()
+        .repository
+        .as_deref()
+        .ok_or_else(|| anyhow!("`{}` does not declare a repository", name))?;
+
+    let tempdir = tempdir()?;
+    let clone = tempdir.path().join("clone");
+    let tags = [
+        format!("v{version}"),
+        version.to_string(),
+        format!("{name}-v{version}"),
+        format!("{name}-{version}"),
+    ];
+    let tag = tags
+        .iter()
+        .find(|tag| {
+            git(&[
+                OsStr::new("clone"),
+                OsStr::new("--quiet"),
+                OsStr::new("--depth=1"),
+                OsStr::new("--branch"),
+                OsStr::new(tag),
+                OsStr::new(url),
+                clone.as_os_str(),
+            ])
+            .is_ok()
+        })
+        .ok_or_else(|| anyhow!("Found none of the tags {:?} in `{}`", tags, url))?;
+
+    let root = find_package_dir(&clone, name.as_str())
+        .with_context(|| format!("`{url}` at `{tag}` does not contain `{name}`"))?;
+    Ok(Checkout {
+        _tempdir: tempdir,
+        root,
+        origin: format!("{url}@{tag}"),
+    })
+}
+
+/// The directory below `dir` with the manifest of the package `name`, which is not necessarily
+/// `dir` itself when the repository is a workspace
+fn find_package_dir(dir: &Path, name: &str) -> anyhow::Result<PathBuf> {
+    for entry in WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git" && entry.file_name() != "target")
+    {
+        let entry = entry?;
+        if entry.file_name() != "Cargo.toml" {
+            continue;
+        }
+        let manifest = fs::read_to_string(entry.path())?;
+        let manifest = match manifest.parse::<toml::Value>() {
+            Ok(manifest) => manifest,
+            Err(_) => continue,
+        };
+        let package_name = manifest
+            .get("package")
+            .and_then(|package| package.get("name"))
+            .and_then(toml::Value::as_str);
+        if package_name == Some(name) {
+            let dir = entry
+                .path()
+                .parent()
+                .ok_or_else(|| anyhow!("Manifest has no parent"))?;
+            return Ok(dir.canonicalize()?);
+        }
+    }
+    Err(anyhow!("No manifest for `{}`", name))
+}
@@ -0,0 +1,63 @@
+//! Walking a package for its source files and turning what is found in each into findings. Shared
+//! by the trawl and the library's `scan_dir`, so that both report the same findings.
+
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use walkdir::{DirEntry, WalkDir};
+
+use super::ast_walker::UnsafeItem;
+use crate::finding::{fingerprint, Finding, Provenance};
+
+pub fn is_file_with_ext(entry: &DirEntry, file_ext: &str) -> bool {
+    if !entry.file_type().is_file() {
+        return false;
+    }
+    let Some(ext) = entry.path().extension() else {
+        return false;
+    };
+    // to_string_lossy is ok since we only want to match against an ASCII
+    // compatible extension and we do not keep the possibly lossy result
+    // around.
+    ext.to_string_lossy() == file_ext
+}
+
+/// The `.rs` files under `dir`, along with the errors met walking it
+pub fn rs_files(dir: &Path) -> impl Iterator<Item = walkdir::Result<DirEntry>> {
+    WalkDir::new(dir).into_iter().filter(|entry| match entry {
+        Ok(entry) => is_file_with_ext(entry, "rs"),
+        Err(_) => true,
+    })
+}
+
+/// Turns the unsafe items of one file into findings
+pub fn to_findings(
+    krate: &Arc<str>,
+    provenance: &Arc<Provenance>,
+    file: &Arc<Path>,
+    generated: bool,
+    items: Vec<UnsafeItem>,
+) -> Vec<Finding> {
+    let mut ordinals = HashMap::new();
+    items
+        .into_iter()
+        .map(|item| {
+            let ordinal = ordinals.entry((item.path.clone(), item.kind)).or_insert(0);
+            let fingerprint = fingerprint(krate, &item.path, item.kind, *ordinal);
+            *ordinal += 1;
+            Finding {
+                krate: krate.clone(),
+                provenance: provenance.clone(),
+                file: file.clone(),
+                line: item.line,
+                item: item.path,
+                kind: item.kind,
+                fingerprint,
+                cfg: item.cfg,
+                unchecked: item.unchecked,
+                tags: item.tags,
+                hook: item.hook,
+                generated,
+            }
+        })
+        .collect()
+}
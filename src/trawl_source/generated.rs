@@ -0,0 +1,77 @@
+//! Heuristics for files generated by bindgen and cbindgen, whose many FFI shims would drown out
+//! the hand-written unsafe code of `-sys` crates
+
+use std::{
+    ffi::OsStr,
+    path::{Component, Path},
+};
+
+/// How much of the start of a file is searched for a generator's header comment
+const HEADER_LEN: usize = 1024;
+
+/// What bindgen and cbindgen write at the top of their output, lowercased
+const HEADERS: &[&str] = &[
+    "automatically generated by rust-bindgen",
+    "generated by cbindgen",
+    "generated with cbindgen",
+];
+
+/// Whether the file at `path` of the package `krate` looks generated: it starts with the header
+/// comment of a generator, it is in the `OUT_DIR` of a build script, or it is a `-sys` crate's
+/// file named like bindings
+pub fn is_generated(krate: &str, path: &Path, contents: &[u8]) -> bool {
+    has_header(contents) || in_out_dir(path) || (krate.ends_with("-sys") && is_bindings(path))
+}
+
+fn has_header(contents: &[u8]) -> bool {
+    let start = String::from_utf8_lossy(&contents[..contents.len().min(HEADER_LEN)]).to_lowercase();
+    HEADERS.iter().any(|header| start.contains(header))
+}
+
+/// Whether `path` is under `target/<profile>/build/<package>-<hash>/out`
+fn in_out_dir(path: &Path) -> bool {
+    let components = path.components().collect::<Vec<_>>();
+    components.windows(3).any(|window| {
+        window[0] == Component::Normal("build".as_ref())
+            && window[2] == Component::Normal("out".as_ref())
+    })
+}
+
+/// `bindings.rs`, `ffi_bindings.rs`, `bindings_x86_64.rs` and the like
+fn is_bindings(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(OsStr::to_str)
+        .is_some_and(|stem| {
+            stem == "bindings" || stem.ends_with("_bindings") || stem.starts_with("bindings_")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers() {
+        let bindgen =
+            b"/* automatically generated by rust-bindgen 0.69.1 */\n\npub const X: u32 = 1;\n";
+        assert!(is_generated("zstd", Path::new("src/ffi.rs"), bindgen));
+        assert!(!is_generated(
+            "zstd",
+            Path::new("src/ffi.rs"),
+            b"pub fn f() {}\n"
+        ));
+    }
+
+    #[test]
+    fn paths() {
+        let out = Path::new("/ws/target/debug/build/zstd-sys-0123abcd/out/bindings.rs");
+        assert!(is_generated("zstd", out, b""));
+        assert!(is_generated(
+            "zstd-sys",
+            Path::new("src/bindings_x86_64.rs"),
+            b""
+        ));
+        assert!(!is_generated("zstd", Path::new("src/bindings.rs"), b""));
+        assert!(!is_generated("zstd-sys", Path::new("src/build.rs"), b""));
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    collections::{HashMap, HashSet},
+    task::Poll,
+};
+
+use cargo::{
+    core::{source::QueryKind, Dependency, Package, PackageId, Source, SourceId},
+    util::CargoResult,
+    Config,
+};
+
+/// The newest version of each registry package according to the local copy of its registry's
+/// index. The index is not updated, so this is only as recent as the last `cargo update` or
+/// `cargo fetch`. Packages from other sources are left out.
+pub fn latest_versions(config: &Config, packs: &[&Package]) -> HashMap<PackageId, String> {
+    let mut by_source = HashMap::<SourceId, Vec<&Package>>::new();
+    for pack in packs {
+        let source_id = pack.package_id().source_id();
+        if source_id.is_registry() {
+            by_source.entry(source_id).or_default().push(pack);
+        }
+    }
+
+    let mut latest = HashMap::new();
+    if by_source.is_empty() {
+        return latest;
+    }
+    let _lock = match config.acquire_package_cache_lock() {
+        Ok(lock) => lock,
+        Err(e) => {
+            warn!("Could not lock the package cache: {:?}", e);
+            return latest;
+        }
+    };
+    for (source_id, packs) in by_source {
+        let mut source = match source_id.load(config, &HashSet::new()) {
+            Ok(source) => source,
+            Err(e) => {
+                warn!("Could not load the index of {}: {:?}", source_id, e);
+                continue;
+            }
+        };
+        for pack in packs {
+            match newest_version(&mut *source, pack) {
+                Ok(Some(version)) => {
+                    latest.insert(pack.package_id(), version);
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Could not look up `{}` in the index: {:?}", pack.name(), e),
+            }
+        }
+    }
+    latest
+}
+
+/// The checksum of the `.crate` file of `id` according to the local copy of its registry's index,
+/// or `None` if the index does not have it. The index is not updated.
+pub fn published_checksum(config: &Config, id: PackageId) -> CargoResult<Option<String>> {
+    let _lock = config.acquire_package_cache_lock()?;
+    let mut source = id.source_id().load(config, &HashSet::new())?;
+    let dep = Dependency::parse(
+        id.name(),
+        Some(&format!("={}", id.version())),
+        id.source_id(),
+    )?;
+    let summaries = loop {
+        match source.query_vec(&dep, QueryKind::Exact)? {
+            Poll::Ready(summaries) => break summaries,
+            Poll::Pending => source.block_until_ready()?,
+        }
+    };
+    Ok(summaries
+        .iter()
+        .find(|summary| summary.package_id() == id)
+        .and_then(|summary| summary.checksum())
+        .map(ToString::to_string))
+}
+
+/// The newest release of `pack`, or the newest pre-release if there are no releases
+fn newest_version(source: &mut dyn Source, pack: &Package) -> CargoResult<Option<String>> {
+    let dep = Dependency::parse(pack.name(), None, pack.package_id().source_id())?;
+    let summaries = loop {
+        match source.query_vec(&dep, QueryKind::Exact)? {
+            Poll::Ready(summaries) => break summaries,
+            Poll::Pending => source.block_until_ready()?,
+        }
+    };
+    let versions = summaries
+        .iter()
+        .map(|summary| summary.version())
+        .collect::<Vec<_>>();
+    let newest = versions
+        .iter()
+        .filter(|version| version.pre.is_empty())
+        .max()
+        .or_else(|| versions.iter().max());
+    Ok(newest.map(ToString::to_string))
+}
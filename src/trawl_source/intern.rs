@@ -0,0 +1,29 @@
+use std::{collections::HashSet, path::Path, sync::Arc};
+
+/// Hands out one shared copy of each crate name and file path, so that the many findings of a
+/// large tree do not each allocate their own
+#[derive(Default)]
+pub struct Interner {
+    strs: HashSet<Arc<str>>,
+    paths: HashSet<Arc<Path>>,
+}
+
+impl Interner {
+    pub fn str(&mut self, s: &str) -> Arc<str> {
+        if let Some(interned) = self.strs.get(s) {
+            return interned.clone();
+        }
+        let interned = Arc::<str>::from(s);
+        self.strs.insert(interned.clone());
+        interned
+    }
+
+    pub fn path(&mut self, path: &Path) -> Arc<Path> {
+        if let Some(interned) = self.paths.get(path) {
+            return interned.clone();
+        }
+        let interned = Arc::<Path>::from(path);
+        self.paths.insert(interned.clone());
+        interned
+    }
+}
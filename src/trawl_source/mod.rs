@@ -1,78 +1,216 @@
+mod archive;
 mod ast_walker;
+mod build_cache;
+mod dedup;
+mod embedded;
+mod fallback;
+mod fetch;
+mod file;
+mod generated;
+mod index;
+mod intern;
+mod scan_cache;
+mod sink;
+mod tamper;
+mod timing;
+mod vendor;
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     env::set_var,
     ffi::OsString,
-    io,
-    path::{Path, PathBuf},
+    fs, io,
+    num::NonZeroUsize,
+    path::{Component, Path, PathBuf},
     sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
 };
 
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
 use cargo::{
     core::{
-        compiler::{CompileMode, Executor, Unit},
+        compiler::{CompileKind, CompileMode, CompileTarget, Executor, RustcTargetData, Unit},
+        dependency::DepKind,
         manifest::TargetKind,
         package::PackageSet,
-        Package, PackageId, Target, Workspace,
+        resolver::features::{CliFeatures, ForceAllTargets, HasDevUnits},
+        Package, PackageId, PackageIdSpec, Resolve, Target, Workspace,
     },
-    ops::CompileOptions,
+    ops::{CompileFilter, CompileOptions, FilterRule, LibRule},
     util::CargoResult,
 };
 use cargo_util::{paths, ProcessBuilder};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use structopt::StructOpt;
 use walkdir::{self, WalkDir};
 
-#[derive(Debug)]
-pub enum RsResolveError {
-    Walkdir(walkdir::Error),
+pub use archive::{unpack, Unpacked};
+pub use ast_walker::ScanFileError;
+pub use build_cache::BuildCache;
+pub use index::published_checksum;
+pub use intern::Interner;
+pub use sink::{FindingSink, JsonLinesSink, SpillingSink};
+pub use tamper::cached_crate_file;
+pub use vendor::{trawl_archive, trawl_vendored};
 
-    /// Like io::Error but with the related path.
-    Io(io::Error, PathBuf),
+use build_cache::{BuiltUnit, UnitKey};
+use dedup::ScannedSources;
+use file::{is_file_with_ext, to_findings};
+use scan_cache::ScanCache;
+use timing::ScanTimings;
 
-    /// Would like cargo::Error here, but it's private, why?
-    /// This is still way better than a panic though.
-    Cargo(String),
+use crate::{
+    cancel::CancellationToken,
+    category, dependency,
+    error::{OnError, Recovery, ScanError},
+    filter::Filter,
+    ignore::{self, Ignore},
+    report::{
+        ApiFunction, ChecksumMismatch, Coverage, CrateSummary, DuplicateSource, FileCoverage,
+        Finding, Provenance, ScanIssue, SkipReason, SkippedCrate,
+    },
+    shard::Shard,
+    utils,
+};
 
-    /// This should not happen unless incorrect assumptions have been made in
-    /// `siderophile` about how the cargo API works.
-    ArcUnwrap(),
+#[derive(StructOpt, Debug)]
+pub struct TrawlOpts {
+    #[structopt(long = "include-tests")]
+    /// Count unsafe usage in tests.
+    pub include_tests: bool,
 
-    /// Failed to get the inner context out of the mutex.
-    InnerContextMutex(String),
+    #[structopt(long = "shard", value_name = "I/N")]
+    /// Only scan the I-th of N parts of the package set (see the `merge` subcommand)
+    pub shard: Option<Shard>,
 
-    /// Failed to parse a .dep file.
-    DepParse(String, PathBuf),
-}
+    #[structopt(long = "on-error", value_name = "POLICY", default_value = "skip")]
+    /// What to do when a package or file cannot be scanned: skip (and list it in the report) or
+    /// fail
+    pub on_error: OnError,
 
-impl Error for RsResolveError {}
+    #[structopt(long = "spill-threshold", value_name = "N")]
+    /// Keep at most N findings in memory while scanning; beyond that, sorted runs are spilled to
+    /// temporary files and merged at the end
+    pub spill_threshold: Option<usize>,
 
-/// Forward Display to Debug.
-impl fmt::Display for RsResolveError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(self, f)
-    }
-}
+    #[structopt(long = "slowest", value_name = "N")]
+    /// After scanning, list the N files and packages that took the longest to scan on stderr
+    pub slowest: Option<usize>,
 
-impl From<PoisonError<CustomExecutorInnerContext>> for RsResolveError {
-    fn from(e: PoisonError<CustomExecutorInnerContext>) -> Self {
-        Self::InnerContextMutex(e.to_string())
-    }
+    #[structopt(long = "download-retries", value_name = "N", default_value = "3")]
+    /// How many more times to try downloading the packages that failed to download
+    pub download_retries: u32,
+
+    #[structopt(
+        long = "download-backoff",
+        value_name = "MILLISECONDS",
+        default_value = "500"
+    )]
+    /// How long to wait before retrying failed downloads the first time. The wait doubles with
+    /// each retry.
+    pub download_backoff: u64,
+
+    #[structopt(long = "download-jobs", value_name = "N")]
+    /// Download at most N packages at once
+    pub download_jobs: Option<NonZeroUsize>,
+
+    #[structopt(long = "offline-fallback")]
+    /// Scan the packages that could not be downloaded from copies of the same versions extracted
+    /// earlier in the cargo cache, if there are any, rather than leaving them out
+    pub offline_fallback: bool,
+
+    #[structopt(long = "hermetic")]
+    /// Never use the network: fail before building, listing the packages that are not in the
+    /// local cargo cache or vendor directory
+    pub hermetic: bool,
+
+    #[structopt(long = "fetch-from-repository", conflicts_with = "hermetic")]
+    /// When a published package leaves out the source files of some of its targets, clone its
+    /// repository at the tag of its version and scan the files from there
+    pub fetch_from_repository: bool,
+
+    #[structopt(long = "trace-benches")]
+    /// Also build the benchmarks, and trace unsafety up to their functions, e.g., to see how much
+    /// unsafety the hot paths they measure reach
+    pub trace_benches: bool,
+
+    #[structopt(long = "no-optional-deps")]
+    /// Leave out the optional dependencies that the default features of the workspace members do
+    /// not enable, instead of resolving and scanning every feature, and list them as present but
+    /// inactive
+    pub no_optional_deps: bool,
+
+    #[structopt(long = "vendor-dir", value_name = "DIR", requires = "lockfile")]
+    /// Scan the packages vendored in DIR by `cargo vendor` instead of building the workspace,
+    /// attributing each directory to its package in the `--lockfile` and verifying its checksums
+    pub vendor_dir: Option<PathBuf>,
+
+    #[structopt(long = "lockfile", value_name = "PATH", requires = "vendor-dir")]
+    /// The `Cargo.lock` of the packages in the `--vendor-dir`
+    pub lockfile: Option<PathBuf>,
+
+    #[structopt(long = "timeout", value_name = "SECONDS")]
+    /// Give up on the scan after SECONDS, between two packages or files. A build that is under way
+    /// is finished first.
+    pub timeout: Option<u64>,
+
+    #[structopt(skip)]
+    /// Cancels the scan, e.g., once the `--timeout` has passed
+    pub cancel: CancellationToken,
+
+    #[structopt(skip)]
+    /// Write the findings of the workspace to stdout as JSON lines as soon as they are found, for
+    /// `--format jsonl`
+    pub stream_findings: bool,
+
+    #[structopt(skip)]
+    /// With `--filter`, the filter that the streamed findings are written through
+    pub stream_filter: Option<Filter>,
+
+    #[structopt(long = "scan-cache", value_name = "DIR")]
+    /// Keep what was found in each source file in DIR, so that later runs only scan the files
+    /// they have not seen. The entries are discarded when the detectors change.
+    pub scan_cache: Option<PathBuf>,
+
+    #[structopt(long = "scan-embedded")]
+    /// Also scan the Rust code in the string literals of build scripts, in `#[cxx::bridge]`
+    /// modules and in the `rust` code blocks of Markdown files
+    pub scan_embedded: bool,
 }
 
-fn is_file_with_ext(entry: &walkdir::DirEntry, file_ext: &str) -> bool {
-    if !entry.file_type().is_file() {
-        return false;
-    }
-    let p = entry.path();
-    let ext = match p.extension() {
-        Some(e) => e,
-        None => return false,
-    };
-    // to_string_lossy is ok since we only want to match against an ASCII
-    // compatible extension and we do not keep the possibly lossy result
-    // around.
-    ext.to_string_lossy() == file_ext
+/// What trawling the sources of the packages in the tree produced
+pub struct Trawl {
+    pub findings: Vec<Finding>,
+
+    /// One entry per package in the tree, whether or not it contains unsafety
+    pub crates: Vec<CrateSummary>,
+
+    /// What was skipped because of errors
+    pub issues: Vec<ScanIssue>,
+
+    pub duplicates: Vec<DuplicateSource>,
+
+    pub timings: ScanTimings,
+
+    /// The features enabled on each workspace member
+    pub features: BTreeMap<String, Vec<String>>,
+
+    /// With `--no-optional-deps`, the packages left out, as `name@version`
+    pub inactive: Vec<String>,
+
+    /// The public functions of the workspace members, with no badness yet
+    pub api: Vec<ApiFunction>,
+
+    /// How the sources of the registry packages scanned differ from what the lockfile pins
+    pub checksum_mismatches: Vec<ChecksumMismatch>,
+
+    /// How many of the files that the build compiled were scanned
+    pub file_coverage: Option<FileCoverage>,
+
+    /// The packages left out of the scan, in whole or in part, sorted
+    pub skipped: Vec<SkippedCrate>,
 }
 
 // TODO: Make a wrapper type for canonical paths and hide all mutable access.
@@ -105,25 +243,18 @@ impl RsFile {
     }
 }
 
-#[allow(clippy::expect_used)]
-pub fn find_rs_files_in_dir(dir: &Path) -> impl Iterator<Item = PathBuf> {
-    let walker = WalkDir::new(dir).into_iter();
-    walker.filter_map(|entry| {
-        let entry = entry.expect("walkdir error."); // TODO: Return result.
-        if !is_file_with_ext(&entry, "rs") {
-            return None;
-        }
-        Some(
-            entry
-                .path()
-                .canonicalize()
-                .expect("Error converting to canonical path"),
-        ) // TODO: Return result.
+pub fn find_rs_files_in_dir(dir: &Path) -> impl Iterator<Item = Result<PathBuf, ScanError>> {
+    file::rs_files(dir).map(|entry| {
+        let entry = entry.map_err(ScanError::Walkdir)?;
+        entry
+            .path()
+            .canonicalize()
+            .map_err(|e| ScanError::Io(e, entry.path().to_path_buf()))
     })
 }
 
-#[allow(clippy::expect_used, clippy::unwrap_used)]
-fn find_rs_files_in_package(pack: &Package) -> Vec<RsFile> {
+fn find_rs_files_in_package(pack: &Package) -> Vec<Result<RsFile, ScanError>> {
+    let mut out = Vec::new();
     // Find all build target entry point source files.
     let mut canon_targets = HashMap::new();
     for t in pack.targets() {
@@ -133,24 +264,30 @@ fn find_rs_files_in_package(pack: &Package) -> Vec<RsFile> {
         };
         if !path.exists() {
             // A package published to crates.io is not required to include
-            // everything. We have to skip this build target.
+            // everything. We have to skip this build target. It is recorded in
+            // the package's coverage (see `fetch::missing_targets`).
             continue;
         }
-        let canon = path
-            .canonicalize() // will Err on non-existing paths.
-            .expect("canonicalize for build target path failed."); // FIXME
+        let canon = match path.canonicalize() {
+            Ok(canon) => canon,
+            Err(e) => {
+                out.push(Err(ScanError::Io(e, path.to_path_buf())));
+                continue;
+            }
+        };
         let targets = canon_targets.entry(canon).or_insert_with(Vec::new);
         targets.push(t);
     }
-    let mut out = Vec::new();
     for p in find_rs_files_in_dir(pack.root()) {
-        if !canon_targets.contains_key(&p) {
-            out.push(RsFile::Other(p));
+        match p {
+            Ok(p) if canon_targets.contains_key(&p) => {}
+            Ok(p) => out.push(Ok(RsFile::Other(p))),
+            Err(e) => out.push(Err(e)),
         }
     }
     for (k, v) in canon_targets {
         for target in v {
-            out.push(into_rs_code_file(target.kind(), k.clone()));
+            out.push(Ok(into_rs_code_file(target.kind(), k.clone())));
         }
     }
     out
@@ -168,111 +305,749 @@ const fn into_rs_code_file(kind: &TargetKind, path: PathBuf) -> RsFile {
     }
 }
 
+/// The root of `pack`, canonicalized like the paths of its `RsFile`s
+fn canonical_root(pack: &Package) -> PathBuf {
+    pack.root()
+        .canonicalize()
+        .unwrap_or_else(|_| pack.root().to_path_buf())
+}
+
+/// Keeps one of the packages with the same root, preferring a workspace member. A member can be
+/// reached as a path dependency of another member under a different source id, e.g. through a
+/// symlink, and its files would otherwise be scanned and summarized twice.
+fn dedup_by_root<'a>(ws: &Workspace, packs: Vec<&'a Package>) -> Vec<&'a Package> {
+    let mut kept = Vec::<&Package>::new();
+    let mut positions = HashMap::new();
+    for pack in packs {
+        match positions.get(&canonical_root(pack)) {
+            Some(&position) => {
+                let other = kept[position];
+                debug!(
+                    "`{}` and `{}` are the same package",
+                    other.package_id(),
+                    pack.package_id()
+                );
+                if ws.is_member(pack) && !ws.is_member(other) {
+                    kept[position] = pack;
+                }
+            }
+            None => {
+                positions.insert(canonical_root(pack), kept.len());
+                kept.push(pack);
+            }
+        }
+    }
+    kept
+}
+
+/// `path` relative to `base`, going up with `..` if it is outside of it, like the files of
+/// `#[path = "../shared/foo.rs"]` modules
+fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    base.components()
+        .skip(common)
+        .map(|_| Component::ParentDir)
+        .chain(path.components().skip(common))
+        .collect()
+}
+
 fn find_rs_files_in_packages<'a>(
-    packs: &'a [&Package],
-) -> impl Iterator<Item = (PackageId, RsFile)> + 'a {
+    packs: &'a [&'a Package],
+) -> impl Iterator<Item = (&'a Package, Result<RsFile, ScanError>)> + 'a {
     packs.iter().flat_map(|pack| {
         find_rs_files_in_package(pack)
             .into_iter()
-            .map(move |path| (pack.package_id(), path))
+            .map(move |path| (*pack, path))
     })
 }
 
-/// This is mostly `PackageSet::get_many`. The only difference is that we don't panic when
-/// downloads fail
-#[allow(clippy::unwrap_used)]
+/// The files of `checkout` that `pack` does not include, along with where they would be in `pack`
+fn find_rs_files_in_checkout(
+    pack: &Package,
+    checkout: &fetch::Checkout,
+    missing_targets: &[PathBuf],
+) -> Vec<Result<(RsFile, PathBuf), ScanError>> {
+    let mut out = Vec::new();
+    for path in find_rs_files_in_dir(&checkout.root) {
+        let path = match path {
+            Ok(path) => path,
+            Err(e) => {
+                out.push(Err(e));
+                continue;
+            }
+        };
+        let relative = path.strip_prefix(&checkout.root).unwrap_or(&path);
+        let reported = pack.root().join(relative);
+        if reported.exists() {
+            continue;
+        }
+        let target = missing_targets
+            .iter()
+            .any(|missing| missing == relative)
+            .then(|| {
+                pack.targets()
+                    .iter()
+                    .find(|target| target.src_path().path() == Some(reported.as_path()))
+            })
+            .flatten();
+        let rs_file = match target {
+            Some(target) => into_rs_code_file(target.kind(), path),
+            None => RsFile::Other(path),
+        };
+        out.push(Ok((rs_file, reported)));
+    }
+    out
+}
+
+/// This is mostly `PackageSet::get_many`. The differences are that at most `--download-jobs`
+/// packages are downloaded at once, that failed downloads are retried with a backoff, and that the
+/// packages that still fail are returned with their errors rather than failing the whole set
 fn get_many<'a>(
     packs: &'a PackageSet,
     ids: impl IntoIterator<Item = PackageId>,
-) -> Vec<&'a Package> {
+    opts: &TrawlOpts,
+) -> Result<(Vec<&'a Package>, BTreeMap<PackageId, String>), ScanError> {
+    let mut pkgs = Vec::new();
+    let mut pending = ids.into_iter().collect::<Vec<_>>();
+    let mut failed = BTreeMap::new();
+    for attempt in 0..=opts.download_retries {
+        if attempt > 0 {
+            if failed.is_empty() {
+                break;
+            }
+            // The wait doubles with each retry
+            let delay = Duration::from_millis(
+                opts.download_backoff
+                    .saturating_mul(1 << (attempt - 1).min(16)),
+            );
+            debug!("Retrying {} failed downloads in {:?}", failed.len(), delay);
+            thread::sleep(delay);
+            opts.cancel.check()?;
+            pending = std::mem::take(&mut failed).into_keys().collect();
+        }
+        let downloaded = download(packs, &pending, opts.download_jobs, &mut failed)?;
+        pkgs.extend(downloaded);
+    }
+    Ok((pkgs, failed))
+}
+
+/// One attempt at downloading `ids`, at most `jobs` at once. The packages that could not be
+/// downloaded are added to `failed` with their error.
+fn download<'a>(
+    packs: &'a PackageSet,
+    ids: &[PackageId],
+    jobs: Option<NonZeroUsize>,
+    failed: &mut BTreeMap<PackageId, String>,
+) -> Result<Vec<&'a Package>, ScanError> {
+    let jobs = jobs.map_or(usize::MAX, NonZeroUsize::get);
     let mut pkgs = Vec::new();
-    let mut downloads = packs.enable_download().unwrap();
-    for id in ids {
+    // cargo does not say which package a failed download was of
+    let mut wait_error = None;
+    let mut downloads = packs
+        .enable_download()
+        .map_err(|e| ScanError::Download(e.to_string()))?;
+    for &id in ids {
+        while downloads.remaining() >= jobs {
+            match downloads.wait() {
+                Ok(pkg) => pkgs.push(pkg),
+                Err(e) => wait_error = Some(format!("Failed to download package, {e:?}")),
+            }
+        }
         match downloads.start(id) {
             // This might not return `Some` right away. It's still downloading.
             Ok(pkg_opt) => pkgs.extend(pkg_opt),
-            Err(e) => warn!("Could not begin downloading {:?}, {:?}", id, e),
+            Err(e) => {
+                failed.insert(id, format!("Could not begin downloading {id:?}, {e:?}"));
+            }
         }
     }
     while downloads.remaining() > 0 {
         // Packages whose `.start()` returned an `Ok(None)` earlier will return now
         match downloads.wait() {
             Ok(pkg) => pkgs.push(pkg),
-            Err(e) => warn!("Failed to download package, {:?}", e),
+            Err(e) => wait_error = Some(format!("Failed to download package, {e:?}")),
         }
     }
-    pkgs
+    if let Some(error) = wait_error {
+        let downloaded = pkgs
+            .iter()
+            .map(|pkg| pkg.package_id())
+            .collect::<HashSet<_>>();
+        for &id in ids {
+            if !downloaded.contains(&id) {
+                failed.entry(id).or_insert_with(|| error.clone());
+            }
+        }
+    }
+    Ok(pkgs)
+}
+
+/// Makes cargo refuse to use the network, as with `cargo --offline`
+pub fn configure_offline(config: &mut cargo::Config) -> CargoResult<()> {
+    config.configure(0, false, None, false, false, true, &None, &[], &[])
+}
+
+/// Fails if any package would have to be downloaded. `packs` must come from an offline config, in
+/// which case starting the download of a package that is not available locally fails at once.
+fn check_hermetic(packs: &PackageSet) -> anyhow::Result<()> {
+    let mut downloads = packs.enable_download()?;
+    let mut missing = packs
+        .package_ids()
+        .filter(|id| downloads.start(*id).is_err())
+        .map(|id| format!("{} {}", id.name(), id.version()))
+        .collect::<Vec<_>>();
+    drop(downloads);
+    if missing.is_empty() {
+        return Ok(());
+    }
+    missing.sort();
+    bail!(
+        "`--hermetic` was given, but these packages are not available locally:\n    {}",
+        missing.join("\n    ")
+    );
+}
+
+fn checksum(resolve: &Resolve, id: PackageId) -> Option<String> {
+    resolve.checksums().get(&id).cloned().flatten()
+}
+
+fn provenance(resolve: &Resolve, id: PackageId) -> Provenance {
+    Provenance {
+        version: id.version().to_string(),
+        source: id.source_id().as_url().to_string(),
+        checksum: checksum(resolve, id),
+    }
 }
 
-/// Finds and outputs all unsafe things to the given file
-#[allow(clippy::panic)]
+/// Finds all unsafe things in the given packages. Findings are pushed to `sink` as each file is
+/// scanned, so that the syntax tree of only one file is alive at a time. The returned `Trawl`'s
+/// `findings` are left empty.
 pub fn find_unsafe_in_packages(
+    ws: &Workspace,
     packs: &PackageSet,
+    resolve: &Resolve,
     mut rs_files_used: HashMap<PathBuf, u32>,
-    allow_partial_results: bool,
-    include_tests: bool,
-) -> (HashMap<PathBuf, u32>, Vec<String>) {
-    let packs = get_many(packs, packs.package_ids());
-    let pack_code_files = find_rs_files_in_packages(&packs);
-    let mut tainted_things = vec![];
-    for (pack_id, rs_code_file) in pack_code_files {
+    build_failed: Option<&(String, String)>,
+    recovery: &mut Recovery,
+    ignore: &Ignore,
+    opts: &TrawlOpts,
+    sink: &mut dyn FindingSink,
+) -> Result<(HashMap<PathBuf, u32>, Trawl), ScanError> {
+    let (mut packs, failed) = get_many(packs, packs.package_ids(), opts)?;
+    // Packages that could not be downloaded, scanned from copies extracted earlier
+    let mut fallbacks = Vec::new();
+    for (id, error) in failed {
+        let fallback = if opts.offline_fallback {
+            fallback::extracted(ws.config(), id)
+        } else {
+            None
+        };
+        match fallback {
+            Some(pack) => {
+                warn!(
+                    "`{}` could not be downloaded, scanning the copy in `{}`: {}",
+                    id,
+                    pack.root().display(),
+                    error
+                );
+                fallbacks.push(pack);
+            }
+            None => recovery.skip(
+                id.name().to_string(),
+                id.version().to_string(),
+                SkipReason::DownloadFailed,
+                ScanError::Download(format!(
+                    "{} (tried {} times)",
+                    error,
+                    opts.download_retries + 1
+                )),
+            )?,
+        }
+    }
+    packs.extend(&fallbacks);
+    packs.sort_by_key(|pack| pack.package_id());
+    let packs = dedup_by_root(ws, packs);
+    // Every package is summarized, but with a shard only some of them are scanned.
+    let scanned = packs
+        .iter()
+        .enumerate()
+        .filter(|(position, _)| opts.shard.map_or(true, |shard| shard.contains(*position)))
+        .map(|(_, pack)| *pack)
+        .collect::<Vec<_>>();
+
+    // Files of targets that published packages leave out, possibly fetched from repositories. The
+    // checkouts are deleted when dropped, so they are kept until the end of the scan.
+    let mut coverage = HashMap::new();
+    let mut checkouts = Vec::new();
+    let mut fetched = HashMap::new();
+    let mut fetched_files = Vec::new();
+    for pack in &scanned {
+        let missing_targets = fetch::missing_targets(pack);
+        if missing_targets.is_empty() {
+            continue;
+        }
+        let mut fetched_from = None;
+        if opts.fetch_from_repository {
+            match fetch::fetch(pack) {
+                Ok(checkout) => {
+                    for file in find_rs_files_in_checkout(pack, &checkout, &missing_targets) {
+                        let rs_file = file.map(|(rs_file, reported)| {
+                            fetched.insert(rs_file.as_path_buf().clone(), reported);
+                            rs_file
+                        });
+                        fetched_files.push((*pack, rs_file));
+                    }
+                    fetched_from = Some(checkout.origin.clone());
+                    checkouts.push(checkout);
+                }
+                Err(e) => recovery.recover(
+                    Some(pack.package_id()),
+                    ScanError::Download(format!("Could not fetch the missing files, {e:?}")),
+                )?,
+            }
+        }
+        coverage.insert(
+            pack.package_id(),
+            Coverage {
+                missing_targets,
+                fetched_from,
+                ..Coverage::default()
+            },
+        );
+    }
+
+    let mut checksum_mismatches = Vec::new();
+    for pack in &scanned {
+        let pack_id = pack.package_id();
+        if !pack_id.source_id().is_registry() {
+            continue;
+        }
+        match tamper::verify(pack, checksum(resolve, pack_id).as_deref()) {
+            Ok(mismatches) => checksum_mismatches.extend(mismatches),
+            Err(e) => recovery.recover(Some(pack_id), e)?,
+        }
+    }
+    for mismatch in &checksum_mismatches {
+        warn!(
+            "`{}` {} does not match its checksum: {}",
+            mismatch.krate, mismatch.version, mismatch.detail
+        );
+    }
+
+    for pack in fallbacks.iter().filter(|pack| scanned.contains(pack)) {
+        coverage.entry(pack.package_id()).or_default().cached_copy =
+            Some(pack.root().to_path_buf());
+    }
+
+    for pack in &scanned {
+        let is_failed = build_failed.is_some_and(|(name, version)| {
+            pack.name().as_str() == name && pack.version().to_string() == *version
+        });
+        if is_failed {
+            coverage.entry(pack.package_id()).or_default().build_failed = true;
+        }
+    }
+
+    // Files of `#[path]` modules outside of their package's root are added as they are found.
+    let scan_cache = opts
+        .scan_cache
+        .as_deref()
+        .and_then(|dir| match ScanCache::open(dir) {
+            Ok(scan_cache) => Some(scan_cache),
+            Err(e) => {
+                warn!(
+                    "Could not open the scan cache `{}`, scanning every file: {}",
+                    dir.display(),
+                    e
+                );
+                None
+            }
+        });
+    let mut pack_code_files = find_rs_files_in_packages(&scanned)
+        .chain(fetched_files)
+        .collect::<VecDeque<_>>();
+    let mut outside_root = HashSet::new();
+    let mut roots = HashMap::new();
+    let mut interner = Interner::default();
+    let mut sources = ScannedSources::default();
+    let mut timings = ScanTimings::default();
+    let mut no_std = HashSet::new();
+    let mut provenances = HashMap::new();
+    let mut public_fns = HashMap::<_, Vec<_>>::new();
+    let mut private_mods = HashMap::<_, HashSet<_>>::new();
+    let mut unsafe_traits = HashMap::<_, Vec<_>>::new();
+    while let Some((pack, rs_code_file)) = pack_code_files.pop_front() {
+        opts.cancel.check()?;
+        let pack_id = pack.package_id();
+        let rs_code_file = match rs_code_file {
+            Ok(rs_code_file) => rs_code_file,
+            Err(e) => {
+                recovery.recover(Some(pack_id), e)?;
+                continue;
+            }
+        };
         let p = rs_code_file.as_path_buf();
+        // Where a file fetched from a repository would be in the package
+        let reported = fetched.get(p).unwrap_or(p);
+        let _span = debug_span!("scan", package = %pack_id, file = %p.display()).entered();
 
         // This .rs file path was found by intercepting rustc arguments or by parsing the .d files
         // produced by rustc. Here we increase the counter for this path to mark that this file has
         // been scanned. Warnings will be printed for .rs files in this collection with a count of
         // 0 (has not been scanned). If this happens, it could indicate a logic error or some
         // incorrect assumption in siderophile.
-        if let Some(c) = rs_files_used.get_mut(p) {
+        if let Some(c) = rs_files_used.get_mut(&utils::normalize_case(p)) {
             *c += 1;
         }
+        // Ignored files are counted as scanned above, so that they are not warned about. Only
+        // local packages can be ignored, even if vendored dependencies are in the workspace.
+        if pack_id.source_id().is_path() && ignore.is_ignored(p) {
+            debug!("`{}` is ignored", p.display());
+            continue;
+        }
 
+        let start = Instant::now();
+        let contents = match fs::read(p) {
+            Ok(contents) => contents,
+            Err(e) => {
+                let e = ScanFileError::Io(e, p.clone());
+                recovery.recover(Some(pack_id), ScanError::Scan(e))?;
+                continue;
+            }
+        };
+        let krate = interner.str(pack_id.name().as_str());
+        let root = roots
+            .entry(pack_id)
+            .or_insert_with(|| canonical_root(pack))
+            .clone();
+        let relative = match reported.strip_prefix(pack.root()) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => relative_path(reported, &root),
+        };
+        let version = pack_id.version().to_string();
         let crate_name = pack_id.name().as_str().replace('-', "_");
-        match ast_walker::find_unsafe_in_file(&crate_name, p, include_tests) {
-            Ok(ast_walker::UnsafeItems(items)) => {
-                // Output unsafe items as we go
-                tainted_things.extend(items);
+        let items = sources.scan(&krate, &version, &relative, &contents, || {
+            let scan =
+                || ast_walker::find_unsafe_in_file(&crate_name, p, &contents, opts.include_tests);
+            match &scan_cache {
+                Some(scan_cache) => {
+                    scan_cache.scan(&crate_name, p, &contents, opts.include_tests, scan)
+                }
+                None => scan(),
             }
-            Err(e) => {
-                if allow_partial_results {
-                    warn!(
-                        "Failed to parse file: {}, {:?}. Continuing...",
-                        p.display(),
-                        e
-                    );
-                } else {
-                    panic!("Failed to parse file: {}, {:?} ", p.display(), e);
+        });
+        match items {
+            Ok(items) => {
+                if items.no_std && matches!(rs_code_file, RsFile::LibRoot(_)) {
+                    no_std.insert(pack_id);
+                }
+                for path_mod in &items.path_mods {
+                    let dir = p.parent().unwrap_or(p);
+                    let path_mod = match dir.join(path_mod).canonicalize() {
+                        Ok(path_mod) => path_mod,
+                        // Left for rustc to complain about
+                        Err(_) => continue,
+                    };
+                    if !path_mod.starts_with(&root)
+                        && outside_root.insert((pack_id, path_mod.clone()))
+                    {
+                        debug!(
+                            "`{}` of `{}` is outside of its root",
+                            path_mod.display(),
+                            pack_id
+                        );
+                        pack_code_files.push_back((pack, Ok(RsFile::Other(path_mod))));
+                    }
+                }
+                let file = interner.path(reported);
+                // Only the `unsafe fn`s of dependencies are kept
+                let is_member = ws.is_member(pack);
+                public_fns.entry(pack_id).or_default().extend(
+                    items
+                        .public_fns
+                        .into_iter()
+                        .filter(|public_fn| is_member || public_fn.unsafe_fn)
+                        .map(|public_fn| (public_fn, file.clone())),
+                );
+                unsafe_traits
+                    .entry(pack_id)
+                    .or_default()
+                    .extend(items.public_unsafe_traits);
+                private_mods
+                    .entry(pack_id)
+                    .or_default()
+                    .extend(items.private_mods);
+                let provenance = provenances
+                    .entry(pack_id)
+                    .or_insert_with(|| Arc::new(provenance(resolve, pack_id)));
+                let generated = generated::is_generated(&krate, p, &contents);
+                let mut found = items.items;
+                if opts.scan_embedded {
+                    found.extend(embedded::scan_rust(
+                        &crate_name,
+                        p,
+                        &contents,
+                        matches!(rs_code_file, RsFile::CustomBuildRoot(_)),
+                        opts.include_tests,
+                    ));
+                }
+                for finding in to_findings(&krate, provenance, &file, generated, found) {
+                    sink.push(finding)?;
+                }
+            }
+            Err(e) => recovery.recover(Some(pack_id), ScanError::Scan(e))?,
+        }
+        timings.record(&format!("{krate} {version}"), reported, start.elapsed());
+    }
+
+    // Markdown files are not compiled, so they are not among the files of the packages.
+    if opts.scan_embedded {
+        for pack in &scanned {
+            let pack_id = pack.package_id();
+            let krate = interner.str(pack_id.name().as_str());
+            let crate_name = pack_id.name().as_str().replace('-', "_");
+            for md_file in embedded::markdown_files(pack.root()) {
+                opts.cancel.check()?;
+                if pack_id.source_id().is_path() && ignore.is_ignored(&md_file) {
+                    continue;
+                }
+                let contents = match fs::read_to_string(&md_file) {
+                    Ok(contents) => contents,
+                    Err(e) => {
+                        debug!("Could not read `{}`: {}", md_file.display(), e);
+                        continue;
+                    }
+                };
+                let items =
+                    embedded::scan_markdown(&crate_name, &md_file, &contents, opts.include_tests);
+                if items.is_empty() {
+                    continue;
+                }
+                let provenance = provenances
+                    .entry(pack_id)
+                    .or_insert_with(|| Arc::new(provenance(resolve, pack_id)));
+                let file = interner.path(&md_file);
+                for finding in to_findings(&krate, provenance, &file, false, items) {
+                    sink.push(finding)?;
                 }
             }
         }
     }
 
-    (rs_files_used, tainted_things)
+    let mut api = Vec::new();
+    let mut public_unsafe_fns = HashMap::new();
+    let mut public_unsafe_traits = HashMap::new();
+    for (pack_id, public_fns) in public_fns {
+        let private_mods = private_mods.remove(&pack_id).unwrap_or_default();
+        let mut traits = unsafe_traits
+            .remove(&pack_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|path| !in_private_mod(path, &private_mods))
+            .collect::<Vec<_>>();
+        traits.sort();
+        traits.dedup();
+        public_unsafe_traits.insert(pack_id, traits);
+        let public_fns = public_fns
+            .into_iter()
+            .filter(|(public_fn, _)| !in_private_mod(&public_fn.path, &private_mods))
+            .map(|(public_fn, file)| ApiFunction {
+                function: public_fn.path,
+                krate: pack_id.name().to_string(),
+                file: file.to_path_buf(),
+                line: public_fn.line,
+                unsafe_fn: public_fn.unsafe_fn,
+                badness: 0,
+            })
+            .collect::<Vec<_>>();
+        let mut unsafe_fns = public_fns
+            .iter()
+            .filter(|function| function.unsafe_fn)
+            .map(|function| function.function.clone())
+            .collect::<Vec<_>>();
+        unsafe_fns.sort();
+        unsafe_fns.dedup();
+        public_unsafe_fns.insert(pack_id, unsafe_fns);
+        if packs
+            .iter()
+            .any(|pack| pack.package_id() == pack_id && ws.is_member(pack))
+        {
+            api.extend(public_fns);
+        }
+    }
+
+    let latest_versions = index::latest_versions(ws.config(), &packs);
+    let crates = packs
+        .iter()
+        .map(|pack| CrateSummary {
+            name: pack.name().to_string(),
+            version: pack.version().to_string(),
+            source: pack.package_id().source_id().as_url().to_string(),
+            checksum: checksum(resolve, pack.package_id()),
+            workspace_member: ws.is_member(pack),
+            no_std: no_std.contains(&pack.package_id()),
+            proc_macro: pack.proc_macro(),
+            repository: pack.manifest().metadata().repository.clone(),
+            license: pack.manifest().metadata().license.clone(),
+            latest_version: latest_versions.get(&pack.package_id()).cloned(),
+            category: category::categorize(
+                pack.name().as_str(),
+                &pack.manifest().metadata().categories,
+                &pack.manifest().metadata().keywords,
+            ),
+            coverage: coverage.remove(&pack.package_id()).unwrap_or_default(),
+            dependencies: {
+                let mut dependencies = resolve
+                    .deps(pack.package_id())
+                    .map(|(id, _)| dependency::id(id.name().as_str(), &id.version().to_string()))
+                    .collect::<Vec<_>>();
+                dependencies.sort();
+                dependencies
+            },
+            findings: 0,
+            blast_radius: 0,
+            public_unsafe_fns: public_unsafe_fns
+                .remove(&pack.package_id())
+                .unwrap_or_default(),
+            public_unsafe_traits: public_unsafe_traits
+                .remove(&pack.package_id())
+                .unwrap_or_default(),
+        })
+        .collect();
+
+    let features = packs
+        .iter()
+        .filter(|pack| ws.is_member(pack))
+        .map(|pack| {
+            let features = resolve.features(pack.package_id());
+            (
+                pack.name().to_string(),
+                features.iter().map(ToString::to_string).collect(),
+            )
+        })
+        .collect();
+
+    Ok((
+        rs_files_used,
+        Trawl {
+            findings: Vec::new(),
+            crates,
+            issues: Vec::new(),
+            duplicates: sources.into_duplicates(),
+            timings,
+            features,
+            inactive: Vec::new(),
+            api,
+            checksum_mismatches,
+            file_coverage: None,
+            skipped: Vec::new(),
+        },
+    ))
+}
+
+/// Whether the item at `path` is in one of `private_mods`, so not part of its crate's API (unless
+/// re-exported, which is not followed)
+fn in_private_mod(path: &str, private_mods: &HashSet<String>) -> bool {
+    private_mods.iter().any(|module| {
+        path.strip_prefix(module.as_str())
+            .is_some_and(|rest| rest.starts_with("::"))
+    })
+}
+
+/// The result of scanning one package on its own, without building it
+pub struct PackageScan {
+    /// The scanned files, relative to the package root, with the SHA-256 digests of their
+    /// contents, sorted
+    pub files: Vec<(PathBuf, Vec<u8>)>,
+
+    pub unsafe_items: usize,
+
+    /// The crate roots (library, binaries and build script) that do not
+    /// `#![forbid(unsafe_code)]`, relative to the package root
+    pub roots_without_forbid: Vec<PathBuf>,
+}
+
+/// Scans the source files of `pack` alone. Unlike `get_tainted`, nothing is built, so this only
+/// covers the files below the package root, not generated code or dependencies.
+pub fn scan_package(pack: &Package, include_tests: bool) -> Result<PackageScan, ScanError> {
+    let crate_name = pack.name().as_str().replace('-', "_");
+    let mut scan = PackageScan {
+        files: Vec::new(),
+        unsafe_items: 0,
+        roots_without_forbid: Vec::new(),
+    };
+    let mut seen = HashSet::new();
+    for rs_file in find_rs_files_in_package(pack) {
+        let rs_file = rs_file?;
+        let p = rs_file.as_path_buf();
+        let contents = fs::read(p).map_err(|e| ScanError::Io(e, p.clone()))?;
+        let items = ast_walker::find_unsafe_in_file(&crate_name, p, &contents, include_tests)
+            .map_err(ScanError::Scan)?;
+        let relative = p.strip_prefix(pack.root()).unwrap_or(p.as_path());
+        let is_root = !matches!(rs_file, RsFile::Other(_));
+        if is_root && !items.forbids_unsafe_code {
+            scan.roots_without_forbid.push(relative.to_path_buf());
+        }
+        // A file can be the root of several targets, but is only counted once.
+        if seen.insert(p.clone()) {
+            scan.unsafe_items += items.items.len();
+            scan.files
+                .push((relative.to_path_buf(), Sha256::digest(&*contents).to_vec()));
+        }
+    }
+    scan.files.sort();
+    scan.roots_without_forbid.sort();
+    scan.roots_without_forbid.dedup();
+    Ok(scan)
 }
 
 /// Trigger a `cargo build` and listen to the cargo/rustc communication to
 /// figure out which source files were used by the build.
+///
+/// A `-sys` crate's build script failing, typically for lack of native headers, does not fail the
+/// scan. The build stops there, and the `(name, version)` of the crate is returned along with the
+/// files used by what was built.
 pub fn resolve_rs_file_deps(
     copt: &CompileOptions,
     ws: &Workspace,
-) -> anyhow::Result<HashMap<PathBuf, u32>> {
+    builds: &BuildCache,
+    recovery: &mut Recovery,
+) -> anyhow::Result<(HashMap<PathBuf, u32>, Option<(String, String)>)> {
     let config = ws.config();
     set_var("RUSTFLAGS", crate::callgraph_gen::RUSTFLAGS);
+    let mut build_failed = None;
     let inner_arc = Arc::new(Mutex::new(CustomExecutorInnerContext::default()));
     {
         let cust_exec = CustomExecutor {
             cwd: config.cwd().to_path_buf(),
             inner_ctx: inner_arc.clone(),
+            builds: builds.clone(),
         };
         let exec: Arc<dyn Executor> = Arc::new(cust_exec);
-        cargo::ops::compile_with_exec(ws, copt, &exec)
-            .map_err(|e| RsResolveError::Cargo(e.to_string()))
-            .with_context(|| "`compile_with_exec` failed")?;
+        if let Err(e) = cargo::ops::compile_with_exec(ws, copt, &exec) {
+            match failed_sys_build(&e) {
+                Some((name, version)) => {
+                    warn!(
+                        "The build script of `{} v{}` failed, scanning its files anyway: {:#}",
+                        name, version, e
+                    );
+                    build_failed = Some((name, version));
+                }
+                None => {
+                    return Err(ScanError::Cargo(e.to_string()))
+                        .with_context(|| "`compile_with_exec` failed")
+                }
+            }
+        }
     }
     let ws_root = ws.root().to_path_buf();
-    let inner_mutex = Arc::try_unwrap(inner_arc).map_err(|_| RsResolveError::ArcUnwrap())?;
+    let inner_mutex = Arc::try_unwrap(inner_arc).map_err(|_| ScanError::ArcUnwrap())?;
     let (rs_files, out_dir_args) = {
-        let ctx = inner_mutex.into_inner()?;
+        let ctx = inner_mutex.into_inner().map_err(ScanError::from)?;
         (ctx.rs_file_args, ctx.out_dir_args)
     };
     let mut hm = HashMap::<PathBuf, u32>::new();
@@ -282,28 +1057,60 @@ pub fn resolve_rs_file_deps(
         // which rustc call. That would allow associating each `.rs` file found
         // in each dep file with a PackageId.
         for ent in WalkDir::new(&out_dir) {
-            let ent = ent.map_err(RsResolveError::Walkdir)?;
+            let ent = match ent {
+                Ok(ent) => ent,
+                Err(e) => {
+                    recovery.recover(None, ScanError::Walkdir(e))?;
+                    continue;
+                }
+            };
             if !is_file_with_ext(&ent, "d") {
                 continue;
             }
-            let deps = parse_rustc_dep_info(ent.path())
-                .map_err(|e| RsResolveError::DepParse(e.to_string(), ent.path().to_path_buf()))?;
+            let deps = match parse_rustc_dep_info(ent.path()) {
+                Ok(deps) => deps,
+                Err(e) => {
+                    recovery.recover(
+                        None,
+                        ScanError::DepParse(e.to_string(), ent.path().to_path_buf()),
+                    )?;
+                    continue;
+                }
+            };
             let canon_paths = deps
                 .into_iter()
                 .flat_map(|t| t.1)
                 .map(PathBuf::from)
                 .map(|pb| ws_root.join(pb))
-                .map(|pb| pb.canonicalize().map_err(|e| RsResolveError::Io(e, pb)));
+                .map(|pb| pb.canonicalize().map_err(|e| ScanError::Io(e, pb)));
             for p in canon_paths {
-                hm.insert(p?, 0);
+                match p {
+                    Ok(p) => {
+                        hm.insert(utils::normalize_case(&p), 0);
+                    }
+                    Err(e) => recovery.recover(None, e)?,
+                }
             }
         }
     }
     for pb in rs_files {
         // rs_files must already be canonicalized
-        hm.insert(pb, 0);
+        hm.insert(utils::normalize_case(&pb), 0);
+    }
+    Ok((hm, build_failed))
+}
+
+/// The `(name, version)` of the `-sys` crate whose build script failed, according to `error`
+fn failed_sys_build(error: &anyhow::Error) -> Option<(String, String)> {
+    let re = Regex::new(r"failed to run custom build command for `([^` ]+) v([^` ]+)`").ok()?;
+    let message = format!("{error:#}");
+    let captures = re.captures(&message)?;
+    let name = &captures[1];
+    if name.ends_with("-sys") {
+        Some((name.to_string(), captures[2].to_string()))
+    } else {
+        None
     }
-    Ok(hm)
 }
 
 /// Copy-pasted (almost) from the private module `cargo::core::compiler::fingerprint`.
@@ -347,9 +1154,11 @@ struct CustomExecutorInnerContext {
     /// Investigate if this needs to be intercepted like this or if it can be
     /// looked up in a nicer way.
     out_dir_args: HashSet<PathBuf>,
-}
 
-use std::sync::PoisonError;
+    /// The units being rebuilt that are not in the build cache yet, by what `exec` is told about
+    /// them
+    pending: HashMap<(PackageId, String, CompileMode), Vec<UnitKey>>,
+}
 
 /// A cargo Executor to intercept all build tasks and store all ".rs" file
 /// paths for later scanning.
@@ -364,6 +1173,9 @@ struct CustomExecutor {
 
     /// Needed since multiple rustc calls can be in flight at the same time.
     inner_ctx: Arc<Mutex<CustomExecutorInnerContext>>,
+
+    /// Units built by earlier scans of the same run, which are not rebuilt
+    builds: BuildCache,
 }
 
 use std::error::Error;
@@ -394,9 +1206,9 @@ impl Executor for CustomExecutor {
     fn exec(
         &self,
         command: &ProcessBuilder,
-        _id: PackageId,
-        _target: &Target,
-        _mode: CompileMode,
+        id: PackageId,
+        target: &Target,
+        mode: CompileMode,
         _on_stdout_line: &mut dyn FnMut(&str) -> CargoResult<()>,
         _on_stderr_line: &mut dyn FnMut(&str) -> CargoResult<()>,
     ) -> CargoResult<()> {
@@ -424,6 +1236,7 @@ impl Executor for CustomExecutor {
                 .inner_ctx
                 .lock()
                 .map_err(|e| CustomExecutorError::InnerContextMutex(e.to_string()))?;
+            let mut rs_files = HashSet::new();
             for tuple in args
                 .iter()
                 .map(|s| (s, s.to_string_lossy().to_lowercase()))
@@ -433,8 +1246,24 @@ impl Executor for CustomExecutor {
                 let p = raw_path
                     .canonicalize()
                     .map_err(|e| CustomExecutorError::Io(e, raw_path))?;
-                ctx.rs_file_args.insert(p);
+                rs_files.insert(p);
+            }
+            // Only cached if the invocation can be told apart from those of other units, e.g.,
+            // the same crate built for the host and for the target
+            if let Some([key]) = ctx
+                .pending
+                .get(&(id, target.name().to_string(), mode))
+                .map(Vec::as_slice)
+            {
+                self.builds.insert(
+                    key.clone(),
+                    BuiltUnit {
+                        rs_files: rs_files.clone(),
+                        out_dir: out_dir.clone(),
+                    },
+                );
             }
+            ctx.rs_file_args.extend(rs_files);
             ctx.out_dir_args.insert(out_dir);
         }
         command.exec()?;
@@ -443,30 +1272,249 @@ impl Executor for CustomExecutor {
 
     /// Queried when queuing each unit of work. If it returns true, then the
     /// unit will always be rebuilt, independent of whether it needs to be.
-    fn force_rebuild(&self, _unit: &Unit) -> bool {
-        true // Overriding the default to force all units to be processed.
+    ///
+    /// Every unit is rebuilt so that its files are seen, except those an earlier scan of the same
+    /// run built, whose files are taken from the build cache.
+    fn force_rebuild(&self, unit: &Unit) -> bool {
+        let key = UnitKey::new(unit);
+        let mut ctx = match self.inner_ctx.lock() {
+            Ok(ctx) => ctx,
+            Err(_) => return true,
+        };
+        if let Some(built) = self.builds.get(&key) {
+            ctx.rs_file_args.extend(built.rs_files);
+            ctx.out_dir_args.insert(built.out_dir);
+            return false;
+        }
+        let (id, target, mode) = key.invocation();
+        let invocation = (id, target.to_string(), mode);
+        ctx.pending.entry(invocation).or_default().push(key);
+        true
+    }
+}
+
+/// What to resolve, build and scan the tree for, instead of every feature and platform
+pub struct Scope {
+    pub features: CliFeatures,
+
+    /// A target triple, or `None` for the host
+    pub target: Option<String>,
+}
+
+impl Scope {
+    fn compile_kind(&self) -> CargoResult<CompileKind> {
+        match &self.target {
+            Some(target) => Ok(CompileKind::Target(CompileTarget::new(target)?)),
+            None => Ok(CompileKind::Host),
+        }
+    }
+}
+
+/// The packages that building the workspace members for `kind` involves. Build dependencies are
+/// built for the host.
+fn activated_packages(
+    ws: &Workspace,
+    resolve: &Resolve,
+    target_data: &RustcTargetData,
+    kind: CompileKind,
+) -> HashSet<PackageId> {
+    let mut activated = HashSet::new();
+    let mut stack = ws.members().map(Package::package_id).collect::<Vec<_>>();
+    while let Some(id) = stack.pop() {
+        if !activated.insert(id) {
+            continue;
+        }
+        for (dep_id, deps) in resolve.deps(id) {
+            let is_activated = deps.iter().any(|dep| {
+                let kind = if dep.kind() == DepKind::Build {
+                    CompileKind::Host
+                } else {
+                    kind
+                };
+                target_data.dep_platform_activated(dep, kind)
+            });
+            if is_activated {
+                stack.push(dep_id);
+            }
+        }
     }
+    activated
+}
+
+fn member_specs(ws: &Workspace) -> Vec<PackageIdSpec> {
+    ws.members()
+        .map(|pack| PackageIdSpec::from_package_id(pack.package_id()))
+        .collect()
 }
 
 pub fn get_tainted(
     config: &cargo::Config,
     workspace: &cargo::core::Workspace,
     _package: &Option<String>,
-    include_tests: bool,
-) -> anyhow::Result<Vec<String>> {
-    let (packages, _resolve) = cargo::ops::resolve_ws(workspace)?;
+    opts: &TrawlOpts,
+    scope: Option<&Scope>,
+    builds: &BuildCache,
+) -> anyhow::Result<Trawl> {
+    // Without a scope, the tree is resolved with every feature of every member enabled, and for
+    // every platform, unless optional dependencies are left out.
+    let mut inactive = Vec::new();
+    let mut skipped = Vec::new();
+    let (packages, resolve, activated) = match scope {
+        None if opts.no_optional_deps => {
+            let (all_packages, _) = cargo::ops::resolve_ws(workspace)?;
+            let requested_kinds = [CompileKind::Host];
+            let mut target_data = RustcTargetData::new(workspace, &requested_kinds)?;
+            let ws_resolve = cargo::ops::resolve_ws_with_opts(
+                workspace,
+                &mut target_data,
+                &requested_kinds,
+                &CliFeatures::new_all(false),
+                &member_specs(workspace),
+                HasDevUnits::Yes,
+                ForceAllTargets::Yes,
+            )?;
+            let active = ws_resolve.pkg_set.package_ids().collect::<HashSet<_>>();
+            for id in all_packages.package_ids().filter(|id| !active.contains(id)) {
+                inactive.push(dependency::id(
+                    id.name().as_str(),
+                    &id.version().to_string(),
+                ));
+                skipped.push(SkippedCrate {
+                    krate: id.name().to_string(),
+                    version: id.version().to_string(),
+                    reason: SkipReason::Inactive,
+                    detail: None,
+                });
+            }
+            inactive.sort();
+            (ws_resolve.pkg_set, ws_resolve.targeted_resolve, None)
+        }
+        None => {
+            let (packages, resolve) = cargo::ops::resolve_ws(workspace)?;
+            (packages, resolve, None)
+        }
+        Some(scope) => {
+            let kind = scope.compile_kind()?;
+            let requested_kinds = [kind];
+            let mut target_data = RustcTargetData::new(workspace, &requested_kinds)?;
+            let specs = member_specs(workspace);
+            let ws_resolve = cargo::ops::resolve_ws_with_opts(
+                workspace,
+                &mut target_data,
+                &requested_kinds,
+                &scope.features,
+                &specs,
+                HasDevUnits::Yes,
+                ForceAllTargets::No,
+            )?;
+            let activated =
+                activated_packages(workspace, &ws_resolve.targeted_resolve, &target_data, kind);
+            (
+                ws_resolve.pkg_set,
+                ws_resolve.targeted_resolve,
+                Some(activated),
+            )
+        }
+    };
 
-    let copt = CompileOptions::new(config, CompileMode::Build)?;
-    let rs_files_used_in_compilation = resolve_rs_file_deps(&copt, workspace)?;
+    if opts.hermetic {
+        check_hermetic(&packages)?;
+    }
 
-    let allow_partial_results = true;
+    let mut recovery = Recovery::new(opts.on_error);
+    let ignore = ignore::load(config)?;
 
-    let (rs_files_scanned, tainted_things) = find_unsafe_in_packages(
-        &packages,
-        rs_files_used_in_compilation,
-        allow_partial_results,
-        include_tests,
-    );
+    let mut copt = CompileOptions::new(config, CompileMode::Build)?;
+    if let Some(scope) = scope {
+        copt.cli_features = scope.features.clone();
+        copt.build_config.requested_kinds = vec![scope.compile_kind()?];
+    }
+    if opts.trace_benches {
+        // What is built by default, plus the benchmarks
+        copt.filter = CompileFilter::new(
+            LibRule::Default,
+            FilterRule::All,
+            FilterRule::none(),
+            FilterRule::none(),
+            FilterRule::All,
+        );
+    }
+    opts.cancel.check()?;
+    let (rs_files_used_in_compilation, build_failed) = info_span!("build")
+        .in_scope(|| resolve_rs_file_deps(&copt, workspace, builds, &mut recovery))?;
+    opts.cancel.check()?;
+
+    // Without a threshold, nothing is ever spilled.
+    let mut sink = SpillingSink::new(opts.spill_threshold.unwrap_or(usize::MAX));
+    let (rs_files_scanned, mut trawl) = {
+        // Only the scan of the workspace itself is streamed, not those of scenarios and targets.
+        let mut stream;
+        let sink_or_stream: &mut dyn FindingSink = if opts.stream_findings && scope.is_none() {
+            stream = JsonLinesSink::new(io::stdout(), opts.stream_filter.as_ref(), &mut sink);
+            &mut stream
+        } else {
+            &mut sink
+        };
+        find_unsafe_in_packages(
+            workspace,
+            &packages,
+            &resolve,
+            rs_files_used_in_compilation,
+            build_failed.as_ref(),
+            &mut recovery,
+            &ignore,
+            opts,
+            sink_or_stream,
+        )?
+    };
+    trawl.findings = sink.into_sorted()?;
+    trawl.issues = recovery.issues;
+    trawl.inactive = inactive;
+    skipped.extend(recovery.skipped);
+    if let Some((name, version)) = build_failed {
+        skipped.push(SkippedCrate {
+            krate: name,
+            version,
+            reason: SkipReason::BuildFailed,
+            detail: Some("all of its files were scanned".to_string()),
+        });
+    }
+
+    // Packages that only other platforms depend on are scanned along with the rest, but left out.
+    if let Some(activated) = activated {
+        let activated = activated
+            .iter()
+            .map(|id| (id.name().to_string(), id.version().to_string()))
+            .collect::<HashSet<_>>();
+        let is_activated = |name: &str, version: &str| {
+            activated.contains(&(name.to_string(), version.to_string()))
+        };
+        trawl
+            .findings
+            .retain(|finding| is_activated(&finding.krate, &finding.provenance.version));
+        trawl
+            .crates
+            .retain(|summary| is_activated(&summary.name, &summary.version));
+    }
+    skipped.sort();
+    trawl.skipped = skipped;
+
+    if let Some(n) = opts.slowest {
+        trawl.timings.write_slowest(n, &mut io::stderr().lock())?;
+    }
+
+    // With a shard, the files of the other shards are never scanned.
+    if opts.shard.is_some() {
+        return Ok(trawl);
+    }
+
+    trawl.file_coverage = Some(FileCoverage {
+        compiled: rs_files_scanned.len(),
+        scanned: rs_files_scanned
+            .values()
+            .filter(|count| **count > 0)
+            .count(),
+    });
 
     rs_files_scanned
         .iter()
@@ -481,5 +1529,5 @@ pub fn get_tainted(
             warn!("Dependency file was never scanned: {}", k.display());
         });
 
-    Ok(tainted_things)
+    Ok(trawl)
 }
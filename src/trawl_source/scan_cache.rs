@@ -0,0 +1,114 @@
+//! With `--scan-cache`, what was found in each source file is kept on disk, so that later runs
+//! only scan the files they have not seen. Entries are kept in a directory named after the hash of
+//! the detector ruleset, which `build.rs` computes from the tokens of the detectors: a change to
+//! what is detected or how it is classified starts a new directory, while a change to comments or
+//! formatting does not.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use super::ast_walker::UnsafeItems;
+
+/// The hash of the detector ruleset the cache entries were produced by
+pub const RULESET: &str = env!("SIDEROPHILE_RULESET");
+
+pub struct ScanCache {
+    dir: PathBuf,
+}
+
+impl ScanCache {
+    /// Opens the cache in `dir`, removing the entries of other rulesets, which are never used
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let is_ruleset = name.len() == RULESET.len()
+                && name
+                    .to_string_lossy()
+                    .chars()
+                    .all(|c| c.is_ascii_hexdigit());
+            if is_ruleset && name != RULESET && entry.file_type()?.is_dir() {
+                debug!("Removing the stale scan cache `{}`", entry.path().display());
+                fs::remove_dir_all(entry.path())?;
+            }
+        }
+        let dir = dir.join(RULESET);
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// The unsafe items of a file, calling `scan` only if the cache has no entry for it
+    pub fn scan<E>(
+        &self,
+        crate_name: &str,
+        file: &Path,
+        contents: &[u8],
+        include_tests: bool,
+        scan: impl FnOnce() -> Result<UnsafeItems, E>,
+    ) -> Result<UnsafeItems, E> {
+        let path = self
+            .dir
+            .join(key(crate_name, file, contents, include_tests));
+        let cached = fs::read(&path)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok());
+        if let Some(items) = cached {
+            debug!("`{}` is in the scan cache", file.display());
+            return Ok(items);
+        }
+        let items = scan()?;
+        let written = serde_json::to_vec(&items)
+            .map_err(io::Error::from)
+            .and_then(|json| fs::write(&path, json));
+        if let Err(e) = written {
+            warn!("Could not write `{}`: {}", path.display(), e);
+        }
+        Ok(items)
+    }
+}
+
+/// Everything the items found in a file depend on besides the ruleset. The path is included
+/// because the module path of the items is taken from it.
+fn key(crate_name: &str, file: &Path, contents: &[u8], include_tests: bool) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        crate_name.as_bytes(),
+        file.to_string_lossy().as_bytes(),
+        &[u8::from(include_tests)],
+        contents,
+    ] {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    let hex = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    format!("{hex}.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::key;
+    use std::path::Path;
+
+    #[test]
+    fn key_covers_every_input() {
+        let file = Path::new("/src/lib.rs");
+        let base = key("foo", file, b"unsafe {}", false);
+        assert_eq!(base, key("foo", file, b"unsafe {}", false));
+        assert_ne!(base, key("bar", file, b"unsafe {}", false));
+        assert_ne!(
+            base,
+            key("foo", Path::new("/src/a.rs"), b"unsafe {}", false)
+        );
+        assert_ne!(base, key("foo", file, b"unsafe { }", false));
+        assert_ne!(base, key("foo", file, b"unsafe {}", true));
+    }
+}
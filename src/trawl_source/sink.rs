@@ -0,0 +1,283 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use tempfile::NamedTempFile;
+
+use super::Interner;
+use crate::{error::ScanError, filter::Filter, report::Finding, utils};
+
+/// Receives findings one at a time, as soon as each file has been scanned
+pub trait FindingSink {
+    fn push(&mut self, finding: Finding) -> Result<(), ScanError>;
+}
+
+impl FindingSink for Vec<Finding> {
+    fn push(&mut self, finding: Finding) -> Result<(), ScanError> {
+        Vec::push(self, finding);
+        Ok(())
+    }
+}
+
+/// Keeps at most `capacity` findings in memory. Whenever the buffer fills up, it is sorted and
+/// spilled to a temporary file as JSON lines, and the runs are merged at the end.
+pub struct SpillingSink {
+    capacity: usize,
+    buffer: Vec<Finding>,
+    runs: Vec<NamedTempFile>,
+}
+
+impl SpillingSink {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            buffer: Vec::new(),
+            runs: Vec::new(),
+        }
+    }
+
+    fn spill(&mut self) -> Result<(), ScanError> {
+        let run = NamedTempFile::new().map_err(|e| ScanError::Io(e, std::env::temp_dir()))?;
+        let io_error = |e| ScanError::Io(e, run.path().to_path_buf());
+        self.buffer.sort_by_cached_key(sort_key);
+        let mut writer = BufWriter::new(run.as_file());
+        for finding in self.buffer.drain(..) {
+            serde_json::to_writer(&mut writer, &finding).map_err(|e| io_error(e.into()))?;
+            writeln!(writer).map_err(io_error)?;
+        }
+        writer.flush().map_err(io_error)?;
+        drop(writer);
+        self.runs.push(run);
+        Ok(())
+    }
+
+    /// All of the findings, sorted by location, with duplicates removed
+    pub fn into_sorted(mut self) -> Result<Vec<Finding>, ScanError> {
+        if self.runs.is_empty() {
+            let mut findings = self.buffer;
+            findings.sort_by_cached_key(sort_key);
+            findings.dedup_by(|a, b| is_duplicate(a, b));
+            return Ok(findings);
+        }
+        if !self.buffer.is_empty() {
+            self.spill()?;
+        }
+
+        let mut readers = self
+            .runs
+            .iter()
+            .map(|run| {
+                File::open(run.path())
+                    .map(|file| BufReader::new(file).lines())
+                    .map_err(|e| ScanError::Io(e, run.path().to_path_buf()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut heap = BinaryHeap::new();
+        for (i, reader) in readers.iter_mut().enumerate() {
+            if let Some(finding) = next_finding(reader)? {
+                heap.push(Reverse(Keyed(finding, i)));
+            }
+        }
+
+        // Findings read back from the runs no longer share their crate names and files
+        let mut interner = Interner::default();
+        let mut findings: Vec<Finding> = Vec::new();
+        while let Some(Reverse(Keyed(mut finding, i))) = heap.pop() {
+            if let Some(next) = next_finding(&mut readers[i])? {
+                heap.push(Reverse(Keyed(next, i)));
+            }
+            if !findings
+                .last()
+                .is_some_and(|last| is_duplicate(&finding, last))
+            {
+                finding.krate = interner.str(&finding.krate);
+                finding.file = interner.path(&finding.file);
+                findings.push(finding);
+            }
+        }
+        Ok(findings)
+    }
+}
+
+impl FindingSink for SpillingSink {
+    fn push(&mut self, finding: Finding) -> Result<(), ScanError> {
+        self.buffer.push(finding);
+        if self.buffer.len() >= self.capacity {
+            self.spill()?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes each finding that `filter` selects to `out` as a line of JSON as soon as it is found,
+/// flushing it so that consumers see it right away, and passes every finding on to `inner`. The
+/// filter must not use the fields of crates, which are not known yet.
+pub struct JsonLinesSink<'a, W: Write> {
+    out: W,
+    filter: Option<&'a Filter>,
+    inner: &'a mut dyn FindingSink,
+}
+
+impl<'a, W: Write> JsonLinesSink<'a, W> {
+    pub fn new(out: W, filter: Option<&'a Filter>, inner: &'a mut dyn FindingSink) -> Self {
+        Self { out, filter, inner }
+    }
+}
+
+impl<W: Write> FindingSink for JsonLinesSink<'_, W> {
+    fn push(&mut self, finding: Finding) -> Result<(), ScanError> {
+        if self
+            .filter
+            .map_or(true, |filter| filter.matches(&finding, None))
+        {
+            let io_error = |e| ScanError::Io(e, PathBuf::from("-"));
+            serde_json::to_writer(&mut self.out, &finding).map_err(|e| io_error(e.into()))?;
+            writeln!(self.out).map_err(io_error)?;
+            self.out.flush().map_err(io_error)?;
+        }
+        self.inner.push(finding)
+    }
+}
+
+/// The location of `finding`, in which the same file reached with different casing is the same file
+/// on filesystems that are usually case-insensitive (see `utils::normalize_case`)
+fn sort_key(finding: &Finding) -> (Arc<str>, PathBuf, usize) {
+    (
+        finding.krate.clone(),
+        utils::normalize_case(&finding.file),
+        finding.line,
+    )
+}
+
+/// Whether `a` is `b`, possibly reached through a path with different casing
+fn is_duplicate(a: &Finding, b: &Finding) -> bool {
+    if a.file == b.file {
+        return a == b;
+    }
+    utils::normalize_case(&a.file) == utils::normalize_case(&b.file)
+        && *a
+            == Finding {
+                file: a.file.clone(),
+                ..b.clone()
+            }
+}
+
+fn next_finding(lines: &mut io::Lines<BufReader<File>>) -> Result<Option<Finding>, ScanError> {
+    let line = match lines.next() {
+        Some(line) => line.map_err(|e| ScanError::Io(e, std::env::temp_dir()))?,
+        None => return Ok(None),
+    };
+    serde_json::from_str(&line)
+        .map(Some)
+        .map_err(|e| ScanError::Io(e.into(), std::env::temp_dir()))
+}
+
+/// A finding from the run at index `.1`, ordered by location
+struct Keyed(Finding, usize);
+
+impl PartialEq for Keyed {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Keyed {}
+
+impl PartialOrd for Keyed {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Keyed {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        sort_key(&self.0)
+            .cmp(&sort_key(&other.0))
+            .then(self.1.cmp(&other.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::fixture::finding;
+
+    #[test]
+    fn spilled_runs_are_merged_in_order() {
+        let mut sink = SpillingSink::new(2);
+        for (krate, line) in [("b", 3), ("a", 7), ("b", 1), ("a", 7), ("a", 2)] {
+            sink.push(finding(krate, line)).unwrap();
+        }
+        let findings = sink.into_sorted().unwrap();
+        assert_eq!(
+            findings,
+            vec![
+                finding("a", 2),
+                finding("a", 7),
+                finding("b", 1),
+                finding("b", 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_that_differ_only_in_case_are_one_file_where_case_is_ignored() {
+        let lower = finding("a", 7);
+        let upper = Finding {
+            file: Path::new("SRC/LIB.rs").into(),
+            ..finding("a", 7)
+        };
+        for capacity in [1, usize::MAX] {
+            let mut sink = SpillingSink::new(capacity);
+            sink.push(lower.clone()).unwrap();
+            sink.push(finding("a", 8)).unwrap();
+            sink.push(upper.clone()).unwrap();
+            let findings = sink.into_sorted().unwrap();
+            if cfg!(any(target_os = "macos", windows)) {
+                assert_eq!(findings, vec![lower.clone(), finding("a", 8)]);
+            } else {
+                assert_eq!(
+                    findings,
+                    vec![upper.clone(), lower.clone(), finding("a", 8)]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn json_lines_are_written_as_found() {
+        let mut out = Vec::new();
+        let mut findings = Vec::new();
+        let mut sink = JsonLinesSink::new(&mut out, None, &mut findings);
+        sink.push(finding("b", 3)).unwrap();
+        sink.push(finding("a", 7)).unwrap();
+        let lines = String::from_utf8(out).unwrap();
+        let streamed = lines
+            .lines()
+            .map(|line| serde_json::from_str::<Finding>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(streamed, vec![finding("b", 3), finding("a", 7)]);
+        assert_eq!(findings, streamed);
+    }
+
+    #[test]
+    fn only_selected_findings_are_streamed() {
+        let mut out = Vec::new();
+        let mut findings = Vec::new();
+        let filter = r#"crate == "a""#.parse::<Filter>().unwrap();
+        let mut sink = JsonLinesSink::new(&mut out, Some(&filter), &mut findings);
+        sink.push(finding("b", 3)).unwrap();
+        sink.push(finding("a", 7)).unwrap();
+        let lines = String::from_utf8(out).unwrap();
+        assert_eq!(lines.lines().count(), 1);
+        assert_eq!(findings, vec![finding("b", 3), finding("a", 7)]);
+    }
+}
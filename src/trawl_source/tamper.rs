@@ -0,0 +1,112 @@
+//! Checks the extracted sources of registry packages against the `.crate` files cargo downloaded
+//! them as, and those against the checksums of the lockfile, so that sources modified after being
+//! extracted are not scanned unnoticed
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+};
+
+use cargo::core::Package;
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+
+use super::find_rs_files_in_dir;
+use crate::{error::ScanError, report::ChecksumMismatch};
+
+/// Where cargo cached the `.crate` file `pack` was extracted from, if it was extracted into the
+/// `src` directory of a registry
+pub fn cached_crate_file(pack: &Package) -> Option<PathBuf> {
+    let index_dir = pack.root().parent()?;
+    let src = index_dir.parent()?;
+    if src.file_name()? != "src" {
+        return None;
+    }
+    Some(
+        src.parent()?
+            .join("cache")
+            .join(index_dir.file_name()?)
+            .join(format!("{}-{}.crate", pack.name(), pack.version())),
+    )
+}
+
+/// How the extracted sources of the registry package `pack` differ from the `.crate` file they
+/// were extracted from, or how that file differs from `checksum`. Nothing is checked when the
+/// `.crate` file is no longer cached.
+pub fn verify(pack: &Package, checksum: Option<&str>) -> Result<Vec<ChecksumMismatch>, ScanError> {
+    let mismatch = |file: Option<PathBuf>, detail: &str| ChecksumMismatch {
+        krate: pack.name().to_string(),
+        version: pack.version().to_string(),
+        file,
+        detail: detail.to_string(),
+    };
+    let (checksum, crate_file) = match (checksum, cached_crate_file(pack)) {
+        (Some(checksum), Some(crate_file)) if crate_file.is_file() => (checksum, crate_file),
+        _ => return Ok(Vec::new()),
+    };
+    let io_error = |e: io::Error| ScanError::Io(e, crate_file.clone());
+    let contents = fs::read(&crate_file).map_err(io_error)?;
+    let digest = Sha256::digest(&contents)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    if digest != checksum {
+        return Ok(vec![mismatch(
+            None,
+            "The cached `.crate` file does not match the checksum in the lockfile",
+        )]);
+    }
+
+    let mut mismatches = Vec::new();
+    let mut archived = HashSet::new();
+    let prefix = format!("{}-{}", pack.name(), pack.version());
+    let mut archive = tar::Archive::new(GzDecoder::new(contents.as_slice()));
+    for entry in archive.entries().map_err(io_error)? {
+        let mut entry = entry.map_err(io_error)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path().map_err(io_error)?.into_owned();
+        let relative = match path.strip_prefix(&prefix) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => continue,
+        };
+        let mut archived_contents = Vec::new();
+        entry
+            .read_to_end(&mut archived_contents)
+            .map_err(io_error)?;
+        let extracted = pack.root().join(&relative);
+        match fs::read(&extracted) {
+            Ok(extracted) if extracted == archived_contents => {}
+            Ok(_) => mismatches.push(mismatch(
+                Some(relative.clone()),
+                "The extracted file differs from the one in the `.crate` file",
+            )),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => mismatches.push(mismatch(
+                Some(relative.clone()),
+                "The file of the `.crate` file is missing from the extracted sources",
+            )),
+            Err(e) => return Err(ScanError::Io(e, extracted)),
+        }
+        archived.insert(relative);
+    }
+
+    // Source files added after extraction would be scanned as if they were published.
+    let root = pack
+        .root()
+        .canonicalize()
+        .map_err(|e| ScanError::Io(e, pack.root().to_path_buf()))?;
+    for path in find_rs_files_in_dir(&root) {
+        let path = path?;
+        let relative = path.strip_prefix(&root).unwrap_or(&path);
+        if !archived.contains(relative) {
+            mismatches.push(mismatch(
+                Some(relative.to_path_buf()),
+                "The file is not in the `.crate` file",
+            ));
+        }
+    }
+    Ok(mismatches)
+}
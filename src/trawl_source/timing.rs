@@ -0,0 +1,56 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// How long reading and scanning each file took
+#[derive(Default)]
+pub struct ScanTimings {
+    files: Vec<(String, PathBuf, Duration)>,
+}
+
+impl ScanTimings {
+    pub fn record(&mut self, package: &str, file: &Path, duration: Duration) {
+        self.files
+            .push((package.to_string(), file.to_path_buf(), duration));
+    }
+
+    /// Writes the `n` slowest files and packages, slowest first
+    pub fn write_slowest(&self, n: usize, out: &mut dyn Write) -> io::Result<()> {
+        let mut files = self.files.iter().collect::<Vec<_>>();
+        files.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.1.cmp(&b.1)));
+
+        let mut packages = HashMap::<&str, (Duration, usize)>::new();
+        for (package, _, duration) in &self.files {
+            let (total, count) = packages.entry(package).or_default();
+            *total += *duration;
+            *count += 1;
+        }
+        let mut packages = packages.into_iter().collect::<Vec<_>>();
+        packages.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then_with(|| a.0.cmp(b.0)));
+
+        writeln!(out, "Slowest files:")?;
+        for (package, file, duration) in files.into_iter().take(n) {
+            writeln!(
+                out,
+                "  {:>9.3}s  {}  {}",
+                duration.as_secs_f64(),
+                package,
+                file.display()
+            )?;
+        }
+        writeln!(out, "Slowest packages:")?;
+        for (package, (duration, count)) in packages.into_iter().take(n) {
+            writeln!(
+                out,
+                "  {:>9.3}s  {} ({} files)",
+                duration.as_secs_f64(),
+                package,
+                count
+            )?;
+        }
+        Ok(())
+    }
+}
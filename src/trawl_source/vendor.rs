@@ -0,0 +1,464 @@
+//! Scanning a `cargo vendor` directory without building anything or using the network. Each
+//! vendored directory is attributed to the package of `Cargo.lock` it holds, and checked against
+//! the checksum the lockfile records and the file checksums `cargo vendor` wrote next to it. An
+//! unpacked `.crate` file is scanned the same way, as a vendored package of its own.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
+
+use anyhow::Context;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::{
+    archive::Unpacked, ast_walker, find_rs_files_in_dir, generated, in_private_mod,
+    timing::ScanTimings, to_findings, FindingSink, Interner, ScanFileError, SpillingSink, Trawl,
+    TrawlOpts,
+};
+use crate::{
+    category, dependency,
+    error::{OnError, Recovery, ScanError},
+    report::{CrateSummary, Provenance, SkipReason},
+};
+
+#[derive(Debug, Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+
+    /// `None` for the workspace members
+    source: Option<String>,
+
+    checksum: Option<String>,
+
+    /// `name`, or `name version` when several versions are locked, possibly followed by
+    /// `(source)`
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+/// The `.cargo-checksum.json` of a vendored directory
+#[derive(Debug, Deserialize)]
+struct Checksums {
+    /// The SHA-256 digest of each file, by path relative to the directory
+    files: BTreeMap<String, String>,
+
+    /// The checksum of the `.crate` file, as in the lockfile, or `None` for git sources
+    package: Option<String>,
+}
+
+/// The parts of a vendored, normalized `Cargo.toml` that are summarized
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    package: ManifestPackage,
+
+    #[serde(default)]
+    lib: ManifestLib,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestPackage {
+    name: String,
+    version: String,
+    repository: Option<String>,
+    license: Option<String>,
+
+    #[serde(default)]
+    categories: Vec<String>,
+
+    #[serde(default)]
+    keywords: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ManifestLib {
+    path: Option<PathBuf>,
+
+    #[serde(default, alias = "proc_macro")]
+    proc_macro: bool,
+}
+
+/// Scans the packages of `lockfile` from their directories in `vendor_dir`. A package that is not
+/// vendored, or whose files do not match their checksums, is skipped and listed as an issue, as is
+/// a directory that no package of the lockfile accounts for. The workspace members are summarized
+/// but not scanned.
+pub fn trawl_vendored(
+    vendor_dir: &Path,
+    lockfile: &Path,
+    opts: &TrawlOpts,
+) -> anyhow::Result<Trawl> {
+    let contents = fs::read_to_string(lockfile)
+        .with_context(|| format!("Could not read `{}`", lockfile.display()))?;
+    let lockfile: Lockfile = toml::from_str(&contents)
+        .with_context(|| format!("Could not parse `{}`", lockfile.display()))?;
+    let mut scanner = Scanner {
+        recovery: Recovery::new(opts.on_error),
+        interner: Interner::default(),
+        sink: SpillingSink::new(opts.spill_threshold.unwrap_or(usize::MAX)),
+        timings: ScanTimings::default(),
+        include_tests: opts.include_tests,
+    };
+
+    let mut dirs = fs::read_dir(vendor_dir)
+        .with_context(|| format!("Could not read `{}`", vendor_dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    dirs.sort();
+    // The vendored directories, by the `name@version` of the package in them
+    let mut vendored = BTreeMap::new();
+    for dir in dirs.into_iter().filter(|dir| dir.is_dir()) {
+        match read_manifest(&dir) {
+            Ok(manifest) => {
+                let id = dependency::id(&manifest.package.name, &manifest.package.version);
+                vendored.insert(id, (dir, manifest));
+            }
+            Err(e) => scanner.recovery.recover_named(None, e)?,
+        }
+    }
+
+    let mut versions = HashMap::<_, Vec<_>>::new();
+    for locked in &lockfile.package {
+        versions
+            .entry(locked.name.as_str())
+            .or_default()
+            .push(locked.version.as_str());
+    }
+    let resolve_dependency = |dependency: &str| {
+        let mut parts = dependency.split(' ');
+        let name = parts.next().unwrap_or_default();
+        match parts.next() {
+            Some(version) => Some(dependency::id(name, version)),
+            None => versions
+                .get(name)
+                .and_then(|versions| versions.first())
+                .map(|version| dependency::id(name, version)),
+        }
+    };
+
+    let mut crates = Vec::new();
+    for (position, locked) in lockfile.package.iter().enumerate() {
+        opts.cancel.check()?;
+        let id = dependency::id(&locked.name, &locked.version);
+        let mut dependencies = locked
+            .dependencies
+            .iter()
+            .filter_map(|dependency| resolve_dependency(dependency))
+            .collect::<Vec<_>>();
+        dependencies.sort();
+        let mut summary = CrateSummary {
+            name: locked.name.clone(),
+            version: locked.version.clone(),
+            source: locked.source.clone().unwrap_or_default(),
+            checksum: locked.checksum.clone(),
+            workspace_member: locked.source.is_none(),
+            no_std: false,
+            proc_macro: false,
+            repository: None,
+            license: None,
+            latest_version: None,
+            category: category::categorize(&locked.name, &[], &[]),
+            coverage: Default::default(),
+            dependencies,
+            findings: 0,
+            blast_radius: 0,
+            public_unsafe_fns: Vec::new(),
+            public_unsafe_traits: Vec::new(),
+        };
+        match vendored.remove(&id) {
+            _ if locked.source.is_none() => {}
+            None => scanner.recovery.skip(
+                locked.name.clone(),
+                locked.version.clone(),
+                SkipReason::NotVendored,
+                ScanError::Vendor(
+                    format!("`{id}` is in the lockfile, but not vendored"),
+                    vendor_dir.to_path_buf(),
+                ),
+            )?,
+            Some((dir, manifest)) => {
+                summary.proc_macro = manifest.lib.proc_macro;
+                summary.category = category::categorize(
+                    &locked.name,
+                    &manifest.package.categories,
+                    &manifest.package.keywords,
+                );
+                summary.repository = manifest.package.repository;
+                summary.license = manifest.package.license;
+                let scanned = opts.shard.map_or(true, |shard| shard.contains(position));
+                match verify(&dir, locked.checksum.as_deref()) {
+                    Ok(checksums) if scanned => {
+                        let lib_root = manifest
+                            .lib
+                            .path
+                            .unwrap_or_else(|| PathBuf::from("src/lib.rs"));
+                        scanner.scan(&dir, locked, &checksums, &lib_root, &mut summary)?;
+                    }
+                    Ok(_) => {}
+                    Err(e) => scanner.recovery.skip(
+                        locked.name.clone(),
+                        locked.version.clone(),
+                        SkipReason::ChecksumFailed,
+                        e,
+                    )?,
+                }
+            }
+        }
+        crates.push(summary);
+    }
+
+    for (id, (dir, manifest)) in vendored {
+        scanner.recovery.skip(
+            manifest.package.name,
+            manifest.package.version,
+            SkipReason::NotVendored,
+            ScanError::Vendor(format!("`{id}` is vendored, but not in the lockfile"), dir),
+        )?;
+    }
+
+    if let Some(n) = opts.slowest {
+        scanner
+            .timings
+            .write_slowest(n, &mut std::io::stderr().lock())?;
+    }
+
+    scanner.recovery.skipped.sort();
+    Ok(Trawl {
+        findings: scanner.sink.into_sorted()?,
+        crates,
+        issues: scanner.recovery.issues,
+        duplicates: Vec::new(),
+        timings: scanner.timings,
+        features: BTreeMap::new(),
+        inactive: Vec::new(),
+        api: Vec::new(),
+        checksum_mismatches: Vec::new(),
+        file_coverage: None,
+        skipped: scanner.recovery.skipped,
+    })
+}
+
+/// Scans the package of a `.crate` file unpacked in `unpacked`, from the registry `source`. Only
+/// the files of the archive are scanned.
+pub fn trawl_archive(
+    unpacked: &Unpacked,
+    source: &str,
+    on_error: OnError,
+    include_tests: bool,
+) -> anyhow::Result<Trawl> {
+    let mut scanner = Scanner {
+        recovery: Recovery::new(on_error),
+        interner: Interner::default(),
+        sink: SpillingSink::new(usize::MAX),
+        timings: ScanTimings::default(),
+        include_tests,
+    };
+    let manifest = read_manifest(&unpacked.root)?;
+    let locked = LockedPackage {
+        name: manifest.package.name,
+        version: manifest.package.version,
+        source: Some(source.to_string()),
+        checksum: Some(unpacked.checksum.clone()),
+        dependencies: Vec::new(),
+    };
+    let checksums = Checksums {
+        files: unpacked.files.clone(),
+        package: Some(unpacked.checksum.clone()),
+    };
+    let mut summary = CrateSummary {
+        name: locked.name.clone(),
+        version: locked.version.clone(),
+        source: source.to_string(),
+        checksum: Some(unpacked.checksum.clone()),
+        workspace_member: false,
+        no_std: false,
+        proc_macro: manifest.lib.proc_macro,
+        repository: manifest.package.repository,
+        license: manifest.package.license,
+        latest_version: None,
+        category: category::categorize(
+            &locked.name,
+            &manifest.package.categories,
+            &manifest.package.keywords,
+        ),
+        coverage: Default::default(),
+        dependencies: Vec::new(),
+        findings: 0,
+        blast_radius: 0,
+        public_unsafe_fns: Vec::new(),
+        public_unsafe_traits: Vec::new(),
+    };
+    let lib_root = manifest
+        .lib
+        .path
+        .unwrap_or_else(|| PathBuf::from("src/lib.rs"));
+    scanner.scan(&unpacked.root, &locked, &checksums, &lib_root, &mut summary)?;
+
+    Ok(Trawl {
+        findings: scanner.sink.into_sorted()?,
+        crates: vec![summary],
+        issues: scanner.recovery.issues,
+        duplicates: Vec::new(),
+        timings: scanner.timings,
+        features: BTreeMap::new(),
+        inactive: Vec::new(),
+        api: Vec::new(),
+        checksum_mismatches: Vec::new(),
+        file_coverage: None,
+        skipped: scanner.recovery.skipped,
+    })
+}
+
+fn read_manifest(dir: &Path) -> Result<Manifest, ScanError> {
+    let path = dir.join("Cargo.toml");
+    let contents = fs::read_to_string(&path).map_err(|e| ScanError::Io(e, path.clone()))?;
+    toml::from_str(&contents)
+        .map_err(|e| ScanError::Vendor(format!("Could not parse the manifest, {e}"), path))
+}
+
+/// The checksums of the vendored directory `dir`, once the package checksum is found to match
+/// `checksum` and every file listed to match its own
+fn verify(dir: &Path, checksum: Option<&str>) -> Result<Checksums, ScanError> {
+    let path = dir.join(".cargo-checksum.json");
+    let contents = fs::read_to_string(&path).map_err(|e| ScanError::Io(e, path.clone()))?;
+    let checksums: Checksums = serde_json::from_str(&contents).map_err(|e| {
+        ScanError::Vendor(format!("Could not parse the checksums, {e}"), path.clone())
+    })?;
+    if checksums.package.as_deref() != checksum {
+        return Err(ScanError::Vendor(
+            format!(
+                "The package checksum {:?} does not match {:?} in the lockfile",
+                checksums.package, checksum
+            ),
+            path,
+        ));
+    }
+    for (file, expected) in &checksums.files {
+        let path = dir.join(file);
+        let contents = fs::read(&path).map_err(|e| ScanError::Io(e, path.clone()))?;
+        let digest = Sha256::digest(&contents)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>();
+        if digest != *expected {
+            return Err(ScanError::Vendor(
+                "The file does not match its checksum".to_string(),
+                path,
+            ));
+        }
+    }
+    Ok(checksums)
+}
+
+struct Scanner {
+    recovery: Recovery,
+    interner: Interner,
+    sink: SpillingSink,
+    timings: ScanTimings,
+    include_tests: bool,
+}
+
+impl Scanner {
+    /// Scans every `.rs` file of the vendored directory `dir` that `checksums` covers, into the
+    /// sink and `summary`
+    fn scan(
+        &mut self,
+        dir: &Path,
+        locked: &LockedPackage,
+        checksums: &Checksums,
+        lib_root: &Path,
+        summary: &mut CrateSummary,
+    ) -> Result<(), ScanError> {
+        let root = dir
+            .canonicalize()
+            .map_err(|e| ScanError::Io(e, dir.to_path_buf()))?;
+        let krate = self.interner.str(&locked.name);
+        let provenance = Arc::new(Provenance {
+            version: locked.version.clone(),
+            source: locked.source.clone().unwrap_or_default(),
+            checksum: locked.checksum.clone(),
+        });
+        let crate_name = locked.name.replace('-', "_");
+        let mut public_unsafe_fns = Vec::new();
+        let mut public_unsafe_traits = Vec::new();
+        let mut private_mods = HashSet::new();
+        for path in find_rs_files_in_dir(&root) {
+            let path = match path {
+                Ok(path) => path,
+                Err(e) => {
+                    self.recovery.recover_named(Some(locked.name.clone()), e)?;
+                    continue;
+                }
+            };
+            let relative = path.strip_prefix(&root).unwrap_or(&path).to_path_buf();
+            // A file added after vendoring is not trusted any more than a modified one.
+            let key = relative.to_string_lossy().replace('\\', "/");
+            if !checksums.files.contains_key(&key) {
+                self.recovery.recover_named(
+                    Some(locked.name.clone()),
+                    ScanError::Vendor("The file is not covered by the checksums".to_string(), path),
+                )?;
+                continue;
+            }
+            let start = Instant::now();
+            let contents = match fs::read(&path) {
+                Ok(contents) => contents,
+                Err(e) => {
+                    let e = ScanFileError::Io(e, path.clone());
+                    self.recovery
+                        .recover_named(Some(locked.name.clone()), ScanError::Scan(e))?;
+                    continue;
+                }
+            };
+            match ast_walker::find_unsafe_in_file(&crate_name, &path, &contents, self.include_tests)
+            {
+                Ok(items) => {
+                    if items.no_std && relative == lib_root {
+                        summary.no_std = true;
+                    }
+                    public_unsafe_fns.extend(
+                        items
+                            .public_fns
+                            .into_iter()
+                            .filter(|public_fn| public_fn.unsafe_fn)
+                            .map(|public_fn| public_fn.path),
+                    );
+                    public_unsafe_traits.extend(items.public_unsafe_traits);
+                    private_mods.extend(items.private_mods);
+                    let file = self.interner.path(&relative);
+                    let generated = generated::is_generated(&krate, &relative, &contents);
+                    for finding in to_findings(&krate, &provenance, &file, generated, items.items) {
+                        self.sink.push(finding)?;
+                    }
+                }
+                Err(e) => self
+                    .recovery
+                    .recover_named(Some(locked.name.clone()), ScanError::Scan(e))?,
+            }
+            self.timings.record(
+                &format!("{krate} {}", locked.version),
+                &relative,
+                start.elapsed(),
+            );
+        }
+        for paths in [&mut public_unsafe_fns, &mut public_unsafe_traits] {
+            paths.retain(|path| !in_private_mod(path, &private_mods));
+            paths.sort();
+            paths.dedup();
+        }
+        summary.public_unsafe_fns = public_unsafe_fns;
+        summary.public_unsafe_traits = public_unsafe_traits;
+        Ok(())
+    }
+}
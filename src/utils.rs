@@ -1,7 +1,10 @@
 use std::collections::{HashMap, HashSet};
 use std::env;
+use std::path::{Component, Path, PathBuf};
 use std::process::Command;
 
+use anyhow::{anyhow, bail, Context};
+
 // This funciton takes a Rust module path like
 // `<T as failure::as_fail::AsFail>::as_fail and strips`
 // down the fully-qualified trait paths within to just the base trait name, like
@@ -72,31 +75,92 @@ pub struct LabelInfo {
     pub debugloc: Option<llvm_ir::DebugLoc>,
 }
 
+/// The path of the source file a debug location points into
+#[must_use]
+pub fn debugloc_path(debugloc: &llvm_ir::DebugLoc) -> PathBuf {
+    debugloc.directory.as_ref().map_or_else(
+        || PathBuf::from(&debugloc.filename),
+        |directory| PathBuf::from(directory).join(&debugloc.filename),
+    )
+}
+
+/// A form of `path` suitable as a key, in which the same file reached with different casing
+/// compares equal on filesystems that are usually case-insensitive (macOS and Windows)
+#[must_use]
+pub fn normalize_case(path: &Path) -> PathBuf {
+    if cfg!(any(target_os = "macos", windows)) {
+        PathBuf::from(path.to_string_lossy().to_lowercase())
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// The path of the source file `file` of the package `krate` below the package's `NAME-VERSION`
+/// directory if it is in one, e.g., in the cargo cache, else its whole path
+#[must_use]
+pub fn package_relative_path(file: &Path, krate: &str, version: &str) -> PathBuf {
+    let package_dir = format!("{krate}-{version}");
+    let components = file
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect::<Vec<_>>();
+    let start = components
+        .iter()
+        .rposition(|component| component.as_os_str() == package_dir.as_str())
+        .map_or(0, |position| position + 1);
+    components[start..].iter().collect()
+}
+
 pub struct CallGraph {
     pub label_to_label_info: HashMap<String, LabelInfo>,
     pub short_label_to_labels: HashMap<String, HashSet<String>>,
 }
 
-#[allow(clippy::missing_panics_doc, clippy::expect_used, clippy::unwrap_used)]
-pub fn configure_rustup_toolchain() {
+impl CallGraph {
+    /// Adds the functions and calls of `other`, the callgraph of another crate linked with this
+    /// one. Functions only declared in one of the crates take their details from the other.
+    pub fn merge(&mut self, other: Self) {
+        for (label, label_info) in other.label_to_label_info {
+            let existing = self.label_to_label_info.entry(label).or_default();
+            existing.caller_labels.extend(label_info.caller_labels);
+            if existing.short_label.is_none() {
+                existing.short_label = label_info.short_label;
+            }
+            if existing.debugloc.is_none() {
+                existing.debugloc = label_info.debugloc;
+            }
+        }
+        for (short_label, labels) in other.short_label_to_labels {
+            self.short_label_to_labels
+                .entry(short_label)
+                .or_default()
+                .extend(labels);
+        }
+    }
+}
+
+#[allow(clippy::missing_errors_doc)]
+pub fn configure_rustup_toolchain() -> anyhow::Result<()> {
     let rsup_default = Command::new("rustup")
         .args(["show", "active-toolchain"])
         .output()
-        .expect("failed to run rustup to configure toolchain");
-    let utf8_toolchain = std::str::from_utf8(&rsup_default.stdout)
-        .unwrap()
+        .context("failed to run rustup to configure toolchain")?;
+    let utf8_toolchain = std::str::from_utf8(&rsup_default.stdout)?
         .split_once(' ')
-        .unwrap()
+        .ok_or_else(|| anyhow!("unexpected output from `rustup show active-toolchain`"))?
         .0;
     env::set_var("RUSTUP_TOOLCHAIN", utf8_toolchain);
 
-    let rustc_version = rustc_version::version_meta().unwrap();
-    let rustc_llvm_version = rustc_version.llvm_version.unwrap();
-    assert_eq!(
-        llvm_ir::llvm_version(),
-        rustc_llvm_version.major.to_string(),
-        "Siderophile was configured to use LLVM {}, but the default rustc emits LLVM {}.",
-        llvm_ir::llvm_version(),
-        rustc_llvm_version.major,
-    );
+    let rustc_version = rustc_version::version_meta()?;
+    let rustc_llvm_version = rustc_version
+        .llvm_version
+        .ok_or_else(|| anyhow!("rustc did not report its LLVM version"))?;
+    if llvm_ir::llvm_version() != rustc_llvm_version.major.to_string() {
+        bail!(
+            "Siderophile was configured to use LLVM {}, but the default rustc emits LLVM {}.",
+            llvm_ir::llvm_version(),
+            rustc_llvm_version.major,
+        );
+    }
+    Ok(())
 }